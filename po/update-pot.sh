@@ -0,0 +1,15 @@
+#!/bin/sh
+# Regenerates po/roadtrip.pot from the files listed in po/POTFILES.in.
+set -e
+cd "$(dirname "$0")/.."
+
+xgettext \
+    --language=Rust \
+    --from-code=UTF-8 \
+    --package-name=roadtrip \
+    --keyword=i18n \
+    --keyword=i18n_f \
+    --keyword=ni18n_f:1,2 \
+    --add-comments=Translators \
+    --files-from=po/POTFILES.in \
+    --output=po/roadtrip.pot
@@ -0,0 +1,80 @@
+// Housekeeping that doesn't have to be done before `entry` returns, like
+// bumping the modification times of files that were read. One task does all
+// of it in the background, a batch at a time, so hits don't wait on the disk.
+
+use filetime::FileTime;
+
+use std::path::PathBuf;
+
+use tokio::sync::{mpsc, oneshot};
+
+use tracing::{debug, warn};
+
+#[derive(Debug)]
+pub(crate) enum Chore {
+    // Marks the file as used, so the next walk knows what's stale.
+    Touch { path: PathBuf, time: FileTime },
+
+    // Sends once everything before it is done.
+    Flush(oneshot::Sender<()>),
+}
+
+impl Chore {
+    // Blocks.
+    fn run(self) {
+        match self {
+            Chore::Touch { path, time } => {
+                // Evicted since, most likely.
+                if let Err(e) = filetime::set_file_mtime(&path, time) {
+                    debug!(path = %path.display(), error = %e, "unable to touch");
+                }
+            }
+            Chore::Flush(done) => {
+                done.send(()).ok();
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Chores {
+    tx: mpsc::UnboundedSender<Chore>,
+}
+
+impl Chores {
+    // Starts the task, which runs until this is dropped.
+    pub fn spawn() -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(run(rx));
+        Self { tx }
+    }
+
+    pub fn push(&self, chore: Chore) {
+        // Only fails once the runtime is shutting down.
+        self.tx.send(chore).ok();
+    }
+
+    pub async fn flush(&self) {
+        let (tx, rx) = oneshot::channel();
+        self.push(Chore::Flush(tx));
+        rx.await.ok();
+    }
+}
+
+async fn run(mut rx: mpsc::UnboundedReceiver<Chore>) {
+    while let Some(chore) = rx.recv().await {
+        let mut batch = vec![chore];
+        while let Ok(chore) = rx.try_recv() {
+            batch.push(chore);
+        }
+
+        let result = tokio::task::spawn_blocking(move || {
+            batch.into_iter().for_each(Chore::run)
+        })
+        .await;
+
+        if let Err(e) = result {
+            warn!(error = %e, "cache chores failed");
+        }
+    }
+}
@@ -14,6 +14,10 @@ pub enum EntryError {
         path: PathBuf,
         source: std::io::Error,
     },
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
     FileTime {
         source: std::io::Error,
     },
@@ -23,11 +27,37 @@ pub enum EntryError {
     Prefix {
         source: std::path::StripPrefixError,
     },
+    // A file didn't match its checksum, or is missing.
+    Corrupt {
+        path: PathBuf,
+    },
+    Verify {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Discard {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    // On disk without the cache knowing about it, and not shaped like an
+    // entry, so it was moved aside.
+    Unexpected {
+        path: PathBuf,
+    },
+    Adopt {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Quarantine {
+        path: PathBuf,
+        source: std::io::Error,
+    },
 }
 
 #[derive(Debug, Snafu)]
 #[snafu(visibility = "pub(crate)")]
 pub enum InsertError {
+    ReadOnly,
     InvalidName,
     Create {
         path: PathBuf,
@@ -41,6 +71,18 @@ pub enum InsertError {
         path: PathBuf,
         source: std::io::Error,
     },
+    Rename {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Compress {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Checksum {
+        path: PathBuf,
+        source: std::io::Error,
+    },
     Reopen {
         path: PathBuf,
         source: std::io::Error,
@@ -50,6 +92,52 @@ pub enum InsertError {
     },
 }
 
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(crate)")]
+pub enum RemoveError {
+    // `ReadOnly` is already taken by `InsertError`.
+    ReadOnlyCache,
+    Key {
+        source: EntryError,
+    },
+    Remove {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Evict {
+        source: std::io::Error,
+    },
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(crate)")]
+pub enum ReserveError {
+    // `ReadOnly` and `ReadOnlyCache` are already taken.
+    ReadOnlyReserve,
+    // More than the cache's capacity, or more than it could free up without
+    // evicting pinned entries.
+    NoRoom { bytes: u64 },
+    MakeRoom { source: std::io::Error },
+}
+
+#[derive(Debug, Snafu)]
+#[snafu(visibility = "pub(crate)")]
+pub enum VerifyError {
+    // `ReadOnly`, `ReadOnlyCache`, and `ReadOnlyReserve` are already taken.
+    ReadOnlyVerify,
+    Scan {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Repair {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Shrink {
+        source: std::io::Error,
+    },
+}
+
 #[derive(Debug, Snafu)]
 #[snafu(visibility = "pub(crate)")]
 pub enum Error {
@@ -74,4 +162,12 @@ pub enum Error {
         source: crate::lock::Error,
     },
     AlreadyLocked,
+    Cancelled,
+    // The cache already has entries laid out the other way.
+    Layout {
+        fan_out: bool,
+    },
+    Marker {
+        source: std::io::Error,
+    },
 }
@@ -0,0 +1,94 @@
+// The contents of recently read entries, kept in memory so reading them again
+// doesn't have to go to disk at all. It's only a copy: everything in it is
+// still on disk, and whatever changes an entry there forgets it here.
+
+use linked_hash_map::LinkedHashMap;
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// A file in an entry, read all the way through.
+#[derive(Debug, Clone)]
+pub(crate) struct HotFile {
+    pub name: String,
+    // Where it is on disk.
+    pub path: PathBuf,
+    pub data: Arc<[u8]>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    // From least to most recently read.
+    entries: LinkedHashMap<PathBuf, Vec<HotFile>>,
+    size: u64,
+}
+
+#[derive(Debug)]
+pub(crate) struct HotTier {
+    capacity: u64,
+    // Only locked briefly, and never across an await.
+    inner: Mutex<Inner>,
+}
+
+impl HotTier {
+    pub fn new(capacity: u64) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::default(),
+        }
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    pub fn get(&self, path: &Path) -> Option<Vec<HotFile>> {
+        self.inner
+            .lock()
+            .unwrap()
+            .entries
+            .get_refresh(path)
+            .cloned()
+    }
+
+    // Keeps `files` for the entry at `path`, forgetting the least recently
+    // read entries to make room. Entries that could never fit aren't kept.
+    pub fn put(&self, path: PathBuf, files: Vec<HotFile>) {
+        let size = size_of(&files);
+        if size > self.capacity {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(old) = inner.entries.insert(path, files) {
+            inner.size -= size_of(&old);
+        }
+        inner.size += size;
+
+        while inner.size > self.capacity {
+            match inner.entries.pop_front() {
+                Some((_, old)) => inner.size -= size_of(&old),
+                None => break,
+            }
+        }
+    }
+
+    pub fn remove(&self, path: &Path) {
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(old) = inner.entries.remove(path) {
+            inner.size -= size_of(&old);
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.entries.clear();
+        inner.size = 0;
+    }
+}
+
+fn size_of(files: &[HotFile]) -> u64 {
+    files.iter().map(|f| f.data.len() as u64).sum()
+}
@@ -0,0 +1,140 @@
+// Remembers every entry's size and last use across runs, so opening a big
+// cache doesn't have to walk and stat every file in it.
+//
+// The index is only written when the cache is closed, and it's deleted as
+// soon as it's read. If the process dies without closing the cache, there's
+// no index to trust and the next open walks the directory like usual.
+
+use crate::entry_path;
+use crate::items::Item;
+
+use filetime::FileTime;
+
+use futures::StreamExt;
+
+use linked_hash_map::LinkedHashMap;
+
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+
+use tracing::debug;
+
+pub(crate) const NAME: &str = ".index";
+const TEMP_NAME: &str = ".index.tmp";
+
+fn parse_line(line: &str) -> Option<(&str, Item)> {
+    let mut parts = line.splitn(6, ' ');
+
+    let size = parts.next()?.parse().ok()?;
+    let files = parts.next()?.parse().ok()?;
+    let secs = parts.next()?.parse().ok()?;
+    let nanos = parts.next()?.parse().ok()?;
+    let uses = parts.next()?.parse().ok()?;
+    let key = parts.next()?;
+
+    let item = Item {
+        size,
+        files,
+        used: FileTime::from_unix_time(secs, nanos),
+        uses,
+    };
+
+    Some((key, item))
+}
+
+async fn dir_names(dir: &Path) -> Option<Vec<String>> {
+    let mut names = Vec::new();
+    let mut dirs = fs::read_dir(dir).await.ok()?;
+
+    while let Some(dir) = dirs.next().await {
+        let name = dir.ok()?.file_name().into_string().ok()?;
+        if !name.starts_with('.') {
+            names.push(name);
+        }
+    }
+
+    Some(names)
+}
+
+// Names of the entry directories actually in the cache.
+async fn entry_names(canon: &Path, fan_out: bool) -> Option<HashSet<String>> {
+    let top = dir_names(canon).await?;
+
+    if !fan_out {
+        return Some(top.into_iter().collect());
+    }
+
+    let mut names = HashSet::new();
+    for prefix in top {
+        names.extend(dir_names(&canon.join(prefix)).await?);
+    }
+
+    Some(names)
+}
+
+// Reads and deletes the index, returning the entries from least to most
+// recently used. `None` if there isn't one, or it doesn't agree with what's
+// on disk.
+pub(crate) async fn load(
+    root: &Path,
+    canon: &Path,
+    fan_out: bool,
+) -> Option<LinkedHashMap<PathBuf, Item>> {
+    let path = root.join(NAME);
+    let text = fs::read_to_string(&path).await.ok()?;
+
+    // From here until it's written again, the index could go stale.
+    if let Err(e) = fs::remove_file(&path).await {
+        debug!(error = %e, "unable to remove cache index");
+        return None;
+    }
+
+    let mut items = LinkedHashMap::new();
+    let mut keys = HashSet::new();
+
+    for line in text.lines() {
+        let (key, item) = parse_line(line)?;
+        keys.insert(key.to_owned());
+        items.insert(entry_path(root, key, fan_out), item);
+    }
+
+    if keys != entry_names(canon, fan_out).await? {
+        debug!("cache index doesn't match the directory");
+        return None;
+    }
+
+    Some(items)
+}
+
+// Blocks, since it has to work from `Drop`.
+pub(crate) fn save(
+    root: &Path,
+    items: &[(PathBuf, Item)],
+) -> std::io::Result<()> {
+    let temp = root.join(TEMP_NAME);
+    let mut file = std::io::BufWriter::new(std::fs::File::create(&temp)?);
+
+    for (path, item) in items {
+        let key = match path.file_name().and_then(|n| n.to_str()) {
+            Some(k) => k,
+            None => continue,
+        };
+
+        writeln!(
+            file,
+            "{} {} {} {} {} {}",
+            item.size,
+            item.files,
+            item.used.unix_seconds(),
+            item.used.nanoseconds(),
+            item.uses,
+            key
+        )?;
+    }
+
+    file.into_inner()?.sync_all()?;
+    std::fs::rename(&temp, root.join(NAME))
+}
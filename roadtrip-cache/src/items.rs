@@ -0,0 +1,250 @@
+// What the cache knows about its entries, split into shards by key so that
+// looking up one entry doesn't wait on another. Each shard keeps its own
+// entries in order of use, and the totals are kept outside of them so they
+// can be read without locking anything.
+
+use filetime::FileTime;
+
+use linked_hash_map::LinkedHashMap;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use tokio::sync::{Mutex, MutexGuard};
+
+const SHARDS: usize = 16;
+
+type Shard = LinkedHashMap<PathBuf, Item>;
+
+// What the cache knows about each entry. `used` is when it was last inserted
+// into or read, and `uses` is how many times that's happened.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Item {
+    pub size: u64,
+    pub files: usize,
+    pub used: FileTime,
+    pub uses: u64,
+}
+
+#[derive(Debug)]
+pub(crate) struct Items {
+    shards: Vec<Mutex<Shard>>,
+    size: AtomicU64,
+    len: AtomicUsize,
+}
+
+impl Default for Items {
+    fn default() -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| Mutex::default()).collect(),
+            size: AtomicU64::new(0),
+            len: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl Items {
+    // `items` should be from least to most recently used.
+    pub fn new<I>(items: I) -> Self
+    where
+        I: IntoIterator<Item = (PathBuf, Item)>,
+    {
+        let mut shards: Vec<Shard> =
+            (0..SHARDS).map(|_| LinkedHashMap::new()).collect();
+        let mut size = 0;
+
+        for (path, item) in items {
+            size += item.size;
+            shards[shard_of(&path)].insert(path, item);
+        }
+
+        let len = shards.iter().map(LinkedHashMap::len).sum();
+
+        Self {
+            shards: shards.into_iter().map(Mutex::new).collect(),
+            size: AtomicU64::new(size),
+            len: AtomicUsize::new(len),
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size.load(Ordering::Relaxed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    async fn shard(&self, path: &Path) -> MutexGuard<'_, Shard> {
+        self.shards[shard_of(path)].lock().await
+    }
+
+    // Marks the entry as used at `now`. False if there's no such entry.
+    pub async fn refresh(&self, path: &Path, now: FileTime) -> bool {
+        match self.shard(path).await.get_refresh(path) {
+            Some(item) => {
+                item.used = now;
+                item.uses += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub async fn get(&self, path: &Path) -> Option<Item> {
+        self.shard(path).await.get(path).copied()
+    }
+
+    // Adds `size` bytes and `files` files to the entry, creating it if
+    // needed.
+    pub async fn add(
+        &self,
+        path: PathBuf,
+        size: u64,
+        files: usize,
+        now: FileTime,
+    ) {
+        let mut shard = self.shard(&path).await;
+
+        match shard.get_refresh(&path) {
+            Some(item) => {
+                item.size += size;
+                item.files += files;
+                item.used = now;
+            }
+            None => {
+                let item = Item {
+                    size,
+                    files,
+                    used: now,
+                    uses: 1,
+                };
+                shard.insert(path, item);
+                self.len.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        self.size.fetch_add(size, Ordering::Relaxed);
+    }
+
+    // Takes `size` bytes off the entry, like when a file is replaced.
+    pub async fn subtract(&self, path: &Path, size: u64) {
+        if let Some(item) = self.shard(path).await.get_mut(path) {
+            let size = size.min(item.size);
+            item.size -= size;
+            self.size.fetch_sub(size, Ordering::Relaxed);
+        }
+    }
+
+    // Sets the entry's size and file count outright, like when they turn
+    // out to be wrong.
+    pub async fn resize(&self, path: &Path, size: u64, files: usize) {
+        if let Some(item) = self.shard(path).await.get_mut(path) {
+            self.size.fetch_sub(item.size, Ordering::Relaxed);
+            self.size.fetch_add(size, Ordering::Relaxed);
+            item.size = size;
+            item.files = files;
+        }
+    }
+
+    pub async fn remove(&self, path: &Path) -> Option<Item> {
+        let item = self.shard(path).await.remove(path)?;
+        self.forget(&item);
+        Some(item)
+    }
+
+    fn forget(&self, item: &Item) {
+        self.size.fetch_sub(item.size, Ordering::Relaxed);
+        self.len.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    // Locks every shard, always in the same order.
+    pub async fn lock_all(&self) -> Locked<'_> {
+        let mut shards = Vec::with_capacity(self.shards.len());
+        for shard in &self.shards {
+            shards.push(shard.lock().await);
+        }
+
+        Locked {
+            items: self,
+            shards,
+        }
+    }
+
+    // Every entry, from least to most recently used. Only one shard is
+    // locked at a time, so entries can change while this runs.
+    pub async fn snapshot(&self) -> Vec<(PathBuf, Item)> {
+        let mut entries = Vec::with_capacity(self.len());
+
+        for shard in &self.shards {
+            let shard = shard.lock().await;
+            entries.extend(shard.iter().map(|(p, i)| (p.clone(), *i)));
+        }
+
+        entries.sort_by_key(|(_, i)| i.used);
+        entries
+    }
+
+    // Like `snapshot`, but `None` instead of waiting if any shard is locked.
+    pub fn try_snapshot(&self) -> Option<Vec<(PathBuf, Item)>> {
+        let mut entries = Vec::with_capacity(self.len());
+
+        for shard in &self.shards {
+            let shard = shard.try_lock().ok()?;
+            entries.extend(shard.iter().map(|(p, i)| (p.clone(), *i)));
+        }
+
+        entries.sort_by_key(|(_, i)| i.used);
+        Some(entries)
+    }
+}
+
+// Every shard at once, for looking across all of the entries.
+#[derive(Debug)]
+pub(crate) struct Locked<'a> {
+    items: &'a Items,
+    shards: Vec<MutexGuard<'a, Shard>>,
+}
+
+impl<'a> Locked<'a> {
+    // The least recently used entry.
+    pub fn oldest(&self) -> Option<PathBuf> {
+        self.shards
+            .iter()
+            .filter_map(|shard| shard.front())
+            .min_by_key(|(_, i)| i.used)
+            .map(|(p, _)| p.clone())
+    }
+
+    // The least entry by `compare` that `skip` doesn't rule out.
+    pub fn min_by<S, C>(&self, mut skip: S, mut compare: C) -> Option<PathBuf>
+    where
+        S: FnMut(&Path) -> bool,
+        C: FnMut(&Item, &Item) -> std::cmp::Ordering,
+    {
+        self.shards
+            .iter()
+            .flat_map(|shard| shard.iter())
+            .filter(|(p, _)| !skip(p))
+            .min_by(|(_, a), (_, b)| compare(a, b))
+            .map(|(p, _)| p.clone())
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&Item> {
+        self.shards[shard_of(path)].get(path)
+    }
+
+    pub fn remove(&mut self, path: &Path) -> Option<Item> {
+        let item = self.shards[shard_of(path)].remove(path)?;
+        self.items.forget(&item);
+        Some(item)
+    }
+}
+
+fn shard_of(path: &Path) -> usize {
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    (hasher.finish() % SHARDS as u64) as usize
+}
@@ -0,0 +1,53 @@
+// Turns keys into directory names that are safe to put on disk. Keys that are
+// already safe, like hex hashes, are used as they are, so caches from before
+// any key could be used still work. Anything else is percent-encoded, which
+// always adds a `%` that safe names can't have, so the two never collide.
+
+use crate::check_path;
+
+use std::fmt::Write;
+
+pub(crate) fn encode(key: &str) -> String {
+    if check_path(key) {
+        return key.to_owned();
+    }
+
+    let mut name = String::with_capacity(key.len());
+
+    for (idx, c) in key.char_indices() {
+        if c.is_alphanumeric() || (c == '.' && idx > 0) {
+            name.push(c);
+            continue;
+        }
+
+        let mut buf = [0; 4];
+        for byte in c.encode_utf8(&mut buf).bytes() {
+            write!(name, "%{:02X}", byte).unwrap();
+        }
+    }
+
+    name
+}
+
+// `None` if `name` couldn't have come from `encode`.
+pub(crate) fn decode(name: &str) -> Option<String> {
+    if !name.contains('%') {
+        return Some(name.to_owned());
+    }
+
+    let mut bytes = Vec::with_capacity(name.len());
+    let mut rest = name.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
@@ -0,0 +1,57 @@
+// Serializes access to each entry within a process, so two tasks asking for
+// the same missing entry don't both try to fill it in. The file lock in
+// `lock` already keeps other processes out of the cache entirely.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex};
+
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+#[derive(Debug, Default)]
+pub(crate) struct KeyLocks {
+    // Only locked briefly, and never across an await, so it can be unlocked
+    // in `Drop`.
+    locks: StdMutex<HashMap<PathBuf, Arc<Mutex<()>>>>,
+}
+
+impl KeyLocks {
+    pub async fn lock(&self, path: &Path) -> KeyGuard<'_> {
+        let lock = {
+            let mut locks = self.locks.lock().unwrap();
+            locks.entry(path.to_owned()).or_default().clone()
+        };
+
+        KeyGuard {
+            locks: self,
+            path: path.to_owned(),
+            guard: Some(lock.lock_owned().await),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct KeyGuard<'a> {
+    locks: &'a KeyLocks,
+    path: PathBuf,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl<'a> Drop for KeyGuard<'a> {
+    fn drop(&mut self) {
+        let mut locks = self.locks.locks.lock().unwrap();
+
+        // One reference is in the map and the other is in `guard`, so nobody
+        // else is waiting and the lock can be forgotten.
+        let idle = match locks.get(&self.path) {
+            Some(l) => Arc::strong_count(l) <= 2,
+            None => false,
+        };
+
+        if idle {
+            locks.remove(&self.path);
+        }
+
+        self.guard.take();
+    }
+}
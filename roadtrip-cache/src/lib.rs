@@ -1,54 +1,139 @@
+mod chores;
 pub mod error;
+mod hot;
+mod index;
+mod items;
+mod key;
+mod key_lock;
 mod lock;
-
-use crate::error::{EntryError, Error, InsertError};
+mod manifest;
+pub mod policy;
+pub mod verify;
+
+use crate::chores::{Chore, Chores};
+use crate::error::{EntryError, Error, InsertError, RemoveError, ReserveError};
+use crate::hot::{HotFile, HotTier};
+use crate::items::{Item, Items};
+use crate::key_lock::{KeyGuard, KeyLocks};
 use crate::lock::Lock;
+use crate::policy::{EvictionPolicy, Lru, Usage};
+
+use async_compression::tokio_02::bufread::ZstdDecoder;
+use async_compression::tokio_02::write::ZstdEncoder;
+use async_compression::Level;
 
-use filetime::{set_file_handle_times, FileTime};
+use filetime::FileTime;
 
-use futures::{pin_mut, StreamExt, TryStreamExt};
+use futures::{pin_mut, stream, Stream, StreamExt, TryStreamExt};
 
 use linked_hash_map as lhm;
 
+use roadtrip_cancel::Token;
+
 use roadtrip_walkdir::WalkDir;
 
 use snafu::{ensure, IntoError, ResultExt};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::future::Future;
-use std::ops::{Deref, DerefMut};
 use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime};
 
 use tokio::fs::{self, File, OpenOptions, ReadDir};
-use tokio::sync::Mutex;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{broadcast, Mutex};
+
+use tracing::{debug, info, warn};
+
+// Added to the names of files that are still being written.
+const TEMP_SUFFIX: &str = ".tmp";
+
+// Added to the names of files that are stored compressed.
+const COMPRESSED_SUFFIX: &str = ".zst";
+
+// Marks a cache whose entries are fanned out into directories named after the
+// first two characters of their keys, like `ab/abcdef/`.
+const FAN_OUT: &str = ".fan-out";
+
+// Where directories that don't look like entries are moved, out of the way.
+const QUARANTINE: &str = ".quarantine";
 
+// Evictions kept for each `on_evict` stream that's fallen behind.
+const EVICTION_BACKLOG: usize = 64;
+
+#[derive(Debug)]
+enum Contents {
+    Plain(File),
+    Zstd(ZstdDecoder<BufReader<File>>),
+    // Already read, and uncompressed, by the hot tier.
+    Memory(std::io::Cursor<Arc<[u8]>>),
+}
+
+// A file in an entry. Reading it gives back what was inserted, whether it's
+// stored compressed or was read from memory.
 #[derive(Debug)]
 pub struct NamedFile {
     name: String,
-    file: File,
+    contents: Contents,
 }
 
-impl Deref for NamedFile {
-    type Target = File;
-
-    fn deref(&self) -> &File {
-        &self.file
+impl AsyncRead for NamedFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match &mut self.contents {
+            Contents::Plain(f) => Pin::new(f).poll_read(cx, buf),
+            Contents::Zstd(d) => Pin::new(d).poll_read(cx, buf),
+            Contents::Memory(m) => Pin::new(m).poll_read(cx, buf),
+        }
     }
 }
 
-impl DerefMut for NamedFile {
-    fn deref_mut(&mut self) -> &mut File {
-        &mut self.file
+impl NamedFile {
+    fn new(name: String, file: File, compressed: bool) -> Self {
+        let contents = if compressed {
+            Contents::Zstd(ZstdDecoder::new(BufReader::new(file)))
+        } else {
+            Contents::Plain(file)
+        };
+
+        Self { name, contents }
+    }
+
+    fn from_memory(hot: HotFile) -> Self {
+        Self {
+            name: hot.name,
+            contents: Contents::Memory(std::io::Cursor::new(hot.data)),
+        }
     }
-}
 
-impl NamedFile {
     pub fn name(&self) -> &str {
         &self.name
     }
 
-    pub fn into_file(self) -> File {
-        self.file
+    // Whether it's stored compressed on disk. Files read from memory were
+    // already uncompressed.
+    pub fn is_compressed(&self) -> bool {
+        match self.contents {
+            Contents::Plain(_) | Contents::Memory(_) => false,
+            Contents::Zstd(_) => true,
+        }
+    }
+
+    // The file as it is on disk, so still compressed if it was stored that
+    // way, or `None` if it was read from memory.
+    pub fn into_file(self) -> Option<File> {
+        match self.contents {
+            Contents::Plain(f) => Some(f),
+            Contents::Zstd(d) => Some(d.into_inner().into_inner()),
+            Contents::Memory(_) => None,
+        }
     }
 }
 
@@ -63,77 +148,139 @@ impl<'a> OccupiedEntry<'a> {
     pub fn into_files(self) -> impl Iterator<Item = NamedFile> {
         self.files.into_iter()
     }
+
+    // Adds another file to the entry, like `VacantEntry::insert_with`. Files
+    // already opened by this entry aren't affected.
+    pub async fn insert_with<F, O>(
+        &self,
+        name: &str,
+        f: F,
+    ) -> Result<NamedFile, InsertError>
+    where
+        F: FnOnce(File) -> O,
+        O: Future<Output = Result<(), std::io::Error>>,
+    {
+        let _guard = self.cache.keys.lock(&self.path).await;
+        self.cache
+            .write_file(&self.path, name, false, None, f)
+            .await
+    }
+
+    // Like `insert_with`, but overwrites the file if it's already there.
+    pub async fn replace_with<F, O>(
+        &self,
+        name: &str,
+        f: F,
+    ) -> Result<NamedFile, InsertError>
+    where
+        F: FnOnce(File) -> O,
+        O: Future<Output = Result<(), std::io::Error>>,
+    {
+        let _guard = self.cache.keys.lock(&self.path).await;
+        self.cache.write_file(&self.path, name, true, None, f).await
+    }
 }
 
 #[derive(Debug)]
 pub struct VacantEntry<'a> {
     cache: &'a Cache,
     path: PathBuf,
+    // Anyone else asking for this entry waits until this one is dropped.
+    _guard: Option<KeyGuard<'a>>,
+    corruption: Option<EntryError>,
+    reservation: Option<Reservation<'a>>,
 }
 
 impl<'a> VacantEntry<'a> {
+    // Why the entry was thrown away, if it was there but failed its
+    // checksums. Its files have already been deleted.
+    pub fn corruption(&self) -> Option<&EntryError> {
+        self.corruption.as_ref()
+    }
+
+    // Has inserts into this entry use up `reservation`, as far as it goes,
+    // instead of evicting to make room of their own.
+    pub fn use_reservation(&mut self, reservation: Reservation<'a>) {
+        self.reservation = Some(reservation);
+    }
+
     pub async fn insert_with<F, O>(
         &self,
         name: &str,
         f: F,
-    ) -> Result<File, InsertError>
+    ) -> Result<NamedFile, InsertError>
     where
         F: FnOnce(File) -> O,
         O: Future<Output = Result<(), std::io::Error>>,
     {
-        ensure!(check_path(name), error::InvalidName);
-
-        match fs::create_dir(&self.path).await {
-            Ok(_) => (),
-            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => (),
-            Err(e) => {
-                return Err(error::Create {
-                    path: self.path.clone(),
-                }
-                .into_error(e))
-            }
-        }
-
-        let path = self.path.join(name);
-        let mut file = OpenOptions::new()
-            .create_new(true)
-            .write(true)
-            .open(&path)
-            .await
-            .with_context(|| error::Create { path: path.clone() })?;
-
-        // TODO: Shouldn't need to clone this. The closure `f` should be able to
-        //       accept an `&mut File`...
-        let file2 = file
-            .try_clone()
+        let reservation = self.reservation.as_ref();
+        self.cache
+            .write_file(&self.path, name, false, reservation, f)
             .await
-            .with_context(|| error::Create { path: path.clone() })?;
+    }
 
-        f(file2)
-            .await
-            .with_context(|| error::Write { path: path.clone() })?;
+    // Like `insert_with`, but copies everything from `reader` into the file.
+    pub async fn insert_from_reader<R>(
+        &self,
+        name: &str,
+        mut reader: R,
+    ) -> Result<NamedFile, InsertError>
+    where
+        R: AsyncRead + Unpin,
+    {
+        self.insert_with(name, |mut f| async move {
+            tokio::io::copy(&mut reader, &mut f).await?;
+            f.flush().await
+        })
+        .await
+    }
 
-        file.sync_all()
-            .await
-            .with_context(|| error::Write { path: path.clone() })?;
+    pub async fn insert_bytes(
+        &self,
+        name: &str,
+        data: &[u8],
+    ) -> Result<NamedFile, InsertError> {
+        self.insert_with(name, |mut f| async move {
+            f.write_all(data).await?;
+            f.flush().await
+        })
+        .await
+    }
+}
 
-        let info = file
-            .metadata()
-            .await
-            .with_context(|| error::Metadata { path: path.clone() })?;
+// Room held in the cache for something that hasn't been inserted yet, so it
+// isn't worked out only to be evicted. Whatever inserts haven't used up is
+// let go when it's dropped.
+#[derive(Debug)]
+pub struct Reservation<'a> {
+    cache: &'a Cache,
+    remaining: AtomicU64,
+}
 
-        let ro = File::open(&path)
-            .await
-            .with_context(|| error::Reopen { path: path.clone() })?;
+impl<'a> Reservation<'a> {
+    pub fn remaining(&self) -> u64 {
+        self.remaining.load(Ordering::Relaxed)
+    }
 
-        drop(file);
+    // Uses up to `bytes` of the reservation, for bytes that are about to be
+    // counted against the cache instead.
+    fn consume(&self, bytes: u64) {
+        let before = self
+            .remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |r| {
+                Some(r.saturating_sub(bytes))
+            })
+            .unwrap_or_else(|r| r);
 
-        self.cache
-            .insert(self.path.clone(), info.len())
-            .await
-            .context(error::Reserve)?;
+        let used = before.min(bytes);
+        self.cache.reserved.fetch_sub(used, Ordering::Relaxed);
+    }
+}
 
-        Ok(ro)
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        let left = self.remaining.load(Ordering::Relaxed);
+        self.cache.reserved.fetch_sub(left, Ordering::Relaxed);
     }
 }
 
@@ -143,31 +290,179 @@ pub enum Entry<'a> {
     Vacant(VacantEntry<'a>),
 }
 
+// What the cache has been up to since it was opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub hits: u64,
+    pub misses: u64,
+    // Bytes written into the cache, and bytes evicted to make room.
+    pub inserted: u64,
+    pub evicted: u64,
+    pub size: u64,
+    pub capacity: u64,
+}
+
+impl Stats {
+    // How full the cache is, where 1.0 is at capacity. Can go over if a
+    // single entry is bigger than the whole cache, or pinned entries are.
+    pub fn utilization(&self) -> f64 {
+        if self.capacity == 0 {
+            0.0
+        } else {
+            self.size as f64 / self.capacity as f64
+        }
+    }
+}
+
+// What the cache knows about one of its entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryMetadata {
+    pub size: u64,
+    pub last_used: SystemTime,
+    pub file_count: usize,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    inserted: AtomicU64,
+    evicted: AtomicU64,
+}
+
 #[derive(Debug)]
 pub struct Cache {
-    lock: Lock,
+    lock: Option<Lock>,
+    root: PathBuf,
+    items: Items,
+    // Held while evicting, so two inserts don't both make room for the
+    // same bytes.
+    evicting: Mutex<()>,
+    // Entries that `insert` won't evict, even to make room.
+    pinned: Mutex<HashSet<PathBuf>>,
+    capacity: AtomicU64,
+    // Bytes held by outstanding reservations, counted as if they were used.
+    reserved: AtomicU64,
+    max_entries: Option<usize>,
+    policy: Box<dyn EvictionPolicy>,
+    // The zstd level new files are compressed with, if they are at all.
+    compression: Option<u32>,
+    fan_out: bool,
+    hot: Option<HotTier>,
+    keys: KeyLocks,
+    counters: Counters,
+    evictions: broadcast::Sender<String>,
+    chores: Chores,
+}
+
+// Options for opening a cache, for when `Cache::new` isn't enough.
+#[derive(Debug)]
+pub struct Builder {
     root: PathBuf,
-    items: Mutex<lhm::LinkedHashMap<PathBuf, u64>>,
     capacity: u64,
+    max_entries: Option<usize>,
+    policy: Box<dyn EvictionPolicy>,
+    compression: Option<u32>,
+    fan_out: bool,
+    hot_tier: Option<u64>,
+    lock_timeout: Duration,
+    lock_poll_interval: Duration,
+    cancel: Token,
 }
 
-impl Cache {
-    pub async fn new<P>(root: P, capacity: u64) -> Result<Self, Error>
+impl Builder {
+    // Waits up to `timeout` for another process to release the cache,
+    // checking again after `poll_interval` and then less and less often.
+    pub fn lock_timeout(
+        mut self,
+        timeout: Duration,
+        poll_interval: Duration,
+    ) -> Self {
+        self.lock_timeout = timeout;
+        self.lock_poll_interval = poll_interval;
+        self
+    }
+
+    // Evicts entries to keep there from being more than `max`, even if
+    // they'd fit in the capacity. For caches of lots of tiny entries.
+    pub fn max_entries(mut self, max: usize) -> Self {
+        self.max_entries = Some(max);
+        self
+    }
+
+    // Chooses which entries are evicted first. Least recently used, unless
+    // told otherwise.
+    pub fn eviction_policy<E>(mut self, policy: E) -> Self
     where
-        P: Into<PathBuf>,
+        E: EvictionPolicy + 'static,
     {
-        let root = root.into();
+        self.policy = Box::new(policy);
+        self
+    }
+
+    // Stores new files compressed with zstd at `level`, from 1 (fastest) to
+    // 21 (smallest). Files are decompressed as they're read either way.
+    pub fn compress(mut self, level: u32) -> Self {
+        self.compression = Some(level);
+        self
+    }
+
+    // Keeps each entry in a directory named after the start of its key, so
+    // no one directory ends up with tens of thousands in it. The layout can
+    // only be changed while the cache is empty.
+    pub fn fan_out(mut self) -> Self {
+        self.fan_out = true;
+        self
+    }
+
+    // Keeps up to `bytes` of the entries read most recently in memory, so
+    // reading them again doesn't go to disk. Entries bigger than that are
+    // always read from disk.
+    pub fn hot_tier(mut self, bytes: u64) -> Self {
+        self.hot_tier = Some(bytes);
+        self
+    }
+
+    // Gives up waiting for the lock, or scanning existing entries, once
+    // `token` is cancelled.
+    pub fn cancel_on(mut self, token: Token) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    pub async fn open(self) -> Result<Cache, Error> {
+        let Builder {
+            root,
+            capacity,
+            max_entries,
+            policy,
+            compression,
+            fan_out,
+            hot_tier,
+            lock_timeout,
+            lock_poll_interval,
+            cancel,
+        } = self;
 
         let lock_path = root.join(".lock");
-        let lock_result = tokio::task::spawn_blocking(|| Lock::new(lock_path))
-            .await
-            .context(error::LockJoin)?;
+        let lock_cancel = cancel.clone();
+        let lock_result = tokio::task::spawn_blocking(move || {
+            Lock::acquire_with(
+                lock_path,
+                lock_timeout,
+                lock_poll_interval,
+                &lock_cancel,
+            )
+        })
+        .await
+        .context(error::LockJoin)?;
 
         let lock = match lock_result {
             Ok(l) => l,
             Err(lock::Error::AlreadyLocked) => {
                 return Err(Error::AlreadyLocked)
             }
+            Err(lock::Error::Cancelled) => return Err(Error::Cancelled),
             Err(source) => return Err(Error::Lock { source }),
         };
 
@@ -175,9 +470,73 @@ impl Cache {
         let canon =
             fs::canonicalize(&root).await.context(error::Canonicalize)?;
 
-        let mut items: HashMap<PathBuf, (FileTime, u64)> = HashMap::new();
+        let fanned_out = is_fanned_out(&root).await;
+
+        // The index is only any good if the layout hasn't changed.
+        let indexed = if fanned_out == fan_out {
+            index::load(&root, &canon, fan_out).await
+        } else {
+            None
+        };
+
+        let packed = match indexed {
+            Some(p) => p,
+            None => Self::walk(&root, &canon, fanned_out, &cancel).await?,
+        };
+
+        if fanned_out != fan_out {
+            ensure!(packed.is_empty(), error::Layout { fan_out });
+
+            let marker = root.join(FAN_OUT);
+            let result = if fan_out {
+                fs::write(&marker, b"").await
+            } else {
+                fs::remove_file(&marker).await
+            };
+            result.context(error::Marker)?;
+        }
+
+        info!(
+            root = %root.display(),
+            entries = packed.len(),
+            size = packed.values().map(|i| i.size).sum::<u64>(),
+            "opened cache"
+        );
+
+        Ok(Cache {
+            items: Items::new(packed),
+            evicting: Mutex::new(()),
+            lock: Some(lock),
+            pinned: Mutex::new(HashSet::new()),
+            root,
+            reserved: AtomicU64::new(0),
+            capacity: AtomicU64::new(capacity),
+            max_entries,
+            policy,
+            compression,
+            fan_out,
+            hot: hot_tier.map(HotTier::new),
+            keys: KeyLocks::default(),
+            counters: Counters::default(),
+            evictions: broadcast::channel(EVICTION_BACKLOG).0,
+            chores: Chores::spawn(),
+        })
+    }
+
+    // Finds every entry by looking at every file, oldest first.
+    async fn walk(
+        root: &Path,
+        canon: &Path,
+        fan_out: bool,
+        cancel: &Token,
+    ) -> Result<lhm::LinkedHashMap<PathBuf, Item>, Error> {
+        let mut items: HashMap<PathBuf, (FileTime, u64, usize)> =
+            HashMap::new();
 
-        let walkdir = WalkDir::new(&canon).walk();
+        let mut walkdir = WalkDir::new(canon);
+        walkdir.cancel_on(cancel.clone());
+
+        let walkdir = walkdir.walk();
         pin_mut!(walkdir);
 
         while let Some(result) = walkdir.next().await {
@@ -187,20 +546,47 @@ impl Cache {
                 continue;
             }
 
-            let relative = match entry.path().strip_prefix(&canon) {
-                Ok(r) if r == Path::new(".lock") => continue,
+            let relative = match entry.path().strip_prefix(canon) {
+                // Like `.lock`, `.index`, and whatever is in `.quarantine`. Keys
+                // can't start with a dot.
+                Ok(r) if is_hidden(r) => continue,
                 Ok(r) => r,
                 Err(_) => continue,
             };
 
+            // Entries are directories of files, maybe in a directory of
+            // entries.
+            let depth = if fan_out { 3 } else { 2 };
+
             let components: Vec<_> = relative.iter().collect();
             ensure!(
-                components.len() == 2,
+                components.len() == depth,
                 error::Structure {
                     path: entry.path().clone()
                 }
             );
 
+            let key = components[..depth - 1]
+                .iter()
+                .fold(root.to_owned(), |p, c| p.join(c));
+            let name = components[depth - 1].to_string_lossy();
+
+            // Left behind by an insert that never finished. The entry is
+            // still remembered, even if that was its only file, so it can be
+            // evicted.
+            if is_temp(&name) {
+                debug!(path = %entry.path().display(), "removing partial file");
+                fs::remove_file(entry.path()).await.ok();
+                items.entry(key).or_insert((FileTime::zero(), 0, 0));
+                continue;
+            }
+
+            // Checksums aren't counted against the capacity.
+            if name == manifest::NAME {
+                items.entry(key).or_insert((FileTime::zero(), 0, 0));
+                continue;
+            }
+
             let metadata =
                 fs::metadata(entry.path()).await.with_context(|| {
                     error::Size {
@@ -210,56 +596,135 @@ impl Cache {
 
             let ft = FileTime::from_last_modification_time(&metadata);
 
-            let key = root.join(components[0]);
-
-            let mut ft_sz = items.entry(key).or_insert((FileTime::zero(), 0));
+            let mut ft_sz =
+                items.entry(key).or_insert((FileTime::zero(), 0, 0));
             ft_sz.0 = std::cmp::max(ft_sz.0, ft);
             ft_sz.1 += metadata.len();
+            ft_sz.2 += 1;
         }
 
+        // A partial walk would make the cache forget about entries, and
+        // they'd never be evicted.
+        ensure!(!cancel.is_cancelled(), error::Cancelled);
+
         let mut sorted: Vec<_> = items.into_iter().collect();
-        sorted.sort_by_key(|(_, (tm, _))| *tm);
+        sorted.sort_by_key(|(_, (tm, _, _))| *tm);
 
         let packed = sorted
             .into_iter()
-            .map(|(path, (_, sz))| (path, sz))
+            .map(|(path, (used, size, files))| {
+                let item = Item {
+                    size,
+                    files,
+                    used,
+                    uses: 1,
+                };
+                (path, item)
+            })
             .collect();
 
+        Ok(packed)
+    }
+}
+
+impl Cache {
+    pub async fn new<P>(root: P, capacity: u64) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        Self::builder(root, capacity).open().await
+    }
+
+    // Like `new`, but waits up to `timeout` for another process to release
+    // the cache, checking again after `poll_interval` and then less and less
+    // often.
+    pub async fn with_lock_timeout<P>(
+        root: P,
+        capacity: u64,
+        timeout: Duration,
+        poll_interval: Duration,
+    ) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        Self::builder(root, capacity)
+            .lock_timeout(timeout, poll_interval)
+            .open()
+            .await
+    }
+
+    pub fn builder<P>(root: P, capacity: u64) -> Builder
+    where
+        P: Into<PathBuf>,
+    {
+        Builder {
+            root: root.into(),
+            capacity,
+            max_entries: None,
+            policy: Box::new(Lru),
+            compression: None,
+            fan_out: false,
+            hot_tier: None,
+            lock_timeout: Duration::default(),
+            lock_poll_interval: Duration::default(),
+            cancel: Token::new(),
+        }
+    }
+
+    // Opens a cache without taking the lock, so it can be read while another
+    // process owns it. Entries can't be inserted, removed, or evicted,
+    // reading doesn't refresh them, and `len`/`size` don't account for
+    // anything on disk.
+    //
+    // There's no shared lock to take instead: it would keep out the owner,
+    // which only ever locks exclusively. Any number of read-only caches can
+    // be open at once, alongside at most one owner.
+    pub async fn open_read_only<P>(root: P) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        let root = root.into();
+
+        fs::canonicalize(&root).await.context(error::Canonicalize)?;
+        let fan_out = is_fanned_out(&root).await;
+
         Ok(Self {
-            items: Mutex::new(packed),
-            lock,
+            items: Items::default(),
+            evicting: Mutex::new(()),
+            lock: None,
+            pinned: Mutex::new(HashSet::new()),
             root,
-            capacity,
+            reserved: AtomicU64::new(0),
+            capacity: AtomicU64::new(0),
+            max_entries: None,
+            policy: Box::new(Lru),
+            compression: None,
+            fan_out,
+            hot: None,
+            keys: KeyLocks::default(),
+            counters: Counters::default(),
+            evictions: broadcast::channel(EVICTION_BACKLOG).0,
+            chores: Chores::spawn(),
         })
     }
 
+    pub fn is_read_only(&self) -> bool {
+        self.lock.is_none()
+    }
+
     async fn vacant_entry<'a>(
         &'a self,
         path: PathBuf,
+        guard: Option<KeyGuard<'a>>,
+        corruption: Option<EntryError>,
     ) -> Result<VacantEntry<'a>, EntryError> {
-        Ok(VacantEntry { cache: self, path })
-    }
-
-    async fn spawn_update_mtime(
-        file: &File,
-        now: FileTime,
-    ) -> Result<(), EntryError> {
-        let clone = file
-            .try_clone()
-            .await
-            .context(error::FileTime)?
-            .into_std()
-            .await;
-
-        let result = tokio::task::spawn_blocking(move || {
-            // TODO: This can probably be done asynchronously
-            set_file_handle_times(&clone, None, Some(now))
-                .context(error::FileTime)
+        Ok(VacantEntry {
+            cache: self,
+            path,
+            _guard: guard,
+            corruption,
+            reservation: None,
         })
-        .await
-        .context(error::Join)??;
-
-        Ok(result)
     }
 
     async fn occupied_entry<'a>(
@@ -277,13 +742,18 @@ impl Cache {
 
                 if file_type.is_file() {
                     // Recover the name from the path.
-                    let name = match entry.path().strip_prefix(&path) {
+                    let mut name = match entry.path().strip_prefix(&path) {
                         Ok(n) => n.to_string_lossy().into_owned(),
                         Err(e) => {
                             return Some(Err(EntryError::Prefix { source: e }))
                         }
                     };
 
+                    // Still being written, by the owner of a read-only cache.
+                    if is_temp(&name) || name == manifest::NAME {
+                        return None;
+                    }
+
                     // Try to open the file.
                     let result =
                         File::open(entry.path()).await.with_context(|| {
@@ -297,12 +767,19 @@ impl Cache {
                         Err(e) => return Some(Err(e)),
                     };
 
-                    // Spawn and wait for a task to update the file's mtime.
-                    if let Err(e) = Self::spawn_update_mtime(&file, now).await {
-                        return Some(Err(e));
+                    if !self.is_read_only() {
+                        self.chores.push(Chore::Touch {
+                            path: entry.path(),
+                            time: now,
+                        });
+                    }
+
+                    let compressed = name.ends_with(COMPRESSED_SUFFIX);
+                    if compressed {
+                        name.truncate(name.len() - COMPRESSED_SUFFIX.len());
                     }
 
-                    Some(Ok(NamedFile { name, file }))
+                    Some(Ok(NamedFile::new(name, file, compressed)))
                 } else {
                     None
                 }
@@ -310,12 +787,22 @@ impl Cache {
             .try_collect()
             .await?;
 
-        let unexpected = self.items.lock().await.get_refresh(&path).is_none();
+        // A read-only cache doesn't track entries, and the owner may have
+        // added this one at any time.
+        if self.is_read_only() {
+            return Ok(OccupiedEntry {
+                cache: self,
+                files,
+                path,
+            });
+        }
 
-        if unexpected {
-            panic!("unexpected directory: {:?}", path);
+        if !self.items.refresh(&path, now).await {
+            self.adopt(&path, now).await?;
         }
 
+        let files = self.warm(&path, files).await?;
+
         Ok(OccupiedEntry {
             cache: self,
             files,
@@ -323,80 +810,905 @@ impl Cache {
         })
     }
 
-    pub async fn entry<'a>(
-        &'a self,
-        key: &'a str,
-    ) -> Result<Entry<'a>, EntryError> {
-        let path = self.to_path(key)?;
+    // Reads `files` into the hot tier, if there is one and they'd fit, and
+    // gives them back ready to be read from there.
+    async fn warm(
+        &self,
+        path: &Path,
+        files: Vec<NamedFile>,
+    ) -> Result<Vec<NamedFile>, EntryError> {
+        let hot = match &self.hot {
+            Some(h) => h,
+            None => return Ok(files),
+        };
 
-        match fs::read_dir(&path).await {
-            Ok(dirs) => {
-                self.occupied_entry(path, dirs).await.map(Entry::Occupied)
-            }
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                self.vacant_entry(path).await.map(Entry::Vacant)
-            }
-            Err(e) => Err(error::ReadDir { path }.into_error(e)),
+        match self.items.get(path).await {
+            Some(item) if item.size <= hot.capacity() => (),
+            _ => return Ok(files),
         }
-    }
 
-    fn to_path(&self, key: &str) -> Result<PathBuf, EntryError> {
-        ensure!(check_path(key), error::InvalidKey);
-        let path = self.root.join(key);
-        Ok(path)
-    }
+        let mut warmed = Vec::with_capacity(files.len());
 
-    pub async fn size(&self) -> u64 {
-        let items = self.items.lock().await;
-        items.values().sum()
-    }
+        for mut file in files {
+            let stored = if file.is_compressed() {
+                format!("{}{}", file.name, COMPRESSED_SUFFIX)
+            } else {
+                file.name.clone()
+            };
+            let stored = path.join(stored);
 
-    pub async fn len(&self) -> usize {
-        let items = self.items.lock().await;
-        items.len()
-    }
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)
+                .await
+                .with_context(|| error::Read {
+                    path: stored.clone(),
+                })?;
 
-    pub async fn capacity(&self) -> u64 {
-        self.capacity
-    }
+            warmed.push(HotFile {
+                name: file.name,
+                path: stored,
+                data: data.into(),
+            });
+        }
 
-    async fn insert(
-        &self,
-        path: PathBuf,
-        new_sz: u64,
-    ) -> Result<(), std::io::Error> {
-        let mut map = self.items.lock().await;
-        let size: u64 = map.values().sum();
-        let available = if self.capacity >= size {
-            self.capacity - size
-        } else {
-            0
-        };
+        hot.put(path.to_owned(), warmed.clone());
 
-        if available < new_sz {
-            let missing = new_sz - available;
-            let mut removed = 0;
+        Ok(warmed.into_iter().map(NamedFile::from_memory).collect())
+    }
 
-            let mut entries = map.entries();
+    // The entry at `path` from the hot tier, without going to disk, if it's
+    // there. It's still marked as used, like it would be if it came from
+    // disk.
+    async fn hot_entry(&self, path: &Path) -> Option<OccupiedEntry<'_>> {
+        let hot = self.hot.as_ref()?;
+        let files = hot.get(path)?;
 
-            while removed < missing {
-                let entry = match entries.next() {
-                    Some(i) => i,
-                    None => break,
-                };
+        let now = FileTime::now();
 
-                if entry.key() == &path {
-                    continue;
-                }
+        // Gone from the cache some way that didn't forget it here.
+        if !self.items.refresh(path, now).await {
+            hot.remove(path);
+            return None;
+        }
 
-                fs::remove_dir_all(entry.key()).await?;
-                removed += entry.remove();
-            }
+        for file in &files {
+            self.chores.push(Chore::Touch {
+                path: file.path.clone(),
+                time: now,
+            });
         }
 
-        *map.entry(path).or_insert(0) += new_sz;
-        Ok(())
+        Some(OccupiedEntry {
+            cache: self,
+            files: files.into_iter().map(NamedFile::from_memory).collect(),
+            path: path.to_owned(),
+        })
     }
+
+    // Forgets the entry at `path` from the hot tier, since it changed on
+    // disk.
+    fn cool(&self, path: &Path) {
+        if let Some(hot) = &self.hot {
+            hot.remove(path);
+        }
+    }
+
+    // Starts keeping track of an entry the cache didn't know was there, like
+    // one copied in by hand. A directory that doesn't look like an entry is
+    // moved aside instead.
+    async fn adopt(
+        &self,
+        path: &Path,
+        now: FileTime,
+    ) -> Result<(), EntryError> {
+        let size = entry_size(path).await.with_context(|| error::Adopt {
+            path: path.to_owned(),
+        })?;
+
+        if let Some((size, files)) = size {
+            warn!(path = %path.display(), size, "adopting unexpected entry");
+
+            self.items.add(path.to_owned(), size, files, now).await;
+            self.evict(Some(path)).await.with_context(|| error::Adopt {
+                path: path.to_owned(),
+            })?;
+
+            return Ok(());
+        }
+
+        let aside = self.quarantine(path, now).await.with_context(|| {
+            error::Quarantine {
+                path: path.to_owned(),
+            }
+        })?;
+
+        warn!(
+            path = %path.display(),
+            aside = %aside.display(),
+            "moved aside unexpected directory"
+        );
+
+        Err(EntryError::Unexpected {
+            path: path.to_owned(),
+        })
+    }
+
+    // Moves `path` into `.quarantine`, out of the way, returning where it
+    // ended up.
+    async fn quarantine(
+        &self,
+        path: &Path,
+        now: FileTime,
+    ) -> std::io::Result<PathBuf> {
+        let quarantine = self.root.join(QUARANTINE);
+        let aside = quarantine.join(format!(
+            "{}.{}",
+            file_name(path),
+            now.unix_seconds()
+        ));
+
+        fs::create_dir_all(&quarantine).await?;
+        fs::rename(path, &aside).await?;
+
+        Ok(aside)
+    }
+
+    // A vacant entry keeps anyone else from getting the same key until it's
+    // dropped, so don't ask for a key again while holding onto it.
+    pub async fn entry<'a>(
+        &'a self,
+        key: &'a str,
+    ) -> Result<Entry<'a>, EntryError> {
+        let path = self.to_path(key)?;
+
+        // Whoever finds the entry missing gets to fill it in, and anyone else
+        // asking for it meanwhile gets whatever they came up with. A read-only
+        // cache can't fill anything in, so there's nothing to wait for.
+        let guard = if self.is_read_only() {
+            None
+        } else {
+            Some(self.keys.lock(&path).await)
+        };
+
+        // Checked when it was read from disk, and nothing has changed it
+        // since.
+        if let Some(hot) = self.hot_entry(&path).await {
+            self.counters.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(Entry::Occupied(hot));
+        }
+
+        let dirs = match fs::read_dir(&path).await {
+            Ok(d) => d,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.counters.misses.fetch_add(1, Ordering::Relaxed);
+                let vacant = self.vacant_entry(path, guard, None).await?;
+                return Ok(Entry::Vacant(vacant));
+            }
+            Err(e) => return Err(error::ReadDir { path }.into_error(e)),
+        };
+
+        match manifest::verify(&path).await {
+            Ok(()) => {
+                self.counters.hits.fetch_add(1, Ordering::Relaxed);
+                self.occupied_entry(path, dirs).await.map(Entry::Occupied)
+            }
+            Err(e @ EntryError::Corrupt { .. }) => {
+                warn!(error = %e, "discarding corrupt cache entry");
+                drop(dirs);
+                self.discard(&path).await?;
+
+                self.counters.misses.fetch_add(1, Ordering::Relaxed);
+                let vacant = self.vacant_entry(path, guard, Some(e)).await?;
+                Ok(Entry::Vacant(vacant))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // Deletes a corrupt entry, for whoever holds its key's lock. A read-only
+    // cache can only leave it for the owner to find.
+    async fn discard(&self, path: &Path) -> Result<(), EntryError> {
+        if self.is_read_only() {
+            return Ok(());
+        }
+
+        self.cool(path);
+
+        match fs::remove_dir_all(path).await {
+            Ok(_) => (),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+            Err(e) => {
+                return Err(error::Discard {
+                    path: path.to_owned(),
+                }
+                .into_error(e))
+            }
+        }
+
+        self.items.remove(path).await;
+        Ok(())
+    }
+
+    fn to_path(&self, key: &str) -> Result<PathBuf, EntryError> {
+        ensure!(!key.is_empty(), error::InvalidKey);
+        let name = key::encode(key);
+        Ok(entry_path(&self.root, &name, self.fan_out))
+    }
+
+    // Keeps the entry for `key` from being evicted until it's unpinned, like
+    // for thumbnails that are on screen. The key doesn't have to exist yet.
+    // Pinned entries still count towards the capacity, so the cache can end
+    // up over it.
+    pub async fn pin(&self, key: &str) -> Result<(), EntryError> {
+        let path = self.to_path(key)?;
+        self.pinned.lock().await.insert(path);
+        Ok(())
+    }
+
+    pub async fn unpin(&self, key: &str) -> Result<(), EntryError> {
+        let path = self.to_path(key)?;
+        self.pinned.lock().await.remove(&path);
+        Ok(())
+    }
+
+    // Deletes the entry for `key`, returning how many bytes that freed. Like
+    // `entry`, this waits for anyone holding a vacant entry for `key`.
+    pub async fn remove(&self, key: &str) -> Result<u64, RemoveError> {
+        ensure!(!self.is_read_only(), error::ReadOnlyCache);
+
+        let path = self.to_path(key).context(error::Key)?;
+        let _guard = self.keys.lock(&path).await;
+        self.cool(&path);
+
+        match fs::remove_dir_all(&path).await {
+            Ok(_) => (),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+            Err(e) => return Err(error::Remove { path }.into_error(e)),
+        }
+
+        let freed = self.items.remove(&path).await.map(|i| i.size);
+        let freed = freed.unwrap_or(0);
+        debug!(path = %path.display(), size = freed, "removed");
+
+        Ok(freed)
+    }
+
+    // Deletes every entry, returning how many bytes that freed. Entries that
+    // are still being filled in for the first time are left alone.
+    pub async fn clear(&self) -> Result<u64, RemoveError> {
+        ensure!(!self.is_read_only(), error::ReadOnlyCache);
+
+        let mut items = self.items.lock_all().await;
+        let mut freed = 0;
+
+        if let Some(hot) = &self.hot {
+            hot.clear();
+        }
+
+        while let Some(path) = items.oldest() {
+            match fs::remove_dir_all(&path).await {
+                Ok(_) => (),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+                Err(e) => return Err(error::Remove { path }.into_error(e)),
+            }
+
+            freed += items.remove(&path).map(|i| i.size).unwrap_or(0);
+        }
+
+        info!(root = %self.root.display(), freed, "cleared cache");
+
+        Ok(freed)
+    }
+
+    // Every entry's key, size, and when it was last used, from least to most
+    // recently used. Entries added after the stream is first polled are left
+    // out.
+    pub fn iter(&self) -> impl Stream<Item = (String, u64, SystemTime)> + '_ {
+        let snapshot = async move {
+            let entries: Vec<_> = self
+                .items
+                .snapshot()
+                .await
+                .into_iter()
+                .filter_map(|(path, item)| {
+                    let key = key_of(&path)?;
+                    Some((key, item.size, system_time(item.used)))
+                })
+                .collect();
+
+            stream::iter(entries)
+        };
+
+        stream::once(snapshot).flatten()
+    }
+
+    pub fn keys(&self) -> impl Stream<Item = String> + '_ {
+        self.iter().map(|(key, _, _)| key)
+    }
+
+    // From what the cache keeps track of, without looking at the disk, so
+    // always `None` for a read-only cache. Doesn't count as using the entry.
+    pub async fn metadata(&self, key: &str) -> Option<EntryMetadata> {
+        let path = self.to_path(key).ok()?;
+        let item = self.items.get(&path).await?;
+
+        Some(EntryMetadata {
+            size: item.size,
+            last_used: system_time(item.used),
+            file_count: item.files,
+        })
+    }
+
+    // Keys of the entries evicted from now on, to make room or after
+    // shrinking. Entries deleted with `remove` or `clear` aren't included.
+    // A stream that falls too far behind skips the oldest keys.
+    pub fn on_evict(&self) -> impl Stream<Item = String> {
+        self.evictions
+            .subscribe()
+            .filter_map(|key| async move { key.ok() })
+    }
+
+    pub async fn size(&self) -> u64 {
+        self.items.size()
+    }
+
+    pub async fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub async fn capacity(&self) -> u64 {
+        self.capacity.load(Ordering::Relaxed)
+    }
+
+    // Changes the most bytes the cache can hold, evicting entries right away
+    // if it's now over. Returns how many bytes were evicted.
+    pub async fn set_capacity(
+        &self,
+        capacity: u64,
+    ) -> Result<u64, RemoveError> {
+        ensure!(!self.is_read_only(), error::ReadOnlyCache);
+
+        self.capacity.store(capacity, Ordering::Relaxed);
+        self.shrink_to_fit().await
+    }
+
+    // Evicts entries until the cache is within its capacity and entry limit,
+    // like after unpinning some. Returns how many bytes were evicted.
+    pub async fn shrink_to_fit(&self) -> Result<u64, RemoveError> {
+        ensure!(!self.is_read_only(), error::ReadOnlyCache);
+        self.evict(None).await.context(error::Evict)
+    }
+
+    // Bytes held for inserts that haven't happened yet.
+    pub async fn reserved(&self) -> u64 {
+        self.reserved.load(Ordering::Relaxed)
+    }
+
+    // Evicts entries to make room for `bytes` more, and holds onto that room
+    // until inserts use it up or the reservation is dropped. Meant for before
+    // working out something expensive, so it isn't wasted if the cache
+    // can't hold it.
+    pub async fn reserve(
+        &self,
+        bytes: u64,
+    ) -> Result<Reservation<'_>, ReserveError> {
+        ensure!(!self.is_read_only(), error::ReadOnlyReserve);
+
+        let capacity = self.capacity.load(Ordering::Relaxed);
+        ensure!(bytes <= capacity, error::NoRoom { bytes });
+
+        self.reserved.fetch_add(bytes, Ordering::Relaxed);
+        let reservation = Reservation {
+            cache: self,
+            remaining: AtomicU64::new(bytes),
+        };
+
+        self.evict(None).await.context(error::MakeRoom)?;
+
+        // Anything still over is pinned.
+        ensure!(self.excess().0 == 0, error::NoRoom { bytes });
+
+        Ok(reservation)
+    }
+
+    // How many bytes and entries have to go to get within the limits.
+    fn excess(&self) -> (u64, usize) {
+        let used = self.items.size() + self.reserved.load(Ordering::Relaxed);
+        let bytes = used.saturating_sub(self.capacity.load(Ordering::Relaxed));
+
+        let entries = match self.max_entries {
+            Some(max) => self.items.len().saturating_sub(max),
+            None => 0,
+        };
+
+        (bytes, entries)
+    }
+
+    // Waits for housekeeping put off by earlier calls, like marking files
+    // that were read as used, to finish.
+    pub async fn flush(&self) {
+        self.chores.flush().await;
+    }
+
+    pub async fn stats(&self) -> Stats {
+        let counters = &self.counters;
+
+        Stats {
+            hits: counters.hits.load(Ordering::Relaxed),
+            misses: counters.misses.load(Ordering::Relaxed),
+            inserted: counters.inserted.load(Ordering::Relaxed),
+            evicted: counters.evicted.load(Ordering::Relaxed),
+            size: self.size().await,
+            capacity: self.capacity().await,
+        }
+    }
+
+    // Evicts entries in the order the policy picks, other than `keep` and
+    // pinned ones, until the cache is within its limits or there's nothing left to
+    // evict. Returns how many bytes were freed.
+    async fn evict(&self, keep: Option<&Path>) -> Result<u64, std::io::Error> {
+        if self.excess() == (0, 0) {
+            return Ok(0);
+        }
+
+        let _evicting = self.evicting.lock().await;
+
+        // Someone else may have made room while this was waiting.
+        let (needed, count) = self.excess();
+        if (needed, count) == (0, 0) {
+            return Ok(0);
+        }
+
+        let mut items = self.items.lock_all().await;
+        let pinned = self.pinned.lock().await;
+        let mut removed = 0;
+        let mut removed_count = 0;
+
+        while removed < needed || removed_count < count {
+            let victim = items.min_by(
+                |p| Some(p) == keep || pinned.contains(p),
+                |a, b| self.policy.compare(&usage(a), &usage(b)),
+            );
+
+            let path = match victim {
+                Some(p) => p,
+                None => break,
+            };
+
+            debug!(
+                path = %path.display(),
+                size = items.get(&path).map(|i| i.size),
+                "evicting"
+            );
+            self.cool(&path);
+            fs::remove_dir_all(&path).await?;
+
+            let size = items.remove(&path).map(|i| i.size).unwrap_or(0);
+            removed += size;
+            removed_count += 1;
+            self.counters.evicted.fetch_add(size, Ordering::Relaxed);
+
+            if let Some(key) = key_of(&path) {
+                // Nobody listening is fine.
+                self.evictions.send(key).ok();
+            }
+        }
+
+        Ok(removed)
+    }
+
+    // Writes the file `name` in the entry at `dir` with `f`, and counts it
+    // against the capacity, or `reservation` if there is one. With `replace`,
+    // the file can already exist.
+    async fn write_file<F, O>(
+        &self,
+        dir: &Path,
+        name: &str,
+        replace: bool,
+        reservation: Option<&Reservation<'_>>,
+        f: F,
+    ) -> Result<NamedFile, InsertError>
+    where
+        F: FnOnce(File) -> O,
+        O: Future<Output = Result<(), std::io::Error>>,
+    {
+        ensure!(!self.is_read_only(), error::ReadOnly);
+        ensure!(
+            check_path(name) && !is_temp(name) && !is_compressed(name),
+            error::InvalidName
+        );
+
+        // Cleans up if this returns early, or is dropped, before it's done.
+        let mut partial = Partial::default();
+
+        // Nobody else can be creating it while this holds the entry's key.
+        if fs::metadata(dir).await.is_err() {
+            partial.dir = Some(dir.to_owned());
+        }
+
+        match fs::create_dir_all(dir).await {
+            Ok(_) => (),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => (),
+            Err(e) => {
+                return Err(error::Create {
+                    path: dir.to_owned(),
+                }
+                .into_error(e))
+            }
+        }
+
+        // The file could be stored either way, depending on how the cache
+        // was opened when it was written.
+        let plain = dir.join(name);
+        let packed = dir.join(format!("{}{}", name, COMPRESSED_SUFFIX));
+
+        let (path, other) = match self.compression {
+            Some(_) => (packed, plain),
+            None => (plain, packed),
+        };
+
+        let mut old_sz = 0;
+        let mut old_files = 0;
+
+        if replace {
+            for old in &[&path, &other] {
+                if let Some(size) = file_size(old).await? {
+                    old_sz += size;
+                    old_files += 1;
+                }
+            }
+        }
+
+        if !replace
+            && (fs::metadata(&path).await.is_ok()
+                || fs::metadata(&other).await.is_ok())
+        {
+            let exists = std::io::ErrorKind::AlreadyExists.into();
+            return Err(error::Create { path }.into_error(exists));
+        }
+
+        // Written off to the side and renamed into place once it's complete,
+        // so nobody ever opens half of a file.
+        let temp = dir.join(format!("{}{}", name, TEMP_SUFFIX));
+        partial.files.push(temp.clone());
+
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(&temp)
+            .await
+            .with_context(|| error::Create { path: temp.clone() })?;
+
+        // TODO: Shouldn't need to clone this. The closure `f` should be able to
+        //       accept an `&mut File`...
+        let file2 = file
+            .try_clone()
+            .await
+            .with_context(|| error::Create { path: temp.clone() })?;
+
+        f(file2)
+            .await
+            .with_context(|| error::Write { path: temp.clone() })?;
+
+        let (temp, mut file) = match self.compression {
+            Some(level) => {
+                drop(file);
+                let packed_temp = append(&path, TEMP_SUFFIX);
+                partial.files.push(packed_temp.clone());
+                let file = compress(&temp, &packed_temp, level).await?;
+                (packed_temp, file)
+            }
+            None => (temp, file),
+        };
+
+        file.sync_all()
+            .await
+            .with_context(|| error::Write { path: temp.clone() })?;
+
+        let info = file
+            .metadata()
+            .await
+            .with_context(|| error::Metadata { path: temp.clone() })?;
+
+        drop(file);
+
+        // Listed before the file is in place, so a crash in between leaves an
+        // entry that fails its checksums rather than one that can't.
+        let sum = manifest::checksum(&temp)
+            .await
+            .with_context(|| error::Checksum { path: temp.clone() })?;
+
+        let manifest_path = dir.join(manifest::NAME);
+        let mut sums =
+            manifest::load(dir).await.with_context(|| error::Checksum {
+                path: manifest_path.clone(),
+            })?;
+
+        sums.remove(&file_name(&other));
+        sums.insert(file_name(&path), sum);
+
+        manifest::save(dir, &sums)
+            .await
+            .with_context(|| error::Checksum {
+                path: manifest_path.clone(),
+            })?;
+
+        self.cool(dir);
+
+        fs::rename(&temp, &path)
+            .await
+            .with_context(|| error::Rename { path: path.clone() })?;
+
+        // It's in place now, so it stays even if something below fails, or
+        // this is dropped before it's counted. `repair` can count it later.
+        partial.done();
+
+        // Replaced by the file that was just written.
+        if replace {
+            match fs::remove_file(&other).await {
+                Ok(_) => (),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+                Err(e) => {
+                    return Err(error::Write { path: other }.into_error(e))
+                }
+            }
+        }
+
+        // Opened before it's counted, since another insert could evict it
+        // after that.
+        let ro = File::open(&path)
+            .await
+            .with_context(|| error::Reopen { path: path.clone() })?;
+
+        self.items.subtract(dir, old_sz).await;
+        // Replacing a file that wasn't there adds one.
+        let new_files = if old_files == 0 { 1 } else { 0 };
+        self.count(dir.to_owned(), info.len(), new_files, reservation)
+            .await;
+
+        self.evict(Some(dir)).await.context(error::Reserve)?;
+
+        let compressed = self.compression.is_some();
+        Ok(NamedFile::new(name.to_owned(), ro, compressed))
+    }
+
+    // Counts `new_sz` more bytes and `new_files` more files against the entry
+    // at `path`, taking them out of `reservation` first. Making room for
+    // them is up to the caller.
+    async fn count(
+        &self,
+        path: PathBuf,
+        new_sz: u64,
+        new_files: usize,
+        reservation: Option<&Reservation<'_>>,
+    ) {
+        self.counters.inserted.fetch_add(new_sz, Ordering::Relaxed);
+
+        if let Some(reservation) = reservation {
+            reservation.consume(new_sz);
+        }
+
+        let now = FileTime::now();
+        self.items.add(path, new_sz, new_files, now).await;
+    }
+}
+
+// Whatever an insert has written so far, removed again unless it gets as far
+// as renaming the file into place. This runs in `Drop`, since an insert can
+// be dropped partway through, so it can't wait on anything and removes the
+// few files involved right away instead.
+//
+// If the entry's checksums were already updated, the entry fails them the
+// next time it's read and is thrown away, just like after a crash.
+#[derive(Debug, Default)]
+struct Partial {
+    files: Vec<PathBuf>,
+    // The entry's directory, if the insert created it.
+    dir: Option<PathBuf>,
+    done: bool,
+}
+
+impl Partial {
+    fn done(mut self) {
+        self.done = true;
+    }
+}
+
+impl Drop for Partial {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+
+        for file in &self.files {
+            match std::fs::remove_file(file) {
+                Ok(_) => (),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+                Err(e) => warn!(
+                    path = %file.display(),
+                    error = %e,
+                    "unable to remove partial file"
+                ),
+            }
+        }
+
+        // Only this insert could have put anything in it.
+        if let Some(dir) = &self.dir {
+            match std::fs::remove_dir_all(dir) {
+                Ok(_) => (),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+                Err(e) => warn!(
+                    path = %dir.display(),
+                    error = %e,
+                    "unable to remove partial entry"
+                ),
+            }
+        }
+    }
+}
+
+impl Drop for Cache {
+    fn drop(&mut self) {
+        if self.is_read_only() {
+            return;
+        }
+
+        // Nobody else can be holding a shard with `&mut self` around.
+        let items = match self.items.try_snapshot() {
+            Some(i) => i,
+            None => return,
+        };
+
+        if let Err(e) = index::save(&self.root, &items) {
+            warn!(error = %e, "unable to save cache index");
+        }
+    }
+}
+
+fn usage(item: &Item) -> Usage {
+    Usage {
+        size: item.size,
+        used: system_time(item.used),
+        uses: item.uses,
+    }
+}
+
+fn system_time(ft: FileTime) -> SystemTime {
+    let secs = ft.unix_seconds();
+    let nanos = Duration::from_nanos(u64::from(ft.nanoseconds()));
+
+    // Nanoseconds always count forwards, even before the epoch.
+    if secs < 0 {
+        SystemTime::UNIX_EPOCH - Duration::from_secs(-secs as u64) + nanos
+    } else {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs as u64) + nanos
+    }
+}
+
+fn is_temp(name: &str) -> bool {
+    name.ends_with(TEMP_SUFFIX)
+}
+
+// The key of the entry at `path`.
+fn key_of(path: &Path) -> Option<String> {
+    key::decode(path.file_name()?.to_str()?)
+}
+
+fn file_name(path: &Path) -> String {
+    match path.file_name() {
+        Some(n) => n.to_string_lossy().into_owned(),
+        None => String::new(),
+    }
+}
+
+fn is_compressed(name: &str) -> bool {
+    name.ends_with(COMPRESSED_SUFFIX)
+}
+
+fn append(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    path.into()
+}
+
+// `None` if there's no file at `path`.
+async fn file_size(path: &Path) -> Result<Option<u64>, InsertError> {
+    match fs::metadata(path).await {
+        Ok(m) => Ok(Some(m.len())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(error::Metadata { path }.into_error(e)),
+    }
+}
+
+// Compresses the file at `raw` into a new file at `packed`, then deletes
+// `raw`.
+async fn compress(
+    raw: &Path,
+    packed: &Path,
+    level: u32,
+) -> Result<File, InsertError> {
+    let mut input = File::open(raw).await.with_context(|| error::Reopen {
+        path: raw.to_owned(),
+    })?;
+
+    let output = OpenOptions::new()
+        .create(true)
+        .truncate(true)
+        .write(true)
+        .open(packed)
+        .await
+        .with_context(|| error::Create {
+            path: packed.to_owned(),
+        })?;
+
+    let mut encoder = ZstdEncoder::with_quality(output, Level::Precise(level));
+
+    tokio::io::copy(&mut input, &mut encoder)
+        .await
+        .with_context(|| error::Compress {
+            path: packed.to_owned(),
+        })?;
+    encoder.shutdown().await.with_context(|| error::Compress {
+        path: packed.to_owned(),
+    })?;
+
+    drop(input);
+    fs::remove_file(raw)
+        .await
+        .with_context(|| error::Compress {
+            path: raw.to_owned(),
+        })?;
+
+    Ok(encoder.into_inner())
+}
+
+// Where the entry named `name`, as encoded from its key, lives.
+pub(crate) fn entry_path(root: &Path, name: &str, fan_out: bool) -> PathBuf {
+    if fan_out {
+        let prefix: String = name.chars().take(2).collect();
+        root.join(prefix).join(name)
+    } else {
+        root.join(name)
+    }
+}
+
+async fn is_fanned_out(root: &Path) -> bool {
+    fs::metadata(root.join(FAN_OUT)).await.is_ok()
+}
+
+fn is_hidden(relative: &Path) -> bool {
+    match relative.iter().next() {
+        Some(name) => name.to_string_lossy().starts_with('.'),
+        None => false,
+    }
+}
+
+// How many bytes the files in an entry's directory add up to, and how many
+// files there are, or `None` if there's anything else in there, so it can't
+// be an entry.
+async fn entry_size(path: &Path) -> std::io::Result<Option<(u64, usize)>> {
+    let mut size = 0;
+    let mut files = 0;
+    let mut dirs = fs::read_dir(path).await?;
+
+    while let Some(dir) = dirs.next_entry().await? {
+        let metadata = dir.metadata().await?;
+        if !metadata.is_file() {
+            return Ok(None);
+        }
+
+        let name = dir.file_name();
+        let name = name.to_string_lossy();
+        if is_temp(&name) || name == manifest::NAME {
+            continue;
+        }
+
+        size += metadata.len();
+        files += 1;
+    }
+
+    Ok(Some((size, files)))
 }
 
 fn check_path(key: &str) -> bool {
@@ -1,21 +1,133 @@
 use fd_lock::{FdLock, FdLockGuard};
 
+use roadtrip_cancel::Token;
+
 use snafu::{ResultExt, Snafu};
 
 use std::ffi::c_void;
 use std::fs::{File, OpenOptions};
-use std::io;
+use std::io::{self, Seek, SeekFrom, Write};
 use std::marker::PhantomPinned;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use tracing::{debug, warn};
 
 #[derive(Debug, Snafu)]
 pub enum Error {
     AlreadyLocked,
     Create { source: io::Error },
+    WriteOwner { source: io::Error },
+    Cancelled,
     Other,
 }
 
+// Written into the lock file so a process that can't take the lock can say
+// who has it. The lock itself is only ever the one on the file: the OS lets
+// go of it when its holder exits, so there's nothing left behind to reclaim.
+// The start time tells a live owner apart from an unrelated process that
+// reused its pid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Owner {
+    pid: u32,
+    start: Option<u64>,
+}
+
+impl Owner {
+    fn current() -> Self {
+        let pid = std::process::id();
+        Self {
+            pid,
+            start: start_time(pid),
+        }
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut fields = text.split_whitespace();
+        let pid = fields.next()?.parse().ok()?;
+        let start = match fields.next() {
+            Some(s) => Some(s.parse().ok()?),
+            None => None,
+        };
+
+        Some(Self { pid, start })
+    }
+
+    fn write(&self, file: &mut File) -> io::Result<()> {
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+
+        match self.start {
+            Some(start) => writeln!(file, "{} {}", self.pid, start)?,
+            None => writeln!(file, "{}", self.pid)?,
+        }
+
+        file.sync_all()
+    }
+
+    fn is_alive(&self) -> bool {
+        if !process_exists(self.pid) {
+            return false;
+        }
+
+        match (self.start, start_time(self.pid)) {
+            (Some(recorded), Some(actual)) => recorded == actual,
+            _ => true,
+        }
+    }
+
+    // Whoever the lock file says owns it, if it names anyone yet.
+    fn read(path: &Path) -> Option<Self> {
+        let text = std::fs::read_to_string(path).ok()?;
+        Self::parse(&text)
+    }
+}
+
+#[cfg(unix)]
+fn process_exists(pid: u32) -> bool {
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0
+        || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_exists(_pid: u32) -> bool {
+    true
+}
+
+// Field 22 of `/proc/<pid>/stat` is the process start time, in clock ticks
+// since boot. The command name (field 2) may contain spaces, so fields are
+// counted from the closing parenthesis.
+#[cfg(target_os = "linux")]
+fn start_time(pid: u32) -> Option<u64> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    let rest = &stat[stat.rfind(')')? + 1..];
+    rest.split_whitespace().nth(19)?.parse().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn start_time(_pid: u32) -> Option<u64> {
+    None
+}
+
+// The previous owner removes the lock file before unlocking it, so a lock
+// taken on that file afterwards is on one that no longer exists at `path`.
+#[cfg(unix)]
+fn is_current(path: &Path, file: &File) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (std::fs::metadata(path), file.metadata()) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn is_current(_path: &Path, _file: &File) -> bool {
+    true
+}
+
 #[derive(Debug)]
 struct Inner {
     _pin: PhantomPinned,
@@ -63,7 +175,7 @@ impl Inner {
             let oaeu = Pin::get_unchecked_mut(mut_ref);
             let lock_res = oaeu.lock.try_lock();
 
-            let guard = match lock_res {
+            let mut guard = match lock_res {
                 Ok(g) => g,
                 Err(e) if matches!(e.kind(), fd_lock::ErrorKind::Locked) => {
                     return Err(Error::AlreadyLocked);
@@ -71,6 +183,12 @@ impl Inner {
                 Err(_) => return Err(Error::Other),
             };
 
+            if !is_current(&oaeu.path, &guard) {
+                return Err(Error::AlreadyLocked);
+            }
+
+            Owner::current().write(&mut guard).context(WriteOwner)?;
+
             oaeu.guard = Box::into_raw(Box::new(guard)) as *mut c_void;
         }
 
@@ -78,6 +196,9 @@ impl Inner {
     }
 }
 
+// Even when asked to, the lock isn't retried any faster than this.
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
 #[derive(Debug)]
 pub struct Lock {
     inner: Option<Pin<Box<Inner>>>,
@@ -90,18 +211,103 @@ impl Lock {
         P: Into<PathBuf>,
     {
         let path = p.into();
+
+        let inner = match Inner::new(&path) {
+            Err(Error::AlreadyLocked) => {
+                match Owner::read(&path) {
+                    Some(owner) if owner.is_alive() => debug!(
+                        path = %path.display(),
+                        pid = owner.pid,
+                        "lock held by another process"
+                    ),
+                    // Whoever wrote the lock file is gone, but something
+                    // (maybe a child it left running) still holds the lock.
+                    Some(owner) => warn!(
+                        path = %path.display(),
+                        pid = owner.pid,
+                        "lock held, but the process that took it has exited"
+                    ),
+                    None => debug!(
+                        path = %path.display(),
+                        "lock held by an unknown process"
+                    ),
+                }
+
+                return Err(Error::AlreadyLocked);
+            }
+            other => other?,
+        };
+
         Ok(Self {
-            inner: Some(Inner::new(&path)?),
+            inner: Some(inner),
             path,
         })
     }
+
+    // Keeps retrying while the lock is held by someone else, giving up with
+    // `AlreadyLocked` once `timeout` has passed. The wait between tries
+    // starts at `poll_interval` and doubles, up to a quarter of `timeout`.
+    pub fn acquire_with<P>(
+        p: P,
+        timeout: Duration,
+        poll_interval: Duration,
+        cancel: &Token,
+    ) -> Result<Self, Error>
+    where
+        P: Into<PathBuf>,
+    {
+        let path = p.into();
+        let deadline = Instant::now() + timeout;
+
+        let mut interval = std::cmp::max(poll_interval, MIN_POLL_INTERVAL);
+        let max_interval = std::cmp::max(timeout / 4, interval);
+        let mut waiting = false;
+
+        // Only the first try says who has the lock, so waiting doesn't log
+        // the same thing over and over.
+        match Self::new(path.clone()) {
+            Err(Error::AlreadyLocked) => (),
+            other => return other,
+        }
+
+        loop {
+            if cancel.is_cancelled() {
+                return Err(Error::Cancelled);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(Error::AlreadyLocked);
+            }
+
+            if !waiting {
+                debug!(path = %path.display(), "waiting for lock");
+                waiting = true;
+            }
+
+            std::thread::sleep(std::cmp::min(interval, deadline - now));
+            interval = std::cmp::min(interval * 2, max_interval);
+
+            match Inner::new(&path) {
+                Err(Error::AlreadyLocked) => (),
+                other => {
+                    return other.map(|inner| Self {
+                        inner: Some(inner),
+                        path,
+                    })
+                }
+            }
+        }
+    }
 }
 
 impl Drop for Lock {
     fn drop(&mut self) {
         if let Some(inner) = self.inner.take() {
-            drop(inner);
+            // Removed while still locked, so anyone who opened this file and
+            // locks it once it's released sees it's gone and tries again.
             std::fs::remove_file(&self.path).ok();
+            drop(inner);
         }
     }
 }
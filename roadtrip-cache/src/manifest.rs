@@ -0,0 +1,119 @@
+// Checksums of the files in an entry, kept next to them in the entry's
+// directory. Files that rotted on disk, or were only partly written, are
+// caught before anybody reads them.
+//
+// Each line is a file's CRC-32 in hex, then its name on disk. Files written
+// before entries had checksums just aren't listed, and are trusted as is.
+
+use crate::error::{self, EntryError};
+
+use snafu::IntoError;
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::io;
+use std::path::Path;
+
+use tokio::fs::{self, File};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+pub(crate) const NAME: &str = ".checksums";
+const TEMP_NAME: &str = ".checksums.tmp";
+
+// Checksums by file name.
+pub(crate) type Manifest = HashMap<String, u32>;
+
+fn parse_line(line: &str) -> Option<(String, u32)> {
+    let mut parts = line.splitn(2, ' ');
+
+    let sum = u32::from_str_radix(parts.next()?, 16).ok()?;
+    let name = parts.next()?;
+
+    Some((name.to_owned(), sum))
+}
+
+// Empty if the entry doesn't have a manifest yet.
+pub(crate) async fn load(dir: &Path) -> io::Result<Manifest> {
+    let text = match fs::read_to_string(dir.join(NAME)).await {
+        Ok(t) => t,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Ok(Manifest::new())
+        }
+        Err(e) => return Err(e),
+    };
+
+    text.lines()
+        .map(parse_line)
+        .collect::<Option<_>>()
+        .ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "malformed checksums")
+        })
+}
+
+pub(crate) async fn save(dir: &Path, manifest: &Manifest) -> io::Result<()> {
+    let mut names: Vec<_> = manifest.keys().collect();
+    names.sort();
+
+    let mut text = String::new();
+    for name in names {
+        writeln!(text, "{:08x} {}", manifest[name], name).unwrap();
+    }
+
+    let temp = dir.join(TEMP_NAME);
+    let mut file = File::create(&temp).await?;
+    file.write_all(text.as_bytes()).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    fs::rename(&temp, dir.join(NAME)).await
+}
+
+pub(crate) async fn checksum(path: &Path) -> io::Result<u32> {
+    let mut file = File::open(path).await?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0; 8192];
+
+    loop {
+        match file.read(&mut buf).await? {
+            0 => break,
+            n => hasher.update(&buf[..n]),
+        }
+    }
+
+    Ok(hasher.finalize())
+}
+
+// Checks every file listed in the entry's manifest against its checksum.
+pub(crate) async fn verify(dir: &Path) -> Result<(), EntryError> {
+    let manifest = match load(dir).await {
+        Ok(m) => m,
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            return Err(EntryError::Corrupt {
+                path: dir.join(NAME),
+            })
+        }
+        Err(e) => {
+            return Err(error::Verify {
+                path: dir.join(NAME),
+            }
+            .into_error(e))
+        }
+    };
+
+    for (name, expected) in manifest {
+        let path = dir.join(name);
+
+        match checksum(&path).await {
+            Ok(actual) if actual == expected => (),
+            Ok(_) => return Err(EntryError::Corrupt { path }),
+            // Listed files are renamed into place after they're listed, so
+            // a missing one never finished being written.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                return Err(EntryError::Corrupt { path })
+            }
+            Err(e) => return Err(error::Verify { path }.into_error(e)),
+        }
+    }
+
+    Ok(())
+}
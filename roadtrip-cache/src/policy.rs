@@ -0,0 +1,54 @@
+// Which entries go first when the cache has to make room.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::time::SystemTime;
+
+// What a policy gets to know about an entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    pub size: u64,
+    // When it was last inserted into or read.
+    pub used: SystemTime,
+    // How many times it's been read, plus one for being inserted. Entries
+    // the cache had to find by walking its directory start over at one.
+    pub uses: u64,
+}
+
+// Orders entries by how soon they should be evicted. The entry that compares
+// as the least goes first.
+pub trait EvictionPolicy: fmt::Debug + Send + Sync {
+    fn compare(&self, a: &Usage, b: &Usage) -> Ordering;
+}
+
+// Least recently used first. The default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lru;
+
+impl EvictionPolicy for Lru {
+    fn compare(&self, a: &Usage, b: &Usage) -> Ordering {
+        a.used.cmp(&b.used)
+    }
+}
+
+// Least often used first, so entries that keep getting read stick around even
+// if they haven't been read lately. Ties go to the least recently used.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lfu;
+
+impl EvictionPolicy for Lfu {
+    fn compare(&self, a: &Usage, b: &Usage) -> Ordering {
+        a.uses.cmp(&b.uses).then_with(|| a.used.cmp(&b.used))
+    }
+}
+
+// Biggest first, so making room evicts as few entries as possible. Ties go to
+// the least recently used.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SizeWeighted;
+
+impl EvictionPolicy for SizeWeighted {
+    fn compare(&self, a: &Usage, b: &Usage) -> Ordering {
+        b.size.cmp(&a.size).then_with(|| a.used.cmp(&b.used))
+    }
+}
@@ -0,0 +1,302 @@
+// Checks what the cache thinks it has against what's actually on disk, for
+// when something other than the cache has been at its directory. Opening a
+// cache only makes sure files are where entries go; this also catches entries
+// that were added, deleted, or changed behind its back since.
+
+use crate::error::{self, VerifyError};
+use crate::{file_name, is_temp, key_of, manifest, Cache};
+
+use filetime::FileTime;
+
+use snafu::{ensure, ResultExt};
+
+use std::collections::BTreeSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+
+use tracing::{info, warn};
+
+// Something about the cache that doesn't add up, and what `repair` does
+// about it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    // An entry on disk that the cache wasn't keeping track of. Repairing it
+    // starts counting it against the capacity.
+    Orphaned {
+        path: PathBuf,
+        size: u64,
+    },
+
+    // An entry the cache was keeping track of that's gone from disk.
+    // Repairing it forgets about it.
+    Missing {
+        path: PathBuf,
+    },
+
+    // An entry that's a different size, or has a different number of files,
+    // than the cache thought. Repairing it goes with what's on disk.
+    Mismatch {
+        path: PathBuf,
+        size: u64,
+        files: usize,
+        actual_size: u64,
+        actual_files: usize,
+    },
+
+    // Something where the cache wouldn't have put it, like a file outside
+    // of an entry, a directory inside of one, or a partial file nobody is
+    // writing anymore. Repairing it moves it into `.quarantine`.
+    Stray {
+        path: PathBuf,
+    },
+}
+
+// Whether to only report problems, or fix them too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Report,
+    Repair,
+}
+
+impl Cache {
+    // Looks for problems without changing anything. Like `entry`, this waits
+    // for anyone holding a vacant entry, one key at a time.
+    pub async fn verify(&self) -> Result<Vec<Problem>, VerifyError> {
+        self.check(Mode::Report).await
+    }
+
+    // Like `verify`, but fixes each problem as it's found, then evicts in
+    // case adopting entries put the cache over its limits.
+    pub async fn repair(&self) -> Result<Vec<Problem>, VerifyError> {
+        let problems = self.check(Mode::Repair).await?;
+
+        self.evict(None).await.context(error::Shrink)?;
+
+        info!(
+            root = %self.root.display(),
+            problems = problems.len(),
+            "repaired cache"
+        );
+
+        Ok(problems)
+    }
+
+    async fn check(&self, mode: Mode) -> Result<Vec<Problem>, VerifyError> {
+        ensure!(!self.is_read_only(), error::ReadOnlyVerify);
+
+        let mut problems = Vec::new();
+
+        let mut paths = self.scan(mode, &mut problems).await?;
+        paths.extend(self.items.snapshot().await.into_iter().map(|(p, _)| p));
+
+        for path in paths {
+            self.check_entry(path, mode, &mut problems).await?;
+        }
+
+        Ok(problems)
+    }
+
+    // Every directory on disk that could be an entry. Anything else where
+    // entries go is stray.
+    async fn scan(
+        &self,
+        mode: Mode,
+        problems: &mut Vec<Problem>,
+    ) -> Result<BTreeSet<PathBuf>, VerifyError> {
+        // Like `.lock` and `.quarantine`. Keys can't start with a dot.
+        let top = children(&self.root)
+            .await?
+            .into_iter()
+            .filter(|(p, _)| !file_name(p).starts_with('.'));
+
+        let mut candidates = Vec::new();
+
+        if self.fan_out {
+            for (path, is_dir) in top {
+                if is_dir {
+                    candidates.extend(children(&path).await?);
+                } else {
+                    self.stray(path, mode, problems).await?;
+                }
+            }
+        } else {
+            candidates.extend(top);
+        }
+
+        let mut entries = BTreeSet::new();
+
+        for (path, is_dir) in candidates {
+            if is_dir {
+                entries.insert(path);
+            } else {
+                self.stray(path, mode, problems).await?;
+            }
+        }
+
+        Ok(entries)
+    }
+
+    async fn check_entry(
+        &self,
+        path: PathBuf,
+        mode: Mode,
+        problems: &mut Vec<Problem>,
+    ) -> Result<(), VerifyError> {
+        let _guard = self.keys.lock(&path).await;
+        let found = problems.len();
+
+        // Looked up before going to disk, so an eviction that's underway
+        // finishes first.
+        let item = self.items.get(&path).await;
+
+        // Where the cache would have put an entry named like this one.
+        let home = key_of(&path).and_then(|k| self.to_path(&k).ok());
+
+        if home.as_ref() != Some(&path) {
+            if fs::metadata(&path).await.is_ok() {
+                self.stray(path.clone(), mode, problems).await?;
+            }
+
+            if mode == Mode::Repair {
+                self.items.remove(&path).await;
+                self.cool(&path);
+            }
+
+            return Ok(());
+        }
+
+        let actual = self.measure(&path, mode, problems).await?;
+
+        // Whatever's in memory might not match what's on disk anymore.
+        if mode == Mode::Repair && problems.len() > found {
+            self.cool(&path);
+        }
+
+        let problem = match (item, actual) {
+            (None, None) => return Ok(()),
+            (None, Some((size, files))) => {
+                if mode == Mode::Repair {
+                    let now = FileTime::now();
+                    self.items.add(path.clone(), size, files, now).await;
+                }
+
+                Problem::Orphaned { path, size }
+            }
+            (Some(_), None) => {
+                if mode == Mode::Repair {
+                    self.items.remove(&path).await;
+                    self.cool(&path);
+                }
+
+                Problem::Missing { path }
+            }
+            (Some(i), Some((size, files)))
+                if i.size != size || i.files != files =>
+            {
+                if mode == Mode::Repair {
+                    self.items.resize(&path, size, files).await;
+                    self.cool(&path);
+                }
+
+                Problem::Mismatch {
+                    path,
+                    size: i.size,
+                    files: i.files,
+                    actual_size: size,
+                    actual_files: files,
+                }
+            }
+            (Some(_), Some(_)) => return Ok(()),
+        };
+
+        warn!(problem = ?problem, "cache entry doesn't add up");
+        problems.push(problem);
+
+        Ok(())
+    }
+
+    // How many bytes the files in the entry at `path` add up to, and how
+    // many there are, or `None` if it isn't there. Anything in it besides
+    // finished files and checksums is stray.
+    async fn measure(
+        &self,
+        path: &Path,
+        mode: Mode,
+        problems: &mut Vec<Problem>,
+    ) -> Result<Option<(u64, usize)>, VerifyError> {
+        let contents = match children(path).await {
+            Ok(c) => c,
+            Err(VerifyError::Scan { source, .. })
+                if source.kind() == io::ErrorKind::NotFound =>
+            {
+                return Ok(None)
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut size = 0;
+        let mut files = 0;
+
+        for (child, is_dir) in contents {
+            let name = file_name(&child);
+
+            // Nobody can be writing to the entry while its key is locked, so
+            // a partial file was abandoned.
+            if is_dir || is_temp(&name) {
+                self.stray(child, mode, problems).await?;
+                continue;
+            }
+
+            if name == manifest::NAME {
+                continue;
+            }
+
+            let metadata =
+                fs::metadata(&child).await.with_context(|| error::Scan {
+                    path: child.clone(),
+                })?;
+
+            size += metadata.len();
+            files += 1;
+        }
+
+        Ok(Some((size, files)))
+    }
+
+    async fn stray(
+        &self,
+        path: PathBuf,
+        mode: Mode,
+        problems: &mut Vec<Problem>,
+    ) -> Result<(), VerifyError> {
+        warn!(path = %path.display(), "stray file in cache");
+
+        if mode == Mode::Repair {
+            self.quarantine(&path, FileTime::now())
+                .await
+                .with_context(|| error::Repair { path: path.clone() })?;
+        }
+
+        problems.push(Problem::Stray { path });
+        Ok(())
+    }
+}
+
+// Everything directly in `dir`, and whether each is a directory.
+async fn children(dir: &Path) -> Result<Vec<(PathBuf, bool)>, VerifyError> {
+    let scan = || error::Scan {
+        path: dir.to_owned(),
+    };
+
+    let mut children = Vec::new();
+    let mut dirs = fs::read_dir(dir).await.with_context(scan)?;
+
+    while let Some(child) = dirs.next_entry().await.with_context(scan)? {
+        let file_type = child.file_type().await.with_context(scan)?;
+        children.push((child.path(), file_type.is_dir()));
+    }
+
+    Ok(children)
+}
@@ -1,10 +1,15 @@
 mod util;
 
+use futures::StreamExt;
+
+use roadtrip_cache::error::{EntryError, InsertError, ReserveError};
+use roadtrip_cache::policy::{Lfu, SizeWeighted};
 use roadtrip_cache::Cache;
 
 use self::util::*;
 
 use std::collections::HashMap;
+use std::time::Duration;
 
 use tempfile::tempdir;
 
@@ -43,6 +48,30 @@ async fn insert_two() -> Result<(), Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn insert_from_reader_and_bytes() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 100).await?;
+
+    {
+        let entry = assert_vacant_entry(cache.entry("one").await?).await?;
+
+        let reader: &[u8] = b"hello earth";
+        entry.insert_from_reader("file0", reader).await?;
+        entry.insert_bytes("file1", b"hello mars").await?;
+    }
+
+    assert_eq(21, cache.size().await)?;
+
+    let expected = MapBuilder::new()
+        .insert("file0", b"hello earth")
+        .insert("file1", b"hello mars")
+        .build();
+    assert_entry_eq(cache.entry("one").await?, expected).await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn insert_one_at_capacity() -> Result<(), Error> {
     let root = tempdir()?;
@@ -285,6 +314,484 @@ async fn insert_evict_multiple_parts() -> Result<(), Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn insert_evict_skips_pinned() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 2).await?;
+
+    for key in &["one", "two"] {
+        let entry = assert_vacant_entry(cache.entry(key).await?).await?;
+
+        entry
+            .insert_with("file0", |mut f| async move {
+                f.write_all(b"0").await?;
+                Ok(())
+            })
+            .await?;
+    }
+
+    cache.pin("one").await?;
+
+    {
+        let entry = assert_vacant_entry(cache.entry("three").await?).await?;
+
+        entry
+            .insert_with("file0", |mut f| async move {
+                f.write_all(b"3").await?;
+                Ok(())
+            })
+            .await?;
+    }
+
+    {
+        assert_vacant_entry(cache.entry("two").await?).await?;
+
+        let mut expected: HashMap<_, &[u8]> = HashMap::new();
+        expected.insert("file0", b"0");
+        assert_entry_eq(cache.entry("one").await?, expected).await?;
+    }
+
+    cache.unpin("one").await?;
+
+    {
+        let entry = assert_vacant_entry(cache.entry("four").await?).await?;
+
+        entry
+            .insert_with("file0", |mut f| async move {
+                f.write_all(b"4").await?;
+                Ok(())
+            })
+            .await?;
+    }
+
+    // Reading `one` made `three` the oldest.
+    assert_vacant_entry(cache.entry("three").await?).await?;
+    assert_eq(2, cache.len().await)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn remove() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 100).await?;
+
+    {
+        let entry = assert_vacant_entry(cache.entry("one").await?).await?;
+
+        entry
+            .insert_with("file0", |mut f| async move {
+                f.write_all(b"hello earth").await?;
+                Ok(())
+            })
+            .await?;
+
+        entry
+            .insert_with("file1", |mut f| async move {
+                f.write_all(b"hello mars").await?;
+                Ok(())
+            })
+            .await?;
+    }
+
+    assert_eq(21, cache.remove("one").await?)?;
+    assert_eq(0, cache.len().await)?;
+    assert_eq(0, cache.size().await)?;
+    assert_eq(root.path().join("one").exists(), false)?;
+
+    assert_vacant_entry(cache.entry("one").await?).await?;
+
+    // Nothing left to free.
+    assert_eq(0, cache.remove("one").await?)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn stats() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 10).await?;
+
+    for key in &["one", "two"] {
+        let entry = assert_vacant_entry(cache.entry(key).await?).await?;
+
+        entry
+            .insert_with("file0", |mut f| async move {
+                f.write_all(b"12345").await?;
+                Ok(())
+            })
+            .await?;
+    }
+
+    assert_entry_eq(
+        cache.entry("one").await?,
+        MapBuilder::new().insert("file0", b"12345").build(),
+    )
+    .await?;
+
+    {
+        let entry = assert_vacant_entry(cache.entry("three").await?).await?;
+
+        entry
+            .insert_with("file0", |mut f| async move {
+                f.write_all(b"1234").await?;
+                Ok(())
+            })
+            .await?;
+    }
+
+    let stats = cache.stats().await;
+    assert_eq(1, stats.hits)?;
+    assert_eq(3, stats.misses)?;
+    assert_eq(14, stats.inserted)?;
+    assert_eq(5, stats.evicted)?;
+    assert_eq(9, stats.size)?;
+    assert_eq(10, stats.capacity)?;
+
+    if (stats.utilization() - 0.9).abs() > f64::EPSILON {
+        return Error::other("cache should be 90% full");
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn set_capacity() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 10).await?;
+
+    for idx in 0..5 {
+        let key = format!("entry{}", idx);
+        let entry = assert_vacant_entry(cache.entry(&key).await?).await?;
+
+        entry
+            .insert_with("file0", |mut f| async move {
+                f.write_all(b"01").await?;
+                Ok(())
+            })
+            .await?;
+    }
+
+    cache.pin("entry0").await?;
+
+    assert_eq(6, cache.set_capacity(5).await?)?;
+    assert_eq(5, cache.capacity().await)?;
+    assert_eq(2, cache.len().await)?;
+    assert_eq(4, cache.size().await)?;
+
+    // entry0 is pinned, so the next oldest went instead.
+    for key in &["entry1", "entry2", "entry3"] {
+        assert_vacant_entry(cache.entry(key).await?).await?;
+    }
+
+    assert_eq(0, cache.shrink_to_fit().await?)?;
+
+    cache.unpin("entry0").await?;
+    assert_eq(2, cache.set_capacity(3).await?)?;
+    assert_vacant_entry(cache.entry("entry0").await?).await?;
+
+    assert_eq(0, cache.set_capacity(100).await?)?;
+    assert_eq(1, cache.len().await)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn clear() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 100).await?;
+
+    for key in &["one", "two", "three"] {
+        let entry = assert_vacant_entry(cache.entry(key).await?).await?;
+
+        entry
+            .insert_with("file0", |mut f| async move {
+                f.write_all(b"hello").await?;
+                Ok(())
+            })
+            .await?;
+    }
+
+    cache.pin("two").await?;
+
+    assert_eq(15, cache.clear().await?)?;
+    assert_eq(0, cache.len().await)?;
+    assert_eq(0, cache.size().await)?;
+
+    for key in &["one", "two", "three"] {
+        assert_vacant_entry(cache.entry(key).await?).await?;
+    }
+
+    // Only the lock is left behind.
+    let mut names = Vec::new();
+    let mut dir = tokio::fs::read_dir(root.path()).await?;
+    while let Some(entry) = dir.next_entry().await? {
+        names.push(entry.file_name());
+    }
+    assert_eq(names, vec![std::ffi::OsString::from(".lock")])?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn insert_evict_max_entries() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::builder(root.path(), 100)
+        .max_entries(2)
+        .open()
+        .await?;
+
+    for key in &["one", "two", "three"] {
+        let entry = assert_vacant_entry(cache.entry(key).await?).await?;
+
+        entry
+            .insert_with("file0", |mut f| async move {
+                f.write_all(b"0").await?;
+                Ok(())
+            })
+            .await?;
+
+        // More files in the same entry don't count against the limit.
+        entry
+            .insert_with("file1", |mut f| async move {
+                f.write_all(b"1").await?;
+                Ok(())
+            })
+            .await?;
+    }
+
+    assert_eq(2, cache.len().await)?;
+    assert_eq(4, cache.size().await)?;
+    assert_vacant_entry(cache.entry("one").await?).await?;
+
+    let expected = MapBuilder::new()
+        .insert("file0", b"0")
+        .insert("file1", b"1")
+        .build();
+    assert_entry_eq(cache.entry("two").await?, expected.clone()).await?;
+    assert_entry_eq(cache.entry("three").await?, expected).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn insert_evict_lfu() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::builder(root.path(), 3)
+        .eviction_policy(Lfu)
+        .open()
+        .await?;
+
+    for key in &["one", "two", "three"] {
+        let entry = assert_vacant_entry(cache.entry(key).await?).await?;
+        entry.insert_bytes("file0", b"0").await?;
+    }
+
+    // "one" is read the most, but least recently.
+    let expected = MapBuilder::new().insert("file0", b"0").build();
+    for key in &["one", "one", "two", "three"] {
+        assert_entry_eq(cache.entry(key).await?, expected.clone()).await?;
+    }
+
+    let entry = assert_vacant_entry(cache.entry("four").await?).await?;
+    entry.insert_bytes("file0", b"0").await?;
+    drop(entry);
+
+    assert_eq(3, cache.len().await)?;
+    assert_vacant_entry(cache.entry("two").await?).await?;
+    assert_entry_eq(cache.entry("one").await?, expected).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn insert_evict_size_weighted() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::builder(root.path(), 10)
+        .eviction_policy(SizeWeighted)
+        .open()
+        .await?;
+
+    let files: [(&str, &[u8]); 4] = [
+        ("one", b"11"),
+        ("two", b"22222"),
+        ("three", b"33"),
+        ("four", b"44"),
+    ];
+
+    for (key, contents) in &files {
+        let entry = assert_vacant_entry(cache.entry(key).await?).await?;
+        entry.insert_bytes("file0", contents).await?;
+    }
+
+    assert_eq(3, cache.len().await)?;
+    assert_eq(6, cache.size().await)?;
+    assert_vacant_entry(cache.entry("two").await?).await?;
+
+    let expected = MapBuilder::new().insert("file0", b"11").build();
+    assert_entry_eq(cache.entry("one").await?, expected).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reserve() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 10).await?;
+
+    for key in &["one", "two", "three"] {
+        let entry = assert_vacant_entry(cache.entry(key).await?).await?;
+        entry.insert_bytes("file0", b"123").await?;
+    }
+
+    match cache.reserve(11).await {
+        Err(ReserveError::NoRoom { bytes: 11 }) => (),
+        _ => return Error::other("expected NoRoom"),
+    }
+
+    // Nothing is evicted if there could never be room.
+    assert_eq(3, cache.len().await)?;
+
+    let reservation = cache.reserve(5).await?;
+    assert_eq(5, cache.reserved().await)?;
+    assert_eq(1, cache.len().await)?;
+    assert_vacant_entry(cache.entry("two").await?).await?;
+
+    // Inserting uses up the reservation instead of evicting what's left.
+    let mut entry = assert_vacant_entry(cache.entry("four").await?).await?;
+    entry.use_reservation(reservation);
+    entry.insert_bytes("file0", b"12").await?;
+    entry.insert_bytes("file1", b"12").await?;
+
+    assert_eq(1, cache.reserved().await)?;
+    assert_eq(2, cache.len().await)?;
+    assert_eq(7, cache.size().await)?;
+
+    // Whatever wasn't used is given back.
+    drop(entry);
+    assert_eq(0, cache.reserved().await)?;
+
+    let expected = MapBuilder::new().insert("file0", b"123").build();
+    assert_entry_eq(cache.entry("three").await?, expected).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn unfinished_inserts_are_removed() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 100).await?;
+
+    // Dropped partway through writing, like when the viewer exits.
+    {
+        let entry = assert_vacant_entry(cache.entry("one").await?).await?;
+        let insert = entry.insert_with("file0", |mut f| async move {
+            f.write_all(b"hello").await?;
+            futures::future::pending().await
+        });
+
+        let timeout = Duration::from_millis(50);
+        if tokio::time::timeout(timeout, insert).await.is_ok() {
+            return Error::other("insert should never finish");
+        }
+    }
+
+    assert_eq(root.path().join("one").exists(), false)?;
+    assert_eq(0, cache.size().await)?;
+    assert_vacant_entry(cache.entry("one").await?).await?;
+
+    // Failing leaves the rest of the entry alone.
+    {
+        let entry = assert_vacant_entry(cache.entry("two").await?).await?;
+        entry.insert_bytes("file0", b"hello").await?;
+
+        let result = entry
+            .insert_with("file1", |mut f| async move {
+                f.write_all(b"hello").await?;
+                Err(std::io::ErrorKind::Other.into())
+            })
+            .await;
+
+        match result {
+            Err(InsertError::Write { .. }) => (),
+            _ => return Error::other("expected Write"),
+        }
+    }
+
+    assert_eq(root.path().join("two/file1.tmp").exists(), false)?;
+    assert_eq(5, cache.size().await)?;
+
+    let expected = MapBuilder::new().insert("file0", b"hello").build();
+    assert_entry_eq(cache.entry("two").await?, expected).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn arbitrary_keys() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 100).await?;
+
+    let keys = [
+        "abc123",
+        "a-b_c",
+        "hello world",
+        ".hidden",
+        "ünïcode",
+        "%41",
+        "A",
+    ];
+
+    for key in &keys {
+        let entry = assert_vacant_entry(cache.entry(key).await?).await?;
+        entry.insert_bytes("file0", key.as_bytes()).await?;
+    }
+
+    for key in &keys {
+        let expected =
+            MapBuilder::new().insert("file0", key.as_bytes()).build();
+        assert_entry_eq(cache.entry(key).await?, expected).await?;
+    }
+
+    let mut listed: Vec<_> = cache.keys().collect().await;
+    listed.sort();
+    let mut expected: Vec<_> = keys.iter().map(|k| k.to_string()).collect();
+    expected.sort();
+    assert_eq(listed, expected)?;
+
+    // Keys that were already safe are stored as they are.
+    assert_eq(root.path().join("abc123").is_dir(), true)?;
+    assert_eq(root.path().join("a%2Db%5Fc").is_dir(), true)?;
+
+    match cache.entry("").await {
+        Err(EntryError::InvalidKey) => (),
+        _ => return Error::other("expected InvalidKey"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn on_evict() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 2).await?;
+    let evicted = cache.on_evict();
+
+    for key in &["one", "two", "three", "four"] {
+        let entry = assert_vacant_entry(cache.entry(key).await?).await?;
+        entry.insert_bytes("file0", b"0").await?;
+    }
+
+    cache.set_capacity(1).await?;
+    cache.remove("four").await?;
+    drop(cache);
+
+    let evicted: Vec<_> = evicted.collect().await;
+    assert_eq(evicted, vec!["one", "two", "three"])?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn lock() -> Result<(), Error> {
     let root = tempdir()?;
@@ -299,3 +806,107 @@ async fn lock() -> Result<(), Error> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn concurrent_miss_waits_for_insert() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 100).await?;
+
+    let fill = async {
+        let entry = assert_vacant_entry(cache.entry("one").await?).await?;
+
+        // Gives `wait` plenty of time to look while the entry is empty.
+        tokio::time::delay_for(Duration::from_millis(100)).await;
+
+        entry
+            .insert_with("file0", |mut f| async move {
+                f.write_all(b"hello earth").await?;
+                Ok(())
+            })
+            .await?;
+
+        Ok::<_, Error>(())
+    };
+
+    // Asks while `fill` is holding the vacant entry.
+    let wait = async { cache.entry("one").await };
+
+    let (filled, waited) = tokio::join!(fill, wait);
+    filled?;
+
+    let mut expected: HashMap<_, &[u8]> = HashMap::new();
+    expected.insert("file0", b"hello earth");
+    assert_entry_eq(waited?, expected).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn concurrent_miss_after_abandoned_insert() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 100).await?;
+
+    let abandon = async {
+        let entry = assert_vacant_entry(cache.entry("one").await?).await?;
+        drop(entry);
+        Ok::<_, Error>(())
+    };
+
+    let wait = async { cache.entry("one").await };
+
+    let (abandoned, waited) = tokio::join!(abandon, wait);
+    abandoned?;
+
+    // Nothing was inserted, so it's still up for grabs.
+    let entry = assert_vacant_entry(waited?).await?;
+
+    entry
+        .insert_with("file0", |mut f| async move {
+            f.write_all(b"hello mars").await?;
+            Ok(())
+        })
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test(threaded_scheduler)]
+async fn concurrent_inserts_stay_within_capacity() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 20).await?;
+
+    let inserts = (0..64).map(|idx| {
+        let cache = &cache;
+        async move {
+            let key = format!("entry{}", idx);
+            let entry = assert_vacant_entry(cache.entry(&key).await?).await?;
+
+            entry
+                .insert_with("file0", |mut f| async move {
+                    f.write_all(b"0").await?;
+                    Ok(())
+                })
+                .await?;
+
+            Ok::<_, Error>(())
+        }
+    });
+
+    for result in futures::future::join_all(inserts).await {
+        result?;
+    }
+
+    assert_eq(20, cache.len().await)?;
+    assert_eq(20, cache.size().await)?;
+
+    let mut on_disk = 0;
+    let mut dir = tokio::fs::read_dir(root.path()).await?;
+    while let Some(entry) = dir.next_entry().await? {
+        if entry.file_type().await?.is_dir() {
+            on_disk += 1;
+        }
+    }
+    assert_eq(20, on_disk)?;
+
+    Ok(())
+}
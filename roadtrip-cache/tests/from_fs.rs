@@ -2,12 +2,18 @@ mod util;
 
 use filetime::FileTime;
 
-use roadtrip_cache::Cache;
+use roadtrip_cache::error::{EntryError, Error as CacheError, InsertError};
+use roadtrip_cache::verify::Problem;
+use roadtrip_cache::{Cache, Entry, EntryMetadata};
 
 use self::util::*;
 
 use tempfile::{tempdir, TempDir};
 
+use futures::StreamExt;
+
+use std::time::{Duration, SystemTime};
+
 use tokio::fs::{self, File};
 use tokio::io::AsyncWriteExt;
 
@@ -64,14 +70,16 @@ async fn evict_one() -> Result<(), Error> {
     let cache = Cache::new(dir.path(), 50).await?;
     assert_eq(cache.len().await, 3)?;
 
-    let entry3 = assert_vacant_entry(cache.entry("entry3").await?).await?;
+    {
+        let entry3 = assert_vacant_entry(cache.entry("entry3").await?).await?;
 
-    entry3
-        .insert_with("f4", |mut f| async move {
-            f.write_all(b"goodbye world").await?;
-            Ok(())
-        })
-        .await?;
+        entry3
+            .insert_with("f4", |mut f| async move {
+                f.write_all(b"goodbye world").await?;
+                Ok(())
+            })
+            .await?;
+    }
 
     assert_eq(cache.len().await, 3)?;
     assert_eq(cache.size().await, 35)?;
@@ -91,3 +99,531 @@ async fn evict_one() -> Result<(), Error> {
     assert_entry_eq(cache.entry("entry3").await?, entry3).await?;
     Ok(())
 }
+
+#[tokio::test]
+async fn reopen_from_index() -> Result<(), Error> {
+    let dir = populate().await?;
+
+    {
+        let cache = Cache::new(dir.path(), 50).await?;
+
+        // Makes entry0 the most recently used.
+        let entry0 = MapBuilder::new()
+            .insert("f0", b"hello world")
+            .insert("f1", b"hello world")
+            .build();
+        assert_entry_eq(cache.entry("entry0").await?, entry0).await?;
+    }
+
+    assert_eq(dir.path().join(".index").exists(), true)?;
+
+    // A walk would think entry0 is the oldest again, but the index knows
+    // better.
+    let old = FileTime::from_unix_time(1, 0);
+    filetime::set_file_mtime(dir.path().join("entry0/f0"), old)?;
+    filetime::set_file_mtime(dir.path().join("entry0/f1"), old)?;
+
+    let cache = Cache::new(dir.path(), 50).await?;
+    assert_eq(cache.len().await, 3)?;
+    assert_eq(cache.size().await, 44)?;
+
+    // Until it's closed again, the index could go stale.
+    assert_eq(dir.path().join(".index").exists(), false)?;
+
+    assert_vacant_entry(cache.entry("entry3").await?)
+        .await?
+        .insert_with("f4", |mut f| async move {
+            f.write_all(b"goodbye world").await?;
+            Ok(())
+        })
+        .await?;
+
+    assert_vacant_entry(cache.entry("entry1").await?).await?;
+    assert_eq(cache.len().await, 3)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reopen_with_stale_index() -> Result<(), Error> {
+    let dir = populate().await?;
+
+    drop(Cache::new(dir.path(), 50).await?);
+
+    fs::create_dir(dir.path().join("entry3")).await?;
+    fs::write(dir.path().join("entry3/f4"), b"goodbye world").await?;
+
+    let cache = Cache::new(dir.path(), 50).await?;
+    assert_eq(cache.len().await, 4)?;
+    assert_eq(cache.size().await, 57)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn iter() -> Result<(), Error> {
+    let dir = populate().await?;
+
+    let cache = Cache::new(dir.path(), 50).await?;
+
+    let entries: Vec<_> = cache.iter().collect().await;
+    let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+
+    assert_eq(
+        entries,
+        vec![
+            ("entry0".to_owned(), 22, at(2)),
+            ("entry1".to_owned(), 11, at(3)),
+            ("entry2".to_owned(), 11, at(4)),
+        ],
+    )?;
+
+    // Reading an entry makes it the most recently used.
+    cache.entry("entry0").await?;
+
+    let keys: Vec<_> = cache.keys().collect().await;
+    assert_eq(keys, vec!["entry1", "entry2", "entry0"])?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn metadata() -> Result<(), Error> {
+    let dir = populate().await?;
+    let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+
+    {
+        let cache = Cache::new(dir.path(), 100).await?;
+
+        let expected = EntryMetadata {
+            size: 22,
+            last_used: at(2),
+            file_count: 2,
+        };
+        assert_eq(cache.metadata("entry0").await, Some(expected))?;
+        assert_eq(cache.metadata("entry9").await, None)?;
+
+        let entry0 = match cache.entry("entry0").await? {
+            Entry::Occupied(o) => o,
+            Entry::Vacant(_) => return Error::other("expected occupied entry"),
+        };
+
+        entry0
+            .insert_with("f4", |mut f| async move {
+                f.write_all(b"goodbye").await?;
+                Ok(())
+            })
+            .await?;
+
+        entry0
+            .replace_with("f0", |mut f| async move {
+                f.write_all(b"hi").await?;
+                Ok(())
+            })
+            .await?;
+    }
+
+    // Remembered in the index, too.
+    let cache = Cache::new(dir.path(), 100).await?;
+    let metadata = match cache.metadata("entry0").await {
+        Some(m) => m,
+        None => return Error::other("expected metadata"),
+    };
+    assert_eq(metadata.size, 20)?;
+    assert_eq(metadata.file_count, 3)?;
+    assert_eq(metadata.last_used > at(2), true)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn add_and_replace_files() -> Result<(), Error> {
+    let dir = populate().await?;
+
+    let cache = Cache::new(dir.path(), 100).await?;
+
+    let entry0 = match cache.entry("entry0").await? {
+        Entry::Occupied(o) => o,
+        Entry::Vacant(_) => return Error::other("expected occupied entry"),
+    };
+
+    entry0
+        .insert_with("f4", |mut f| async move {
+            f.write_all(b"goodbye").await?;
+            Ok(())
+        })
+        .await?;
+
+    entry0
+        .replace_with("f0", |mut f| async move {
+            f.write_all(b"hi").await?;
+            Ok(())
+        })
+        .await?;
+
+    // Adding a file that's already there needs `replace_with`.
+    let result = entry0
+        .insert_with("f1", |mut f| async move {
+            f.write_all(b"hi").await?;
+            Ok(())
+        })
+        .await;
+
+    if result.is_ok() {
+        return Error::other("insert_with shouldn't overwrite files");
+    }
+
+    drop(entry0);
+
+    assert_eq(cache.size().await, 42)?;
+
+    let expected = MapBuilder::new()
+        .insert("f0", b"hi")
+        .insert("f1", b"hello world")
+        .insert("f4", b"goodbye")
+        .build();
+    assert_entry_eq(cache.entry("entry0").await?, expected).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn compressed_files() -> Result<(), Error> {
+    let dir = tempdir()?;
+    let data = vec![b'a'; 1000];
+
+    {
+        let cache = Cache::builder(dir.path(), 10_000)
+            .compress(3)
+            .open()
+            .await?;
+
+        let entry = assert_vacant_entry(cache.entry("entry0").await?).await?;
+        let file = entry.insert_bytes("f0", &data).await?;
+        assert_eq(file.is_compressed(), true)?;
+        drop(entry);
+
+        // What's stored, and counted, is much smaller than what was inserted.
+        assert_eq(cache.size().await < 100, true)?;
+        assert_eq(
+            fs::metadata(dir.path().join("entry0/f0.zst")).await?.len(),
+            cache.size().await,
+        )?;
+
+        let expected = MapBuilder::new().insert("f0", &data).build();
+        assert_entry_eq(cache.entry("entry0").await?, expected).await?;
+    }
+
+    // Files stay readable after the cache stops compressing new ones.
+    let cache = Cache::new(dir.path(), 10_000).await?;
+
+    let expected = MapBuilder::new().insert("f0", &data).build();
+    assert_entry_eq(cache.entry("entry0").await?, expected).await?;
+
+    let entry0 = match cache.entry("entry0").await? {
+        Entry::Occupied(o) => o,
+        Entry::Vacant(_) => return Error::other("expected occupied entry"),
+    };
+
+    let result = entry0
+        .insert_with("f1.zst", |mut f| async move {
+            f.write_all(b"hi").await?;
+            Ok(())
+        })
+        .await;
+
+    // Only the cache gets to decide what's compressed.
+    match result {
+        Err(InsertError::InvalidName) => (),
+        _ => return Error::other("expected InvalidName"),
+    }
+
+    let file = entry0
+        .replace_with("f0", |mut f| async move {
+            f.write_all(b"hi").await?;
+            Ok(())
+        })
+        .await?;
+    assert_eq(file.is_compressed(), false)?;
+    drop(entry0);
+
+    assert_eq(cache.size().await, 2)?;
+
+    let expected = MapBuilder::new().insert("f0", b"hi").build();
+    assert_entry_eq(cache.entry("entry0").await?, expected).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn corrupt_entries_are_discarded() -> Result<(), Error> {
+    let dir = tempdir()?;
+    let cache = Cache::new(dir.path(), 100).await?;
+
+    for key in &["entry0", "entry1"] {
+        let entry = assert_vacant_entry(cache.entry(key).await?).await?;
+        entry.insert_bytes("f0", b"hello").await?;
+        entry.insert_bytes("f1", b"world").await?;
+    }
+
+    // One file rots, and another goes missing.
+    fs::write(dir.path().join("entry0/f0"), b"jello").await?;
+    fs::remove_file(dir.path().join("entry1/f1")).await?;
+
+    for key in &["entry0", "entry1"] {
+        let entry = assert_vacant_entry(cache.entry(key).await?).await?;
+
+        match entry.corruption() {
+            Some(EntryError::Corrupt { .. }) => (),
+            _ => return Error::other("expected Corrupt"),
+        }
+
+        assert_eq(fs::metadata(dir.path().join(key)).await.is_err(), true)?;
+    }
+
+    assert_eq(cache.len().await, 0)?;
+    assert_eq(cache.size().await, 0)?;
+
+    // Entries that haven't been tampered with are fine.
+    let entry = assert_vacant_entry(cache.entry("entry0").await?).await?;
+    assert_eq(entry.corruption().is_none(), true)?;
+    entry.insert_bytes("f0", b"hello").await?;
+    drop(entry);
+
+    let expected = MapBuilder::new().insert("f0", b"hello").build();
+    assert_entry_eq(cache.entry("entry0").await?, expected).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn unexpected_directories() -> Result<(), Error> {
+    let dir = tempdir()?;
+    let cache = Cache::new(dir.path(), 100).await?;
+
+    // Copied in by hand after the cache was opened.
+    fs::create_dir(dir.path().join("entry0")).await?;
+    fs::write(dir.path().join("entry0/f0"), b"hello").await?;
+
+    let expected = MapBuilder::new().insert("f0", b"hello").build();
+    assert_entry_eq(cache.entry("entry0").await?, expected).await?;
+    assert_eq(cache.len().await, 1)?;
+    assert_eq(cache.size().await, 5)?;
+
+    // Not shaped like an entry at all.
+    fs::create_dir_all(dir.path().join("entry1/nested")).await?;
+    fs::write(dir.path().join("entry1/nested/f1"), b"world").await?;
+
+    match cache.entry("entry1").await {
+        Err(EntryError::Unexpected { .. }) => (),
+        _ => return Error::other("expected Unexpected"),
+    }
+
+    assert_eq(cache.len().await, 1)?;
+    assert_vacant_entry(cache.entry("entry1").await?).await?;
+
+    let mut aside = fs::read_dir(dir.path().join(".quarantine")).await?;
+    assert_eq(aside.next().await.is_some(), true)?;
+
+    // Whatever was moved aside doesn't get in the way of opening again.
+    drop(cache);
+    fs::remove_file(dir.path().join(".index")).await?;
+    let cache = Cache::new(dir.path(), 100).await?;
+    assert_eq(cache.len().await, 1)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fan_out() -> Result<(), Error> {
+    let dir = tempdir()?;
+    let expected = MapBuilder::new().insert("f0", b"hello").build();
+
+    {
+        let cache = Cache::builder(dir.path(), 100).fan_out().open().await?;
+        let entry = assert_vacant_entry(cache.entry("abcdef").await?).await?;
+        entry.insert_bytes("f0", b"hello").await?;
+    }
+
+    assert_eq(dir.path().join("ab/abcdef/f0").exists(), true)?;
+
+    // Once from the index, and once by walking the directory.
+    for _ in 0..2 {
+        let cache = Cache::builder(dir.path(), 100).fan_out().open().await?;
+        assert_eq(cache.len().await, 1)?;
+        assert_eq(cache.size().await, 5)?;
+        assert_entry_eq(cache.entry("abcdef").await?, expected.clone()).await?;
+        drop(cache);
+
+        fs::remove_file(dir.path().join(".index")).await?;
+    }
+
+    let reader = Cache::open_read_only(dir.path()).await?;
+    assert_entry_eq(reader.entry("abcdef").await?, expected).await?;
+
+    match Cache::new(dir.path(), 100).await {
+        Err(CacheError::Layout { fan_out: false }) => (),
+        _ => return Error::other("expected Layout"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn partial_files_are_removed() -> Result<(), Error> {
+    let dir = populate().await?;
+
+    // As if inserts were interrupted, one into an existing entry and one
+    // into a new entry.
+    fs::write(dir.path().join("entry0/f5.tmp"), b"hello").await?;
+    fs::create_dir(dir.path().join("entry3")).await?;
+    fs::write(dir.path().join("entry3/f6.tmp"), b"hello").await?;
+
+    let cache = Cache::new(dir.path(), 50).await?;
+    assert_eq(cache.size().await, 44)?;
+
+    assert_eq(dir.path().join("entry0/f5.tmp").exists(), false)?;
+    assert_eq(dir.path().join("entry3/f6.tmp").exists(), false)?;
+
+    let entry0 = MapBuilder::new()
+        .insert("f0", b"hello world")
+        .insert("f1", b"hello world")
+        .build();
+    assert_entry_eq(cache.entry("entry0").await?, entry0).await?;
+
+    // Temporary names are reserved.
+    let entry4 = assert_vacant_entry(cache.entry("entry4").await?).await?;
+    match entry4.insert_bytes("f7.tmp", b"hello").await {
+        Err(InsertError::InvalidName) => (),
+        _ => return Error::other("temporary names should be rejected"),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reads_touch_files() -> Result<(), Error> {
+    let dir = populate().await?;
+
+    let cache = Cache::new(dir.path(), 50).await?;
+
+    let entry1 = MapBuilder::new().insert("f2", b"hello world").build();
+    assert_entry_eq(cache.entry("entry1").await?, entry1).await?;
+
+    // Touching happens in the background, so it might not be done yet.
+    cache.flush().await;
+
+    let metadata = fs::metadata(dir.path().join("entry1/f2")).await?;
+    let mtime = FileTime::from_last_modification_time(&metadata);
+    assert_eq(mtime > FileTime::from_unix_time(3, 0), true)?;
+
+    // Entries that weren't read are left alone.
+    let metadata = fs::metadata(dir.path().join("entry2/f3")).await?;
+    let mtime = FileTime::from_last_modification_time(&metadata);
+    assert_eq(mtime, FileTime::from_unix_time(4, 0))?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn verify_and_repair() -> Result<(), Error> {
+    let dir = populate().await?;
+    let cache = Cache::new(dir.path(), 100).await?;
+
+    // Everything the cache can't see coming, done behind its back.
+    fs::remove_dir_all(dir.path().join("entry1")).await?;
+    fs::write(dir.path().join("entry2/f4"), b"hi").await?;
+    fs::create_dir(dir.path().join("entry3")).await?;
+    fs::write(dir.path().join("entry3/f5"), b"hello").await?;
+    fs::create_dir(dir.path().join("entry0/nested")).await?;
+    fs::write(dir.path().join("loose"), b"hello").await?;
+
+    let mut expected = vec![
+        Problem::Stray {
+            path: dir.path().join("loose"),
+        },
+        Problem::Stray {
+            path: dir.path().join("entry0/nested"),
+        },
+        Problem::Missing {
+            path: dir.path().join("entry1"),
+        },
+        Problem::Mismatch {
+            path: dir.path().join("entry2"),
+            size: 11,
+            files: 1,
+            actual_size: 13,
+            actual_files: 2,
+        },
+        Problem::Orphaned {
+            path: dir.path().join("entry3"),
+            size: 5,
+        },
+    ];
+
+    let sort = |p: &mut Vec<Problem>| p.sort_by_key(|p| format!("{:?}", p));
+    sort(&mut expected);
+
+    // Only looking doesn't change anything.
+    let mut problems = cache.verify().await?;
+    sort(&mut problems);
+    assert_eq(&problems, &expected)?;
+    assert_eq(cache.size().await, 44)?;
+
+    let mut problems = cache.repair().await?;
+    sort(&mut problems);
+    assert_eq(&problems, &expected)?;
+
+    assert_eq(cache.verify().await?, vec![])?;
+    assert_eq(cache.len().await, 3)?;
+    assert_eq(cache.size().await, 40)?;
+    assert_eq(dir.path().join("loose").exists(), false)?;
+    assert_eq(dir.path().join("entry0/nested").exists(), false)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn hot_tier() -> Result<(), Error> {
+    let dir = populate().await?;
+    let cache = Cache::builder(dir.path(), 100).hot_tier(20).open().await?;
+
+    let entry0 = MapBuilder::new()
+        .insert("f0", b"hello world")
+        .insert("f1", b"hello world")
+        .build();
+    let entry1 = MapBuilder::new().insert("f2", b"hello world").build();
+
+    assert_entry_eq(cache.entry("entry0").await?, entry0.clone()).await?;
+    assert_entry_eq(cache.entry("entry1").await?, entry1.clone()).await?;
+
+    // Changed behind the cache's back, so only what's in memory still has
+    // the old contents. `entry0` is too big to have been kept.
+    fs::write(dir.path().join("entry0/f0"), b"HELLO WORLD").await?;
+    fs::write(dir.path().join("entry1/f2"), b"HELLO WORLD").await?;
+
+    let changed = MapBuilder::new()
+        .insert("f0", b"HELLO WORLD")
+        .insert("f1", b"hello world")
+        .build();
+    assert_entry_eq(cache.entry("entry0").await?, changed).await?;
+    assert_entry_eq(cache.entry("entry1").await?, entry1).await?;
+    assert_eq(cache.stats().await.hits, 4)?;
+
+    // Inserting through the cache forgets what's in memory.
+    match cache.entry("entry1").await? {
+        Entry::Occupied(o) => {
+            o.insert_with("f3", |mut f| async move { f.write_all(b"hi").await })
+                .await?
+        }
+        _ => return Error::other("expected occupied entry"),
+    };
+
+    let entry1 = MapBuilder::new()
+        .insert("f2", b"HELLO WORLD")
+        .insert("f3", b"hi")
+        .build();
+    assert_entry_eq(cache.entry("entry1").await?, entry1).await?;
+
+    Ok(())
+}
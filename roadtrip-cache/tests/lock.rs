@@ -0,0 +1,285 @@
+mod util;
+
+use roadtrip_cache::Cache;
+
+use roadtrip_cancel::Token;
+
+use self::util::*;
+
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use tempfile::tempdir;
+
+use tokio::fs;
+
+#[tokio::test]
+async fn lock_records_owner() -> Result<(), Error> {
+    let root = tempdir()?;
+    let _cache = Cache::new(root.path(), 10).await?;
+
+    let text = fs::read_to_string(root.path().join(".lock")).await?;
+    let pid = text.split_whitespace().next().unwrap_or_default();
+
+    assert_eq(pid, std::process::id().to_string().as_str())
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn reopen_after_owner_is_killed() -> Result<(), Error> {
+    let root = tempdir()?;
+    let lock = root.path().join(".lock");
+
+    // Another process takes the lock, then dies without cleaning up.
+    let script = format!(
+        "exec 9>>'{}'; flock 9; echo locked; exec sleep 30",
+        lock.display()
+    );
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(&script)
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap()).read_line(&mut line)?;
+    fs::write(&lock, format!("{}\n", child.id())).await?;
+
+    match Cache::new(root.path(), 10).await {
+        Err(roadtrip_cache::error::Error::AlreadyLocked) => (),
+        _ => return Error::other("cache dir should have been locked"),
+    }
+
+    child.kill()?;
+    child.wait()?;
+
+    let cache = Cache::new(root.path(), 10).await?;
+
+    let text = fs::read_to_string(&lock).await?;
+    let pid = text.split_whitespace().next().unwrap_or_default();
+    assert_eq(pid, std::process::id().to_string().as_str())?;
+
+    drop(cache);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reopen_over_leftover_owner() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache0 = Cache::new(root.path(), 10).await?;
+    drop(cache0);
+
+    // A lock file naming someone else, with nobody holding the lock.
+    let owner = format!("{} 1\n", std::process::id());
+    fs::write(root.path().join(".lock"), owner).await?;
+
+    let cache1 = Cache::new(root.path(), 10).await?;
+    drop(cache1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn keep_lock_from_live_owner() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache0 = Cache::new(root.path(), 10).await?;
+
+    fs::write(root.path().join(".lock"), b"garbage").await?;
+
+    match Cache::new(root.path(), 10).await {
+        Err(roadtrip_cache::error::Error::AlreadyLocked) => (),
+        _ => return Error::other("cache dir should have been locked"),
+    }
+
+    drop(cache0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn wait_for_lock_release() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache0 = Cache::new(root.path(), 10).await?;
+
+    let holder = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(200));
+        drop(cache0);
+    });
+
+    let cache1 = Cache::with_lock_timeout(
+        root.path(),
+        10,
+        Duration::from_secs(10),
+        Duration::from_millis(20),
+    )
+    .await?;
+
+    holder.join().unwrap();
+
+    // The lock file must still be ours after the old owner cleaned up.
+    let text = fs::read_to_string(root.path().join(".lock")).await?;
+    let pid = text.split_whitespace().next().unwrap_or_default();
+    assert_eq(pid, std::process::id().to_string().as_str())?;
+
+    drop(cache1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn lock_timeout_expires() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache0 = Cache::new(root.path(), 10).await?;
+
+    let start = Instant::now();
+    let timeout = Duration::from_millis(200);
+
+    let result = Cache::with_lock_timeout(
+        root.path(),
+        10,
+        timeout,
+        Duration::from_millis(20),
+    )
+    .await;
+
+    match result {
+        Err(roadtrip_cache::error::Error::AlreadyLocked) => (),
+        _ => return Error::other("cache dir should have been locked"),
+    }
+
+    if start.elapsed() < timeout {
+        return Error::other("gave up before the timeout");
+    }
+
+    drop(cache0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn wait_without_poll_interval() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache0 = Cache::new(root.path(), 10).await?;
+
+    let holder = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(100));
+        drop(cache0);
+    });
+
+    // Still backs off between tries rather than spinning.
+    let cache1 = Cache::with_lock_timeout(
+        root.path(),
+        10,
+        Duration::from_secs(10),
+        Duration::from_secs(0),
+    )
+    .await?;
+
+    holder.join().unwrap();
+    drop(cache1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancel_lock_wait() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache0 = Cache::new(root.path(), 10).await?;
+
+    let token = Token::new();
+    let canceller = token.clone();
+
+    tokio::spawn(async move {
+        tokio::time::delay_for(Duration::from_millis(50)).await;
+        canceller.cancel();
+    });
+
+    let start = Instant::now();
+
+    let result = Cache::builder(root.path(), 10)
+        .lock_timeout(Duration::from_secs(30), Duration::from_millis(10))
+        .cancel_on(token)
+        .open()
+        .await;
+
+    match result {
+        Err(roadtrip_cache::error::Error::Cancelled) => (),
+        _ => return Error::other("lock wait should have been cancelled"),
+    }
+
+    if start.elapsed() >= Duration::from_secs(30) {
+        return Error::other("waited for the whole timeout");
+    }
+
+    drop(cache0);
+
+    Ok(())
+}
+
+// Set to a cache directory to have `lock_child` take turns with other
+// processes at locking it.
+const CHILD_ROOT: &str = "ROADTRIP_CACHE_LOCK_CHILD";
+
+// Only does anything when run by `exclusive_across_processes`.
+#[tokio::test]
+async fn lock_child() -> Result<(), Error> {
+    let root = match std::env::var_os(CHILD_ROOT) {
+        Some(r) => PathBuf::from(r),
+        None => return Ok(()),
+    };
+
+    // Next to the cache, so opening it doesn't find anything unexpected.
+    let held = root.with_extension("held");
+
+    for _ in 0..50 {
+        let cache = Cache::with_lock_timeout(
+            &root,
+            10,
+            Duration::from_secs(30),
+            Duration::from_millis(1),
+        )
+        .await?;
+
+        // Fails if another process thinks it has the lock too.
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&held)?;
+        std::thread::sleep(Duration::from_millis(1));
+        std::fs::remove_file(&held)?;
+
+        drop(cache);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn exclusive_across_processes() -> Result<(), Error> {
+    let dir = tempdir()?;
+    let root = dir.path().join("cache");
+    fs::create_dir(&root).await?;
+
+    let exe = std::env::current_exe()?;
+    let children = (0..3)
+        .map(|_| {
+            Command::new(&exe)
+                .args(&["lock_child", "--exact", "--quiet"])
+                .env(CHILD_ROOT, &root)
+                .stdout(Stdio::null())
+                .spawn()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for mut child in children {
+        if !child.wait()?.success() {
+            return Error::other("two processes held the lock at once");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,131 @@
+mod util;
+
+use filetime::FileTime;
+
+use roadtrip_cache::error::{InsertError, RemoveError};
+use roadtrip_cache::Cache;
+
+use self::util::*;
+
+use tempfile::tempdir;
+
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+#[tokio::test]
+async fn read_while_locked() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 100).await?;
+
+    assert_vacant_entry(cache.entry("one").await?)
+        .await?
+        .insert_with("file0", |mut f| async move {
+            f.write_all(b"hello earth").await?;
+            Ok(())
+        })
+        .await?;
+
+    let file0 = root.path().join("one").join("file0");
+    let before = FileTime::from_unix_time(1, 0);
+    filetime::set_file_mtime(&file0, before)?;
+
+    let reader = Cache::open_read_only(root.path()).await?;
+    assert_eq(reader.is_read_only(), true)?;
+
+    let expected = MapBuilder::new().insert("file0", b"hello earth").build();
+    assert_entry_eq(reader.entry("one").await?, expected).await?;
+
+    // Reading through the read-only cache must not refresh the entry.
+    let metadata = fs::metadata(&file0).await?;
+    let after = FileTime::from_last_modification_time(&metadata);
+    assert_eq(after, before)?;
+
+    drop(cache);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn read_only_rejects_insert() -> Result<(), Error> {
+    let root = tempdir()?;
+    let _cache = Cache::new(root.path(), 100).await?;
+
+    let reader = Cache::open_read_only(root.path()).await?;
+    let entry = assert_vacant_entry(reader.entry("one").await?).await?;
+
+    let result = entry
+        .insert_with("file0", |mut f| async move {
+            f.write_all(b"hello earth").await?;
+            Ok(())
+        })
+        .await;
+
+    match result {
+        Err(InsertError::ReadOnly) => (),
+        _ => return Error::other("read-only cache should reject inserts"),
+    }
+
+    assert_eq(root.path().join("one").exists(), false)
+}
+
+#[tokio::test]
+async fn read_only_rejects_remove() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 100).await?;
+
+    assert_vacant_entry(cache.entry("one").await?)
+        .await?
+        .insert_with("file0", |mut f| async move {
+            f.write_all(b"hello earth").await?;
+            Ok(())
+        })
+        .await?;
+
+    let reader = Cache::open_read_only(root.path()).await?;
+
+    match reader.remove("one").await {
+        Err(RemoveError::ReadOnlyCache) => (),
+        _ => return Error::other("read-only cache should reject removes"),
+    }
+
+    assert_eq(root.path().join("one").exists(), true)
+}
+
+#[tokio::test]
+async fn readers_share_with_owner() -> Result<(), Error> {
+    let root = tempdir()?;
+    let cache = Cache::new(root.path(), 100).await?;
+
+    assert_vacant_entry(cache.entry("one").await?)
+        .await?
+        .insert_with("file0", |mut f| async move {
+            f.write_all(b"hello earth").await?;
+            Ok(())
+        })
+        .await?;
+
+    let first = Cache::open_read_only(root.path()).await?;
+    let second = Cache::open_read_only(root.path()).await?;
+
+    for reader in &[&first, &second] {
+        let expected =
+            MapBuilder::new().insert("file0", b"hello earth").build();
+        assert_entry_eq(reader.entry("one").await?, expected).await?;
+
+        match reader.set_capacity(0).await {
+            Err(RemoveError::ReadOnlyCache) => (),
+            _ => return Error::other("read-only cache should reject evicts"),
+        }
+
+        match reader.shrink_to_fit().await {
+            Err(RemoveError::ReadOnlyCache) => (),
+            _ => return Error::other("read-only cache should reject evicts"),
+        }
+    }
+
+    // The owner isn't held up by any of them.
+    let expected = MapBuilder::new().insert("file0", b"hello earth").build();
+    assert_entry_eq(cache.entry("one").await?, expected).await?;
+
+    Ok(())
+}
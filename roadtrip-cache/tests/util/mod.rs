@@ -26,6 +26,18 @@ pub enum Error {
     CacheInsert {
         source: roadtrip_cache::error::InsertError,
     },
+    #[snafu(context(false))]
+    CacheRemove {
+        source: roadtrip_cache::error::RemoveError,
+    },
+    #[snafu(context(false))]
+    CacheReserve {
+        source: roadtrip_cache::error::ReserveError,
+    },
+    #[snafu(context(false))]
+    CacheVerify {
+        source: roadtrip_cache::error::VerifyError,
+    },
 
     Missing,
 
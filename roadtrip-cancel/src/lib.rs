@@ -0,0 +1,158 @@
+use futures::stream::{Stream, StreamExt, TakeUntil};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll, Waker};
+
+pub type Cancellable<S> = TakeUntil<S, WaitForCancel>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "operation cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    next_waiter: AtomicUsize,
+    waiters: Mutex<HashMap<usize, Waker>>,
+    children: Mutex<Vec<Weak<Inner>>>,
+}
+
+impl Inner {
+    fn cancel(&self) {
+        if self.cancelled.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let waiters: Vec<_> = {
+            let mut locked = self.waiters.lock().unwrap();
+            locked.drain().map(|(_, w)| w).collect()
+        };
+
+        for waker in waiters {
+            waker.wake();
+        }
+
+        let children = std::mem::take(&mut *self.children.lock().unwrap());
+
+        for child in children.iter().filter_map(Weak::upgrade) {
+            child.cancel();
+        }
+    }
+}
+
+// A cheaply cloneable flag shared between everything working on behalf of
+// one operation. Async code can wait on it, and blocking code can poll
+// `is_cancelled` between steps.
+#[derive(Debug, Clone, Default)]
+pub struct Token {
+    inner: Arc<Inner>,
+}
+
+impl Token {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    // A token that is cancelled along with this one, but can also be
+    // cancelled on its own without affecting this one.
+    pub fn child(&self) -> Self {
+        let child = Self::new();
+
+        {
+            let mut children = self.inner.children.lock().unwrap();
+
+            // Long-lived tokens hand out many children, so forget the ones
+            // that have already been dropped.
+            children.retain(|c| c.strong_count() > 0);
+            children.push(Arc::downgrade(&child.inner));
+        }
+
+        // Checked after registering, so a concurrent `cancel` can't be missed.
+        if self.is_cancelled() {
+            child.cancel();
+        }
+
+        child
+    }
+
+    pub fn cancelled(&self) -> WaitForCancel {
+        WaitForCancel {
+            id: self.inner.next_waiter.fetch_add(1, Ordering::Relaxed),
+            inner: self.inner.clone(),
+        }
+    }
+
+    // Ends `stream` early once this token is cancelled.
+    pub fn stream<S>(&self, stream: S) -> Cancellable<S>
+    where
+        S: Stream,
+    {
+        stream.take_until(self.cancelled())
+    }
+}
+
+#[derive(Debug)]
+pub struct WaitForCancel {
+    id: usize,
+    inner: Arc<Inner>,
+}
+
+impl Future for WaitForCancel {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.inner.cancelled.load(Ordering::SeqCst) {
+            return Poll::Ready(());
+        }
+
+        self.inner
+            .waiters
+            .lock()
+            .unwrap()
+            .insert(self.id, cx.waker().clone());
+
+        // `cancel` sets the flag before taking the wakers, so checking again
+        // catches a cancel that raced with the insert above.
+        if self.inner.cancelled.load(Ordering::SeqCst) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for WaitForCancel {
+    fn drop(&mut self) {
+        if let Ok(mut waiters) = self.inner.waiters.lock() {
+            waiters.remove(&self.id);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use futures::stream::{self, StreamExt};
+
+use roadtrip_cancel::{Cancelled, Token};
+
+use std::time::Duration;
+
+#[tokio::test]
+async fn cancel_wakes_waiters() {
+    let token = Token::new();
+    let waiter = tokio::spawn(token.cancelled());
+
+    tokio::time::delay_for(Duration::from_millis(50)).await;
+    assert!(!token.is_cancelled());
+
+    token.cancel();
+
+    tokio::time::timeout(Duration::from_secs(5), waiter)
+        .await
+        .expect("waiter was never woken")
+        .unwrap();
+
+    assert!(token.is_cancelled());
+    assert_eq!(token.check(), Err(Cancelled));
+}
+
+#[tokio::test]
+async fn already_cancelled() {
+    let token = Token::new();
+    token.cancel();
+
+    token.cancelled().await;
+}
+
+#[tokio::test]
+async fn stream_ends_when_cancelled() {
+    let token = Token::new();
+
+    let slow = stream::iter(0..).then(|x| async move {
+        tokio::time::delay_for(Duration::from_millis(10)).await;
+        x
+    });
+    let mut items = Box::pin(token.stream(slow));
+
+    assert_eq!(items.next().await, Some(0));
+
+    token.cancel();
+
+    assert_eq!(items.next().await, None);
+}
+
+#[tokio::test]
+async fn children_follow_parents() {
+    let parent = Token::new();
+    let child = parent.child();
+    let sibling = parent.child();
+
+    child.cancel();
+
+    assert!(child.is_cancelled());
+    assert!(!sibling.is_cancelled());
+    assert!(!parent.is_cancelled());
+
+    parent.cancel();
+
+    assert!(sibling.is_cancelled());
+    assert!(parent.child().is_cancelled());
+}
@@ -0,0 +1,208 @@
+// Tracks as GeoJSON features, for web maps and other tools. GeoJSON has no
+// place for time, so it goes in the feature's properties the same way
+// togeojson does: `time` for a single point, `coordTimes` with one entry
+// per position for a line, and a list of those for each line of a
+// `MultiLineString`.
+
+mod error {
+    use snafu::Snafu;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility = "pub(super)")]
+    pub enum Error {
+        NotFeature,
+        Unsupported { kind: String },
+        Coordinates,
+        NoTimestamp,
+        Timestamp { source: chrono::ParseError },
+        TimeCount { positions: usize, times: usize },
+        NoPoints,
+    }
+}
+
+use crate::datetime::DateTime;
+use crate::geometry::{Geometry, Path, Point};
+use crate::media::Media;
+
+pub use self::error::Error;
+
+use serde_json::{json, Map, Value};
+
+use snafu::{ensure, OptionExt, ResultExt};
+
+fn position(point: &Point) -> Value {
+    match point.altitude() {
+        Some(alt) => json!([point.longitude(), point.latitude(), alt]),
+        None => json!([point.longitude(), point.latitude()]),
+    }
+}
+
+fn time(point: &Point) -> Value {
+    Value::String(point.time().to_rfc3339())
+}
+
+fn parse_time(value: &Value) -> Result<DateTime, Error> {
+    let text = value.as_str().context(error::NoTimestamp)?;
+    let time =
+        chrono::DateTime::parse_from_rfc3339(text).context(error::Timestamp)?;
+    Ok(time.into())
+}
+
+fn parse_point(position: &Value, time: &Value) -> Result<Point, Error> {
+    let position = position.as_array().context(error::Coordinates)?;
+    ensure!(position.len() >= 2, error::Coordinates);
+
+    let lng = position[0].as_f64().context(error::Coordinates)?;
+    let lat = position[1].as_f64().context(error::Coordinates)?;
+    let alt = position.get(2).and_then(Value::as_f64);
+
+    Ok(Point::new(lat, lng, parse_time(time)?).with_altitude(alt))
+}
+
+fn parse_line(positions: &Value, times: &Value) -> Result<Path, Error> {
+    let positions = positions.as_array().context(error::Coordinates)?;
+    let times = times.as_array().context(error::NoTimestamp)?;
+
+    ensure!(
+        positions.len() == times.len(),
+        error::TimeCount {
+            positions: positions.len(),
+            times: times.len(),
+        }
+    );
+    ensure!(!positions.is_empty(), error::NoPoints);
+
+    let points = positions
+        .iter()
+        .zip(times)
+        .map(|(p, t)| parse_point(p, t))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Path::from_iter(points))
+}
+
+fn feature(geometry: &Geometry, mut properties: Map<String, Value>) -> Value {
+    let geometry = match geometry {
+        Geometry::Point(p) => {
+            properties.insert("time".into(), time(p));
+            json!({ "type": "Point", "coordinates": position(p) })
+        }
+        Geometry::Path(p) => {
+            let times = p.iter().map(|x| time(&x)).collect();
+            properties.insert("coordTimes".into(), Value::Array(times));
+
+            let positions: Vec<_> = p.iter().map(|x| position(&x)).collect();
+            json!({ "type": "LineString", "coordinates": positions })
+        }
+        Geometry::Pieces(ps) => {
+            let times = ps
+                .iter()
+                .map(|p| p.iter().map(|x| time(&x)).collect())
+                .collect();
+            properties.insert("coordTimes".into(), Value::Array(times));
+
+            let lines: Vec<Vec<_>> = ps
+                .iter()
+                .map(|p| p.iter().map(|x| position(&x)).collect())
+                .collect();
+            json!({ "type": "MultiLineString", "coordinates": lines })
+        }
+    };
+
+    json!({
+        "type": "Feature",
+        "geometry": geometry,
+        "properties": properties,
+    })
+}
+
+impl Geometry {
+    // A GeoJSON `Feature` with a `Point`, `LineString`, or (for pieces)
+    // `MultiLineString` geometry.
+    pub fn to_geojson(&self) -> Value {
+        feature(self, Map::new())
+    }
+
+    // Reads a feature written by `to_geojson`, or by anything else that
+    // records times the same way.
+    pub fn from_geojson(value: &Value) -> Result<Geometry, Error> {
+        ensure!(value["type"] == "Feature", error::NotFeature);
+
+        let geometry = &value["geometry"];
+        let properties = &value["properties"];
+        let coordinates = &geometry["coordinates"];
+
+        match geometry["type"].as_str() {
+            Some("Point") => {
+                let point = parse_point(coordinates, &properties["time"])?;
+                Ok(Geometry::Point(point))
+            }
+            Some("LineString") => {
+                let path = parse_line(coordinates, &properties["coordTimes"])?;
+                Ok(Geometry::Path(path))
+            }
+            Some("MultiLineString") => {
+                let lines =
+                    coordinates.as_array().context(error::Coordinates)?;
+                let times = properties["coordTimes"]
+                    .as_array()
+                    .context(error::NoTimestamp)?;
+
+                ensure!(
+                    lines.len() == times.len(),
+                    error::TimeCount {
+                        positions: lines.len(),
+                        times: times.len(),
+                    }
+                );
+                ensure!(!lines.is_empty(), error::NoPoints);
+
+                let pieces = lines
+                    .iter()
+                    .zip(times)
+                    .map(|(l, t)| parse_line(l, t))
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(Geometry::Pieces(pieces))
+            }
+            other => error::Unsupported {
+                kind: other.unwrap_or_default(),
+            }
+            .fail(),
+        }
+    }
+}
+
+impl Media {
+    // A GeoJSON `Feature` for the media's track, with enough properties to
+    // find the media again.
+    pub fn to_geojson(&self) -> Value {
+        let mut properties = Map::new();
+
+        properties.insert(
+            "path".into(),
+            Value::String(self.path().to_string_lossy().into_owned()),
+        );
+        properties
+            .insert("hash".into(), Value::String(self.hash().to_string()));
+
+        if let Some(camera) = self.camera() {
+            properties.insert("camera".into(), Value::String(camera.into()));
+        }
+
+        feature(self.geometry(), properties)
+    }
+}
+
+// A GeoJSON `FeatureCollection` with one feature for each of `media`.
+pub fn feature_collection<'a, I>(media: I) -> Value
+where
+    I: IntoIterator<Item = &'a Media>,
+{
+    let features: Vec<_> = media.into_iter().map(Media::to_geojson).collect();
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
@@ -1,16 +1,112 @@
 use crate::datetime::DateTime;
+use crate::media::{Metadata, MetadataValue};
 
-use geo::prelude::Contains;
-
-use geo_types::PointsIter;
+use geo::prelude::{Contains, HaversineDistance};
 
 use std::fmt;
 
+// Gaps between fixes longer than this are more likely the camera being off
+// than a slow logger, so they're never filled in.
+const MAX_DENSIFY_GAP: i64 = 10 * 60;
+
+// In metres, the same as geo uses for haversine distances.
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+// Parts of a line between two points, as fractions of the way along it.
+type Spans = Vec<(f64, f64)>;
+
+fn intersect_spans(a: &[(f64, f64)], b: &[(f64, f64)]) -> Spans {
+    let mut out = Vec::new();
+
+    for (a_lo, a_hi) in a {
+        for (b_lo, b_hi) in b {
+            let (lo, hi) = (a_lo.max(*b_lo), a_hi.min(*b_hi));
+            if lo <= hi {
+                out.push((lo, hi));
+            }
+        }
+    }
+
+    out
+}
+
+// Degrees east from `from` to `to` the short way around, so tracks and
+// circles that cross the antimeridian aren't taken the long way round.
+fn lng_delta(from: f64, to: f64) -> f64 {
+    (to - from + 540.0).rem_euclid(360.0) - 180.0
+}
+
+// Metres from `p` to the closest part of the line from `a` to `b`, treating
+// the earth as flat around `a`.
+fn segment_distance(
+    a: geo::Coordinate<f64>,
+    b: geo::Coordinate<f64>,
+    p: geo::Coordinate<f64>,
+) -> f64 {
+    let per_degree = EARTH_RADIUS.to_radians();
+    let x_scale = per_degree * a.y.to_radians().cos();
+
+    let (dx, dy) = ((b.x - a.x) * x_scale, (b.y - a.y) * per_degree);
+    let (px, py) = ((p.x - a.x) * x_scale, (p.y - a.y) * per_degree);
+
+    let length = dx * dx + dy * dy;
+    let frac = if length == 0.0 {
+        0.0
+    } else {
+        ((px * dx + py * dy) / length).max(0.0).min(1.0)
+    };
+
+    (px - dx * frac).hypot(py - dy * frac)
+}
+
+// How to fill in points between fixes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Interpolation {
+    // Straight lines between fixes.
+    Linear,
+
+    // A Catmull-Rom spline through the fixes, which rounds off corners.
+    Spline,
+}
+
+impl Interpolation {
+    // Somewhere between `b` and `c`, `frac` of the way along, where `a` comes
+    // before `b` and `d` after `c`.
+    fn interpolate(self, a: f64, b: f64, c: f64, d: f64, frac: f64) -> f64 {
+        match self {
+            Interpolation::Linear => b + (c - b) * frac,
+            Interpolation::Spline => {
+                let f2 = frac * frac;
+                let f3 = f2 * frac;
+
+                0.5 * ((2.0 * b)
+                    + (c - a) * frac
+                    + (2.0 * a - 5.0 * b + 4.0 * c - d) * f2
+                    + (3.0 * b - a - 3.0 * c + d) * f3)
+            }
+        }
+    }
+}
+
+// Which media to show. Place and time are about tracks, so `Point`, `Path`
+// and `Geometry` check them on their own. Camera and metadata are about the
+// media, and only `Media::matches` checks them, along with its track.
+// Matching a geometry directly, or using `bounds`, ignores them.
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(into = "repr::Filter", try_from = "repr::Filter")
+)]
 pub struct Filter {
     rect: Option<geo::Rect<f64>>,
+    polygon: Option<geo::Polygon<f64>>,
+    near: Option<(geo::Point<f64>, f64)>,
     start: Option<DateTime>,
     end: Option<DateTime>,
+    camera: Option<String>,
+    metadata: Vec<(String, MetadataValue)>,
 }
 
 impl Eq for Filter {}
@@ -25,6 +121,30 @@ impl PartialEq for Filter {
             return false;
         }
 
+        if self.camera != other.camera {
+            return false;
+        }
+
+        if self.metadata != other.metadata {
+            return false;
+        }
+
+        if !Self::polygon_eq(self.polygon.as_ref(), other.polygon.as_ref()) {
+            return false;
+        }
+
+        match (self.near, other.near) {
+            (Some((s, s_radius)), Some((o, o_radius))) => {
+                if !Self::coord_eq(s.0, o.0)
+                    || s_radius.to_bits() != o_radius.to_bits()
+                {
+                    return false;
+                }
+            }
+            (None, None) => (),
+            _ => return false,
+        }
+
         match (self.rect, other.rect) {
             (Some(s), Some(o)) => {
                 Self::coord_eq(s.min(), o.min())
@@ -41,6 +161,21 @@ impl Filter {
         a.x.to_bits() == b.x.to_bits() && a.y.to_bits() == b.y.to_bits()
     }
 
+    fn polygon_eq(
+        a: Option<&geo::Polygon<f64>>,
+        b: Option<&geo::Polygon<f64>>,
+    ) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                let (a, b) = (&a.exterior().0, &b.exterior().0);
+                a.len() == b.len()
+                    && a.iter().zip(b).all(|(a, b)| Self::coord_eq(*a, *b))
+            }
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
     pub fn end(mut self, end: DateTime) -> Self {
         self.end = Some(end);
         self
@@ -51,6 +186,298 @@ impl Filter {
         self
     }
 
+    // Only matches media from `camera`. Checked by `Media::matches`, not
+    // against tracks.
+    pub fn camera<S>(mut self, camera: S) -> Self
+    where
+        S: Into<String>,
+    {
+        self.camera = Some(camera.into());
+        self
+    }
+
+    pub(crate) fn camera_matches(&self, camera: Option<&str>) -> bool {
+        match &self.camera {
+            Some(wanted) => camera == Some(wanted.as_str()),
+            None => true,
+        }
+    }
+
+    // Only matches media with `value` for `key`. Can be used more than once
+    // to require several values. Like `camera`, only `Media::matches` checks
+    // it.
+    pub fn metadata<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: Into<String>,
+        V: Into<MetadataValue>,
+    {
+        self.metadata.push((key.into(), value.into()));
+        self
+    }
+
+    pub(crate) fn metadata_matches(&self, metadata: &Metadata) -> bool {
+        self.metadata
+            .iter()
+            .all(|(key, value)| metadata.get(key) == Some(value))
+    }
+
+    // When the line from `a` to `b` is within the time range, assuming the
+    // camera moved along it at a steady pace.
+    fn time_spans(&self, a: &Point, b: &Point) -> Spans {
+        let span = (b.time - a.time).num_milliseconds() as f64;
+
+        if span <= 0.0 {
+            // The end points have already been checked on their own.
+            return match (self.start, self.end) {
+                (None, None) => vec![(0.0, 1.0)],
+                _ => Vec::new(),
+            };
+        }
+
+        let frac = |t: DateTime| (t - a.time).num_milliseconds() as f64 / span;
+        let lo = self.start.map(frac).unwrap_or(0.0).max(0.0);
+        let hi = self.end.map(frac).unwrap_or(1.0).min(1.0);
+
+        if lo <= hi {
+            vec![(lo, hi)]
+        } else {
+            Vec::new()
+        }
+    }
+
+    // Clips the line from `a` to `b` to `rect` (Liang-Barsky).
+    fn rect_spans(
+        rect: &geo::Rect<f64>,
+        a: geo::Coordinate<f64>,
+        b: geo::Coordinate<f64>,
+    ) -> Spans {
+        let (min, max) = (rect.min(), rect.max());
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let (mut lo, mut hi) = (0.0f64, 1.0f64);
+
+        let edges = [
+            (-dx, a.x - min.x),
+            (dx, max.x - a.x),
+            (-dy, a.y - min.y),
+            (dy, max.y - a.y),
+        ];
+
+        for (p, q) in &edges {
+            if *p == 0.0 {
+                // Parallel to this edge, and entirely outside of it.
+                if *q < 0.0 {
+                    return Vec::new();
+                }
+            } else if *p < 0.0 {
+                lo = lo.max(q / p);
+            } else {
+                hi = hi.min(q / p);
+            }
+        }
+
+        if lo <= hi {
+            vec![(lo, hi)]
+        } else {
+            Vec::new()
+        }
+    }
+
+    // The part of the line from `a` to `b` within `radius` metres of
+    // `center`. Treats the earth as flat around `center`, which is close
+    // enough for anything short of a continent.
+    fn near_spans(
+        center: geo::Point<f64>,
+        radius: f64,
+        a: geo::Coordinate<f64>,
+        b: geo::Coordinate<f64>,
+    ) -> Spans {
+        let per_degree = EARTH_RADIUS.to_radians();
+        let x_scale = per_degree * center.y().to_radians().cos();
+
+        let ax = lng_delta(center.x(), a.x) * x_scale;
+        let ay = (a.y - center.y()) * per_degree;
+        let dx = lng_delta(a.x, b.x) * x_scale;
+        let dy = (b.y - a.y) * per_degree;
+
+        // Where the distance from the center is exactly `radius`.
+        let qa = dx * dx + dy * dy;
+        let qb = 2.0 * (ax * dx + ay * dy);
+        let qc = ax * ax + ay * ay - radius * radius;
+
+        // Both ends are in the same place.
+        if qa == 0.0 && qc <= 0.0 {
+            return vec![(0.0, 1.0)];
+        } else if qa == 0.0 {
+            return Vec::new();
+        }
+
+        let discriminant = qb * qb - 4.0 * qa * qc;
+        if discriminant < 0.0 {
+            return Vec::new();
+        }
+
+        let root = discriminant.sqrt();
+        let lo = ((-qb - root) / (2.0 * qa)).max(0.0);
+        let hi = ((-qb + root) / (2.0 * qa)).min(1.0);
+
+        if lo <= hi {
+            vec![(lo, hi)]
+        } else {
+            Vec::new()
+        }
+    }
+
+    // The parts of the line from `a` to `b` inside `polygon`, found by
+    // cutting it wherever it crosses an edge.
+    fn polygon_spans(
+        polygon: &geo::Polygon<f64>,
+        a: geo::Coordinate<f64>,
+        b: geo::Coordinate<f64>,
+    ) -> Spans {
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let mut cuts = vec![0.0, 1.0];
+
+        for edge in polygon.exterior().lines() {
+            let (fx, fy) =
+                (edge.end.x - edge.start.x, edge.end.y - edge.start.y);
+            let (ex, ey) = (edge.start.x - a.x, edge.start.y - a.y);
+
+            let denominator = dx * fy - dy * fx;
+            if denominator == 0.0 {
+                continue;
+            }
+
+            let along_line = (ex * fy - ey * fx) / denominator;
+            let along_edge = (ex * dy - ey * dx) / denominator;
+
+            if (0.0..=1.0).contains(&along_line)
+                && (0.0..=1.0).contains(&along_edge)
+            {
+                cuts.push(along_line);
+            }
+        }
+
+        cuts.sort_by(|x, y| x.partial_cmp(y).unwrap());
+
+        cuts.windows(2)
+            .filter(|w| {
+                let mid = (w[0] + w[1]) / 2.0;
+                let point = geo::Point::new(a.x + dx * mid, a.y + dy * mid);
+                polygon.contains(&point)
+            })
+            .map(|w| (w[0], w[1]))
+            .collect()
+    }
+
+    // The corners of a box holding everywhere this filter could match, as
+    // `(min, max)`, or `None` if it matches anywhere. If nowhere matches, `min`
+    // ends up past `max`, like for a polygon without any vertices.
+    pub(crate) fn bounds(
+        &self,
+    ) -> Option<(geo::Coordinate<f64>, geo::Coordinate<f64>)> {
+        let mut boxes = Vec::new();
+
+        if let Some(rect) = &self.rect {
+            boxes.push((rect.min(), rect.max()));
+        }
+
+        if let Some(polygon) = &self.polygon {
+            let coords = &polygon.exterior().0;
+            let nowhere = (
+                geo::Coordinate {
+                    x: f64::INFINITY,
+                    y: f64::INFINITY,
+                },
+                geo::Coordinate {
+                    x: f64::NEG_INFINITY,
+                    y: f64::NEG_INFINITY,
+                },
+            );
+
+            boxes.push(coords.iter().fold(nowhere, |(lo, hi), c| {
+                let lo = geo::Coordinate {
+                    x: lo.x.min(c.x),
+                    y: lo.y.min(c.y),
+                };
+                let hi = geo::Coordinate {
+                    x: hi.x.max(c.x),
+                    y: hi.y.max(c.y),
+                };
+                (lo, hi)
+            }));
+        }
+
+        if let Some((center, radius)) = self.near {
+            let per_degree = EARTH_RADIUS.to_radians();
+            let dy = radius / per_degree;
+
+            // Close enough to a pole, any longitude might be in range.
+            let dx = match center.y().to_radians().cos() * per_degree {
+                x if x * 180.0 <= radius => 180.0,
+                x => radius / x,
+            };
+
+            // Wrapping around the antimeridian would need two boxes, so take
+            // every longitude instead.
+            let (min_x, max_x) = (center.x() - dx, center.x() + dx);
+            let (min_x, max_x) = if min_x < -180.0 || max_x > 180.0 {
+                (-180.0, 180.0)
+            } else {
+                (min_x, max_x)
+            };
+
+            boxes.push((
+                geo::Coordinate {
+                    x: min_x,
+                    y: center.y() - dy,
+                },
+                geo::Coordinate {
+                    x: max_x,
+                    y: center.y() + dy,
+                },
+            ));
+        }
+
+        boxes.into_iter().fold(None, |acc, (lo, hi)| match acc {
+            None => Some((lo, hi)),
+            Some((a_lo, a_hi)) => Some((
+                geo::Coordinate {
+                    x: a_lo.x.max(lo.x),
+                    y: a_lo.y.max(lo.y),
+                },
+                geo::Coordinate {
+                    x: a_hi.x.min(hi.x),
+                    y: a_hi.y.min(hi.y),
+                },
+            )),
+        })
+    }
+
+    // Whether any part of the line from `a` to `b` matches, for tracks that
+    // pass through somewhere between fixes.
+    fn segment_matches(&self, a: &Point, b: &Point) -> bool {
+        let (ac, bc) = (a.position.0, b.position.0);
+        let mut spans = self.time_spans(a, b);
+
+        if let Some(rect) = &self.rect {
+            spans = intersect_spans(&spans, &Self::rect_spans(rect, ac, bc));
+        }
+
+        if let Some((center, radius)) = self.near {
+            let near = Self::near_spans(center, radius, ac, bc);
+            spans = intersect_spans(&spans, &near);
+        }
+
+        if let Some(polygon) = &self.polygon {
+            if !spans.is_empty() {
+                let inside = Self::polygon_spans(polygon, ac, bc);
+                spans = intersect_spans(&spans, &inside);
+            }
+        }
+
+        !spans.is_empty()
+    }
+
     pub fn rect(
         mut self,
         min_lat: f64,
@@ -71,11 +498,58 @@ impl Filter {
         self.rect = Some(geo::Rect::new(min, max));
         self
     }
+
+    // Only matches inside the region bounded by `vertices`, which are
+    // `(lat, lng)` pairs. The last vertex is joined back to the first.
+    pub fn polygon(mut self, vertices: Vec<(f64, f64)>) -> Self {
+        let exterior: Vec<_> = vertices
+            .into_iter()
+            .map(|(lat, lng)| geo::Coordinate { y: lat, x: lng })
+            .collect();
+
+        let polygon = geo::Polygon::new(exterior.into(), Vec::new());
+        self.polygon = Some(polygon);
+        self
+    }
+
+    // Only matches within `radius` metres of `lat`, `lng`.
+    pub fn near(mut self, lat: f64, lng: f64, radius: f64) -> Self {
+        self.near = Some((geo::Point::new(lng, lat), radius));
+        self
+    }
+}
+
+// Somewhere to leave out of tracks, like around home.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrivacyZone {
+    filter: Filter,
+}
+
+impl PrivacyZone {
+    // Everything within `radius` metres of `lat`, `lng`.
+    pub fn circle(lat: f64, lng: f64, radius: f64) -> Self {
+        Self {
+            filter: Filter::default().near(lat, lng, radius),
+        }
+    }
+
+    // The region bounded by `vertices`, which are `(lat, lng)` pairs.
+    pub fn polygon(vertices: Vec<(f64, f64)>) -> Self {
+        Self {
+            filter: Filter::default().polygon(vertices),
+        }
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        point.matches(&self.filter)
+    }
 }
 
 enum GeometryIter<'a> {
     One(std::iter::Once<Point>),
     Many(PathIter<'a>),
+    Pieces(Box<dyn Iterator<Item = Point> + 'a>),
 }
 
 impl<'a> std::iter::Iterator for GeometryIter<'a> {
@@ -85,21 +559,47 @@ impl<'a> std::iter::Iterator for GeometryIter<'a> {
         match self {
             GeometryIter::One(o) => o.next(),
             GeometryIter::Many(p) => p.next(),
+            GeometryIter::Pieces(p) => p.next(),
         }
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Geometry {
     Point(Point),
     Path(Path),
+
+    // A track with parts left out, like where it passed through a privacy
+    // zone. The pieces are in order, and aren't joined to each other.
+    Pieces(Vec<Path>),
 }
 
 impl Geometry {
+    // Puts pieces, like the ones from `redacted`, back into one geometry
+    // without joining them up. `None` if there aren't any.
+    pub fn from_pieces(mut pieces: Vec<Geometry>) -> Option<Geometry> {
+        if pieces.len() < 2 {
+            return pieces.pop();
+        }
+
+        let paths = pieces
+            .into_iter()
+            .flat_map(|piece| match piece {
+                Geometry::Point(p) => vec![Path::from_iter(Some(p))],
+                Geometry::Path(p) => vec![p],
+                Geometry::Pieces(ps) => ps,
+            })
+            .collect();
+
+        Some(Geometry::Pieces(paths))
+    }
+
     pub fn matches(&self, filter: &Filter) -> bool {
         match self {
             Geometry::Point(p) => p.matches(filter),
             Geometry::Path(p) => p.matches(filter),
+            Geometry::Pieces(ps) => ps.iter().any(|p| p.matches(filter)),
         }
     }
 
@@ -107,6 +607,7 @@ impl Geometry {
         match self {
             Geometry::Point(_) => 1,
             Geometry::Path(p) => p.len(),
+            Geometry::Pieces(ps) => ps.iter().map(Path::len).sum(),
         }
     }
 
@@ -114,6 +615,138 @@ impl Geometry {
         match self {
             Geometry::Point(p) => GeometryIter::One(std::iter::once(*p)),
             Geometry::Path(p) => GeometryIter::Many(p.iter()),
+            Geometry::Pieces(ps) => {
+                GeometryIter::Pieces(Box::new(ps.iter().flat_map(Path::iter)))
+            }
+        }
+    }
+
+    // Moves every point by `by`, for correcting a camera's clock.
+    pub fn shift_time(&mut self, by: chrono::Duration) {
+        match self {
+            Geometry::Point(p) => {
+                *p = Point {
+                    time: p.time + by,
+                    ..*p
+                }
+            }
+            Geometry::Path(p) => p.shift_time(by),
+            Geometry::Pieces(ps) => {
+                ps.iter_mut().for_each(|p| p.shift_time(by))
+            }
+        }
+    }
+
+    pub fn densify(
+        &mut self,
+        interval: chrono::Duration,
+        interpolation: Interpolation,
+    ) {
+        match self {
+            Geometry::Point(_) => (),
+            Geometry::Path(p) => p.densify(interval, interpolation),
+            Geometry::Pieces(ps) => ps
+                .iter_mut()
+                .for_each(|p| p.densify(interval, interpolation)),
+        }
+    }
+
+    // Metres travelled, which is zero for a single point.
+    pub fn length_meters(&self) -> f64 {
+        match self {
+            Geometry::Point(_) => 0.0,
+            Geometry::Path(p) => p.length_meters(),
+            Geometry::Pieces(ps) => ps.iter().map(Path::length_meters).sum(),
+        }
+    }
+
+    // For pieces, only the time spent on them, the same as their length
+    // leaves out the distance between them.
+    pub fn duration(&self) -> chrono::Duration {
+        match self {
+            Geometry::Point(_) => chrono::Duration::zero(),
+            Geometry::Path(p) => p.duration(),
+            Geometry::Pieces(ps) => ps
+                .iter()
+                .fold(chrono::Duration::zero(), |sum, p| sum + p.duration()),
+        }
+    }
+
+    // In metres per second, or `None` if no time passed.
+    pub fn average_speed(&self) -> Option<f64> {
+        let millis = self.duration().num_milliseconds();
+        if millis <= 0 {
+            return None;
+        }
+
+        Some(self.length_meters() / (millis as f64 / 1000.0))
+    }
+
+    // Splits wherever more than `max_gap` passes between fixes, like when
+    // the camera was switched off, and between pieces.
+    pub fn split_by_gap(&self, max_gap: chrono::Duration) -> Vec<Geometry> {
+        match self {
+            Geometry::Point(p) => vec![Geometry::Point(*p)],
+            Geometry::Path(p) => p
+                .split_by_gap(max_gap)
+                .into_iter()
+                .map(Geometry::Path)
+                .collect(),
+            Geometry::Pieces(ps) => ps
+                .iter()
+                .flat_map(|p| p.split_by_gap(max_gap))
+                .map(Geometry::Path)
+                .collect(),
+        }
+    }
+
+    pub fn simplified(&self, epsilon: f64) -> Geometry {
+        match self {
+            Geometry::Point(p) => Geometry::Point(*p),
+            Geometry::Path(p) => Geometry::Path(p.simplified(epsilon)),
+            Geometry::Pieces(ps) => Geometry::Pieces(
+                ps.iter().map(|p| p.simplified(epsilon)).collect(),
+            ),
+        }
+    }
+
+    // The pieces left outside of `zones`, like `split_by_gap`, so tracks stop
+    // at their edges instead of cutting across them. Empty if everything is
+    // inside one.
+    pub fn redacted(&self, zones: &[PrivacyZone]) -> Vec<Geometry> {
+        match self {
+            Geometry::Point(p) if zones.iter().any(|z| z.contains(p)) => {
+                Vec::new()
+            }
+            Geometry::Point(p) => vec![Geometry::Point(*p)],
+            Geometry::Path(p) => {
+                p.redacted(zones).into_iter().map(Geometry::Path).collect()
+            }
+            Geometry::Pieces(ps) => ps
+                .iter()
+                .flat_map(|p| p.redacted(zones))
+                .map(Geometry::Path)
+                .collect(),
+        }
+    }
+
+    // Whether any of this is inside one of `zones`, or passes through one
+    // between fixes. Anything `redacted` would change.
+    pub fn enters(&self, zones: &[PrivacyZone]) -> bool {
+        match self {
+            Geometry::Point(p) => zones.iter().any(|z| z.contains(p)),
+            Geometry::Path(p) => p.enters(zones),
+            Geometry::Pieces(ps) => ps.iter().any(|p| p.enters(zones)),
+        }
+    }
+
+    // `None` between pieces, the same as outside of a path.
+    pub fn position_at(&self, time: DateTime) -> Option<Point> {
+        match self {
+            Geometry::Point(p) if p.time == time => Some(*p),
+            Geometry::Point(_) => None,
+            Geometry::Path(p) => p.position_at(time),
+            Geometry::Pieces(ps) => ps.iter().find_map(|p| p.position_at(time)),
         }
     }
 }
@@ -130,10 +763,49 @@ impl From<Point> for Geometry {
     }
 }
 
+// How the camera was moving at a point, for sources that record it.
+#[derive(Debug, Default, Clone, Copy)]
+struct Motion {
+    speed: Option<f64>,
+    altitude: Option<f64>,
+    heading: Option<f64>,
+}
+
+impl Motion {
+    // `frac` of the way from `a` to `b`, for whatever both of them have.
+    fn interpolate(a: Motion, b: Motion, frac: f64) -> Motion {
+        let lerp = |a: Option<f64>, b: Option<f64>| match (a, b) {
+            (Some(a), Some(b)) => Some(a + (b - a) * frac),
+            _ => None,
+        };
+
+        // The short way around, so from 350 to 10 degrees goes through north.
+        let heading = match (a.heading, b.heading) {
+            (Some(a), Some(b)) => {
+                let turn = (b - a + 540.0).rem_euclid(360.0) - 180.0;
+                Some((a + turn * frac).rem_euclid(360.0))
+            }
+            _ => None,
+        };
+
+        Motion {
+            speed: lerp(a.speed, b.speed),
+            altitude: lerp(a.altitude, b.altitude),
+            heading,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(into = "repr::Point", try_from = "repr::Point")
+)]
 pub struct Point {
     position: geo::Point<f64>,
     time: DateTime,
+    motion: Motion,
 }
 
 impl Point {
@@ -141,9 +813,30 @@ impl Point {
         Self {
             position: geo::Point::new(lng, lat),
             time,
+            motion: Motion::default(),
         }
     }
 
+    // In metres per second.
+    pub fn with_speed(mut self, speed: Option<f64>) -> Self {
+        self.motion.speed = speed.filter(|s| s.is_finite());
+        self
+    }
+
+    // In metres above sea level.
+    pub fn with_altitude(mut self, altitude: Option<f64>) -> Self {
+        self.motion.altitude = altitude.filter(|a| a.is_finite());
+        self
+    }
+
+    // In degrees clockwise from true north.
+    pub fn with_heading(mut self, heading: Option<f64>) -> Self {
+        self.motion.heading = heading
+            .filter(|h| h.is_finite())
+            .map(|h| h.rem_euclid(360.0));
+        self
+    }
+
     pub fn matches(&self, filter: &Filter) -> bool {
         if let Some(start) = filter.start {
             if self.time < start {
@@ -163,9 +856,26 @@ impl Point {
             }
         }
 
+        if let Some(polygon) = &filter.polygon {
+            if !polygon.contains(&self.position) {
+                return false;
+            }
+        }
+
+        if let Some((center, radius)) = filter.near {
+            if self.position.haversine_distance(&center) > radius {
+                return false;
+            }
+        }
+
         true
     }
 
+    // Metres between the two, along the surface of the earth.
+    pub fn distance(&self, other: &Point) -> f64 {
+        self.position.haversine_distance(&other.position)
+    }
+
     pub fn latitude(&self) -> f64 {
         self.position.lat()
     }
@@ -177,10 +887,23 @@ impl Point {
     pub fn time(&self) -> DateTime {
         self.time
     }
+
+    pub fn speed(&self) -> Option<f64> {
+        self.motion.speed
+    }
+
+    pub fn altitude(&self) -> Option<f64> {
+        self.motion.altitude
+    }
+
+    pub fn heading(&self) -> Option<f64> {
+        self.motion.heading
+    }
 }
 
 pub struct PathIter<'a> {
-    inner: std::iter::Zip<PointsIter<'a, f64>, std::slice::Iter<'a, DateTime>>,
+    path: &'a Path,
+    idx: usize,
 }
 
 impl<'a> fmt::Debug for PathIter<'a> {
@@ -193,17 +916,24 @@ impl<'a> std::iter::Iterator for PathIter<'a> {
     type Item = Point;
 
     fn next(&mut self) -> Option<Point> {
-        self.inner.next().map(|(position, time)| Point {
-            position,
-            time: *time,
-        })
+        let point = self.path.get(self.idx)?;
+        self.idx += 1;
+        Some(point)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.path.len().saturating_sub(self.idx);
+        (remaining, Some(remaining))
     }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "Vec<Point>", from = "Vec<Point>"))]
 pub struct Path {
     positions: geo::LineString<f64>,
     times: Vec<DateTime>,
+    motion: Vec<Motion>,
 }
 
 impl Path {
@@ -213,43 +943,409 @@ impl Path {
     {
         let mut times = Vec::new();
         let mut gpoints = Vec::new();
+        let mut motion = Vec::new();
 
         for point in points.into_iter() {
             times.push(point.time);
-            gpoints.push(geo::Point::new(point.latitude(), point.longitude()));
+            gpoints.push(point.position);
+            motion.push(point.motion);
         }
 
         Self {
             times,
             positions: geo::LineString::from(gpoints),
+            motion,
         }
     }
 
     pub fn matches(&self, filter: &Filter) -> bool {
-        // TODO: Might be more efficient to use the intersects method.
-        for (time, position) in
-            self.times.iter().zip(self.positions.points_iter())
-        {
-            let point = Point {
-                position,
-                time: *time,
-            };
-
-            if point.matches(filter) {
-                return true;
-            }
+        if self.iter().any(|p| p.matches(filter)) {
+            return true;
         }
 
-        false
+        self.iter()
+            .zip(self.iter().skip(1))
+            .any(|(a, b)| filter.segment_matches(&a, &b))
     }
 
     pub fn iter(&self) -> PathIter {
-        PathIter {
-            inner: self.positions.points_iter().zip(self.times.iter()),
-        }
+        PathIter { path: self, idx: 0 }
     }
 
     pub fn len(&self) -> usize {
         self.times.len()
     }
+
+    // Metres along the surface of the earth, from fix to fix.
+    pub fn length_meters(&self) -> f64 {
+        self.iter()
+            .zip(self.iter().skip(1))
+            .map(|(a, b)| a.distance(&b))
+            .sum()
+    }
+
+    // Time from the first fix to the last.
+    pub fn duration(&self) -> chrono::Duration {
+        match (self.times.first(), self.times.last()) {
+            (Some(first), Some(last)) => *last - *first,
+            _ => chrono::Duration::zero(),
+        }
+    }
+
+    pub fn shift_time(&mut self, by: chrono::Duration) {
+        self.times = self.times.iter().map(|t| *t + by).collect();
+    }
+
+    // Adds points between fixes, so there's one at least every `interval`.
+    // The fixes themselves are kept as they are.
+    pub fn densify(
+        &mut self,
+        interval: chrono::Duration,
+        interpolation: Interpolation,
+    ) {
+        if interval <= chrono::Duration::zero() || self.len() < 2 {
+            return;
+        }
+
+        let max_gap = chrono::Duration::seconds(MAX_DENSIFY_GAP);
+        let coords = &self.positions.0;
+        let last = coords.len() - 1;
+
+        let mut times = Vec::with_capacity(self.times.len());
+        let mut positions = Vec::with_capacity(coords.len());
+        let mut motion = Vec::with_capacity(self.motion.len());
+
+        for idx in 0..last {
+            let (start, end) = (self.times[idx], self.times[idx + 1]);
+
+            times.push(start);
+            positions.push(coords[idx]);
+            motion.push(self.motion[idx]);
+
+            let gap = end - start;
+            if gap <= interval || gap > max_gap {
+                continue;
+            }
+
+            let a = coords[idx.saturating_sub(1)];
+            let b = coords[idx];
+            let c = coords[idx + 1];
+            let d = coords[(idx + 2).min(last)];
+
+            let span = gap.num_milliseconds() as f64;
+
+            for step in 1.. {
+                let time = start + interval * step;
+                if time >= end {
+                    break;
+                }
+
+                let frac = (time - start).num_milliseconds() as f64 / span;
+
+                times.push(time);
+                positions.push(geo::Coordinate {
+                    x: interpolation.interpolate(a.x, b.x, c.x, d.x, frac),
+                    y: interpolation.interpolate(a.y, b.y, c.y, d.y, frac),
+                });
+                motion.push(Motion::interpolate(
+                    self.motion[idx],
+                    self.motion[idx + 1],
+                    frac,
+                ));
+            }
+        }
+
+        times.push(self.times[last]);
+        positions.push(coords[last]);
+        motion.push(self.motion[last]);
+
+        self.times = times;
+        self.positions = geo::LineString(positions);
+        self.motion = motion;
+    }
+
+    // The pieces between gaps of more than `max_gap`, in order. Every point
+    // ends up in exactly one piece.
+    pub fn split_by_gap(&self, max_gap: chrono::Duration) -> Vec<Path> {
+        let mut pieces = Vec::new();
+        let mut current: Vec<Point> = Vec::new();
+
+        for point in self.iter() {
+            if let Some(last) = current.last() {
+                if point.time - last.time > max_gap {
+                    pieces.push(Path::from_iter(current.drain(..)));
+                }
+            }
+
+            current.push(point);
+        }
+
+        if !current.is_empty() {
+            pieces.push(Path::from_iter(current));
+        }
+
+        pieces
+    }
+
+    // The pieces left after dropping the points inside any of `zones`, in
+    // order. Also splits between two points outside when the line between
+    // them passes through a zone.
+    pub fn redacted(&self, zones: &[PrivacyZone]) -> Vec<Path> {
+        let mut pieces = Vec::new();
+        let mut current: Vec<Point> = Vec::new();
+
+        for point in self.iter() {
+            let hidden = zones.iter().any(|z| z.contains(&point));
+            let crossed = current.last().map_or(false, |last| {
+                zones.iter().any(|z| z.filter.segment_matches(last, &point))
+            });
+
+            if (hidden || crossed) && !current.is_empty() {
+                pieces.push(Path::from_iter(current.drain(..)));
+            }
+
+            if !hidden {
+                current.push(point);
+            }
+        }
+
+        if !current.is_empty() {
+            pieces.push(Path::from_iter(current));
+        }
+
+        pieces
+    }
+
+    pub fn enters(&self, zones: &[PrivacyZone]) -> bool {
+        let mut last: Option<Point> = None;
+
+        self.iter().any(|point| {
+            let crossed = last.map_or(false, |last| {
+                zones
+                    .iter()
+                    .any(|z| z.filter.segment_matches(&last, &point))
+            });
+            last = Some(point);
+
+            crossed || zones.iter().any(|z| z.contains(&point))
+        })
+    }
+
+    // A copy with fewer points, for drawing (Douglas-Peucker). No point is
+    // moved by more than `epsilon` metres, and the ends are always kept.
+    pub fn simplified(&self, epsilon: f64) -> Path {
+        let coords = &self.positions.0;
+        if coords.len() < 3 {
+            return self.clone();
+        }
+
+        let mut keep = vec![false; coords.len()];
+        keep[0] = true;
+        keep[coords.len() - 1] = true;
+
+        // Ranges still to be simplified, without recursing on long tracks.
+        let mut pending = vec![(0, coords.len() - 1)];
+
+        while let Some((first, last)) = pending.pop() {
+            let (a, b) = (coords[first], coords[last]);
+
+            // A non-finite coordinate makes the distance NaN, which can't be
+            // compared, so those points are treated as on the line.
+            let farthest = (first + 1..last)
+                .map(|idx| {
+                    let distance = segment_distance(a, b, coords[idx]);
+                    (idx, if distance.is_nan() { 0.0 } else { distance })
+                })
+                .max_by(|x, y| x.1.partial_cmp(&y.1).unwrap());
+
+            if let Some((idx, distance)) = farthest {
+                if distance > epsilon {
+                    keep[idx] = true;
+                    pending.push((first, idx));
+                    pending.push((idx, last));
+                }
+            }
+        }
+
+        Path::from_iter(
+            keep.iter()
+                .enumerate()
+                .filter(|(_, k)| **k)
+                .filter_map(|(idx, _)| self.get(idx)),
+        )
+    }
+
+    fn get(&self, idx: usize) -> Option<Point> {
+        Some(Point {
+            position: geo::Point(*self.positions.0.get(idx)?),
+            time: *self.times.get(idx)?,
+            motion: *self.motion.get(idx)?,
+        })
+    }
+
+    pub fn position_at(&self, time: DateTime) -> Option<Point> {
+        let idx = match self.times.binary_search(&time) {
+            Ok(idx) => return self.get(idx),
+            Err(0) => return None,
+            Err(idx) => idx,
+        };
+
+        let before = self.get(idx - 1)?;
+        let after = self.get(idx)?;
+
+        let span = (after.time - before.time).num_milliseconds() as f64;
+        let elapsed = (time - before.time).num_milliseconds() as f64;
+        let frac = elapsed / span;
+
+        let x = before.position.x()
+            + (after.position.x() - before.position.x()) * frac;
+        let y = before.position.y()
+            + (after.position.y() - before.position.y()) * frac;
+
+        Some(Point {
+            position: geo::Point::new(x, y),
+            time,
+            motion: Motion::interpolate(before.motion, after.motion, frac),
+        })
+    }
+}
+
+// The serialized forms of the types above. Neither chrono nor geo is built
+// with serde support, so times are written as RFC 3339 strings and positions
+// as plain latitudes and longitudes.
+#[cfg(feature = "serde")]
+mod repr {
+    use serde::{Deserialize, Serialize};
+
+    use std::convert::TryFrom;
+
+    fn parse_time(text: &str) -> Result<super::DateTime, chrono::ParseError> {
+        chrono::DateTime::parse_from_rfc3339(text).map(Into::into)
+    }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct Point {
+        latitude: f64,
+        longitude: f64,
+        time: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        speed: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        altitude: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        heading: Option<f64>,
+    }
+
+    impl From<super::Point> for Point {
+        fn from(p: super::Point) -> Self {
+            Self {
+                latitude: p.latitude(),
+                longitude: p.longitude(),
+                time: p.time.to_rfc3339(),
+                speed: p.speed(),
+                altitude: p.altitude(),
+                heading: p.heading(),
+            }
+        }
+    }
+
+    impl TryFrom<Point> for super::Point {
+        type Error = chrono::ParseError;
+
+        fn try_from(p: Point) -> Result<Self, Self::Error> {
+            let time = parse_time(&p.time)?;
+
+            Ok(super::Point::new(p.latitude, p.longitude, time)
+                .with_speed(p.speed)
+                .with_altitude(p.altitude)
+                .with_heading(p.heading))
+        }
+    }
+
+    impl From<super::Path> for Vec<super::Point> {
+        fn from(p: super::Path) -> Self {
+            p.iter().collect()
+        }
+    }
+
+    impl From<Vec<super::Point>> for super::Path {
+        fn from(points: Vec<super::Point>) -> Self {
+            super::Path::from_iter(points)
+        }
+    }
+
+    // Positions are `(lat, lng)`, the same as the builder methods on
+    // `Filter` take them.
+    #[derive(Default, Serialize, Deserialize)]
+    pub struct Filter {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rect: Option<((f64, f64), (f64, f64))>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        polygon: Option<Vec<(f64, f64)>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        near: Option<((f64, f64), f64)>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        end: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        camera: Option<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        metadata: Vec<(String, super::MetadataValue)>,
+    }
+
+    impl From<super::Filter> for Filter {
+        fn from(f: super::Filter) -> Self {
+            let rect = f.rect.map(|r| {
+                let (min, max) = (r.min(), r.max());
+                ((min.y, min.x), (max.y, max.x))
+            });
+
+            let polygon = f
+                .polygon
+                .map(|p| p.exterior().0.iter().map(|c| (c.y, c.x)).collect());
+
+            Self {
+                rect,
+                polygon,
+                near: f.near.map(|(c, radius)| ((c.y(), c.x()), radius)),
+                start: f.start.map(|t| t.to_rfc3339()),
+                end: f.end.map(|t| t.to_rfc3339()),
+                camera: f.camera,
+                metadata: f.metadata,
+            }
+        }
+    }
+
+    impl TryFrom<Filter> for super::Filter {
+        type Error = chrono::ParseError;
+
+        fn try_from(f: Filter) -> Result<Self, Self::Error> {
+            let mut out = super::Filter::default();
+
+            if let Some(((min_lat, min_lng), (max_lat, max_lng))) = f.rect {
+                out = out.rect(min_lat, min_lng, max_lat, max_lng);
+            }
+
+            if let Some(vertices) = f.polygon {
+                out = out.polygon(vertices);
+            }
+
+            if let Some(((lat, lng), radius)) = f.near {
+                out = out.near(lat, lng, radius);
+            }
+
+            if let Some(start) = f.start {
+                out = out.start(parse_time(&start)?);
+            }
+
+            if let Some(end) = f.end {
+                out = out.end(parse_time(&end)?);
+            }
+
+            out.camera = f.camera;
+            out.metadata = f.metadata;
+            Ok(out)
+        }
+    }
 }
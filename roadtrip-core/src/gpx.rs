@@ -0,0 +1,77 @@
+// Tracks as GPX 1.1, which nearly every mapping tool can open. A path
+// becomes a single track segment, pieces become a segment each, and a lone
+// point becomes a waypoint.
+
+use crate::geometry::{Geometry, Path, Point};
+
+use chrono::SecondsFormat;
+
+use std::io::{self, Write};
+
+const HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="roadtrip"
+ xmlns="http://www.topografix.com/GPX/1/1"
+ xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+ xsi:schemaLocation="http://www.topografix.com/GPX/1/1 http://www.topografix.com/GPX/1/1/gpx.xsd">
+"#;
+
+fn write_segment<W: Write>(w: &mut W, path: &Path) -> io::Result<()> {
+    writeln!(w, "<trkseg>")?;
+
+    for point in path.iter() {
+        write_point(w, "trkpt", &point)?;
+    }
+
+    writeln!(w, "</trkseg>")
+}
+
+fn write_point<W: Write>(
+    w: &mut W,
+    tag: &str,
+    point: &Point,
+) -> io::Result<()> {
+    writeln!(
+        w,
+        r#"<{} lat="{}" lon="{}">"#,
+        tag,
+        point.latitude(),
+        point.longitude(),
+    )?;
+
+    // The schema wants `ele` before `time`.
+    if let Some(alt) = point.altitude() {
+        writeln!(w, "  <ele>{}</ele>", alt)?;
+    }
+
+    let time = point.time().to_rfc3339_opts(SecondsFormat::AutoSi, true);
+    writeln!(w, "  <time>{}</time>", time)?;
+
+    writeln!(w, "</{}>", tag)
+}
+
+impl Geometry {
+    pub fn write_gpx<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(HEADER.as_bytes())?;
+
+        match self {
+            Geometry::Point(p) => write_point(&mut w, "wpt", p)?,
+            Geometry::Path(p) => {
+                writeln!(w, "<trk>")?;
+                write_segment(&mut w, p)?;
+                writeln!(w, "</trk>")?;
+            }
+            Geometry::Pieces(ps) => {
+                writeln!(w, "<trk>")?;
+
+                for p in ps {
+                    write_segment(&mut w, p)?;
+                }
+
+                writeln!(w, "</trk>")?;
+            }
+        }
+
+        writeln!(w, "</gpx>")?;
+        w.flush()
+    }
+}
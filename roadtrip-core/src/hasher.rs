@@ -0,0 +1,162 @@
+use crate::Hash;
+
+use sha3::Digest;
+
+use std::fs::Metadata;
+use std::io;
+
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+// How much to read at a time when the file system doesn't say.
+pub const DEFAULT_BUFFER_LEN: usize = 10240;
+
+// A buffer size for reading the file `metadata` describes: the smallest
+// multiple of its block size (`st_blksize`) that's at least
+// `DEFAULT_BUFFER_LEN`.
+pub fn buffer_len(metadata: &Metadata) -> usize {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let block = metadata.blksize() as usize;
+        if block > 0 {
+            return (DEFAULT_BUFFER_LEN + block - 1) / block * block;
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = metadata;
+
+    DEFAULT_BUFFER_LEN
+}
+
+// Feeds everything left in `reader` to `hasher`, `buf_len` bytes at a time.
+pub async fn update_from_async_read<R>(
+    hasher: &mut dyn Hasher,
+    mut reader: R,
+    buf_len: usize,
+) -> io::Result<()>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut buf = vec![0u8; buf_len.max(1)];
+
+    loop {
+        let n_read = reader.read(&mut buf).await?;
+        if n_read == 0 {
+            break;
+        }
+
+        hasher.update(&buf[..n_read]);
+    }
+
+    Ok(())
+}
+
+impl Hash {
+    pub async fn from_async_read<R>(
+        reader: R,
+        algorithm: Algorithm,
+        buf_len: usize,
+    ) -> io::Result<Hash>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let mut hasher = algorithm.hasher();
+        update_from_async_read(&mut *hasher, reader, buf_len).await?;
+        Ok(hasher.finish())
+    }
+}
+
+// Computes a full `Hash` from data fed to it a piece at a time.
+pub trait Hasher: Send {
+    fn update(&mut self, data: &[u8]);
+
+    fn finish(self: Box<Self>) -> Hash;
+}
+
+// Media hashed with one algorithm never matches media hashed with another, so
+// switching algorithms means the same file shows up with a new identity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Algorithm {
+    Sha3_256,
+    Blake3,
+}
+
+impl Algorithm {
+    pub fn hasher(self) -> Box<dyn Hasher> {
+        match self {
+            Algorithm::Sha3_256 => Box::new(Sha3_256::default()),
+            Algorithm::Blake3 => Box::new(Blake3::default()),
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Algorithm::Sha3_256 => "sha3",
+            Algorithm::Blake3 => "blake3",
+        }
+    }
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sha3" => Some(Algorithm::Sha3_256),
+            "blake3" => Some(Algorithm::Blake3),
+            _ => None,
+        }
+    }
+}
+
+// How a hash was made. It's part of the hash, so two hashes made differently
+// are never equal, whatever their bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Kind {
+    // Every byte of the file.
+    Full(Algorithm),
+
+    // Only the file's size and a few pieces of it.
+    Sampled(Algorithm),
+}
+
+impl Kind {
+    pub fn algorithm(self) -> Algorithm {
+        match self {
+            Kind::Full(a) | Kind::Sampled(a) => a,
+        }
+    }
+}
+
+// What every hash was before there were other kinds.
+impl Default for Kind {
+    fn default() -> Self {
+        Kind::Full(Algorithm::Sha3_256)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Sha3_256(sha3::Sha3_256);
+
+impl Hasher for Sha3_256 {
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn finish(self: Box<Self>) -> Hash {
+        let array: [u8; 32] = self.0.finalize().into();
+        Hash::new(Kind::Full(Algorithm::Sha3_256), array)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Blake3(blake3::Hasher);
+
+impl Hasher for Blake3 {
+    fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    fn finish(self: Box<Self>) -> Hash {
+        let array: [u8; 32] = self.0.finalize().into();
+        Hash::new(Kind::Full(Algorithm::Blake3), array)
+    }
+}
@@ -0,0 +1,121 @@
+// Finds the media matching a filter without checking every one of them, by
+// keeping the bounding box of each track in an R-tree. Only media whose box
+// overlaps the filter's area get the full (and much slower) check.
+
+use crate::geometry::{Filter, Geometry};
+use crate::media::Media;
+
+use rstar::{RTree, RTreeObject, AABB};
+
+#[derive(Debug)]
+struct Entry {
+    idx: usize,
+    envelope: AABB<[f64; 2]>,
+}
+
+impl RTreeObject for Entry {
+    type Envelope = AABB<[f64; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        self.envelope
+    }
+}
+
+// `[lng, lat]` corners of the box around every point in `geometry`.
+fn envelope(geometry: &Geometry) -> Option<AABB<[f64; 2]>> {
+    let mut points = geometry.iter().map(|p| [p.longitude(), p.latitude()]);
+    let first = points.next()?;
+
+    let (lo, hi) = points.fold((first, first), |(lo, hi), p| {
+        (
+            [lo[0].min(p[0]), lo[1].min(p[1])],
+            [hi[0].max(p[0]), hi[1].max(p[1])],
+        )
+    });
+
+    Some(AABB::from_corners(lo, hi))
+}
+
+#[derive(Debug, Default)]
+pub struct MediaIndex {
+    media: Vec<Media>,
+    tree: RTree<Entry>,
+}
+
+impl MediaIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_iter<I>(media: I) -> Self
+    where
+        I: IntoIterator<Item = Media>,
+    {
+        let media: Vec<_> = media.into_iter().collect();
+
+        // Loading everything at once builds a better tree than inserting one
+        // at a time.
+        let entries = media
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, m)| {
+                Some(Entry {
+                    idx,
+                    envelope: envelope(m.geometry())?,
+                })
+            })
+            .collect();
+
+        Self {
+            media,
+            tree: RTree::bulk_load(entries),
+        }
+    }
+
+    pub fn insert(&mut self, media: Media) {
+        let idx = self.media.len();
+
+        if let Some(envelope) = envelope(media.geometry()) {
+            self.tree.insert(Entry { idx, envelope });
+        }
+
+        self.media.push(media);
+    }
+
+    pub fn len(&self) -> usize {
+        self.media.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.media.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Media> {
+        self.media.iter()
+    }
+
+    // Every media that matches `filter`, in no particular order.
+    pub fn matching<'a>(
+        &'a self,
+        filter: &'a Filter,
+    ) -> impl Iterator<Item = &'a Media> + 'a {
+        let bounds = filter.bounds();
+        let candidates: Box<dyn Iterator<Item = &Media>> = match bounds {
+            None => Box::new(self.media.iter()),
+            Some((lo, hi)) if lo.x > hi.x || lo.y > hi.y => {
+                Box::new(std::iter::empty())
+            }
+            Some((lo, hi)) => {
+                let area = AABB::from_corners([lo.x, lo.y], [hi.x, hi.y]);
+
+                Box::new(
+                    self.tree
+                        .locate_in_envelope_intersecting(&area)
+                        .map(move |e| &self.media[e.idx]),
+                )
+            }
+        };
+
+        candidates.filter(move |m| m.matches(filter))
+    }
+}
@@ -1,21 +1,130 @@
 pub mod datetime;
+#[cfg(feature = "geojson")]
+pub mod geojson;
 pub mod geometry;
+#[cfg(feature = "gpx")]
+pub mod gpx;
+pub mod hasher;
+pub mod index;
 pub mod media;
 
+mod error {
+    use snafu::Snafu;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility = "pub(super)")]
+    pub enum ParseHashError {
+        Hex { source: hex::FromHexError },
+        UnknownKind { name: String },
+    }
+}
+
+pub use self::error::ParseHashError;
+
+use crate::hasher::{Algorithm, Kind};
+
+use snafu::{OptionExt, ResultExt};
+
+use std::fmt;
 use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
-pub struct Hash(pub [u8; 32]);
+pub struct Hash {
+    kind: Kind,
+    bytes: [u8; 32],
+}
 
 impl Hash {
+    pub fn new(kind: Kind, bytes: [u8; 32]) -> Self {
+        Self { kind, bytes }
+    }
+
+    // Of the default kind, like `From<[u8; 32]>`.
     pub fn from_slice(slice: &[u8]) -> Self {
-        let mut new = Hash([0; 32]);
+        let mut new = Hash::from([0; 32]);
         new.copy_from_slice(slice);
         new
     }
 
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    // Only the bytes. `to_string` includes the kind too.
     pub fn to_hex(&self) -> String {
-        hex::encode(&self.0)
+        hex::encode(&self.bytes)
+    }
+
+    // The reverse of `to_hex`, accepting either case. The hash is of the
+    // default kind.
+    pub fn from_hex(text: &str) -> Result<Self, ParseHashError> {
+        let mut hash = Hash::from([0; 32]);
+        hex::decode_to_slice(text, &mut hash.bytes).context(error::Hex)?;
+        Ok(hash)
+    }
+
+    // Takes the same time whichever bytes differ, for comparing against a
+    // hash an attacker might be guessing at.
+    pub fn constant_time_eq(&self, other: &Hash) -> bool {
+        let diff = self
+            .bytes
+            .iter()
+            .zip(&other.bytes)
+            .fold(0, |d, (a, b)| d | (a ^ b));
+        self.kind == other.kind && diff == 0
+    }
+}
+
+// The hex bytes, after the algorithm (unless it's SHA3-256) and whether it
+// was sampled, all separated by dots: `blake3.sampled.<hex>`. Hashes of the
+// default kind are only hex, just like `to_hex`.
+impl fmt::Display for Hash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (algorithm, sampled) = match self.kind {
+            Kind::Full(a) => (a, false),
+            Kind::Sampled(a) => (a, true),
+        };
+
+        if algorithm != Algorithm::Sha3_256 {
+            write!(f, "{}.", algorithm.name())?;
+        }
+
+        if sampled {
+            f.write_str("sampled.")?;
+        }
+
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl FromStr for Hash {
+    type Err = ParseHashError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<_> = text.split('.').collect();
+        let hex = parts.pop().unwrap_or_default();
+
+        let mut algorithm = Algorithm::Sha3_256;
+        let mut sampled = false;
+
+        for part in parts {
+            if part == "sampled" {
+                sampled = true;
+            } else {
+                algorithm = Algorithm::from_name(part)
+                    .context(error::UnknownKind { name: part })?;
+            }
+        }
+
+        let mut hash = Hash::from_hex(hex)?;
+        hash.kind = if sampled {
+            Kind::Sampled(algorithm)
+        } else {
+            Kind::Full(algorithm)
+        };
+
+        Ok(hash)
     }
 }
 
@@ -23,24 +132,48 @@ impl Deref for Hash {
     type Target = [u8; 32];
 
     fn deref(&self) -> &[u8; 32] {
-        &self.0
+        &self.bytes
     }
 }
 
 impl DerefMut for Hash {
     fn deref_mut(&mut self) -> &mut [u8; 32] {
-        &mut self.0
+        &mut self.bytes
     }
 }
 
 impl From<Hash> for [u8; 32] {
     fn from(other: Hash) -> Self {
-        other.0
+        other.bytes
     }
 }
 
 impl From<[u8; 32]> for Hash {
     fn from(other: [u8; 32]) -> Self {
-        Hash(other)
+        Hash::new(Kind::default(), other)
+    }
+}
+
+// Written as a string, the same as `to_string`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Hash {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Hash {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(D::Error::custom)
     }
 }
@@ -1,16 +1,90 @@
-use crate::geometry::Geometry;
+use crate::geometry::{Filter, Geometry, Interpolation, PrivacyZone};
 use crate::Hash;
 
-use std::fs::File;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use typed_builder::TypedBuilder;
 
+// Names for the metadata most sources can fill in. Ingesters are free to add
+// their own, too. The camera model has a field of its own (`Media::camera`).
+pub mod keys {
+    // Size of the file, in bytes.
+    pub const FILE_SIZE: &str = "file-size";
+
+    // Who made the camera or logger, like `GoPro`.
+    pub const DEVICE: &str = "device";
+
+    // The camera's serial number.
+    pub const SERIAL: &str = "serial";
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MetadataValue {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+}
+
+impl fmt::Display for MetadataValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MetadataValue::Text(t) => f.write_str(t),
+            MetadataValue::Integer(i) => write!(f, "{}", i),
+            MetadataValue::Float(x) => write!(f, "{}", x),
+        }
+    }
+}
+
+impl From<String> for MetadataValue {
+    fn from(t: String) -> Self {
+        MetadataValue::Text(t)
+    }
+}
+
+impl From<&str> for MetadataValue {
+    fn from(t: &str) -> Self {
+        MetadataValue::Text(t.to_owned())
+    }
+}
+
+impl From<i64> for MetadataValue {
+    fn from(i: i64) -> Self {
+        MetadataValue::Integer(i)
+    }
+}
+
+impl From<f64> for MetadataValue {
+    fn from(x: f64) -> Self {
+        MetadataValue::Float(x)
+    }
+}
+
+// Extra details about a media that only some sources know, by name.
+pub type Metadata = BTreeMap<String, MetadataValue>;
+
 #[derive(Debug, TypedBuilder, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Media {
     path: PathBuf,
     geometry: Geometry,
     hash: Hash,
+    #[builder(default)]
+    camera: Option<String>,
+    #[builder(default)]
+    duration: Option<Duration>,
+    #[builder(default)]
+    resolution: Option<(u32, u32)>,
+    #[builder(default)]
+    container: Option<String>,
+    #[builder(default)]
+    codec: Option<String>,
+    #[builder(default)]
+    #[cfg_attr(feature = "serde", serde(default))]
+    metadata: Metadata,
 }
 
 impl Media {
@@ -25,22 +99,166 @@ impl Media {
     pub fn hash(&self) -> &Hash {
         &self.hash
     }
+
+    pub fn camera(&self) -> Option<&str> {
+        self.camera.as_deref()
+    }
+
+    // Length of the clip itself, which can differ from the span of its track.
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    // Width and height, in pixels.
+    pub fn resolution(&self) -> Option<(u32, u32)> {
+        self.resolution
+    }
+
+    // Short name of the file format, like `mov,mp4,m4a,3gp,3g2,mj2`.
+    pub fn container(&self) -> Option<&str> {
+        self.container.as_deref()
+    }
+
+    // Short name of the video codec, like `h264`.
+    pub fn codec(&self) -> Option<&str> {
+        self.codec.as_deref()
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    pub fn set_metadata<K, V>(&mut self, key: K, value: V)
+    where
+        K: Into<String>,
+        V: Into<MetadataValue>,
+    {
+        self.metadata.insert(key.into(), value.into());
+    }
+
+    pub fn shift_time(&mut self, by: chrono::Duration) {
+        self.geometry.shift_time(by);
+    }
+
+    pub fn densify(
+        &mut self,
+        interval: chrono::Duration,
+        interpolation: Interpolation,
+    ) {
+        self.geometry.densify(interval, interpolation);
+    }
+
+    // A copy with its track cut into the pieces outside of `zones`, or
+    // `None` if nothing is left.
+    pub fn redacted(&self, zones: &[PrivacyZone]) -> Option<Media> {
+        let geometry = Geometry::from_pieces(self.geometry.redacted(zones))?;
+        Some(Media {
+            geometry,
+            ..self.clone()
+        })
+    }
+
+    pub fn matches(&self, filter: &Filter) -> bool {
+        filter.camera_matches(self.camera())
+            && filter.metadata_matches(&self.metadata)
+            && self.geometry.matches(filter)
+    }
+}
+
+// One of the images making up a media's thumbnails. `index` is its place in
+// the sequence, earliest in the clip first.
+#[derive(Debug)]
+pub struct Thumbnail {
+    index: usize,
+    name: String,
+    data: Vec<u8>,
+}
+
+impl Thumbnail {
+    pub fn new(index: usize, name: String, data: Vec<u8>) -> Self {
+        Self { index, name, data }
+    }
+
+    // For files named after their index, like `03.jpg`.
+    pub fn from_name(name: String, data: Vec<u8>) -> Option<Self> {
+        let stem = name.split('.').next()?;
+        let index = stem.parse().ok()?;
+        Some(Self::new(index, name, data))
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
 }
 
 #[derive(Debug)]
 pub struct Thumbnails {
     media_hash: Hash,
-    files: Vec<File>,
+    thumbnails: Vec<Thumbnail>,
 }
 
 impl Thumbnails {
-    pub fn new<I>(media_hash: Hash, files: I) -> Self
+    pub fn new<I>(media_hash: Hash, thumbnails: I) -> Self
     where
-        I: Iterator<Item = File>,
+        I: IntoIterator<Item = Thumbnail>,
     {
+        let mut thumbnails: Vec<_> = thumbnails.into_iter().collect();
+        thumbnails.sort_by_key(Thumbnail::index);
+
+        Self {
+            media_hash,
+            thumbnails,
+        }
+    }
+
+    pub fn media_hash(&self) -> &Hash {
+        &self.media_hash
+    }
+
+    pub fn len(&self) -> usize {
+        self.thumbnails.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.thumbnails.is_empty()
+    }
+
+    // In order of their indices.
+    pub fn into_thumbnails(self) -> impl Iterator<Item = Thumbnail> {
+        self.thumbnails.into_iter()
+    }
+
+    // In order of their indices.
+    pub fn into_data(self) -> impl Iterator<Item = Vec<u8>> {
+        self.into_thumbnails().map(Thumbnail::into_data)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Frame {
+    media_hash: Hash,
+    offset: Duration,
+    data: Vec<u8>,
+}
+
+impl Frame {
+    pub fn new(media_hash: Hash, offset: Duration, data: Vec<u8>) -> Self {
         Self {
             media_hash,
-            files: files.collect(),
+            offset,
+            data,
         }
     }
 
@@ -48,7 +266,15 @@ impl Thumbnails {
         &self.media_hash
     }
 
-    pub fn into_files(self) -> impl Iterator<Item = File> {
-        self.files.into_iter()
+    pub fn offset(&self) -> Duration {
+        self.offset
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
     }
 }
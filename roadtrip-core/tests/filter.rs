@@ -0,0 +1,165 @@
+use chrono::{Duration, TimeZone, Utc};
+
+use roadtrip_core::datetime::DateTime;
+use roadtrip_core::geometry::{Filter, Geometry, Path, Point};
+use roadtrip_core::index::MediaIndex;
+use roadtrip_core::media::Media;
+use roadtrip_core::Hash;
+
+fn noon() -> DateTime {
+    Utc.ymd(2020, 8, 26).and_hms(12, 0, 0)
+}
+
+// A straight line between two fixes ten minutes apart, as `(lat, lng)`.
+fn line(a: (f64, f64), b: (f64, f64)) -> Geometry {
+    let start = Point::new(a.0, a.1, noon());
+    let end = Point::new(b.0, b.1, noon() + Duration::minutes(10));
+    Geometry::from(Path::from_iter(vec![start, end]))
+}
+
+fn media(geometry: Geometry) -> Media {
+    Media::builder()
+        .path("clip.mp4".into())
+        .geometry(geometry)
+        .hash(Hash::from([1; 32]))
+        .build()
+}
+
+// Whether `MediaIndex` agrees with matching directly, which it only can if
+// the filter's bounds hold everywhere it matches.
+fn indexed(geometry: Geometry, filter: &Filter) -> bool {
+    let index = MediaIndex::from_iter(Some(media(geometry.clone())));
+    let found = index.matching(filter).count() == 1;
+
+    assert_eq!(found, geometry.matches(filter));
+    found
+}
+
+#[test]
+fn time_between_fixes() {
+    let geometry = line((0.0, 0.0), (0.0, 1.0));
+
+    let inside = Filter::default()
+        .start(noon() + Duration::minutes(4))
+        .end(noon() + Duration::minutes(6));
+    assert!(indexed(geometry.clone(), &inside));
+
+    let after = Filter::default().start(noon() + Duration::minutes(11));
+    assert!(!indexed(geometry, &after));
+}
+
+#[test]
+fn time_and_place_together() {
+    // Passes through the box between 0.4 and 0.6 degrees, so four to six
+    // minutes in.
+    let geometry = line((0.0, 0.0), (0.0, 1.0));
+    let rect = Filter::default().rect(-0.1, 0.4, 0.1, 0.6);
+
+    let during = rect.clone().start(noon() + Duration::minutes(5));
+    assert!(indexed(geometry.clone(), &during));
+
+    let before = rect.end(noon() + Duration::minutes(3));
+    assert!(!indexed(geometry, &before));
+}
+
+#[test]
+fn time_without_duration() {
+    let point = Point::new(0.0, 0.0, noon());
+    let geometry = Geometry::from(Path::from_iter(vec![point, point]));
+
+    let filter = Filter::default().start(noon() + Duration::minutes(1));
+    assert!(!indexed(geometry, &filter));
+}
+
+#[test]
+fn rect_between_fixes() {
+    let rect = Filter::default().rect(-0.1, 0.4, 0.1, 0.6);
+
+    assert!(indexed(line((0.0, 0.0), (0.0, 1.0)), &rect));
+    assert!(indexed(line((-1.0, 0.0), (1.0, 1.0)), &rect));
+    assert!(!indexed(line((1.0, 0.0), (1.0, 1.0)), &rect));
+
+    // Diagonal past the corner.
+    assert!(!indexed(line((0.0, 0.75), (0.2, 0.55)), &rect));
+}
+
+#[test]
+fn rect_zero_length() {
+    let rect = Filter::default().rect(-0.1, 0.4, 0.1, 0.6);
+
+    assert!(indexed(line((0.0, 0.5), (0.0, 0.5)), &rect));
+    assert!(!indexed(line((0.0, 1.0), (0.0, 1.0)), &rect));
+}
+
+#[test]
+fn near_between_fixes() {
+    let near = Filter::default().near(0.0, 0.5, 1_000.0);
+
+    assert!(indexed(line((0.0, 0.0), (0.0, 1.0)), &near));
+    assert!(!indexed(line((0.1, 0.0), (0.1, 1.0)), &near));
+}
+
+#[test]
+fn near_tangent() {
+    // 0.01 degrees of latitude north of the line.
+    let distance = 6_371_008.8f64.to_radians() * 0.01;
+    let geometry = line((0.0, 0.0), (0.0, 1.0));
+
+    let touching = Filter::default().near(0.01, 0.5, distance * 1.000_001);
+    assert!(indexed(geometry.clone(), &touching));
+
+    let apart = Filter::default().near(0.01, 0.5, distance * 0.999_999);
+    assert!(!indexed(geometry, &apart));
+}
+
+#[test]
+fn near_zero_length() {
+    let near = Filter::default().near(0.0, 0.5, 1_000.0);
+
+    assert!(indexed(line((0.0, 0.5), (0.0, 0.5)), &near));
+    assert!(!indexed(line((0.0, 1.0), (0.0, 1.0)), &near));
+}
+
+#[test]
+fn near_antimeridian() {
+    let near = Filter::default().near(0.0, 179.999, 500.0);
+
+    // About 220 metres away, across the antimeridian.
+    let point = Point::new(0.0, -179.999, noon());
+    assert!(indexed(Geometry::from(point), &near));
+
+    // Each end is over a kilometre away, but the line between them passes
+    // right by.
+    assert!(indexed(line((0.0, 179.99), (0.0, -179.99)), &near));
+}
+
+#[test]
+fn polygon_between_fixes() {
+    let triangle =
+        Filter::default().polygon(vec![(-0.1, 0.4), (-0.1, 0.6), (0.1, 0.5)]);
+
+    assert!(indexed(line((0.0, 0.0), (0.0, 1.0)), &triangle));
+    assert!(!indexed(line((0.2, 0.0), (0.2, 1.0)), &triangle));
+
+    // Inside the triangle's box, but past its slanted edge.
+    assert!(!indexed(line((0.09, 0.0), (0.09, 0.45)), &triangle));
+}
+
+#[test]
+fn polygon_zero_length() {
+    let triangle =
+        Filter::default().polygon(vec![(-0.1, 0.4), (-0.1, 0.6), (0.1, 0.5)]);
+
+    assert!(indexed(line((0.0, 0.5), (0.0, 0.5)), &triangle));
+    assert!(!indexed(line((0.0, 1.0), (0.0, 1.0)), &triangle));
+}
+
+#[test]
+fn empty_polygon() {
+    let empty = Filter::default().polygon(Vec::new());
+
+    assert!(!indexed(line((0.0, 0.0), (0.0, 1.0)), &empty));
+
+    let point = Point::new(0.0, 0.0, noon());
+    assert!(!indexed(Geometry::from(point), &empty));
+}
@@ -0,0 +1,63 @@
+use roadtrip_core::hasher::{Algorithm, Kind};
+use roadtrip_core::{Hash, ParseHashError};
+
+const HEX: &str =
+    "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+fn counting() -> [u8; 32] {
+    let mut bytes = [0; 32];
+    for (idx, b) in bytes.iter_mut().enumerate() {
+        *b = idx as u8;
+    }
+    bytes
+}
+
+#[test]
+fn hex_round_trip() {
+    let hash = Hash::from(counting());
+
+    assert_eq!(hash.to_hex(), HEX);
+    assert_eq!(Hash::from_hex(HEX).unwrap(), hash);
+}
+
+#[test]
+fn hex_either_case() {
+    let upper = HEX.to_uppercase();
+    assert_eq!(Hash::from_hex(&upper).unwrap(), Hash::from(counting()));
+}
+
+#[test]
+fn hex_wrong_length() {
+    let short = Hash::from_hex(&HEX[2..]);
+    assert!(matches!(short, Err(ParseHashError::Hex { .. })));
+
+    let long = format!("{}00", HEX);
+    let long = Hash::from_hex(&long);
+    assert!(matches!(long, Err(ParseHashError::Hex { .. })));
+
+    assert!(Hash::from_hex("").is_err());
+}
+
+#[test]
+fn hex_not_hex() {
+    let text = HEX.replace('a', "g");
+    assert!(matches!(
+        Hash::from_hex(&text),
+        Err(ParseHashError::Hex { .. })
+    ));
+}
+
+#[test]
+fn parse_with_kind() {
+    let full = Hash::from(counting());
+    assert_eq!(full.to_string(), HEX);
+    assert_eq!(HEX.parse::<Hash>().unwrap(), full);
+
+    let sampled = Hash::new(Kind::Sampled(Algorithm::Blake3), counting());
+    let text = sampled.to_string();
+    assert_eq!(text, format!("blake3.sampled.{}", HEX));
+    assert_eq!(text.parse::<Hash>().unwrap(), sampled);
+
+    let unknown = format!("md5.{}", HEX).parse::<Hash>();
+    assert!(matches!(unknown, Err(ParseHashError::UnknownKind { .. })));
+}
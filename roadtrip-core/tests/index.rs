@@ -0,0 +1,117 @@
+use chrono::{Duration, TimeZone, Utc};
+
+use roadtrip_core::datetime::DateTime;
+use roadtrip_core::geometry::{Filter, Geometry, Path, Point};
+use roadtrip_core::index::MediaIndex;
+use roadtrip_core::media::Media;
+use roadtrip_core::Hash;
+
+fn noon() -> DateTime {
+    Utc.ymd(2020, 8, 26).and_hms(12, 0, 0)
+}
+
+// A line from `a` to `b`, as `(lat, lng)`, starting `start` hours past noon.
+fn clip(id: u8, start: i64, a: (f64, f64), b: (f64, f64)) -> Media {
+    let time = noon() + Duration::hours(start);
+    let points = vec![
+        Point::new(a.0, a.1, time),
+        Point::new(b.0, b.1, time + Duration::minutes(10)),
+    ];
+
+    Media::builder()
+        .path(format!("clip-{}.mp4", id).into())
+        .geometry(Geometry::from(Path::from_iter(points)))
+        .hash(Hash::from([id; 32]))
+        .build()
+}
+
+fn found(index: &MediaIndex, filter: &Filter) -> Vec<u8> {
+    let mut ids: Vec<_> = index.matching(filter).map(|m| m.hash()[0]).collect();
+    ids.sort();
+    ids
+}
+
+fn index() -> MediaIndex {
+    let mut index = MediaIndex::from_iter(vec![
+        clip(1, 0, (0.0, 0.0), (0.0, 1.0)),
+        clip(2, 1, (10.5, 10.0), (10.5, 11.0)),
+        // Its box covers the middle of the diagonal, but it doesn't.
+        clip(3, 2, (0.0, 0.65), (0.4, 1.05)),
+    ]);
+
+    index.insert(clip(4, 3, (10.5, 10.5), (10.5, 10.5)));
+    index
+}
+
+#[test]
+fn len() {
+    assert!(MediaIndex::new().is_empty());
+
+    let index = index();
+    assert_eq!(index.len(), 4);
+    assert!(!index.is_empty());
+    assert_eq!(index.iter().count(), 4);
+}
+
+#[test]
+fn matching_area() {
+    let index = index();
+
+    let rect = Filter::default().rect(-0.1, 0.4, 0.1, 0.6);
+    assert_eq!(found(&index, &rect), [1]);
+
+    let near = Filter::default().near(10.5, 10.5, 1_000.0);
+    assert_eq!(found(&index, &near), [2, 4]);
+
+    let diagonal = Filter::default().polygon(vec![
+        (0.3, 0.65),
+        (0.35, 0.65),
+        (0.35, 0.7),
+        (0.3, 0.7),
+    ]);
+    assert_eq!(found(&index, &diagonal), Vec::<u8>::new());
+}
+
+#[test]
+fn matching_anywhere() {
+    let index = index();
+
+    assert_eq!(found(&index, &Filter::default()), [1, 2, 3, 4]);
+
+    let later = Filter::default().start(noon() + Duration::hours(2));
+    assert_eq!(found(&index, &later), [3, 4]);
+}
+
+#[test]
+fn matching_nowhere() {
+    let index = index();
+
+    let both = Filter::default()
+        .rect(0.0, 0.0, 1.0, 1.0)
+        .near(10.5, 10.5, 1_000.0);
+    assert_eq!(found(&index, &both), Vec::<u8>::new());
+}
+
+#[test]
+fn matching_camera() {
+    let base = clip(5, 0, (0.0, 0.0), (0.0, 1.0));
+    let dashcam = Media::builder()
+        .path(base.path().to_owned())
+        .geometry(base.geometry().clone())
+        .hash(base.hash().clone())
+        .camera(Some("dashcam".to_owned()))
+        .build();
+
+    let mut index = index();
+    index.insert(dashcam.clone());
+
+    let filter = Filter::default()
+        .rect(-0.1, 0.4, 0.1, 0.6)
+        .camera("dashcam");
+    assert_eq!(found(&index, &filter), [5]);
+
+    // Tracks don't know which camera recorded them.
+    let other = Filter::default().camera("phone");
+    assert!(dashcam.geometry().matches(&other));
+    assert!(!dashcam.matches(&other));
+}
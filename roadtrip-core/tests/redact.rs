@@ -0,0 +1,130 @@
+use chrono::{Duration, TimeZone, Utc};
+
+use roadtrip_core::geometry::{Filter, Geometry, Path, Point, PrivacyZone};
+use roadtrip_core::media::Media;
+use roadtrip_core::Hash;
+
+// Heading east along the equator, one point a minute, a kilometre or so
+// apart.
+fn east(from: i64, to: i64) -> Path {
+    let noon = Utc.ymd(2020, 8, 26).and_hms(12, 0, 0);
+
+    Path::from_iter((from..to).map(|m| {
+        let time = noon + Duration::minutes(m);
+        Point::new(0.0, m as f64 * 0.01, time)
+    }))
+}
+
+fn lngs(geometry: &Geometry) -> Vec<f64> {
+    geometry
+        .iter()
+        .map(|p| (p.longitude() * 100.0).round())
+        .collect()
+}
+
+#[test]
+fn splits_at_zone() {
+    // Covers the points at 0.04 and 0.05.
+    let zone = PrivacyZone::circle(0.0, 0.045, 1_000.0);
+
+    let pieces = Geometry::from(east(0, 10)).redacted(&[zone]);
+
+    assert_eq!(pieces.len(), 2);
+    assert_eq!(lngs(&pieces[0]), [0.0, 1.0, 2.0, 3.0]);
+    assert_eq!(lngs(&pieces[1]), [6.0, 7.0, 8.0, 9.0]);
+}
+
+#[test]
+fn splits_where_line_crosses_zone() {
+    // Between the points at 0.04 and 0.05, without covering either.
+    let zone = PrivacyZone::circle(0.0, 0.045, 200.0);
+
+    let pieces = Geometry::from(east(0, 10)).redacted(&[zone]);
+
+    assert_eq!(pieces.len(), 2);
+    assert_eq!(lngs(&pieces[0]), [0.0, 1.0, 2.0, 3.0, 4.0]);
+    assert_eq!(lngs(&pieces[1]), [5.0, 6.0, 7.0, 8.0, 9.0]);
+}
+
+#[test]
+fn outside_zones_is_untouched() {
+    let zone = PrivacyZone::circle(10.0, 10.0, 1_000.0);
+
+    let pieces = Geometry::from(east(0, 10)).redacted(&[zone]);
+
+    assert_eq!(pieces.len(), 1);
+    assert_eq!(pieces[0].len(), 10);
+}
+
+#[test]
+fn inside_zone_is_empty() {
+    let zone =
+        PrivacyZone::polygon(vec![(-1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)]);
+    let point = Point::new(0.0, 0.5, Utc.ymd(2020, 8, 26).and_hms(12, 0, 0));
+
+    assert!(Geometry::from(point).redacted(&[zone.clone()]).is_empty());
+
+    let path = Path::from_iter(vec![point, point]);
+    assert!(Geometry::from(path).redacted(&[zone]).is_empty());
+}
+
+#[test]
+fn pieces_are_not_joined() {
+    let zone = PrivacyZone::circle(0.0, 0.045, 1_000.0);
+    let media = Media::builder()
+        .path("clip.mp4".into())
+        .geometry(Geometry::from(east(0, 10)))
+        .hash(Hash::from([1; 32]))
+        .build();
+
+    let redacted = media.redacted(&[zone]).unwrap();
+    let geometry = redacted.geometry();
+
+    assert!(matches!(geometry, Geometry::Pieces(ps) if ps.len() == 2));
+    assert_eq!(geometry.len(), 8);
+    assert_eq!(geometry.split_by_gap(Duration::hours(1)).len(), 2);
+
+    // Only the pieces count, not the distance between them.
+    let piece = Geometry::from(east(0, 4));
+    assert!(
+        (geometry.length_meters() - 2.0 * piece.length_meters()).abs() < 1e-6
+    );
+    assert_eq!(geometry.duration(), Duration::minutes(6));
+
+    // Nowhere near where the pieces were joined up before.
+    let time = Utc.ymd(2020, 8, 26).and_hms(12, 4, 30);
+    assert!(geometry.position_at(time).is_none());
+}
+
+#[test]
+fn hidden_points_do_not_match() {
+    let zone = PrivacyZone::circle(0.0, 0.045, 1_000.0);
+    let filter = Filter::default().near(0.0, 0.045, 100.0);
+
+    let geometry = Geometry::from(east(0, 10));
+    assert!(geometry.matches(&filter));
+
+    let redacted = Geometry::from_pieces(geometry.redacted(&[zone])).unwrap();
+    assert!(!redacted.matches(&filter));
+}
+
+#[test]
+fn enters() {
+    let geometry = Geometry::from(east(0, 10));
+
+    let covering = PrivacyZone::circle(0.0, 0.045, 1_000.0);
+    assert!(geometry.enters(&[covering]));
+
+    // Only the line between two fixes goes through it.
+    let between = PrivacyZone::circle(0.0, 0.045, 200.0);
+    assert!(geometry.enters(&[between.clone()]));
+
+    let away = PrivacyZone::circle(10.0, 10.0, 1_000.0);
+    assert!(!geometry.enters(&[away]));
+    assert!(!geometry.enters(&[]));
+
+    // Nothing redacted is left in the zone.
+    let zones = [between];
+    let pieces = Geometry::from_pieces(geometry.redacted(&zones)).unwrap();
+    assert!(!pieces.enters(&zones));
+}
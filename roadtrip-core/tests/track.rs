@@ -0,0 +1,133 @@
+use chrono::{Duration, TimeZone, Utc};
+
+use roadtrip_core::datetime::DateTime;
+use roadtrip_core::geometry::{Geometry, Path, Point};
+
+// Metres in a hundredth of a degree along the equator.
+const STEP: f64 = 6_371_008.8 * 0.01 * std::f64::consts::PI / 180.0;
+
+fn noon() -> DateTime {
+    Utc.ymd(2020, 8, 26).and_hms(12, 0, 0)
+}
+
+// Heading east along the equator, a hundredth of a degree a minute.
+fn east(minutes: i64) -> Geometry {
+    let points = (0..=minutes).map(|m| {
+        Point::new(0.0, m as f64 * 0.01, noon() + Duration::minutes(m))
+    });
+
+    Geometry::from(Path::from_iter(points))
+}
+
+#[test]
+fn length_and_speed() {
+    let geometry = east(10);
+
+    assert!((geometry.length_meters() - 10.0 * STEP).abs() < 1e-6);
+    assert_eq!(geometry.duration(), Duration::minutes(10));
+
+    let speed = geometry.average_speed().unwrap();
+    assert!((speed - STEP / 60.0).abs() < 1e-9);
+}
+
+#[test]
+fn point_has_no_speed() {
+    let geometry = Geometry::from(Point::new(0.0, 0.0, noon()));
+
+    assert_eq!(geometry.length_meters(), 0.0);
+    assert_eq!(geometry.duration(), Duration::zero());
+    assert_eq!(geometry.average_speed(), None);
+}
+
+#[test]
+fn no_time_has_no_speed() {
+    let points =
+        vec![Point::new(0.0, 0.0, noon()), Point::new(0.0, 0.01, noon())];
+    let geometry = Geometry::from(Path::from_iter(points));
+
+    assert!((geometry.length_meters() - STEP).abs() < 1e-6);
+    assert_eq!(geometry.average_speed(), None);
+}
+
+#[test]
+fn simplified_straight_line() {
+    let simple = east(10).simplified(1.0);
+
+    let lngs: Vec<_> = simple.iter().map(|p| p.longitude()).collect();
+    assert_eq!(lngs, [0.0, 0.1]);
+}
+
+#[test]
+fn simplified_keeps_corners() {
+    // A kilometre north halfway along, and back.
+    let points = (0..=10).map(|m| {
+        let lat = if m == 5 { 0.01 } else { 0.0 };
+        Point::new(lat, m as f64 * 0.01, noon() + Duration::minutes(m))
+    });
+    let geometry = Geometry::from(Path::from_iter(points));
+
+    let kept = geometry.simplified(STEP / 2.0);
+    let lats: Vec<_> = kept.iter().map(|p| p.latitude()).collect();
+    assert_eq!(lats, [0.0, 0.0, 0.01, 0.0, 0.0]);
+
+    // Nothing moves far enough to matter.
+    assert_eq!(geometry.simplified(STEP * 2.0).len(), 2);
+}
+
+#[test]
+fn simplified_short_paths() {
+    let one = Geometry::from(Point::new(0.0, 0.0, noon()));
+    assert_eq!(one.simplified(1.0).len(), 1);
+
+    assert_eq!(east(1).simplified(1e9).len(), 2);
+}
+
+#[test]
+fn split_by_gap() {
+    // Parked for an hour after the third fix.
+    let points = (0..6).map(|m| {
+        let minutes = if m < 3 { m } else { m + 60 };
+        Point::new(0.0, m as f64 * 0.01, noon() + Duration::minutes(minutes))
+    });
+    let geometry = Geometry::from(Path::from_iter(points));
+
+    let pieces = geometry.split_by_gap(Duration::minutes(10));
+    let lens: Vec<_> = pieces.iter().map(Geometry::len).collect();
+    assert_eq!(lens, [3, 3]);
+
+    // Exactly the gap isn't more than it.
+    assert_eq!(geometry.split_by_gap(Duration::minutes(61)).len(), 1);
+}
+
+#[test]
+fn split_by_gap_point() {
+    let point = Geometry::from(Point::new(0.0, 0.0, noon()));
+    assert_eq!(point.split_by_gap(Duration::zero()).len(), 1);
+
+    // Every fix is a minute apart.
+    assert_eq!(east(3).split_by_gap(Duration::seconds(30)).len(), 4);
+}
+
+#[test]
+fn simplified_nan() {
+    let points = (0..=4).map(|m| {
+        let lat = if m == 2 { f64::NAN } else { 0.0 };
+        Point::new(lat, m as f64 * 0.01, noon() + Duration::minutes(m))
+    });
+    let geometry = Geometry::from(Path::from_iter(points));
+
+    let lngs: Vec<_> = geometry
+        .simplified(1.0)
+        .iter()
+        .map(|p| p.longitude())
+        .collect();
+    assert_eq!(lngs, [0.0, 0.04]);
+
+    // Nor when an end is the bad fix.
+    let points = (0..=4).map(|m| {
+        let lat = if m == 0 { f64::NAN } else { 0.0 };
+        Point::new(lat, m as f64 * 0.01, noon() + Duration::minutes(m))
+    });
+    let geometry = Geometry::from(Path::from_iter(points));
+    assert_eq!(geometry.simplified(1.0).len(), 2);
+}
@@ -0,0 +1,191 @@
+use roadtrip::core::geometry::Filter;
+use roadtrip::core::media::Media;
+use roadtrip::core::Hash;
+use roadtrip::viewer::config::Config;
+use roadtrip::viewer::error::SendError;
+use roadtrip::viewer::{Handle, Resolution};
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tokio::sync::mpsc::{
+    unbounded_channel, UnboundedReceiver, UnboundedSender,
+};
+
+use tracing::error;
+
+#[derive(Debug)]
+enum Request {
+    Filter(Option<Filter>),
+    ScanMedia(PathBuf),
+    RemoveMedia(Vec<Hash>),
+    DeleteMedia(Vec<Hash>),
+    ExportMedia(Vec<Hash>, PathBuf),
+    ExportTracks(Vec<Hash>, PathBuf),
+    Favorite(Vec<Hash>, bool),
+    Tag(Vec<Hash>, String),
+    ResolveDuplicates(Hash, Resolution),
+    ImportHistory,
+    ListMedia,
+    Cameras,
+    Config,
+    SaveConfig(Config),
+    ThumbnailSize(u32),
+    FrameAt(Media, Duration),
+}
+
+impl Request {
+    async fn send(self, handle: &mut Handle) -> Result<(), SendError> {
+        match self {
+            Request::Filter(f) => handle.filter(f).await,
+            Request::ScanMedia(p) => handle.scan_media(p).await.map(|_| ()),
+            Request::RemoveMedia(h) => handle.remove_media(h).await,
+            Request::DeleteMedia(h) => handle.delete_media(h).await,
+            Request::ExportMedia(h, d) => handle.export_media(h, d).await,
+            Request::ExportTracks(h, d) => handle.export_tracks(h, d).await,
+            Request::Favorite(h, f) => handle.favorite(h, f).await,
+            Request::Tag(h, t) => handle.tag(h, t).await,
+            Request::ResolveDuplicates(h, r) => {
+                handle.resolve_duplicates(h, r).await
+            }
+            Request::ImportHistory => handle.import_history().await,
+            Request::ListMedia => handle.list_media().await,
+            Request::Cameras => handle.cameras().await,
+            Request::Config => handle.config().await,
+            Request::SaveConfig(c) => handle.save_config(c).await,
+            Request::ThumbnailSize(s) => handle.thumbnail_size(s).await,
+            Request::FrameAt(m, o) => handle.frame_at(m, o).await,
+        }
+    }
+}
+
+// Queues commands for the viewer without ever blocking the GTK main loop.
+// Requests are forwarded in order by a task on the viewer's runtime, which
+// absorbs any backpressure from the viewer's command channel. Results come
+// back to the UI as events.
+#[derive(Debug, Clone)]
+pub struct Bridge {
+    sender: UnboundedSender<Request>,
+}
+
+impl Bridge {
+    // Must be called from within the viewer's tokio runtime.
+    pub fn spawn(handle: Handle) -> Self {
+        let (sender, receiver) = unbounded_channel();
+        tokio::spawn(Self::forward(handle, receiver));
+        Self { sender }
+    }
+
+    async fn forward(
+        mut handle: Handle,
+        mut requests: UnboundedReceiver<Request>,
+    ) {
+        while let Some(request) = requests.recv().await {
+            if let Err(e) = request.send(&mut handle).await {
+                error!(error = %e, "unable to send command to viewer");
+                break;
+            }
+        }
+    }
+
+    fn send(&self, request: Request) {
+        // The receiver only goes away once the viewer has shut down, at which
+        // point there is nobody left to handle the request.
+        self.sender.send(request).ok();
+    }
+
+    pub fn filter<F>(&self, filter: F)
+    where
+        F: Into<Option<Filter>>,
+    {
+        self.send(Request::Filter(filter.into()))
+    }
+
+    pub fn scan_media<P>(&self, path: P)
+    where
+        P: Into<PathBuf>,
+    {
+        self.send(Request::ScanMedia(path.into()))
+    }
+
+    pub fn remove_media<I>(&self, hashes: I)
+    where
+        I: IntoIterator<Item = Hash>,
+    {
+        self.send(Request::RemoveMedia(hashes.into_iter().collect()))
+    }
+
+    pub fn delete_media<I>(&self, hashes: I)
+    where
+        I: IntoIterator<Item = Hash>,
+    {
+        self.send(Request::DeleteMedia(hashes.into_iter().collect()))
+    }
+
+    pub fn export_media<I, P>(&self, hashes: I, dest: P)
+    where
+        I: IntoIterator<Item = Hash>,
+        P: Into<PathBuf>,
+    {
+        let hashes = hashes.into_iter().collect();
+        self.send(Request::ExportMedia(hashes, dest.into()))
+    }
+
+    pub fn export_tracks<I, P>(&self, hashes: I, dest: P)
+    where
+        I: IntoIterator<Item = Hash>,
+        P: Into<PathBuf>,
+    {
+        let hashes = hashes.into_iter().collect();
+        self.send(Request::ExportTracks(hashes, dest.into()))
+    }
+
+    pub fn favorite<I>(&self, hashes: I, favorite: bool)
+    where
+        I: IntoIterator<Item = Hash>,
+    {
+        let hashes = hashes.into_iter().collect();
+        self.send(Request::Favorite(hashes, favorite))
+    }
+
+    pub fn tag<I, S>(&self, hashes: I, tag: S)
+    where
+        I: IntoIterator<Item = Hash>,
+        S: Into<String>,
+    {
+        let hashes = hashes.into_iter().collect();
+        self.send(Request::Tag(hashes, tag.into()))
+    }
+
+    pub fn resolve_duplicates(&self, hash: Hash, resolution: Resolution) {
+        self.send(Request::ResolveDuplicates(hash, resolution))
+    }
+
+    pub fn import_history(&self) {
+        self.send(Request::ImportHistory)
+    }
+
+    pub fn list_media(&self) {
+        self.send(Request::ListMedia)
+    }
+
+    pub fn cameras(&self) {
+        self.send(Request::Cameras)
+    }
+
+    pub fn config(&self) {
+        self.send(Request::Config)
+    }
+
+    pub fn save_config(&self, config: Config) {
+        self.send(Request::SaveConfig(config))
+    }
+
+    pub fn thumbnail_size(&self, size: u32) {
+        self.send(Request::ThumbnailSize(size))
+    }
+
+    pub fn frame_at(&self, media: Media, offset: Duration) {
+        self.send(Request::FrameAt(media, offset))
+    }
+}
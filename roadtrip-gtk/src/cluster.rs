@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+const TILE_SIZE: f64 = 256.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cluster {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub count: usize,
+}
+
+pub fn project(lat: f64, lng: f64, zoom: i32) -> (f64, f64) {
+    let scale = TILE_SIZE * 2f64.powi(zoom);
+    let lat = lat.max(-85.0511).min(85.0511).to_radians();
+
+    let x = (lng + 180.0) / 360.0 * scale;
+    let y = (1.0 - (lat.tan() + 1.0 / lat.cos()).ln() / PI) / 2.0 * scale;
+
+    (x, y)
+}
+
+pub fn cluster<I>(points: I, zoom: i32, cell_px: f64) -> Vec<Cluster>
+where
+    I: IntoIterator<Item = (f64, f64)>,
+{
+    let mut cells: HashMap<(i64, i64), (f64, f64, usize)> = HashMap::new();
+
+    for (lat, lng) in points {
+        let (x, y) = project(lat, lng, zoom);
+        let key = ((x / cell_px) as i64, (y / cell_px) as i64);
+
+        let cell = cells.entry(key).or_insert((0.0, 0.0, 0));
+        cell.0 += lat;
+        cell.1 += lng;
+        cell.2 += 1;
+    }
+
+    cells
+        .into_iter()
+        .map(|(_, (lat, lng, count))| Cluster {
+            latitude: lat / count as f64,
+            longitude: lng / count as f64,
+            count,
+        })
+        .collect()
+}
+
+pub fn marker(count: usize) -> Option<gdk_pixbuf::Pixbuf> {
+    let radius = 12.0 + 4.0 * (count as f64).log10();
+    let size = (2.0 * radius).ceil() as i32 + 2;
+
+    let surface =
+        cairo::ImageSurface::create(cairo::Format::ARgb32, size, size).ok()?;
+
+    {
+        let cr = cairo::Context::new(&surface);
+        let center = f64::from(size) / 2.0;
+
+        cr.arc(center, center, radius, 0.0, 2.0 * PI);
+        cr.set_source_rgba(0.2, 0.4, 0.8, 0.8);
+        cr.fill_preserve();
+        cr.set_source_rgba(1.0, 1.0, 1.0, 0.9);
+        cr.set_line_width(1.5);
+        cr.stroke();
+
+        let text = count.to_string();
+        cr.set_font_size(radius * 0.9);
+        let extents = cr.text_extents(&text);
+        cr.move_to(
+            center - extents.width / 2.0 - extents.x_bearing,
+            center - extents.height / 2.0 - extents.y_bearing,
+        );
+        cr.show_text(&text);
+    }
+
+    gdk::pixbuf_get_from_surface(&surface, 0, 0, size, size)
+}
@@ -0,0 +1,62 @@
+use gio::prelude::*;
+
+use glib::ToVariant;
+
+use gtk::prelude::*;
+
+use std::path::Path;
+
+const NAME: &str = "org.freedesktop.FileManager1";
+const OBJECT: &str = "/org/freedesktop/FileManager1";
+
+fn call_show_items(uris: Vec<String>) -> Result<(), glib::Error> {
+    let bus =
+        gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>)?;
+    let args = (uris, "").to_variant();
+
+    bus.call_sync(
+        Some(NAME),
+        OBJECT,
+        NAME,
+        "ShowItems",
+        Some(&args),
+        None,
+        gio::DBusCallFlags::NONE,
+        -1,
+        None::<&gio::Cancellable>,
+    )?;
+
+    Ok(())
+}
+
+// Reveals `paths` in the file manager, selecting them when the file manager
+// implements `org.freedesktop.FileManager1`. Otherwise the folder containing
+// the first path is opened without a selection.
+pub fn show_items<W, I, P>(parent: &W, paths: I) -> Result<(), glib::Error>
+where
+    W: IsA<gtk::Window>,
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let files: Vec<_> = paths
+        .into_iter()
+        .map(|p| gio::File::new_for_path(p.as_ref()))
+        .collect();
+
+    let first = match files.first() {
+        Some(f) => f.clone(),
+        None => return Ok(()),
+    };
+
+    let uris = files.iter().map(|f| f.get_uri().to_string()).collect();
+    if call_show_items(uris).is_ok() {
+        return Ok(());
+    }
+
+    let folder = first.get_parent().unwrap_or(first);
+    gtk::show_uri_on_window(
+        Some(parent),
+        &folder.get_uri(),
+        gtk::get_current_event_time(),
+    )
+}
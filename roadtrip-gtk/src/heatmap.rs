@@ -0,0 +1,80 @@
+use crate::cluster::project;
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+const CELL_PX: f64 = 8.0;
+const MAX_ALPHA: f64 = 0.6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub zoom: i32,
+    pub width: f64,
+    pub height: f64,
+}
+
+// Maps a density between zero and one onto a blue → yellow → red ramp.
+fn color(density: f64) -> (f64, f64, f64) {
+    if density < 0.5 {
+        let t = density * 2.0;
+        (t, t, 1.0 - t)
+    } else {
+        let t = (density - 0.5) * 2.0;
+        (1.0, 1.0 - t, 0.0)
+    }
+}
+
+pub fn draw<I>(cr: &cairo::Context, view: View, points: I)
+where
+    I: IntoIterator<Item = (f64, f64)>,
+{
+    let (cx, cy) = project(view.latitude, view.longitude, view.zoom);
+    let left = cx - view.width / 2.0;
+    let top = cy - view.height / 2.0;
+
+    let mut cells: HashMap<(i64, i64), usize> = HashMap::new();
+
+    for (lat, lng) in points {
+        let (x, y) = project(lat, lng, view.zoom);
+        let (x, y) = (x - left, y - top);
+
+        if x < -CELL_PX
+            || y < -CELL_PX
+            || x > view.width + CELL_PX
+            || y > view.height + CELL_PX
+        {
+            continue;
+        }
+
+        let key = ((x / CELL_PX).floor() as i64, (y / CELL_PX).floor() as i64);
+        *cells.entry(key).or_default() += 1;
+    }
+
+    let max = match cells.values().max() {
+        Some(m) => *m as f64,
+        None => return,
+    };
+
+    // Logarithmic scaling keeps a single busy road from washing out every
+    // route that was only driven a few times.
+    let scale = (max + 1.0).ln();
+    let radius = CELL_PX * 1.5;
+
+    for ((col, row), count) in cells {
+        let density = (count as f64 + 1.0).ln() / scale;
+        let (r, g, b) = color(density);
+
+        let x = (col as f64 + 0.5) * CELL_PX;
+        let y = (row as f64 + 0.5) * CELL_PX;
+
+        let gradient = cairo::RadialGradient::new(x, y, 0.0, x, y, radius);
+        gradient.add_color_stop_rgba(0.0, r, g, b, MAX_ALPHA * density);
+        gradient.add_color_stop_rgba(1.0, r, g, b, 0.0);
+
+        cr.set_source(&gradient);
+        cr.arc(x, y, radius, 0.0, 2.0 * PI);
+        cr.fill();
+    }
+}
@@ -0,0 +1,51 @@
+use gettextrs::{gettext, ngettext, LocaleCategory};
+
+use std::time::SystemTime;
+
+const DOMAIN: &str = "roadtrip";
+
+pub fn init() {
+    let dir = option_env!("ROADTRIP_LOCALEDIR").unwrap_or("/usr/share/locale");
+
+    gettextrs::setlocale(LocaleCategory::LcAll, "");
+    gettextrs::bindtextdomain(DOMAIN, dir);
+    gettextrs::bind_textdomain_codeset(DOMAIN, "UTF-8");
+    gettextrs::textdomain(DOMAIN);
+}
+
+// Translated strings use `{}` placeholders, filled in order from `args`.
+fn substitute(text: &str, args: &[&str]) -> String {
+    let mut parts = text.split("{}");
+    let mut out = parts.next().unwrap_or_default().to_owned();
+
+    for (idx, part) in parts.enumerate() {
+        out.push_str(args.get(idx).copied().unwrap_or_default());
+        out.push_str(part);
+    }
+
+    out
+}
+
+pub fn i18n(text: &str) -> String {
+    gettext(text)
+}
+
+pub fn i18n_f(format: &str, args: &[&str]) -> String {
+    substitute(&gettext(format), args)
+}
+
+pub fn ni18n_f(single: &str, plural: &str, n: u32, args: &[&str]) -> String {
+    substitute(&ngettext(single, plural, n), args)
+}
+
+pub fn format_time(time: SystemTime) -> String {
+    let secs = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(d) => d.as_secs() as i64,
+        Err(_) => return String::new(),
+    };
+
+    glib::DateTime::new_from_unix_local(secs)
+        .format("%c")
+        .map(|s| s.to_string())
+        .unwrap_or_default()
+}
@@ -1,5 +1,15 @@
+mod bridge;
+mod cluster;
+mod file_manager;
+mod heatmap;
+mod i18n;
 mod main_window;
+mod player;
+mod track_style;
+mod ui_state;
+mod welcome;
 
+use crate::bridge::Bridge;
 use crate::main_window::Main;
 
 use futures::StreamExt;
@@ -8,27 +18,29 @@ use gio::prelude::*;
 
 use glib::MainContext;
 
-use roadtrip::viewer::{SyncHandle, Viewer};
+use roadtrip::viewer::Viewer;
 
 use std::cell::RefCell;
 use std::sync::mpsc::{sync_channel, SyncSender};
 use std::thread;
 
+use tracing_subscriber::EnvFilter;
+
 thread_local! {
     static MAIN: RefCell<Option<Main>> = RefCell::new(None);
 }
 
 #[tokio::main]
-async fn viewer(sender: SyncSender<SyncHandle>) {
+async fn viewer(sender: SyncSender<Bridge>) {
     let viewer = Viewer::spawn().await.unwrap();
 
-    let handle = viewer.handle().into_sync().clone();
-    sender.send(handle.clone()).unwrap();
+    let bridge = Bridge::spawn(viewer.handle());
+    sender.send(bridge.clone()).unwrap();
 
     let exit_handle = viewer.handle().clone();
     tokio::spawn(async move {
         // Wait for the sender to be dropped, then exit.
-        tokio::task::spawn_blocking(move || sender.send(handle))
+        tokio::task::spawn_blocking(move || sender.send(bridge))
             .await
             .unwrap()
             .unwrap_err();
@@ -51,6 +63,12 @@ async fn viewer(sender: SyncSender<SyncHandle>) {
 }
 
 fn main() -> Result<(), i32> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .init();
+
+    i18n::init();
+
     let application = gtk::Application::new(
         Some("rocks.tabby.roadtrip"),
         gio::ApplicationFlags::empty(),
@@ -63,14 +81,14 @@ fn main() -> Result<(), i32> {
         .spawn(|| viewer(sender))
         .expect("unable to start viewer thread");
 
-    let handle = receiver.recv().unwrap();
+    let bridge = receiver.recv().unwrap();
 
     application.connect_startup(move |app| {
         MAIN.with(|m| {
             let mut holder = m.borrow_mut();
             assert!(holder.is_none());
 
-            let main = main_window::Main::new(app.clone(), handle.clone());
+            let main = main_window::Main::new(app.clone(), bridge.clone());
             main.actions();
             main.build();
             main.show_all();
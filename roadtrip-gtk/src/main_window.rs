@@ -1,23 +1,37 @@
+use crate::bridge::Bridge;
+use crate::cluster;
+use crate::file_manager;
+use crate::heatmap;
+use crate::i18n::{format_time, i18n, i18n_f, ni18n_f};
+use crate::player::Player;
+use crate::track_style::{self, ColorBy, TrackStyle};
+use crate::ui_state::{MapView, UiState, Ymd};
+use crate::welcome::Welcome;
+
 use gio::prelude::*;
 
 use glib::clone;
 
 use gtk::prelude::*;
 
-use osmgpsmap::{
-    MapExt, MapPoint, MapPolygon, MapPolygonExt, MapTrackExt,
-};
+use osmgpsmap::{MapExt, MapPoint, MapPolygon, MapPolygonExt, MapTrackExt};
 
-use roadtrip::core::geometry::Filter;
-use roadtrip::core::media::{Media, Thumbnails};
+use roadtrip::core::geometry::{Filter, Point};
+use roadtrip::core::media::{Frame, Media, Thumbnails};
 use roadtrip::core::Hash;
-use roadtrip::ingest::error::Error as IngestError;
-use roadtrip::viewer::{Event, SyncHandle};
+use roadtrip::viewer::config::Config;
+use roadtrip::viewer::labels::Labels;
+use roadtrip::viewer::notification::{Kind as NotificationKind, Notification};
+use roadtrip::viewer::{Event, Resolution};
 
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::convert::TryInto;
+use std::path::{Path, PathBuf};
 use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+use tracing::{debug, error, warn};
 
 const ICON: &[u8] = include_bytes!("../assets/icon.gdk");
 const PLACEHOLDER: &[u8] = include_bytes!("../assets/placeholder.gdk");
@@ -70,6 +84,28 @@ impl DatePicker {
         self.vbox.pack_end(&self.calendar, true, true, 0);
     }
 
+    pub fn get_ymd(&self) -> Option<Ymd> {
+        if self.switch.get_active() {
+            Some(self.calendar.get_date())
+        } else {
+            None
+        }
+    }
+
+    pub fn set_ymd(&self, ymd: Option<Ymd>) {
+        let (year, month, day) = match ymd {
+            Some(d) => d,
+            None => {
+                self.switch.set_active(false);
+                return;
+            }
+        };
+
+        self.switch.set_active(true);
+        self.calendar.select_month(month, year);
+        self.calendar.select_day(day);
+    }
+
     pub fn get_date(&self) -> Option<glib::Date> {
         if !self.switch.get_active() {
             return None;
@@ -104,6 +140,10 @@ struct FilterMenu {
     btn: gtk::MenuButton,
     img: gtk::Image,
     pop: gtk::Popover,
+    vbox: gtk::Box,
+    camera_box: gtk::Box,
+    camera_label: gtk::Label,
+    camera: gtk::ComboBoxText,
     dates_box: gtk::Box,
     hide_after: DatePicker,
     hide_before: DatePicker,
@@ -115,9 +155,13 @@ impl FilterMenu {
         Self {
             img: gtk::Image::new(),
             pop: gtk::Popover::new(Some(&btn)),
+            vbox: gtk::Box::new(gtk::Orientation::Vertical, 12),
+            camera_box: gtk::Box::new(gtk::Orientation::Horizontal, 6),
+            camera_label: gtk::Label::new(Some(&*i18n("Camera"))),
+            camera: gtk::ComboBoxText::new(),
             dates_box: gtk::Box::new(gtk::Orientation::Horizontal, 10),
-            hide_after: DatePicker::new("Hide After"),
-            hide_before: DatePicker::new("Hide Before"),
+            hide_after: DatePicker::new(&i18n("Hide After")),
+            hide_before: DatePicker::new(&i18n("Hide Before")),
             btn,
         }
     }
@@ -137,8 +181,49 @@ impl FilterMenu {
             .add(&gtk::Separator::new(gtk::Orientation::Vertical));
         self.dates_box.add(&self.hide_after.vbox);
 
-        self.pop.add(&self.dates_box);
-        self.dates_box.show_all();
+        self.camera_label.set_halign(gtk::Align::Start);
+        self.camera_box
+            .pack_start(&self.camera_label, true, true, 0);
+        self.camera_box.pack_end(&self.camera, false, false, 0);
+        self.set_cameras(&[]);
+
+        self.vbox.set_border_width(6);
+        self.vbox.add(&self.camera_box);
+        self.vbox.add(&self.dates_box);
+
+        self.pop.add(&self.vbox);
+        self.vbox.show_all();
+    }
+
+    pub fn get_camera(&self) -> Option<String> {
+        self.camera
+            .get_active_id()
+            .map(String::from)
+            .filter(|id| !id.is_empty())
+    }
+
+    pub fn set_camera(&self, camera: Option<&str>) {
+        let camera = camera.unwrap_or_default();
+
+        // Cameras arrive from the viewer asynchronously, so make sure the
+        // requested one is selectable before the list is populated.
+        if !self.camera.set_active_id(Some(camera)) {
+            self.camera.append(Some(camera), camera);
+            self.camera.set_active_id(Some(camera));
+        }
+    }
+
+    pub fn set_cameras(&self, cameras: &[String]) {
+        let current = self.get_camera();
+
+        self.camera.remove_all();
+        self.camera.append(Some(""), &i18n("All Cameras"));
+
+        for camera in cameras {
+            self.camera.append(Some(camera), camera);
+        }
+
+        self.set_camera(current.as_deref());
     }
 }
 
@@ -148,6 +233,11 @@ struct MainMenu {
     img: gtk::Image,
     pop: gtk::Popover,
     menu: gio::Menu,
+    import_menu: gio::Menu,
+    view_menu: gio::Menu,
+    color_menu: gio::Menu,
+    width_menu: gio::Menu,
+    recent_menu: gio::Menu,
     app_menu: gio::Menu,
 }
 
@@ -157,6 +247,11 @@ impl MainMenu {
 
         Self {
             menu: gio::Menu::new(),
+            import_menu: gio::Menu::new(),
+            view_menu: gio::Menu::new(),
+            color_menu: gio::Menu::new(),
+            width_menu: gio::Menu::new(),
+            recent_menu: gio::Menu::new(),
             app_menu: gio::Menu::new(),
             img: gtk::Image::new(),
             pop: gtk::Popover::new(Some(&btn)),
@@ -165,9 +260,58 @@ impl MainMenu {
     }
 
     pub fn build(&self) {
-        self.app_menu.append(Some("About"), Some("app.about"));
+        self.app_menu
+            .append(Some(&*i18n("About")), Some("app.about"));
         self.app_menu.freeze();
 
+        self.import_menu
+            .append_submenu(Some(&*i18n("Recent Imports")), &self.recent_menu);
+        self.import_menu
+            .append(Some(&*i18n("Export Map…")), Some("app.export-map"));
+        self.import_menu
+            .append(Some(&*i18n("Duplicates…")), Some("app.show-duplicates"));
+        self.import_menu.freeze();
+
+        let colors = [
+            (i18n("Uniform"), ColorBy::Uniform),
+            (i18n("Speed"), ColorBy::Speed),
+            (i18n("Date"), ColorBy::Date),
+            (i18n("Camera"), ColorBy::Camera),
+        ];
+
+        for (label, color_by) in &colors {
+            let item = gio::MenuItem::new(Some(label.as_str()), None);
+            item.set_action_and_target_value(
+                Some("app.track-color"),
+                Some(&color_by.as_str().to_variant()),
+            );
+            self.color_menu.append_item(&item);
+        }
+        self.color_menu.freeze();
+
+        let widths =
+            [(i18n("Thin"), 2), (i18n("Normal"), 4), (i18n("Thick"), 6)];
+
+        for (label, width) in &widths {
+            let item = gio::MenuItem::new(Some(label.as_str()), None);
+            item.set_action_and_target_value(
+                Some("app.track-width"),
+                Some(&width.to_variant()),
+            );
+            self.width_menu.append_item(&item);
+        }
+        self.width_menu.freeze();
+
+        self.view_menu
+            .append(Some(&*i18n("Heatmap")), Some("app.heatmap"));
+        self.view_menu
+            .append_submenu(Some(&*i18n("Color Tracks By")), &self.color_menu);
+        self.view_menu
+            .append_submenu(Some(&*i18n("Track Width")), &self.width_menu);
+        self.view_menu.freeze();
+
+        self.menu.append_section(None, &self.import_menu);
+        self.menu.append_section(None, &self.view_menu);
         self.menu.append_section(None, &self.app_menu);
         self.menu.freeze();
 
@@ -181,6 +325,214 @@ impl MainMenu {
     }
 }
 
+impl MainMenu {
+    pub fn set_recent(&self, roots: &[PathBuf]) {
+        self.recent_menu.remove_all();
+
+        for root in roots {
+            let label = root.to_string_lossy();
+            let item = gio::MenuItem::new(Some(&*label), None);
+            item.set_action_and_target_value(
+                Some("app.import"),
+                Some(&label.to_variant()),
+            );
+            self.recent_menu.append_item(&item);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ErrorCenter {
+    btn: gtk::Button,
+    img: gtk::Image,
+    store: gtk::ListStore,
+}
+
+impl ErrorCenter {
+    const COL_TIME: u32 = 0;
+    const COL_PATH: u32 = 1;
+    const COL_MESSAGE: u32 = 2;
+
+    const CLEAR: gtk::ResponseType = gtk::ResponseType::Other(1);
+    const RETRY: gtk::ResponseType = gtk::ResponseType::Other(2);
+
+    pub fn new() -> Self {
+        let cols = &[
+            String::static_type(),
+            String::static_type(),
+            String::static_type(),
+        ];
+
+        Self {
+            btn: gtk::Button::new(),
+            img: gtk::Image::new(),
+            store: gtk::ListStore::new(cols),
+        }
+    }
+
+    pub fn build(&self) {
+        self.img.set_from_icon_name(
+            Some("dialog-warning-symbolic"),
+            gtk::IconSize::Button,
+        );
+
+        self.btn.set_image(Some(&self.img));
+        self.btn.set_always_show_image(true);
+        self.btn.set_tooltip_text(Some(&*i18n("Show errors")));
+        self.btn.set_action_name(Some("app.show-errors"));
+    }
+
+    pub fn push(&self, notification: &Notification) {
+        let time = format_time(notification.time());
+
+        let path = notification.path().to_string_lossy().into_owned();
+
+        self.store.insert_with_values(
+            None,
+            &[Self::COL_TIME, Self::COL_PATH, Self::COL_MESSAGE],
+            &[&time, &path, &notification.message()],
+        );
+
+        self.update();
+    }
+
+    pub fn update(&self) {
+        let count = self.store.iter_n_children(None);
+        self.btn.set_label(&count.to_string());
+        self.btn.set_visible(count > 0);
+    }
+
+    fn view(&self) -> gtk::TreeView {
+        let view = gtk::TreeView::with_model(&self.store);
+        let columns = &[
+            (i18n("Time"), Self::COL_TIME),
+            (i18n("File"), Self::COL_PATH),
+            (i18n("Error"), Self::COL_MESSAGE),
+        ];
+
+        for (title, col) in columns {
+            let renderer = gtk::CellRendererText::new();
+            let column = gtk::TreeViewColumn::new();
+            column.set_title(title);
+            column.set_resizable(true);
+            column.pack_start(&renderer, true);
+            column.add_attribute(&renderer, "text", *col as i32);
+            view.append_column(&column);
+        }
+
+        view.get_selection().set_mode(gtk::SelectionMode::Multiple);
+        view
+    }
+
+    fn take_selected(&self, view: &gtk::TreeView) -> Vec<String> {
+        let (rows, _) = view.get_selection().get_selected_rows();
+
+        let iters: Vec<_> = rows
+            .iter()
+            .filter_map(|row| self.store.get_iter(row))
+            .collect();
+
+        let mut paths = Vec::with_capacity(iters.len());
+        for iter in iters {
+            let value = self.store.get_value(&iter, Self::COL_PATH as i32);
+            if let Ok(Some(path)) = value.get::<String>() {
+                paths.push(path);
+            }
+            self.store.remove(&iter);
+        }
+
+        paths
+    }
+
+    pub fn run<F>(&self, parent: &gtk::ApplicationWindow, mut retry: F)
+    where
+        F: FnMut(String),
+    {
+        let dialog = gtk::Dialog::with_buttons(
+            Some(&*i18n("Errors")),
+            Some(parent),
+            gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+            &[
+                (&*i18n("Clear"), Self::CLEAR),
+                (&*i18n("Retry"), Self::RETRY),
+                (&*i18n("Close"), gtk::ResponseType::Close),
+            ],
+        );
+
+        let view = self.view();
+        let scroll = gtk::ScrolledWindow::new::<gtk::Adjustment, gtk::Adjustment>(
+            None, None,
+        );
+        scroll.set_min_content_width(600);
+        scroll.set_min_content_height(300);
+        scroll.add(&view);
+
+        dialog.get_content_area().pack_start(&scroll, true, true, 0);
+        dialog.show_all();
+
+        loop {
+            match dialog.run() {
+                Self::CLEAR => self.store.clear(),
+                Self::RETRY => {
+                    for path in self.take_selected(&view) {
+                        retry(path);
+                    }
+                }
+                _ => break,
+            }
+
+            self.update();
+        }
+
+        self.update();
+        dialog.close();
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MediaMenu {
+    menu: gio::Menu,
+}
+
+impl MediaMenu {
+    pub fn new() -> Self {
+        Self {
+            menu: gio::Menu::new(),
+        }
+    }
+
+    pub fn build(&self) {
+        let labels = gio::Menu::new();
+        labels.append(
+            Some(&*i18n("Toggle Favorite")),
+            Some("app.favorite-media"),
+        );
+        labels.append(Some(&*i18n("Add Tag…")), Some("app.tag-media"));
+        self.menu.append_section(None, &labels);
+
+        let files = gio::Menu::new();
+        files.append(Some(&*i18n("Open Folder")), Some("app.open-folder"));
+        files.append(Some(&*i18n("Export…")), Some("app.export-media"));
+        files.append(Some(&*i18n("Export Tracks…")), Some("app.export-tracks"));
+        files.append(
+            Some(&*i18n("Remove from Library")),
+            Some("app.remove-media"),
+        );
+        files.append(Some(&*i18n("Delete File…")), Some("app.delete-media"));
+        self.menu.append_section(None, &files);
+
+        self.menu.freeze();
+    }
+}
+
+#[derive(Debug)]
+struct MediaRow {
+    iter: gtk::TreeIter,
+    polygons: Vec<MapPolygon>,
+    media: Media,
+    labels: Labels,
+}
+
 #[derive(Debug, Clone)]
 pub struct Main(Rc<Inner>);
 
@@ -205,67 +557,133 @@ impl glib::clone::Upgrade for MainWeak {
 
 #[derive(Debug)]
 struct Inner {
-    viewer: RefCell<SyncHandle>,
+    viewer: Bridge,
+    ui_state: RefCell<UiState>,
+    config: RefCell<Config>,
     application: gtk::Application,
     window: gtk::ApplicationWindow,
     header_bar: gtk::HeaderBar,
     main_menu: MainMenu,
     filter_menu: FilterMenu,
+    media_menu: MediaMenu,
+    error_center: ErrorCenter,
     add_media_btn: gtk::Button,
     status_box: gtk::Box,
     status_bar: gtk::Statusbar,
+    footer: gtk::Box,
+    thumbnail_scale: gtk::Scale,
+    thumbnail_size: Cell<i32>,
+    thumbnail_timeout: RefCell<Option<glib::SourceId>>,
     icon_scroll: gtk::ScrolledWindow,
     icon_view: gtk::IconView,
     paned: gtk::Paned,
+    stack: gtk::Stack,
+    welcome: Welcome,
+    watches: RefCell<Vec<gio::FileMonitor>>,
 
-    placeholder: gdk_pixbuf::Pixbuf,
-    media: RefCell<HashMap<Hash, gtk::TreeIter>>,
+    placeholder: RefCell<gdk_pixbuf::Pixbuf>,
+    media: RefCell<HashMap<Hash, MediaRow>>,
     media_store: gtk::ListStore,
 
     map: osmgpsmap::Map,
+    map_overlay: gtk::Overlay,
+    heatmap: gtk::DrawingArea,
+    map_press: Cell<Option<(f64, f64)>>,
+    map_tracks_shown: Cell<bool>,
+    map_refresh_queued: Cell<bool>,
+
+    thumbnails_pulsing: Cell<bool>,
+
+    player: RefCell<Option<Player>>,
+    recent_imports: RefCell<Vec<PathBuf>>,
+    duplicates: RefCell<HashMap<Hash, Vec<PathBuf>>>,
 
     status_media_scan: u32,
+    status_export: u32,
 }
 
 impl Main {
     const COL_NAME: u32 = 0;
     const COL_PIXBUF: u32 = 1;
+    const COL_PENDING: u32 = 2;
+    const COL_PULSE: u32 = 3;
+
+    const PULSE_MS: u32 = 80;
 
-    pub fn new(application: gtk::Application, viewer: SyncHandle) -> Self {
+    const TRACK_TOLERANCE: f64 = 10.0;
+
+    const CLUSTER_BELOW_ZOOM: i32 = 11;
+    const CLUSTER_CELL_PX: f64 = 64.0;
+
+    const THUMBNAIL_MIN: f64 = 96.0;
+    const THUMBNAIL_MAX: f64 = 512.0;
+    const THUMBNAIL_STEP: f64 = 32.0;
+    const THUMBNAIL_DELAY_MS: u32 = 300;
+
+    pub fn new(application: gtk::Application, viewer: Bridge) -> Self {
         let status_bar = gtk::Statusbar::new();
 
-        let media_cols =
-            &[String::static_type(), gdk_pixbuf::Pixbuf::static_type()];
+        let media_cols = &[
+            String::static_type(),
+            gdk_pixbuf::Pixbuf::static_type(),
+            bool::static_type(),
+            u32::static_type(),
+        ];
 
-        // TODO: Figure out how to generate this at the correct size instead of
-        //       scaling.
-        let placeholder = gdk_pixbuf::Pixbuf::from_inline(PLACEHOLDER, false)
-            .unwrap()
-            .scale_simple(200, 200, gdk_pixbuf::InterpType::Bilinear)
-            .unwrap();
+        let ui_state = UiState::load();
+        let thumbnail_size = ui_state.thumbnail_size;
+        let placeholder = Self::placeholder(thumbnail_size);
 
         let inner = Inner {
             window: gtk::ApplicationWindow::new(&application),
             header_bar: gtk::HeaderBar::new(),
             main_menu: MainMenu::new(),
             filter_menu: FilterMenu::new(),
+            media_menu: MediaMenu::new(),
+            error_center: ErrorCenter::new(),
             add_media_btn: gtk::Button::new(),
             status_box: gtk::Box::new(gtk::Orientation::Vertical, 0),
             paned: gtk::Paned::new(gtk::Orientation::Vertical),
+            stack: gtk::Stack::new(),
+            welcome: Welcome::new(),
+            watches: Default::default(),
             icon_view: gtk::IconView::new(),
             icon_scroll: gtk::ScrolledWindow::new::<
                 gtk::Adjustment,
                 gtk::Adjustment,
             >(None, None),
             map: osmgpsmap::Map::new(),
+            map_overlay: gtk::Overlay::new(),
+            heatmap: gtk::DrawingArea::new(),
+            map_press: Cell::new(None),
+            map_tracks_shown: Cell::new(true),
+            map_refresh_queued: Cell::new(false),
+
+            thumbnails_pulsing: Cell::new(false),
+
+            player: RefCell::new(None),
+            recent_imports: RefCell::new(Vec::new()),
+            duplicates: Default::default(),
 
-            placeholder,
+            placeholder: RefCell::new(placeholder),
             media: Default::default(),
             media_store: gtk::ListStore::new(media_cols),
 
             status_media_scan: status_bar.get_context_id("media-scan"),
-
-            viewer: RefCell::new(viewer),
+            status_export: status_bar.get_context_id("export"),
+
+            viewer,
+            ui_state: RefCell::new(ui_state),
+            config: Default::default(),
+            footer: gtk::Box::new(gtk::Orientation::Horizontal, 6),
+            thumbnail_scale: gtk::Scale::with_range(
+                gtk::Orientation::Horizontal,
+                Self::THUMBNAIL_MIN,
+                Self::THUMBNAIL_MAX,
+                Self::THUMBNAIL_STEP,
+            ),
+            thumbnail_size: Cell::new(thumbnail_size),
+            thumbnail_timeout: RefCell::new(None),
             status_bar,
             application,
         };
@@ -273,6 +691,80 @@ impl Main {
         Main(Rc::new(inner))
     }
 
+    fn placeholder(size: i32) -> gdk_pixbuf::Pixbuf {
+        // TODO: Figure out how to generate this at the correct size instead of
+        //       scaling.
+        gdk_pixbuf::Pixbuf::from_inline(PLACEHOLDER, false)
+            .unwrap()
+            .scale_simple(size, size, gdk_pixbuf::InterpType::Bilinear)
+            .unwrap()
+    }
+
+    fn thumbnail_size_changed(&self) {
+        let inner = &self.0;
+
+        let raw = inner.thumbnail_scale.get_value();
+        let snapped =
+            (raw / Self::THUMBNAIL_STEP).round() * Self::THUMBNAIL_STEP;
+        let size =
+            snapped.max(Self::THUMBNAIL_MIN).min(Self::THUMBNAIL_MAX) as i32;
+
+        if size == inner.thumbnail_size.get() {
+            return;
+        }
+
+        inner.thumbnail_size.set(size);
+        inner.icon_view.set_item_width(size + 10);
+
+        // Rescale what's already visible, so the grid responds immediately
+        // while the viewer renders thumbnails at the new resolution.
+        *inner.placeholder.borrow_mut() = Self::placeholder(size);
+
+        for row in inner.media.borrow().values() {
+            let value = inner
+                .media_store
+                .get_value(&row.iter, Self::COL_PIXBUF as i32);
+
+            let pixbuf = match value.get::<gdk_pixbuf::Pixbuf>() {
+                Ok(Some(p)) => p,
+                _ => continue,
+            };
+
+            if let Some(scaled) = pixbuf.scale_simple(
+                size,
+                size,
+                gdk_pixbuf::InterpType::Bilinear,
+            ) {
+                inner.media_store.set_value(
+                    &row.iter,
+                    Self::COL_PIXBUF,
+                    &glib::Value::from(&scaled),
+                );
+            }
+        }
+
+        if let Some(source) = inner.thumbnail_timeout.borrow_mut().take() {
+            glib::source_remove(source);
+        }
+
+        let source = glib::timeout_add_local(
+            Self::THUMBNAIL_DELAY_MS,
+            clone!(@weak self as this => @default-return glib::Continue(false),
+            move || {
+                this.0.thumbnail_timeout.borrow_mut().take();
+                this.request_thumbnail_size();
+                glib::Continue(false)
+            }),
+        );
+
+        *inner.thumbnail_timeout.borrow_mut() = Some(source);
+    }
+
+    fn request_thumbnail_size(&self) {
+        let size = self.0.thumbnail_size.get() as u32;
+        self.0.viewer.thumbnail_size(size);
+    }
+
     fn about(&self) {
         // TODO: Fill the rest of this out.
 
@@ -285,8 +777,10 @@ impl Main {
             .transient_for(&self.0.window)
             .program_name("Roadtrip")
             .version(env!("CARGO_PKG_VERSION"))
-            .title("About")
-            .comments("A media player for dashcams and other geotagged content")
+            .title(&i18n("About"))
+            .comments(&i18n(
+                "A media player for dashcams and other geotagged content",
+            ))
             .authors(authors)
             .build();
 
@@ -300,17 +794,23 @@ impl Main {
             .get_str()
             .expect("import activated with non-str parameter");
 
-        self.0.viewer.borrow_mut().scan_media(path_str).unwrap();
+        let viewer = &self.0.viewer;
+        viewer.scan_media(path_str);
+        viewer.import_history();
     }
 
     fn choose_import(&self) {
         let dialog = gtk::FileChooserNativeBuilder::new()
             .select_multiple(true)
             .transient_for(&self.0.window)
-            .title("Import From")
+            .title(&i18n("Import From"))
             .action(gtk::FileChooserAction::SelectFolder)
             .build();
 
+        for root in self.0.recent_imports.borrow().iter() {
+            dialog.add_shortcut_folder(root).ok();
+        }
+
         if dialog.run() != gtk::ResponseType::Accept {
             return;
         }
@@ -328,102 +828,1116 @@ impl Main {
         }
     }
 
-    pub fn actions(&self) {
-        let about = gio::SimpleAction::new("about", None);
-        about.connect_activate(
-            clone!(@weak self as this => move |_, _| this.about()),
-        );
-        self.0.application.add_action(&about);
+    fn choose_watch(&self) {
+        let dialog = gtk::FileChooserNativeBuilder::new()
+            .select_multiple(true)
+            .transient_for(&self.0.window)
+            .title(&i18n("Watch Folder"))
+            .action(gtk::FileChooserAction::SelectFolder)
+            .build();
 
-        let choose_import = gio::SimpleAction::new("choose-import", None);
-        choose_import.connect_activate(
-            clone!(@weak self as this => move |_, _| this.choose_import()),
-        );
-        self.0.application.add_action(&choose_import);
+        if dialog.run() != gtk::ResponseType::Accept {
+            return;
+        }
 
-        let import = gio::SimpleAction::new(
-            "import",
-            Some(&String::static_variant_type()),
-        );
-        import.connect_activate(
-            clone!(@weak self as this => move |_, v| this.import(v)),
-        );
-        self.0.application.add_action(&import);
+        let config = {
+            let mut config = self.0.config.borrow_mut();
+
+            for filename in dialog.get_filenames() {
+                if config.watched.contains(&filename) {
+                    continue;
+                }
+
+                self.watch(&filename);
+                config.watched.push(filename);
+            }
+
+            config.clone()
+        };
+
+        self.0.viewer.save_config(config);
     }
 
-    fn filter(&self) {
+    // Imports everything in `path` now, then imports each file that finishes
+    // being written into it while the application is running.
+    fn watch(&self, path: &Path) {
         let inner = &self.0;
+        let dir = gio::File::new_for_path(path);
 
-        let opt_before = inner
-            .filter_menu
-            .hide_before
-            .get_date()
-            .map(Self::date_to_midnight_local)
-            .as_ref()
-            .map(|b| b.to_utc().expect("tz convert"))
-            .map(Self::glib_datetime_to_chrono);
+        let monitor = match dir.monitor_directory(
+            gio::FileMonitorFlags::WATCH_MOVES,
+            None::<&gio::Cancellable>,
+        ) {
+            Ok(m) => m,
+            Err(e) => {
+                warn!(
+                    path = %path.display(),
+                    error = %e,
+                    "unable to watch folder"
+                );
+                return;
+            }
+        };
 
-        let opt_after = inner
-            .filter_menu
-            .hide_after
-            .get_date()
-            .map(Self::date_to_midnight_local)
-            .map(|a| a.add_days(1).expect("days out of range"))
-            .as_ref()
-            .map(|a| a.to_utc().expect("tz convert"))
-            .map(Self::glib_datetime_to_chrono);
+        monitor.connect_changed(
+            clone!(@weak self as this => move |_, file, other, event| {
+                let file = match event {
+                    gio::FileMonitorEvent::ChangesDoneHint => file,
+                    gio::FileMonitorEvent::MovedIn => file,
+                    gio::FileMonitorEvent::Renamed => match other {
+                        Some(o) => o,
+                        None => return,
+                    },
+                    _ => return,
+                };
+
+                let path = file.get_path();
+                let path = match path.as_ref().and_then(|p| p.to_str()) {
+                    Some(p) => p,
+                    None => return,
+                };
+
+                this.0
+                    .application
+                    .activate_action("import", Some(&path.to_variant()));
+            }),
+        );
 
-        let mut filter = Filter::default();
-        if let Some(before) = opt_before {
-            filter = filter.start(before);
-        }
+        inner.watches.borrow_mut().push(monitor);
 
-        if let Some(after) = opt_after {
-            filter = filter.end(after);
+        if let Some(path_str) = path.to_str() {
+            inner
+                .application
+                .activate_action("import", Some(&path_str.to_variant()));
         }
+    }
+
+    fn selected_media(&self) -> Vec<(Hash, PathBuf)> {
+        let inner = &self.0;
+        let selected = inner.icon_view.get_selected_items();
 
-        inner.viewer.borrow_mut().filter(filter).unwrap();
+        inner
+            .media
+            .borrow()
+            .iter()
+            .filter(|(_, row)| match inner.media_store.get_path(&row.iter) {
+                Some(p) => selected.contains(&p),
+                None => false,
+            })
+            .map(|(hash, row)| (hash.clone(), row.media.path().to_owned()))
+            .collect()
     }
 
-    fn glib_datetime_to_chrono(
-        date: glib::DateTime,
-    ) -> chrono::DateTime<chrono::Utc> {
-        assert_eq!(date.get_timezone(), Some(glib::TimeZone::new_utc()));
+    fn play(&self, tree_path: &gtk::TreePath) {
+        let inner = &self.0;
 
-        let (year, month, day) = date.get_ymd();
-        let cyear: i32 = year.try_into().expect("year out of range");
-        let cmonth: u32 = month.try_into().expect("month out of range");
-        let cday: u32 = day.try_into().expect("day out of range");
+        let media = inner
+            .media
+            .borrow()
+            .values()
+            .find(|row| {
+                inner.media_store.get_path(&row.iter).as_ref()
+                    == Some(tree_path)
+            })
+            .map(|row| row.media.clone());
+
+        let media = match media {
+            Some(m) => m,
+            None => return,
+        };
 
-        let chour: u32 = date.get_hour().try_into().expect("hour out of range");
-        let cminute: u32 =
-            date.get_minute().try_into().expect("minute out of range");
-        let csecond: u32 =
-            date.get_second().try_into().expect("second out of range");
+        if let Some(old) = inner.player.borrow_mut().take() {
+            old.close();
+        }
 
-        let naive = chrono::NaiveDate::from_ymd(cyear, 1 + cmonth, cday)
-            .and_hms(chour, cminute, csecond);
+        let player = match Player::new(&inner.window, media) {
+            Some(p) => p,
+            None => {
+                self.show_error(&i18n(
+                    "Unable to play video. Is the GStreamer GTK plugin installed?",
+                ));
+                return;
+            }
+        };
 
-        chrono::DateTime::from_utc(naive, chrono::Utc)
-    }
+        player.connect_position(
+            clone!(@weak self as this => move |p| this.player_position(p)),
+        );
+        player.connect_preview(
+            clone!(@weak self as this => move |o| this.player_preview(o)),
+        );
+        player.connect_close(
+            clone!(@weak self as this => move |p| this.player_closed(p)),
+        );
 
-    fn date_to_midnight_local(date: glib::Date) -> glib::DateTime {
-        use glib::DateMonth::*;
+        player.build();
+        player.show_all();
 
-        let month = match date.get_month() {
-            January => 0,
-            February => 1,
-            March => 2,
-            April => 3,
-            May => 4,
-            June => 5,
-            July => 6,
-            August => 7,
-            September => 8,
-            October => 9,
-            November => 10,
-            December => 11,
-            _ => panic!("month out of range"),
+        *inner.player.borrow_mut() = Some(player);
+    }
+
+    fn player_position(&self, offset: Duration) {
+        let player = match self.0.player.borrow().clone() {
+            Some(p) => p,
+            None => return,
+        };
+
+        let geometry = player.media().geometry();
+        let start = match geometry.iter().next() {
+            Some(p) => p.time(),
+            None => return,
+        };
+
+        let offset = match chrono::Duration::from_std(offset) {
+            Ok(o) => o,
+            Err(_) => return,
+        };
+
+        let map = &self.0.map;
+        map.gps_clear();
+
+        if let Some(point) = geometry.position_at(start + offset) {
+            map.gps_add(
+                point.latitude() as f32,
+                point.longitude() as f32,
+                std::f32::NAN,
+            );
+        }
+    }
+
+    fn player_preview(&self, offset: Duration) {
+        let player = match self.0.player.borrow().clone() {
+            Some(p) => p,
+            None => return,
+        };
+
+        let media = player.media().clone();
+        self.0.viewer.frame_at(media, offset);
+    }
+
+    fn player_closed(&self, closed: &Player) {
+        let mut player = self.0.player.borrow_mut();
+
+        let current = match &*player {
+            Some(p) => p.ptr_eq(closed),
+            None => false,
+        };
+
+        if current {
+            self.0.map.gps_clear();
+            player.take();
+        }
+    }
+
+    fn screen_to_degrees(&self, x: f64, y: f64) -> (f32, f32) {
+        let mut point = MapPoint::new_degrees(0.0, 0.0);
+        self.0
+            .map
+            .convert_screen_to_geographic(x as i32, y as i32, &mut point);
+        point.get_degrees()
+    }
+
+    fn seek_to_track(&self, x: f64, y: f64) {
+        let player = match self.0.player.borrow().clone() {
+            Some(p) => p,
+            None => return,
+        };
+
+        let geometry = player.media().geometry();
+        let start = match geometry.iter().next() {
+            Some(p) => p.time(),
+            None => return,
+        };
+
+        let (lat, lng) = self.screen_to_degrees(x, y);
+        let (tol_lat, tol_lng) =
+            self.screen_to_degrees(x + Self::TRACK_TOLERANCE, y);
+        let tolerance = (tol_lat - lat).hypot(tol_lng - lng) as f64;
+
+        let distance = |p: &Point| {
+            (p.latitude() - lat as f64).hypot(p.longitude() - lng as f64)
+        };
+
+        let nearest = geometry.iter().min_by(|a, b| {
+            distance(a)
+                .partial_cmp(&distance(b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let nearest = match nearest {
+            Some(n) if distance(&n) <= tolerance => n,
+            _ => return,
+        };
+
+        if let Ok(offset) = (nearest.time() - start).to_std() {
+            player.seek(offset);
+        }
+    }
+
+    fn map_zoom(&self) -> Option<i32> {
+        self.0.map.get_property("zoom").ok()?.get().ok()?
+    }
+
+    fn queue_map_refresh(&self) {
+        if self.0.map_refresh_queued.replace(true) {
+            return;
+        }
+
+        glib::idle_add_local(
+            clone!(@weak self as this => @default-return glib::Continue(false),
+            move || {
+                this.0.map_refresh_queued.set(false);
+                this.refresh_map();
+                glib::Continue(false)
+            }),
+        );
+    }
+
+    fn pulse_thumbnails(&self) {
+        if self.0.thumbnails_pulsing.replace(true) {
+            return;
+        }
+
+        glib::timeout_add_local(
+            Self::PULSE_MS,
+            clone!(@weak self as this => @default-return glib::Continue(false),
+            move || {
+                let pending = this.pulse_tick();
+                this.0.thumbnails_pulsing.set(pending);
+                glib::Continue(pending)
+            }),
+        );
+    }
+
+    fn pulse_tick(&self) -> bool {
+        let inner = &self.0;
+        let mut pending = false;
+
+        for row in inner.media.borrow().values() {
+            let store = &inner.media_store;
+
+            let active = store
+                .get_value(&row.iter, Self::COL_PENDING as i32)
+                .get_some::<bool>()
+                .unwrap_or(false);
+
+            if !active {
+                continue;
+            }
+
+            pending = true;
+
+            let pulse = store
+                .get_value(&row.iter, Self::COL_PULSE as i32)
+                .get_some::<u32>()
+                .unwrap_or(0);
+
+            store.set_value(
+                &row.iter,
+                Self::COL_PULSE,
+                &pulse.wrapping_add(1).to_value(),
+            );
+        }
+
+        pending
+    }
+
+    fn refresh_map(&self) {
+        let inner = &self.0;
+        let zoom = self.map_zoom().unwrap_or(Self::CLUSTER_BELOW_ZOOM);
+        let show_tracks = zoom >= Self::CLUSTER_BELOW_ZOOM;
+
+        inner.map.image_remove_all();
+        inner.heatmap.queue_draw();
+
+        if show_tracks != inner.map_tracks_shown.replace(show_tracks) {
+            inner.map.polygon_remove_all();
+
+            if show_tracks {
+                for row in inner.media.borrow().values() {
+                    for polygon in &row.polygons {
+                        inner.map.polygon_add(polygon);
+                    }
+                }
+            }
+        }
+
+        if show_tracks {
+            return;
+        }
+
+        let media = inner.media.borrow();
+        let points = media.values().filter_map(|row| {
+            let geometry = row.media.geometry();
+            let point = geometry.iter().nth(geometry.len() / 2)?;
+            Some((point.latitude(), point.longitude()))
+        });
+
+        for cluster in cluster::cluster(points, zoom, Self::CLUSTER_CELL_PX) {
+            if let Some(marker) = cluster::marker(cluster.count) {
+                inner.map.image_add(
+                    cluster.latitude as f32,
+                    cluster.longitude as f32,
+                    &marker,
+                );
+            }
+        }
+    }
+
+    fn set_heatmap(&self, shown: bool) {
+        let inner = &self.0;
+
+        inner.ui_state.borrow_mut().heatmap = shown;
+        inner.heatmap.set_no_show_all(!shown);
+        inner.heatmap.set_visible(shown);
+    }
+
+    fn draw_heatmap(
+        &self,
+        area: &gtk::DrawingArea,
+        cr: &cairo::Context,
+    ) -> gtk::Inhibit {
+        let view = match self.map_view() {
+            Some(v) => v,
+            None => return gtk::Inhibit(false),
+        };
+
+        let view = heatmap::View {
+            latitude: view.latitude.into(),
+            longitude: view.longitude.into(),
+            zoom: view.zoom,
+            width: area.get_allocated_width().into(),
+            height: area.get_allocated_height().into(),
+        };
+
+        let media = self.0.media.borrow();
+        let points = media
+            .values()
+            .flat_map(|row| row.media.geometry().iter())
+            .map(|p| (p.latitude(), p.longitude()));
+
+        heatmap::draw(cr, view, points);
+
+        gtk::Inhibit(false)
+    }
+
+    fn map_button_press(&self, event: &gdk::EventButton) -> gtk::Inhibit {
+        if event.get_button() == 1 {
+            self.0.map_press.set(Some(event.get_position()));
+        }
+
+        gtk::Inhibit(false)
+    }
+
+    fn map_button_release(&self, event: &gdk::EventButton) -> gtk::Inhibit {
+        let pressed = self.0.map_press.take();
+
+        // Only treat this as a click on the track if the map wasn't dragged.
+        if event.get_button() == 1 && pressed == Some(event.get_position()) {
+            let (x, y) = event.get_position();
+            self.seek_to_track(x, y);
+        }
+
+        gtk::Inhibit(false)
+    }
+
+    fn show_error(&self, text: &str) {
+        let dialog = gtk::MessageDialog::new(
+            Some(&self.0.window),
+            gtk::DialogFlags::MODAL,
+            gtk::MessageType::Error,
+            gtk::ButtonsType::Close,
+            text,
+        );
+        dialog.run();
+        dialog.close();
+    }
+
+    fn export_map(&self) {
+        let inner = &self.0;
+
+        let dialog = gtk::FileChooserNativeBuilder::new()
+            .transient_for(&inner.window)
+            .title(&i18n("Export Map"))
+            .action(gtk::FileChooserAction::Save)
+            .do_overwrite_confirmation(true)
+            .build();
+
+        dialog.set_current_name("map.png");
+
+        if dialog.run() != gtk::ResponseType::Accept {
+            return;
+        }
+
+        let path = match dialog.get_filename() {
+            Some(p) => p,
+            None => return,
+        };
+
+        if let Err(e) = self.write_map_png(&path) {
+            let msg = i18n_f(
+                "Unable to export map to {}: {}",
+                &[&path.to_string_lossy(), &e],
+            );
+            self.show_error(&msg);
+        }
+    }
+
+    fn write_map_png(&self, path: &std::path::Path) -> Result<(), String> {
+        let map = &self.0.map;
+        let width = map.get_allocated_width();
+        let height = map.get_allocated_height();
+
+        let surface =
+            cairo::ImageSurface::create(cairo::Format::ARgb32, width, height)
+                .map_err(|e| format!("{:?}", e))?;
+
+        {
+            let cr = cairo::Context::new(&surface);
+            map.draw(&cr);
+        }
+
+        let mut file =
+            std::fs::File::create(path).map_err(|e| e.to_string())?;
+
+        surface.write_to_png(&mut file).map_err(|e| e.to_string())
+    }
+
+    fn confirm(
+        &self,
+        text: &str,
+        buttons: &[(&str, gtk::ResponseType)],
+    ) -> gtk::ResponseType {
+        let dialog = gtk::MessageDialog::new(
+            Some(&self.0.window),
+            gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+            gtk::MessageType::Question,
+            gtk::ButtonsType::None,
+            text,
+        );
+
+        dialog.add_button(&i18n("Cancel"), gtk::ResponseType::Cancel);
+        for (label, response) in buttons {
+            dialog.add_button(label, *response);
+        }
+        dialog.set_default_response(gtk::ResponseType::Cancel);
+
+        let response = dialog.run();
+        dialog.close();
+        response
+    }
+
+    fn remove_media(&self) {
+        let selected = self.selected_media();
+        if selected.is_empty() {
+            return;
+        }
+
+        let text = ni18n_f(
+            "Remove {} item from the library?\n\n\
+             The file will not be deleted.",
+            "Remove {} items from the library?\n\n\
+             The files will not be deleted.",
+            selected.len() as u32,
+            &[&selected.len().to_string()],
+        );
+
+        let response = self
+            .confirm(&text, &[(&*i18n("Remove"), gtk::ResponseType::Accept)]);
+
+        if response != gtk::ResponseType::Accept {
+            return;
+        }
+
+        let hashes = selected.into_iter().map(|(hash, _)| hash);
+        self.0.viewer.remove_media(hashes);
+    }
+
+    fn delete_media(&self) {
+        const TRASH: gtk::ResponseType = gtk::ResponseType::Other(1);
+        const DELETE: gtk::ResponseType = gtk::ResponseType::Other(2);
+
+        let selected = self.selected_media();
+        if selected.is_empty() {
+            return;
+        }
+
+        let text = ni18n_f(
+            "Delete {} file from disk?\n\n\
+             Files deleted permanently cannot be recovered.",
+            "Delete {} files from disk?\n\n\
+             Files deleted permanently cannot be recovered.",
+            selected.len() as u32,
+            &[&selected.len().to_string()],
+        );
+
+        let response = self.confirm(
+            &text,
+            &[
+                (&*i18n("Delete Permanently"), DELETE),
+                (&*i18n("Move to Trash"), TRASH),
+            ],
+        );
+
+        let viewer = &self.0.viewer;
+
+        match response {
+            TRASH => {
+                let mut trashed = Vec::with_capacity(selected.len());
+
+                for (hash, path) in selected {
+                    let file = gio::File::new_for_path(&path);
+                    match file.trash(None::<&gio::Cancellable>) {
+                        Ok(()) => trashed.push(hash),
+                        Err(e) => {
+                            // TODO: Surface this in the UI.
+                            error!(
+                                path = %path.display(),
+                                error = %e,
+                                "unable to trash file"
+                            );
+                        }
+                    }
+                }
+
+                viewer.remove_media(trashed);
+            }
+            DELETE => {
+                let hashes = selected.into_iter().map(|(hash, _)| hash);
+                viewer.delete_media(hashes);
+            }
+            _ => (),
+        }
+    }
+
+    fn open_folder(&self) {
+        let selected = self.selected_media();
+        let paths = selected.iter().map(|(_, path)| path);
+
+        if let Err(e) = file_manager::show_items(&self.0.window, paths) {
+            self.show_error(&i18n_f(
+                "Unable to open folder: {}",
+                &[&e.to_string()],
+            ));
+        }
+    }
+
+    fn favorite_media(&self) {
+        let selected = self.selected_media();
+        if selected.is_empty() {
+            return;
+        }
+
+        // Clear the favorite only when every selected item is already one.
+        let all_favorite = {
+            let media = self.0.media.borrow();
+            selected.iter().all(|(hash, _)| {
+                media
+                    .get(hash)
+                    .map(|r| r.labels.favorite())
+                    .unwrap_or(false)
+            })
+        };
+
+        let hashes = selected.into_iter().map(|(hash, _)| hash);
+        self.0.viewer.favorite(hashes, !all_favorite);
+    }
+
+    fn tag_media(&self) {
+        let inner = &self.0;
+
+        let selected = self.selected_media();
+        if selected.is_empty() {
+            return;
+        }
+
+        let dialog = gtk::Dialog::with_buttons(
+            Some(&*i18n("Add Tag")),
+            Some(&inner.window),
+            gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+            &[
+                (&*i18n("Cancel"), gtk::ResponseType::Cancel),
+                (&*i18n("Add"), gtk::ResponseType::Accept),
+            ],
+        );
+        dialog.set_default_response(gtk::ResponseType::Accept);
+
+        let entry = gtk::Entry::new();
+        entry.set_activates_default(true);
+        entry.set_placeholder_text(Some(&*i18n("Tag")));
+
+        let content = dialog.get_content_area();
+        content.set_border_width(12);
+        content.add(&entry);
+        dialog.show_all();
+
+        let response = dialog.run();
+        let tag = entry.get_text().trim().to_owned();
+        dialog.close();
+
+        if response != gtk::ResponseType::Accept || tag.is_empty() {
+            return;
+        }
+
+        let hashes = selected.into_iter().map(|(hash, _)| hash);
+        inner.viewer.tag(hashes, tag);
+    }
+
+    fn export_media(&self) {
+        let inner = &self.0;
+
+        let selected = self.selected_media();
+        if selected.is_empty() {
+            return;
+        }
+
+        let dialog = gtk::FileChooserNativeBuilder::new()
+            .transient_for(&inner.window)
+            .title(&i18n("Export Media"))
+            .action(gtk::FileChooserAction::SelectFolder)
+            .build();
+
+        if dialog.run() != gtk::ResponseType::Accept {
+            return;
+        }
+
+        let dest = match dialog.get_filename() {
+            Some(p) => p,
+            None => return,
+        };
+
+        let hashes = selected.into_iter().map(|(hash, _)| hash);
+        inner.viewer.export_media(hashes, dest);
+    }
+
+    fn export_tracks(&self) {
+        let inner = &self.0;
+
+        let selected = self.selected_media();
+        if selected.is_empty() {
+            return;
+        }
+
+        let dialog = gtk::FileChooserNativeBuilder::new()
+            .transient_for(&inner.window)
+            .title(&i18n("Export Tracks"))
+            .action(gtk::FileChooserAction::SelectFolder)
+            .build();
+
+        if dialog.run() != gtk::ResponseType::Accept {
+            return;
+        }
+
+        let dest = match dialog.get_filename() {
+            Some(p) => p,
+            None => return,
+        };
+
+        let hashes = selected.into_iter().map(|(hash, _)| hash);
+        inner.viewer.export_tracks(hashes, dest);
+    }
+
+    fn media_context_menu(
+        &self,
+        view: &gtk::IconView,
+        event: &gdk::EventButton,
+    ) -> gtk::Inhibit {
+        if event.get_button() != 3 {
+            return gtk::Inhibit(false);
+        }
+
+        let (x, y) = event.get_position();
+        let path = match view.get_path_at_pos(x as i32, y as i32) {
+            Some(p) => p,
+            None => return gtk::Inhibit(false),
+        };
+
+        if !view.path_is_selected(&path) {
+            view.unselect_all();
+            view.select_path(&path);
+        }
+
+        let menu = gtk::Menu::from_model(&self.0.media_menu.menu);
+        menu.set_attach_widget(Some(view));
+        let trigger: &gdk::Event = event;
+        menu.popup_at_pointer(Some(trigger));
+
+        gtk::Inhibit(true)
+    }
+
+    fn show_duplicates(&self) {
+        let inner = &self.0;
+
+        let dialog = gtk::Dialog::with_buttons(
+            Some(&*i18n("Duplicates")),
+            Some(&inner.window),
+            gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+            &[(&*i18n("Close"), gtk::ResponseType::Close)],
+        );
+        dialog.set_default_size(600, 400);
+
+        let list = gtk::Box::new(gtk::Orientation::Vertical, 12);
+        list.set_border_width(12);
+
+        let groups = inner.duplicates.borrow().clone();
+        if groups.is_empty() {
+            list.add(&gtk::Label::new(Some(&*i18n("No duplicates found"))));
+        }
+
+        for (hash, paths) in groups {
+            list.add(&self.duplicate_group(hash, paths));
+        }
+
+        let scroll = gtk::ScrolledWindow::new::<gtk::Adjustment, gtk::Adjustment>(
+            None, None,
+        );
+        scroll.set_vexpand(true);
+        scroll.add(&list);
+
+        dialog.get_content_area().add(&scroll);
+        dialog.show_all();
+        dialog.run();
+        dialog.close();
+    }
+
+    fn duplicate_group(&self, hash: Hash, paths: Vec<PathBuf>) -> gtk::Frame {
+        let frame = gtk::Frame::new(None);
+        let vbox = gtk::Box::new(gtk::Orientation::Vertical, 6);
+        vbox.set_border_width(6);
+
+        let mut radios: Vec<gtk::RadioButton> = Vec::new();
+        for path in &paths {
+            let label = path.to_string_lossy();
+            let radio = match radios.first() {
+                Some(first) => {
+                    gtk::RadioButton::with_label_from_widget(first, &label)
+                }
+                None => gtk::RadioButton::with_label(&label),
+            };
+
+            vbox.add(&radio);
+            radios.push(radio);
+        }
+
+        let chosen = Rc::new(move || {
+            radios
+                .iter()
+                .zip(paths.iter())
+                .find(|(radio, _)| radio.get_active())
+                .map(|(_, path)| path.clone())
+        });
+
+        let buttons = gtk::ButtonBox::new(gtk::Orientation::Horizontal);
+        buttons.set_layout(gtk::ButtonBoxStyle::End);
+        buttons.set_spacing(6);
+
+        let ignore = gtk::Button::with_label(&i18n("Ignore"));
+        ignore.connect_clicked(
+            clone!(@weak self as this, @weak frame, @strong hash => move |_| {
+                this.resolve_duplicates(&frame, &hash, Resolution::Ignore);
+            }),
+        );
+
+        let keep = gtk::Button::with_label(&i18n("Keep Selected"));
+        keep.connect_clicked(
+            clone!(@weak self as this, @weak frame, @strong hash,
+                   @strong chosen => move |_| {
+                if let Some(path) = chosen() {
+                    let resolution = Resolution::Keep(path);
+                    this.resolve_duplicates(&frame, &hash, resolution);
+                }
+            }),
+        );
+
+        let delete = gtk::Button::with_label(&i18n("Delete Extras"));
+        delete.connect_clicked(
+            clone!(@weak self as this, @weak frame, @strong hash,
+                   @strong chosen => move |_| {
+                let path = match chosen() {
+                    Some(p) => p,
+                    None => return,
+                };
+
+                let text = i18n(
+                    "Delete every other copy of this file from disk?\n\n\
+                     Files deleted permanently cannot be recovered.",
+                );
+                let response = this.confirm(
+                    &text,
+                    &[(&*i18n("Delete"), gtk::ResponseType::Accept)],
+                );
+
+                if response == gtk::ResponseType::Accept {
+                    let resolution = Resolution::DeleteExtras(path);
+                    this.resolve_duplicates(&frame, &hash, resolution);
+                }
+            }),
+        );
+
+        buttons.add(&ignore);
+        buttons.add(&keep);
+        buttons.add(&delete);
+        vbox.add(&buttons);
+
+        frame.add(&vbox);
+        frame
+    }
+
+    fn resolve_duplicates(
+        &self,
+        frame: &gtk::Frame,
+        hash: &Hash,
+        resolution: Resolution,
+    ) {
+        frame.set_sensitive(false);
+        self.0.viewer.resolve_duplicates(hash.clone(), resolution);
+    }
+
+    fn show_errors(&self) {
+        let inner = &self.0;
+        inner.error_center.run(&inner.window, |path| {
+            inner
+                .application
+                .activate_action("import", Some(&path.to_variant()));
+        });
+    }
+
+    pub fn actions(&self) {
+        let about = gio::SimpleAction::new("about", None);
+        about.connect_activate(
+            clone!(@weak self as this => move |_, _| this.about()),
+        );
+        self.0.application.add_action(&about);
+
+        let choose_import = gio::SimpleAction::new("choose-import", None);
+        choose_import.connect_activate(
+            clone!(@weak self as this => move |_, _| this.choose_import()),
+        );
+        self.0.application.add_action(&choose_import);
+
+        let choose_watch = gio::SimpleAction::new("choose-watch", None);
+        choose_watch.connect_activate(
+            clone!(@weak self as this => move |_, _| this.choose_watch()),
+        );
+        self.0.application.add_action(&choose_watch);
+
+        let import = gio::SimpleAction::new(
+            "import",
+            Some(&String::static_variant_type()),
+        );
+        import.connect_activate(
+            clone!(@weak self as this => move |_, v| this.import(v)),
+        );
+        self.0.application.add_action(&import);
+
+        let remove_media = gio::SimpleAction::new("remove-media", None);
+        remove_media.connect_activate(
+            clone!(@weak self as this => move |_, _| this.remove_media()),
+        );
+        self.0.application.add_action(&remove_media);
+
+        let delete_media = gio::SimpleAction::new("delete-media", None);
+        delete_media.connect_activate(
+            clone!(@weak self as this => move |_, _| this.delete_media()),
+        );
+        self.0.application.add_action(&delete_media);
+
+        let favorite_media = gio::SimpleAction::new("favorite-media", None);
+        favorite_media.connect_activate(
+            clone!(@weak self as this => move |_, _| this.favorite_media()),
+        );
+        self.0.application.add_action(&favorite_media);
+
+        let tag_media = gio::SimpleAction::new("tag-media", None);
+        tag_media.connect_activate(
+            clone!(@weak self as this => move |_, _| this.tag_media()),
+        );
+        self.0.application.add_action(&tag_media);
+
+        let open_folder = gio::SimpleAction::new("open-folder", None);
+        open_folder.connect_activate(
+            clone!(@weak self as this => move |_, _| this.open_folder()),
+        );
+        self.0.application.add_action(&open_folder);
+
+        let export_media = gio::SimpleAction::new("export-media", None);
+        export_media.connect_activate(
+            clone!(@weak self as this => move |_, _| this.export_media()),
+        );
+        self.0.application.add_action(&export_media);
+
+        let export_tracks = gio::SimpleAction::new("export-tracks", None);
+        export_tracks.connect_activate(
+            clone!(@weak self as this => move |_, _| this.export_tracks()),
+        );
+        self.0.application.add_action(&export_tracks);
+
+        let export_map = gio::SimpleAction::new("export-map", None);
+        export_map.connect_activate(
+            clone!(@weak self as this => move |_, _| this.export_map()),
+        );
+        self.0.application.add_action(&export_map);
+
+        let show_duplicates = gio::SimpleAction::new("show-duplicates", None);
+        show_duplicates.connect_activate(
+            clone!(@weak self as this => move |_, _| this.show_duplicates()),
+        );
+        self.0.application.add_action(&show_duplicates);
+
+        let shown = self.0.ui_state.borrow().heatmap;
+        let heatmap = gio::SimpleAction::new_stateful(
+            "heatmap",
+            None,
+            &shown.to_variant(),
+        );
+        heatmap.connect_change_state(
+            clone!(@weak self as this => move |action, value| {
+                if let Some(value) = value {
+                    action.set_state(value);
+                    this.set_heatmap(value.get().unwrap_or(false));
+                }
+            }),
+        );
+        self.0.application.add_action(&heatmap);
+
+        let style = self.0.ui_state.borrow().track_style;
+
+        let track_color = gio::SimpleAction::new_stateful(
+            "track-color",
+            Some(&String::static_variant_type()),
+            &style.color_by.as_str().to_variant(),
+        );
+        track_color.connect_change_state(
+            clone!(@weak self as this => move |action, value| {
+                let value = match value {
+                    Some(v) => v,
+                    None => return,
+                };
+
+                let color_by = match value.get_str().and_then(ColorBy::parse) {
+                    Some(c) => c,
+                    None => return,
+                };
+
+                action.set_state(value);
+
+                let style = this.0.ui_state.borrow().track_style;
+                this.set_track_style(TrackStyle { color_by, ..style });
+            }),
+        );
+        self.0.application.add_action(&track_color);
+
+        let track_width = gio::SimpleAction::new_stateful(
+            "track-width",
+            Some(&i32::static_variant_type()),
+            &style.line_width.to_variant(),
+        );
+        track_width.connect_change_state(
+            clone!(@weak self as this => move |action, value| {
+                let value = match value {
+                    Some(v) => v,
+                    None => return,
+                };
+
+                let line_width = match value.get::<i32>() {
+                    Some(w) => w,
+                    None => return,
+                };
+
+                action.set_state(value);
+
+                let style = this.0.ui_state.borrow().track_style;
+                this.set_track_style(TrackStyle { line_width, ..style });
+            }),
+        );
+        self.0.application.add_action(&track_width);
+
+        let show_errors = gio::SimpleAction::new("show-errors", None);
+        show_errors.connect_activate(
+            clone!(@weak self as this => move |_, _| this.show_errors()),
+        );
+        self.0.application.add_action(&show_errors);
+    }
+
+    fn filter(&self) {
+        let inner = &self.0;
+
+        let opt_before = inner
+            .filter_menu
+            .hide_before
+            .get_date()
+            .map(Self::date_to_midnight_local)
+            .as_ref()
+            .map(|b| b.to_utc().expect("tz convert"))
+            .map(Self::glib_datetime_to_chrono);
+
+        let opt_after = inner
+            .filter_menu
+            .hide_after
+            .get_date()
+            .map(Self::date_to_midnight_local)
+            .map(|a| a.add_days(1).expect("days out of range"))
+            .as_ref()
+            .map(|a| a.to_utc().expect("tz convert"))
+            .map(Self::glib_datetime_to_chrono);
+
+        let mut filter = Filter::default();
+        if let Some(before) = opt_before {
+            filter = filter.start(before);
+        }
+
+        if let Some(after) = opt_after {
+            filter = filter.end(after);
+        }
+
+        if let Some(camera) = inner.filter_menu.get_camera() {
+            filter = filter.camera(camera);
+        }
+
+        let viewer = &inner.viewer;
+        viewer.filter(filter);
+        viewer.list_media();
+    }
+
+    fn glib_datetime_to_chrono(
+        date: glib::DateTime,
+    ) -> chrono::DateTime<chrono::Utc> {
+        assert_eq!(date.get_timezone(), Some(glib::TimeZone::new_utc()));
+
+        let (year, month, day) = date.get_ymd();
+        let cyear: i32 = year.try_into().expect("year out of range");
+        let cmonth: u32 = month.try_into().expect("month out of range");
+        let cday: u32 = day.try_into().expect("day out of range");
+
+        let chour: u32 = date.get_hour().try_into().expect("hour out of range");
+        let cminute: u32 =
+            date.get_minute().try_into().expect("minute out of range");
+        let csecond: u32 =
+            date.get_second().try_into().expect("second out of range");
+
+        let naive = chrono::NaiveDate::from_ymd(cyear, 1 + cmonth, cday)
+            .and_hms(chour, cminute, csecond);
+
+        chrono::DateTime::from_utc(naive, chrono::Utc)
+    }
+
+    fn date_to_midnight_local(date: glib::Date) -> glib::DateTime {
+        use glib::DateMonth::*;
+
+        let month = match date.get_month() {
+            January => 0,
+            February => 1,
+            March => 2,
+            April => 3,
+            May => 4,
+            June => 5,
+            July => 6,
+            August => 7,
+            September => 8,
+            October => 9,
+            November => 10,
+            December => 11,
+            _ => panic!("month out of range"),
         };
 
         glib::DateTime::new_local(
@@ -444,14 +1958,33 @@ impl Main {
 
         inner.main_menu.build();
         inner.filter_menu.build();
+        inner.media_menu.build();
+        inner.error_center.build();
+
+        let state = inner.ui_state.borrow().clone();
+
+        inner.window.set_default_size(state.width, state.height);
+        if state.maximized {
+            inner.window.maximize();
+        }
+
+        inner.window.connect_delete_event(
+            clone!(@weak self as this => @default-return gtk::Inhibit(false),
+            move |_, _| {
+                this.save_ui_state();
+                gtk::Inhibit(false)
+            }),
+        );
 
-        inner.window.set_default_size(800, 600);
+        inner.filter_menu.hide_before.set_ymd(state.hide_before);
+        inner.filter_menu.hide_after.set_ymd(state.hide_after);
+        inner.filter_menu.set_camera(state.camera.as_deref());
 
         inner.filter_menu.pop.connect_closed(
             clone!(@weak self as this => move |_| this.filter()),
         );
 
-        inner.add_media_btn.set_label("Import");
+        inner.add_media_btn.set_label(&i18n("Import"));
         inner
             .add_media_btn
             .set_action_name(Some("app.choose-import"));
@@ -460,6 +1993,7 @@ impl Main {
         inner.header_bar.set_title(Some("Roadtrip"));
         inner.header_bar.pack_end(&inner.main_menu.btn);
         inner.header_bar.pack_end(&inner.filter_menu.btn);
+        inner.header_bar.pack_end(&inner.error_center.btn);
         inner.header_bar.pack_start(&inner.add_media_btn);
 
         inner.window.set_titlebar(Some(&inner.header_bar));
@@ -471,69 +2005,346 @@ impl Main {
         inner.icon_view.set_model(Some(&inner.media_store));
         inner.icon_view.set_text_column(Self::COL_NAME as i32);
         inner.icon_view.set_pixbuf_column(Self::COL_PIXBUF as i32);
-        inner.icon_view.set_item_width(210);
+
+        let spinner = gtk::CellRendererSpinner::new();
+        inner.icon_view.pack_end(&spinner, false);
+        inner.icon_view.add_attribute(
+            &spinner,
+            "active",
+            Self::COL_PENDING as i32,
+        );
+        inner.icon_view.add_attribute(
+            &spinner,
+            "pulse",
+            Self::COL_PULSE as i32,
+        );
+        inner
+            .icon_view
+            .set_item_width(inner.thumbnail_size.get() + 10);
+        inner
+            .icon_view
+            .set_selection_mode(gtk::SelectionMode::Multiple);
+        inner.icon_view.connect_item_activated(
+            clone!(@weak self as this => move |_, path| this.play(path)),
+        );
+        inner.icon_view.connect_button_press_event(
+            clone!(@weak self as this => @default-return gtk::Inhibit(false),
+                move |view, event| this.media_context_menu(view, event)),
+        );
         inner.icon_scroll.add(&inner.icon_view);
 
         inner.map.layer_add(&osmgpsmap::MapOsd::new());
+        inner.map.connect_notify_local(
+            Some("zoom"),
+            clone!(@weak self as this => move |_, _| this.queue_map_refresh()),
+        );
+        inner.map.connect_button_press_event(
+            clone!(@weak self as this => @default-return gtk::Inhibit(false),
+                move |_, event| this.map_button_press(event)),
+        );
+        inner.map.connect_button_release_event(
+            clone!(@weak self as this => @default-return gtk::Inhibit(false),
+                move |_, event| this.map_button_release(event)),
+        );
+
+        inner.heatmap.set_no_show_all(!state.heatmap);
+        inner.heatmap.connect_draw(
+            clone!(@weak self as this => @default-return gtk::Inhibit(false),
+                move |area, cr| this.draw_heatmap(area, cr)),
+        );
+        inner
+            .map
+            .connect_local("changed", false, {
+                let area = inner.heatmap.clone();
+                move |_| {
+                    area.queue_draw();
+                    None
+                }
+            })
+            .ok();
 
-        inner.paned.pack1(&inner.map, true, false);
+        inner.map_overlay.add(&inner.map);
+        inner.map_overlay.add_overlay(&inner.heatmap);
+        inner
+            .map_overlay
+            .set_overlay_pass_through(&inner.heatmap, true);
+
+        inner.paned.pack1(&inner.map_overlay, true, false);
         inner.paned.pack2(&inner.icon_scroll, true, false);
 
-        inner.status_box.add(&inner.paned);
-        inner.status_box.set_child_expand(&inner.paned, true);
-        inner.status_box.set_child_fill(&inner.paned, true);
+        if let Some(pos) = state.pane_position {
+            inner.paned.set_position(pos);
+        }
+
+        if let Some(view) = state.map {
+            inner.map.set_center_and_zoom(
+                view.latitude,
+                view.longitude,
+                view.zoom,
+            );
+        }
+
+        inner.welcome.build();
+
+        inner.stack.add_named(&inner.paned, "library");
+        inner.stack.add_named(&inner.welcome.page, "welcome");
+
+        inner.status_box.add(&inner.stack);
+        inner.status_box.set_child_expand(&inner.stack, true);
+        inner.status_box.set_child_fill(&inner.stack, true);
 
         let foo = inner.status_bar.get_context_id("foo");
         inner.status_bar.push(foo, "hello world");
+
+        inner.thumbnail_scale.set_draw_value(false);
+        inner.thumbnail_scale.set_size_request(150, -1);
+        inner
+            .thumbnail_scale
+            .set_value(inner.thumbnail_size.get().into());
+        inner.thumbnail_scale.connect_value_changed(
+            clone!(@weak self as this => move |_| {
+                this.thumbnail_size_changed()
+            }),
+        );
+
+        inner.footer.pack_start(&inner.status_bar, true, true, 0);
         inner
-            .status_box
-            .pack_end(&inner.status_bar, false, false, 0);
+            .footer
+            .pack_end(&inner.thumbnail_scale, false, false, 6);
+        inner.status_box.pack_end(&inner.footer, false, false, 0);
 
         inner.window.add(&inner.status_box);
+
+        self.request_thumbnail_size();
+        self.filter();
+
+        let viewer = &inner.viewer;
+        viewer.import_history();
+        viewer.cameras();
+        viewer.config();
+    }
+
+    fn map_view(&self) -> Option<MapView> {
+        let map = &self.0.map;
+
+        let latitude = map.get_property("latitude").ok()?.get().ok()??;
+        let longitude = map.get_property("longitude").ok()?.get().ok()??;
+        let zoom = map.get_property("zoom").ok()?.get().ok()??;
+
+        Some(MapView {
+            latitude,
+            longitude,
+            zoom,
+        })
+    }
+
+    fn save_ui_state(&self) {
+        let inner = &self.0;
+        let mut state = inner.ui_state.borrow_mut();
+
+        state.maximized = inner.window.is_maximized();
+        if !state.maximized {
+            let (width, height) = inner.window.get_size();
+            state.width = width;
+            state.height = height;
+        }
+
+        state.pane_position = Some(inner.paned.get_position());
+        state.thumbnail_size = inner.thumbnail_size.get();
+        state.map = self.map_view();
+        state.hide_before = inner.filter_menu.hide_before.get_ymd();
+        state.hide_after = inner.filter_menu.hide_after.get_ymd();
+        state.camera = inner.filter_menu.get_camera();
+
+        if let Err(e) = state.save() {
+            warn!(error = %e, "unable to save window state");
+        }
     }
 
     pub fn show_all(&self) {
         self.0.window.show_all();
+        self.0.error_center.update();
     }
 
     pub fn event(&self, event: Event) {
         match event {
             Event::MediaScanStarted => self.event_media_scan_started(),
+            Event::MediaScanProgress {
+                discovered,
+                ingested,
+                errored,
+            } => self.event_media_scan_progress(discovered, ingested, errored),
             Event::MediaScanCompleted => self.event_media_scan_completed(),
-            Event::MediaScanError(err) => self.event_media_scan_error(err),
+            Event::Notification(n) => self.event_notification(n),
             Event::FilterChanged => self.event_filter_changed(),
             Event::FilterMatched(media) => self.event_filter_matched(media),
             Event::Thumbnails(thumbs) => self.event_thumbnails(thumbs),
-            _ => eprintln!("EVT: {:?}", event),
+            Event::MediaRemoved(hash) => self.event_media_removed(hash),
+            Event::MediaExported(count) => self.event_media_exported(count),
+            Event::TracksExported(count) => self.event_tracks_exported(count),
+            Event::LabelsChanged(hash, labels) => {
+                self.event_labels_changed(hash, labels)
+            }
+            Event::Duplicates(hash, paths) => {
+                self.event_duplicates(hash, paths)
+            }
+            Event::Cameras(cameras) => self.0.filter_menu.set_cameras(&cameras),
+            Event::LibrarySize(size) => self.event_library_size(size),
+            Event::Config(config) => self.event_config(config),
+            Event::Frame(frame) => self.event_frame(frame),
+            Event::ImportHistory(roots) => self.event_import_history(roots),
+            // Every clip is still shown on its own.
+            Event::Trips(_) => (),
+            _ => debug!(?event, "unhandled event"),
+        }
+    }
+
+    fn event_config(&self, mut config: Config) {
+        let inner = &self.0;
+
+        // Folders from before the shared config are moved into it, once.
+        let old_watched =
+            std::mem::take(&mut inner.ui_state.borrow_mut().old_watched);
+        let mut moved = false;
+
+        for path in old_watched {
+            if !config.watched.contains(&path) {
+                config.watched.push(path);
+                moved = true;
+            }
+        }
+
+        if moved {
+            inner.viewer.save_config(config.clone());
         }
+
+        // Watches only need setting up once, when the config first arrives.
+        if inner.watches.borrow().is_empty() {
+            for path in &config.watched {
+                self.watch(path);
+            }
+        }
+
+        *inner.config.borrow_mut() = config;
+    }
+
+    fn event_library_size(&self, size: usize) {
+        let page = if size == 0 { "welcome" } else { "library" };
+        self.0.stack.set_visible_child_name(page);
     }
 
     fn event_media_scan_started(&self) {
         let inner = &self.0;
         let ctx = inner.status_media_scan;
         inner.status_bar.remove_all(ctx);
-        inner.status_bar.push(ctx, "Media scan started...");
+        inner.status_bar.push(ctx, &i18n("Media scan started..."));
     }
 
-    fn event_media_scan_completed(&self) {
+    fn event_media_scan_progress(
+        &self,
+        discovered: usize,
+        ingested: usize,
+        errored: usize,
+    ) {
         let inner = &self.0;
         let ctx = inner.status_media_scan;
+
+        let scanned = (ingested + errored).to_string();
+        let text = ni18n_f(
+            "Scanning media: {} of {} file ({} failed)",
+            "Scanning media: {} of {} files ({} failed)",
+            discovered as u32,
+            &[&scanned, &discovered.to_string(), &errored.to_string()],
+        );
+
         inner.status_bar.remove_all(ctx);
-        inner.status_bar.push(ctx, "Media scan complete");
+        inner.status_bar.push(ctx, &text);
     }
 
-    fn event_media_scan_error(&self, error: IngestError) {
+    fn event_media_scan_completed(&self) {
         let inner = &self.0;
         let ctx = inner.status_media_scan;
         inner.status_bar.remove_all(ctx);
-        let msg = format!("Error scanning {}", error.path().to_string_lossy());
-        inner.status_bar.push(ctx, &msg);
+        inner.status_bar.push(ctx, &i18n("Media scan complete"));
+
+        inner.viewer.cameras();
+    }
+
+    fn event_notification(&self, notification: Notification) {
+        let inner = &self.0;
+
+        // A failed thumbnail will never arrive, so stop waiting for it.
+        if notification.kind() == NotificationKind::Thumbnail {
+            for row in inner.media.borrow().values() {
+                if row.media.path() == notification.path() {
+                    inner.media_store.set_value(
+                        &row.iter,
+                        Self::COL_PENDING,
+                        &false.to_value(),
+                    );
+                }
+            }
+        }
+
+        inner.error_center.push(&notification);
+    }
+
+    fn track_polygons(&self, media: &Media) -> Vec<MapPolygon> {
+        let style = self.0.ui_state.borrow().track_style;
+
+        track_style::segments(media, style)
+            .into_iter()
+            .map(|segment| {
+                let poly = MapPolygon::new();
+                let track = poly.get_track().unwrap();
+
+                for point in segment.points {
+                    let mut map_point = MapPoint::new_degrees(
+                        point.latitude() as f32,
+                        point.longitude() as f32,
+                    );
+
+                    track.insert_point(&mut map_point, track.n_points());
+                }
+
+                track.set_property("color", &segment.color).ok();
+                track
+                    .set_property("line-width", &(style.line_width as f32))
+                    .ok();
+
+                poly
+            })
+            .collect()
+    }
+
+    fn set_track_style(&self, style: TrackStyle) {
+        let inner = &self.0;
+        inner.ui_state.borrow_mut().track_style = style;
+
+        let mut media = inner.media.borrow_mut();
+        for row in media.values_mut() {
+            let polygons = self.track_polygons(&row.media);
+            let old = std::mem::replace(&mut row.polygons, polygons);
+
+            if !inner.map_tracks_shown.get() {
+                continue;
+            }
+
+            for polygon in &old {
+                inner.map.polygon_remove(polygon);
+            }
+
+            for polygon in &row.polygons {
+                inner.map.polygon_add(polygon);
+            }
+        }
     }
 
     fn event_filter_changed(&self) {
         self.0.map.polygon_remove_all();
         self.0.media.borrow_mut().clear();
         self.0.media_store.clear();
+        self.queue_map_refresh();
     }
 
     fn event_filter_matched(&self, media: Media) {
@@ -544,47 +2355,218 @@ impl Main {
             None => return, // TODO: Log this?
         };
 
-        let poly = MapPolygon::new();
-        let track = poly.get_track().unwrap();
-        for point in media.geometry().iter() {
-            let mut map_point = MapPoint::new_degrees(
-                point.latitude() as f32,
-                point.longitude() as f32,
-            );
+        inner.stack.set_visible_child_name("library");
 
-            track.insert_point(&mut map_point, track.n_points());
-        }
+        let polygons = self.track_polygons(&media);
 
-        inner.map.polygon_add(&poly);
+        if inner.map_tracks_shown.get() {
+            for polygon in &polygons {
+                inner.map.polygon_add(polygon);
+            }
+        }
 
         let iter = inner.media_store.insert_with_values(
             None,
-            &[Self::COL_NAME, Self::COL_PIXBUF],
-            &[&file_name, &inner.placeholder],
+            &[Self::COL_NAME, Self::COL_PIXBUF, Self::COL_PENDING],
+            &[&file_name, &*inner.placeholder.borrow(), &true],
+        );
+
+        let row = MediaRow {
+            iter,
+            polygons,
+            media: media.clone(),
+            labels: Labels::default(),
+        };
+
+        let old = inner.media.borrow_mut().insert(media.hash().clone(), row);
+        if let Some(old) = old {
+            for polygon in &old.polygons {
+                inner.map.polygon_remove(polygon);
+            }
+            inner.media_store.remove(&old.iter);
+        }
+
+        self.update_row_name(media.hash());
+        self.queue_map_refresh();
+        self.pulse_thumbnails();
+    }
+
+    fn event_media_removed(&self, hash: Hash) {
+        let inner = &self.0;
+        let row = match inner.media.borrow_mut().remove(&hash) {
+            Some(r) => r,
+            None => return,
+        };
+
+        for polygon in &row.polygons {
+            inner.map.polygon_remove(polygon);
+        }
+        inner.media_store.remove(&row.iter);
+        inner.duplicates.borrow_mut().remove(&hash);
+
+        self.queue_map_refresh();
+    }
+
+    fn event_media_exported(&self, count: usize) {
+        let inner = &self.0;
+        let ctx = inner.status_export;
+        inner.status_bar.remove_all(ctx);
+
+        let text = ni18n_f(
+            "Exported {} file",
+            "Exported {} files",
+            count as u32,
+            &[&count.to_string()],
+        );
+        inner.status_bar.push(ctx, &text);
+    }
+
+    fn event_tracks_exported(&self, count: usize) {
+        let inner = &self.0;
+        let ctx = inner.status_export;
+        inner.status_bar.remove_all(ctx);
+
+        let text = ni18n_f(
+            "Exported {} track",
+            "Exported {} tracks",
+            count as u32,
+            &[&count.to_string()],
+        );
+        inner.status_bar.push(ctx, &text);
+    }
+
+    fn update_row_name(&self, hash: &Hash) {
+        let inner = &self.0;
+        let media = inner.media.borrow();
+
+        let row = match media.get(hash) {
+            Some(r) => r,
+            None => return,
+        };
+
+        let file_name = match row.media.path().file_name() {
+            Some(f) => f.to_string_lossy(),
+            None => return,
+        };
+
+        let mut name = if row.labels.favorite() {
+            format!("★ {}", file_name)
+        } else {
+            file_name.into_owned()
+        };
+
+        if let Some(duration) = row.media.duration() {
+            let secs = duration.as_secs();
+            name = format!("{} · {}:{:02}", name, secs / 60, secs % 60);
+        }
+
+        if let Some(paths) = inner.duplicates.borrow().get(hash) {
+            name = ni18n_f(
+                "{} ({} copy)",
+                "{} ({} copies)",
+                paths.len() as u32,
+                &[&name, &paths.len().to_string()],
+            );
+        }
+
+        inner.media_store.set_value(
+            &row.iter,
+            Self::COL_NAME,
+            &name.to_value(),
+        );
+    }
+
+    fn event_labels_changed(&self, hash: Hash, labels: Labels) {
+        if let Some(row) = self.0.media.borrow_mut().get_mut(&hash) {
+            row.labels = labels;
+        }
+
+        self.update_row_name(&hash);
+    }
+
+    fn event_duplicates(&self, hash: Hash, paths: Vec<PathBuf>) {
+        {
+            let mut duplicates = self.0.duplicates.borrow_mut();
+            if paths.len() > 1 {
+                duplicates.insert(hash.clone(), paths);
+            } else {
+                duplicates.remove(&hash);
+            }
+        }
+
+        self.update_row_name(&hash);
+    }
+
+    fn event_import_history(&self, roots: Vec<PathBuf>) {
+        self.0.main_menu.set_recent(&roots);
+        *self.0.recent_imports.borrow_mut() = roots;
+    }
+
+    fn event_frame(&self, frame: Frame) {
+        let player = match self.0.player.borrow().clone() {
+            Some(p) => p,
+            None => return,
+        };
+
+        if player.media().hash() != frame.media_hash() {
+            return;
+        }
+
+        let offset = frame.offset();
+        let bytes = glib::Bytes::from_owned(frame.into_data());
+        let stream = gio::MemoryInputStream::from_bytes(&bytes);
+
+        let pixbuf = gdk_pixbuf::Pixbuf::from_stream_at_scale(
+            &stream,
+            160,
+            160,
+            true,
+            None::<&gio::Cancellable>,
         );
-        inner.media.borrow_mut().insert(media.hash().clone(), iter);
+
+        match pixbuf {
+            Ok(p) => player.add_preview(offset, p),
+            Err(e) => {
+                player.preview_failed(offset);
+                warn!(
+                    ?offset,
+                    error = %e,
+                    "unable to load preview frame"
+                );
+            }
+        }
     }
 
     fn event_thumbnails(&self, thumbs: Thumbnails) {
         let media = self.0.media.borrow();
 
         let iter = match media.get(thumbs.media_hash()) {
-            Some(i) => i,
+            Some(r) => &r.iter,
             None => return,
         };
 
-        let file = thumbs.into_files().next().unwrap();
+        let bytes = glib::Bytes::from_owned(thumbs.into_data().next().unwrap());
+        let stream = gio::MemoryInputStream::from_bytes(&bytes);
 
-        let pixbuf = gdk_pixbuf::Pixbuf::from_stream(
-            &gio::ReadInputStream::new_seekable(file),
+        let size = self.0.thumbnail_size.get();
+        let pixbuf = gdk_pixbuf::Pixbuf::from_stream_at_scale(
+            &stream,
+            size,
+            size,
+            true,
             None::<&gio::Cancellable>,
         )
         .unwrap();
 
         self.0.media_store.set_value(
-            &iter,
+            iter,
             Self::COL_PIXBUF,
             &glib::Value::from(&pixbuf),
         );
+        self.0.media_store.set_value(
+            iter,
+            Self::COL_PENDING,
+            &false.to_value(),
+        );
     }
 }
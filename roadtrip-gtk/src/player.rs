@@ -0,0 +1,407 @@
+use crate::file_manager;
+use crate::i18n::{i18n, i18n_f};
+
+use glib::clone;
+
+use gstreamer::{self as gst, ClockTime, ElementExt, ElementExtManual};
+
+use gtk::prelude::*;
+
+use roadtrip::core::media::Media;
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::time::Duration;
+
+type PositionFn = Box<dyn Fn(Duration)>;
+
+#[derive(Debug, Clone)]
+pub struct Player(Rc<Inner>);
+
+struct Inner {
+    media: Media,
+    window: gtk::Window,
+    playbin: gst::Element,
+    video: gtk::Widget,
+    controls: gtk::Box,
+    play_btn: gtk::Button,
+    play_img: gtk::Image,
+    folder_btn: gtk::Button,
+    seek: gtk::Scale,
+    preview: gtk::Popover,
+    preview_img: gtk::Image,
+    preview_hover: Cell<Option<u64>>,
+    preview_timeout: RefCell<Option<glib::SourceId>>,
+    previews: RefCell<HashMap<u64, gdk_pixbuf::Pixbuf>>,
+    previews_pending: RefCell<HashSet<u64>>,
+    on_preview: RefCell<Vec<PositionFn>>,
+    on_position: RefCell<Vec<PositionFn>>,
+    on_close: RefCell<Vec<Box<dyn Fn(&Player)>>>,
+}
+
+impl std::fmt::Debug for Inner {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Inner {{ media: {:?}, ... }}", self.media)
+    }
+}
+
+impl Player {
+    const TICK_MS: u32 = 100;
+
+    // How long the pointer has to rest on the seek bar before asking for a
+    // preview, so sweeping across it doesn't decode a frame for every second.
+    const PREVIEW_DELAY_MS: u32 = 150;
+
+    pub fn new<W>(parent: &W, media: Media) -> Option<Self>
+    where
+        W: IsA<gtk::Window>,
+    {
+        let uri = glib::filename_to_uri(media.path(), None).ok()?;
+
+        let sink = gst::ElementFactory::make("gtksink", None).ok()?;
+        let video = sink.get_property("widget").ok()?.get().ok()??;
+
+        let playbin = gst::ElementFactory::make("playbin", None).ok()?;
+        playbin.set_property("uri", &uri).ok()?;
+        playbin.set_property("video-sink", &sink).ok()?;
+
+        let window = gtk::Window::new(gtk::WindowType::Toplevel);
+        window.set_transient_for(Some(parent));
+
+        let title = media
+            .path()
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        window.set_title(&title);
+
+        let seek =
+            gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, 1.0, 1.0);
+
+        let inner = Inner {
+            preview: gtk::Popover::new(Some(&seek)),
+            preview_img: gtk::Image::new(),
+            preview_hover: Cell::new(None),
+            preview_timeout: Default::default(),
+            previews: Default::default(),
+            previews_pending: Default::default(),
+            on_preview: Default::default(),
+            seek,
+            media,
+            window,
+            playbin,
+            video,
+            controls: gtk::Box::new(gtk::Orientation::Horizontal, 6),
+            play_btn: gtk::Button::new(),
+            play_img: gtk::Image::new(),
+            folder_btn: gtk::Button::from_icon_name(
+                Some("folder-open"),
+                gtk::IconSize::Button,
+            ),
+            on_position: Default::default(),
+            on_close: Default::default(),
+        };
+
+        Some(Player(Rc::new(inner)))
+    }
+
+    pub fn ptr_eq(&self, other: &Player) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+
+    pub fn media(&self) -> &Media {
+        &self.0.media
+    }
+
+    pub fn connect_position<F>(&self, f: F)
+    where
+        F: 'static + Fn(Duration),
+    {
+        self.0.on_position.borrow_mut().push(Box::new(f));
+    }
+
+    pub fn connect_preview<F>(&self, f: F)
+    where
+        F: 'static + Fn(Duration),
+    {
+        self.0.on_preview.borrow_mut().push(Box::new(f));
+    }
+
+    pub fn connect_close<F>(&self, f: F)
+    where
+        F: 'static + Fn(&Player),
+    {
+        self.0.on_close.borrow_mut().push(Box::new(f));
+    }
+
+    pub fn build(&self) {
+        let inner = &self.0;
+
+        inner.play_btn.set_image(Some(&inner.play_img));
+        inner.play_btn.connect_clicked(
+            clone!(@weak self as this => move |_| this.toggle()),
+        );
+
+        inner
+            .folder_btn
+            .set_tooltip_text(Some(&*i18n("Open Folder")));
+        inner.folder_btn.connect_clicked(
+            clone!(@weak self as this => move |_| this.open_folder()),
+        );
+
+        inner.seek.set_draw_value(false);
+        inner.seek.set_hexpand(true);
+        inner.seek.connect_change_value(
+            clone!(@weak self as this => @default-return gtk::Inhibit(false),
+            move |_, _, value| {
+                this.seek(Duration::from_secs_f64(value.max(0.0)));
+                gtk::Inhibit(false)
+            }),
+        );
+
+        inner.seek.add_events(
+            gdk::EventMask::POINTER_MOTION_MASK
+                | gdk::EventMask::LEAVE_NOTIFY_MASK,
+        );
+        inner.seek.connect_motion_notify_event(
+            clone!(@weak self as this => @default-return gtk::Inhibit(false),
+            move |_, event| {
+                let (x, _) = event.get_position();
+                this.hover(x);
+                gtk::Inhibit(false)
+            }),
+        );
+        inner.seek.connect_leave_notify_event(
+            clone!(@weak self as this => @default-return gtk::Inhibit(false),
+            move |_, _| {
+                this.unhover();
+                gtk::Inhibit(false)
+            }),
+        );
+
+        inner.preview.set_modal(false);
+        inner.preview.set_position(gtk::PositionType::Top);
+        inner.preview.add(&inner.preview_img);
+        inner.preview_img.show();
+
+        inner.controls.pack_start(&inner.play_btn, false, false, 0);
+        inner.controls.pack_start(&inner.seek, true, true, 0);
+        inner.controls.pack_end(&inner.folder_btn, false, false, 0);
+
+        let vbox = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        vbox.pack_start(&inner.video, true, true, 0);
+        vbox.pack_end(&inner.controls, false, false, 0);
+
+        inner.window.add(&vbox);
+        inner.window.set_default_size(640, 420);
+
+        inner.window.connect_destroy(
+            clone!(@weak self as this => move |_| this.closed()),
+        );
+
+        glib::timeout_add_local(
+            Self::TICK_MS,
+            clone!(@weak self as this => @default-return glib::Continue(false),
+                move || glib::Continue(this.tick())),
+        );
+    }
+
+    pub fn show_all(&self) {
+        self.0.window.show_all();
+        self.play();
+    }
+
+    pub fn close(&self) {
+        self.0.window.close();
+    }
+
+    fn closed(&self) {
+        self.0.playbin.set_state(gst::State::Null).ok();
+
+        for f in self.0.on_close.borrow().iter() {
+            f(self);
+        }
+    }
+
+    fn open_folder(&self) {
+        let inner = &self.0;
+        let paths = std::iter::once(inner.media.path());
+
+        if let Err(e) = file_manager::show_items(&inner.window, paths) {
+            let dialog = gtk::MessageDialog::new(
+                Some(&inner.window),
+                gtk::DialogFlags::MODAL,
+                gtk::MessageType::Error,
+                gtk::ButtonsType::Close,
+                &i18n_f("Unable to open folder: {}", &[&e.to_string()]),
+            );
+            dialog.run();
+            dialog.close();
+        }
+    }
+
+    fn is_playing(&self) -> bool {
+        let (_, current, _) =
+            self.0.playbin.get_state(ClockTime::from_mseconds(0));
+        current == gst::State::Playing
+    }
+
+    fn play(&self) {
+        self.0.playbin.set_state(gst::State::Playing).ok();
+        self.0.play_img.set_from_icon_name(
+            Some("media-playback-pause"),
+            gtk::IconSize::Button,
+        );
+    }
+
+    fn pause(&self) {
+        self.0.playbin.set_state(gst::State::Paused).ok();
+        self.0.play_img.set_from_icon_name(
+            Some("media-playback-start"),
+            gtk::IconSize::Button,
+        );
+    }
+
+    fn toggle(&self) {
+        if self.is_playing() {
+            self.pause();
+        } else {
+            self.play();
+        }
+    }
+
+    fn hover(&self, x: f64) {
+        let inner = &self.0;
+
+        let width = inner.seek.get_allocated_width();
+        let upper = inner.seek.get_adjustment().get_upper();
+        if width <= 0 || upper <= 0.0 {
+            return;
+        }
+
+        let fraction = (x / f64::from(width)).max(0.0).min(1.0);
+        let second = (fraction * upper) as u64;
+
+        let rect = gdk::Rectangle {
+            x: x as i32,
+            y: 0,
+            width: 1,
+            height: inner.seek.get_allocated_height(),
+        };
+
+        inner.preview.set_pointing_to(&rect);
+        inner.preview_hover.set(Some(second));
+
+        if let Some(pixbuf) = inner.previews.borrow().get(&second) {
+            inner.preview_img.set_from_pixbuf(Some(pixbuf));
+            inner.preview.popup();
+            return;
+        }
+
+        self.cancel_preview();
+
+        let source = glib::timeout_add_local(
+            Self::PREVIEW_DELAY_MS,
+            clone!(@weak self as this => @default-return glib::Continue(false),
+            move || {
+                this.0.preview_timeout.borrow_mut().take();
+                this.request_preview(second);
+                glib::Continue(false)
+            }),
+        );
+
+        *inner.preview_timeout.borrow_mut() = Some(source);
+    }
+
+    fn request_preview(&self, second: u64) {
+        let inner = &self.0;
+
+        if inner.preview_hover.get() != Some(second) {
+            return;
+        }
+
+        if inner.previews_pending.borrow_mut().insert(second) {
+            let offset = Duration::from_secs(second);
+            for f in inner.on_preview.borrow().iter() {
+                f(offset);
+            }
+        }
+    }
+
+    fn cancel_preview(&self) {
+        if let Some(source) = self.0.preview_timeout.borrow_mut().take() {
+            glib::source_remove(source);
+        }
+    }
+
+    fn unhover(&self) {
+        self.cancel_preview();
+        self.0.preview_hover.set(None);
+        self.0.preview.popdown();
+    }
+
+    pub fn add_preview(&self, offset: Duration, pixbuf: gdk_pixbuf::Pixbuf) {
+        let inner = &self.0;
+        let second = offset.as_secs();
+
+        inner.previews_pending.borrow_mut().remove(&second);
+
+        if inner.preview_hover.get() == Some(second) {
+            inner.preview_img.set_from_pixbuf(Some(&pixbuf));
+            inner.preview.popup();
+        }
+
+        inner.previews.borrow_mut().insert(second, pixbuf);
+    }
+
+    // Lets the frame at `offset` be asked for again the next time it's
+    // hovered.
+    pub fn preview_failed(&self, offset: Duration) {
+        self.0
+            .previews_pending
+            .borrow_mut()
+            .remove(&offset.as_secs());
+    }
+
+    pub fn seek(&self, position: Duration) {
+        let nanos = position.as_nanos() as u64;
+        self.0
+            .playbin
+            .seek_simple(
+                gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT,
+                ClockTime::from_nseconds(nanos),
+            )
+            .ok();
+    }
+
+    fn tick(&self) -> bool {
+        let inner = &self.0;
+
+        let position = inner
+            .playbin
+            .query_position::<ClockTime>()
+            .and_then(|p| p.nseconds())
+            .map(Duration::from_nanos);
+
+        let duration = inner
+            .playbin
+            .query_duration::<ClockTime>()
+            .and_then(|d| d.nseconds())
+            .map(Duration::from_nanos);
+
+        if let Some(duration) = duration {
+            inner.seek.set_range(0.0, duration.as_secs_f64());
+        }
+
+        if let Some(position) = position {
+            inner.seek.set_value(position.as_secs_f64());
+
+            for f in inner.on_position.borrow().iter() {
+                f(position);
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,191 @@
+use chrono::Datelike;
+
+use roadtrip::core::geometry::{Geometry, Point};
+use roadtrip::core::media::Media;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+// Speeds at or above this are drawn in the hottest color.
+const MAX_SPEED_MPS: f64 = 35.0;
+const SPEED_BUCKETS: usize = 8;
+
+// Points closer than this to the simplified track aren't drawn, which keeps
+// long libraries responsive without a visible change at street level.
+const SIMPLIFY_M: f64 = 2.0;
+
+// Tracks are drawn in pieces where fixes are further apart than this, rather
+// than with a straight line across wherever the camera was switched off.
+const MAX_GAP_MINUTES: i64 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorBy {
+    Uniform,
+    Speed,
+    Date,
+    Camera,
+}
+
+impl ColorBy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ColorBy::Uniform => "uniform",
+            ColorBy::Speed => "speed",
+            ColorBy::Date => "date",
+            ColorBy::Camera => "camera",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        match text {
+            "uniform" => Some(ColorBy::Uniform),
+            "speed" => Some(ColorBy::Speed),
+            "date" => Some(ColorBy::Date),
+            "camera" => Some(ColorBy::Camera),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackStyle {
+    pub color_by: ColorBy,
+    pub line_width: i32,
+}
+
+impl Default for TrackStyle {
+    fn default() -> Self {
+        Self {
+            color_by: ColorBy::Uniform,
+            line_width: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub color: gdk::RGBA,
+    pub points: Vec<Point>,
+}
+
+fn rgba((red, green, blue): (f64, f64, f64)) -> gdk::RGBA {
+    gdk::RGBA {
+        red,
+        green,
+        blue,
+        alpha: 1.0,
+    }
+}
+
+fn uniform() -> gdk::RGBA {
+    rgba((0.2, 0.4, 0.8))
+}
+
+fn hue(h: f64) -> gdk::RGBA {
+    rgba(gtk::hsv_to_rgb(h, 0.8, 0.85))
+}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    let (lat1, lat2) = (a.latitude().to_radians(), b.latitude().to_radians());
+    let dlat = lat2 - lat1;
+    let dlng = (b.longitude() - a.longitude()).to_radians();
+
+    let h = (dlat / 2.0).sin().powi(2)
+        + lat1.cos() * lat2.cos() * (dlng / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_M * h.sqrt().asin()
+}
+
+// The recorded speed if there is one, or else the speed needed to get from
+// `a` to `b` in a straight line.
+fn speed(a: &Point, b: &Point) -> f64 {
+    if let Some(speed) = a.speed() {
+        return speed;
+    }
+
+    let millis = (b.time() - a.time()).num_milliseconds();
+    if millis <= 0 {
+        return 0.0;
+    }
+
+    distance(a, b) / (millis as f64 / 1000.0)
+}
+
+fn speed_bucket(a: &Point, b: &Point) -> usize {
+    let speed = speed(a, b);
+    let frac = (speed / MAX_SPEED_MPS).min(1.0);
+
+    ((frac * (SPEED_BUCKETS - 1) as f64).round()) as usize
+}
+
+// Slow segments are green and fast segments are red.
+fn speed_color(bucket: usize) -> gdk::RGBA {
+    let frac = bucket as f64 / (SPEED_BUCKETS - 1) as f64;
+    hue((1.0 - frac) / 3.0)
+}
+
+// Consecutive points in the same speed bucket share a segment, so a track is
+// split only where its color changes.
+fn speed_segments(points: Vec<Point>) -> Vec<Segment> {
+    let mut out: Vec<(usize, Vec<Point>)> = Vec::new();
+
+    for pair in points.windows(2) {
+        let bucket = speed_bucket(&pair[0], &pair[1]);
+
+        match out.last_mut() {
+            Some((b, segment)) if *b == bucket => segment.push(pair[1]),
+            _ => out.push((bucket, pair.to_vec())),
+        }
+    }
+
+    if out.is_empty() {
+        out.push((0, points));
+    }
+
+    out.into_iter()
+        .map(|(bucket, points)| Segment {
+            color: speed_color(bucket),
+            points,
+        })
+        .collect()
+}
+
+pub fn segments(media: &Media, style: TrackStyle) -> Vec<Segment> {
+    let geometry = media.geometry().simplified(SIMPLIFY_M);
+    let gap = chrono::Duration::minutes(MAX_GAP_MINUTES);
+
+    geometry
+        .split_by_gap(gap)
+        .iter()
+        .flat_map(|piece| piece_segments(media, piece, style))
+        .collect()
+}
+
+fn piece_segments(
+    media: &Media,
+    piece: &Geometry,
+    style: TrackStyle,
+) -> Vec<Segment> {
+    let points: Vec<_> = piece.iter().collect();
+
+    let color = match style.color_by {
+        ColorBy::Speed => return speed_segments(points),
+        ColorBy::Uniform => uniform(),
+        ColorBy::Date => match points.first() {
+            Some(p) => hue(f64::from(p.time().ordinal0()) / 366.0),
+            None => uniform(),
+        },
+        ColorBy::Camera => match media.camera() {
+            Some(camera) => {
+                let mut hasher = DefaultHasher::new();
+                camera.hash(&mut hasher);
+                hue((hasher.finish() % 360) as f64 / 360.0)
+            }
+            None => rgba((0.5, 0.5, 0.5)),
+        },
+    };
+
+    vec![Segment { color, points }]
+}
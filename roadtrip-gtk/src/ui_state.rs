@@ -0,0 +1,232 @@
+use crate::track_style::{ColorBy, TrackStyle};
+
+use roadtrip::viewer::dirs::Dirs;
+
+use std::path::PathBuf;
+
+const WINDOW: &str = "window";
+const MAP: &str = "map";
+const FILTER: &str = "filter";
+const LIBRARY: &str = "library";
+
+pub type Ymd = (u32, u32, u32);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapView {
+    pub latitude: f32,
+    pub longitude: f32,
+    pub zoom: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UiState {
+    pub width: i32,
+    pub height: i32,
+    pub maximized: bool,
+    pub pane_position: Option<i32>,
+    pub thumbnail_size: i32,
+    pub map: Option<MapView>,
+    pub heatmap: bool,
+    pub track_style: TrackStyle,
+    pub hide_before: Option<Ymd>,
+    pub hide_after: Option<Ymd>,
+    pub camera: Option<String>,
+
+    // Folders watched by older versions, which kept them here instead of in
+    // the shared `Config`. Written back until they've been moved over, so
+    // they aren't lost if that never happens.
+    pub old_watched: Vec<PathBuf>,
+}
+
+impl Default for UiState {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 600,
+            maximized: false,
+            pane_position: None,
+            thumbnail_size: 200,
+            map: None,
+            heatmap: false,
+            track_style: TrackStyle::default(),
+            hide_before: None,
+            hide_after: None,
+            camera: None,
+            old_watched: Vec::new(),
+        }
+    }
+}
+
+impl UiState {
+    // Next to `config.toml`, or `None` if there's no home directory to put
+    // it in.
+    fn path() -> Option<PathBuf> {
+        let dirs = Dirs::new()?;
+        Some(dirs.paths().config_dir.join("ui-state.ini"))
+    }
+
+    // Where it was kept before there was a config directory, which is only
+    // read if there's nothing in the new place yet.
+    fn old_path() -> PathBuf {
+        glib::get_user_config_dir()
+            .join("roadtrip")
+            .join("ui-state.ini")
+    }
+
+    fn parse_ymd(text: &str) -> Option<Ymd> {
+        let mut parts = text.splitn(3, '-').map(|p| p.parse::<u32>().ok());
+        let year = parts.next()??;
+        let month = parts.next()??;
+        let day = parts.next()??;
+
+        if month == 0 || month > 12 || day == 0 || day > 31 {
+            return None;
+        }
+
+        // Months are stored one-based, but `gtk::Calendar` is zero-based.
+        Some((year, month - 1, day))
+    }
+
+    fn format_ymd((year, month, day): Ymd) -> String {
+        format!("{:04}-{:02}-{:02}", year, month + 1, day)
+    }
+
+    pub fn load() -> Self {
+        let mut state = Self::default();
+        let file = glib::KeyFile::new();
+
+        let loaded = Self::path()
+            .into_iter()
+            .chain(Some(Self::old_path()))
+            .any(|p| file.load_from_file(p, glib::KeyFileFlags::NONE).is_ok());
+
+        if !loaded {
+            return state;
+        }
+
+        if let Ok(w) = file.get_integer(WINDOW, "width") {
+            state.width = w;
+        }
+
+        if let Ok(h) = file.get_integer(WINDOW, "height") {
+            state.height = h;
+        }
+
+        state.maximized =
+            file.get_boolean(WINDOW, "maximized").unwrap_or(false);
+        state.pane_position = file.get_integer(WINDOW, "pane-position").ok();
+
+        if let Ok(size) = file.get_integer(WINDOW, "thumbnail-size") {
+            state.thumbnail_size = size;
+        }
+
+        let lat = file.get_double(MAP, "latitude");
+        let lng = file.get_double(MAP, "longitude");
+        let zoom = file.get_integer(MAP, "zoom");
+
+        if let (Ok(lat), Ok(lng), Ok(zoom)) = (lat, lng, zoom) {
+            state.map = Some(MapView {
+                latitude: lat as f32,
+                longitude: lng as f32,
+                zoom,
+            });
+        }
+
+        state.heatmap = file.get_boolean(MAP, "heatmap").unwrap_or(false);
+
+        if let Some(color_by) = file
+            .get_string(MAP, "track-color")
+            .ok()
+            .and_then(|c| ColorBy::parse(&c))
+        {
+            state.track_style.color_by = color_by;
+        }
+
+        if let Ok(width) = file.get_integer(MAP, "track-width") {
+            state.track_style.line_width = width;
+        }
+
+        state.hide_before = file
+            .get_string(FILTER, "hide-before")
+            .ok()
+            .and_then(|t| Self::parse_ymd(&t));
+
+        state.hide_after = file
+            .get_string(FILTER, "hide-after")
+            .ok()
+            .and_then(|t| Self::parse_ymd(&t));
+
+        state.camera = file
+            .get_string(FILTER, "camera")
+            .ok()
+            .map(String::from)
+            .filter(|c| !c.is_empty());
+
+        state.old_watched = file
+            .get_string_list(LIBRARY, "watched")
+            .unwrap_or_default()
+            .into_iter()
+            .map(|p| PathBuf::from(p.as_str()))
+            .collect();
+
+        state
+    }
+
+    pub fn save(&self) -> Result<(), glib::Error> {
+        let file = glib::KeyFile::new();
+
+        file.set_integer(WINDOW, "width", self.width);
+        file.set_integer(WINDOW, "height", self.height);
+        file.set_boolean(WINDOW, "maximized", self.maximized);
+
+        if let Some(pos) = self.pane_position {
+            file.set_integer(WINDOW, "pane-position", pos);
+        }
+
+        file.set_integer(WINDOW, "thumbnail-size", self.thumbnail_size);
+
+        if let Some(map) = &self.map {
+            file.set_double(MAP, "latitude", map.latitude.into());
+            file.set_double(MAP, "longitude", map.longitude.into());
+            file.set_integer(MAP, "zoom", map.zoom);
+        }
+
+        file.set_boolean(MAP, "heatmap", self.heatmap);
+        file.set_string(MAP, "track-color", self.track_style.color_by.as_str());
+        file.set_integer(MAP, "track-width", self.track_style.line_width);
+
+        if let Some(ymd) = self.hide_before {
+            file.set_string(FILTER, "hide-before", &Self::format_ymd(ymd));
+        }
+
+        if let Some(ymd) = self.hide_after {
+            file.set_string(FILTER, "hide-after", &Self::format_ymd(ymd));
+        }
+
+        if let Some(camera) = &self.camera {
+            file.set_string(FILTER, "camera", camera);
+        }
+
+        if !self.old_watched.is_empty() {
+            let watched: Vec<_> = self
+                .old_watched
+                .iter()
+                .map(|p| p.to_string_lossy())
+                .collect();
+            let watched: Vec<_> = watched.iter().map(|p| &**p).collect();
+            file.set_string_list(LIBRARY, "watched", &watched);
+        }
+
+        // Nowhere to keep it.
+        let path = match Self::path() {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).ok();
+        }
+
+        file.save_to_file(path)
+    }
+}
@@ -0,0 +1,107 @@
+use crate::i18n::i18n;
+
+use gstreamer as gst;
+
+use gtk::prelude::*;
+
+#[derive(Debug, Clone)]
+pub struct Welcome {
+    pub page: gtk::Box,
+    title: gtk::Label,
+    body: gtk::Label,
+    buttons: gtk::Box,
+    import_btn: gtk::Button,
+    watch_btn: gtk::Button,
+    checks: gtk::Box,
+}
+
+impl Welcome {
+    pub fn new() -> Self {
+        Self {
+            page: gtk::Box::new(gtk::Orientation::Vertical, 18),
+            title: gtk::Label::new(None),
+            body: gtk::Label::new(None),
+            buttons: gtk::Box::new(gtk::Orientation::Horizontal, 12),
+            import_btn: gtk::Button::with_label(&i18n("Import a Folder…")),
+            watch_btn: gtk::Button::with_label(&i18n("Watch a Folder…")),
+            checks: gtk::Box::new(gtk::Orientation::Vertical, 6),
+        }
+    }
+
+    fn exiftool_available() -> bool {
+        glib::find_program_in_path("exiftool").is_some()
+    }
+
+    fn player_available() -> bool {
+        if gst::init().is_err() {
+            return false;
+        }
+
+        ["playbin", "gtksink"]
+            .iter()
+            .all(|name| gst::ElementFactory::find(name).is_some())
+    }
+
+    fn add_check(&self, message: &str) {
+        let bar = gtk::InfoBar::new();
+        bar.set_message_type(gtk::MessageType::Warning);
+
+        let label = gtk::Label::new(Some(message));
+        label.set_line_wrap(true);
+        label.set_xalign(0.0);
+        bar.get_content_area().add(&label);
+
+        self.checks.add(&bar);
+    }
+
+    pub fn build(&self) {
+        let markup = format!(
+            "<span size=\"xx-large\" weight=\"bold\">{}</span>",
+            glib::markup_escape_text(&i18n("Welcome to Roadtrip"))
+        );
+        self.title.set_markup(&markup);
+
+        self.body.set_text(&i18n(
+            "Roadtrip shows dashcam footage and other geotagged media on a \
+             map. Import a folder to get started, or watch one to pick up \
+             new recordings automatically.",
+        ));
+        self.body.set_line_wrap(true);
+        self.body.set_max_width_chars(60);
+        self.body.set_justify(gtk::Justification::Center);
+
+        self.import_btn.set_action_name(Some("app.choose-import"));
+        self.import_btn
+            .get_style_context()
+            .add_class("suggested-action");
+        self.watch_btn.set_action_name(Some("app.choose-watch"));
+
+        self.buttons.set_halign(gtk::Align::Center);
+        self.buttons.add(&self.import_btn);
+        self.buttons.add(&self.watch_btn);
+
+        if !Self::exiftool_available() {
+            self.add_check(&i18n(
+                "ExifTool was not found, so media cannot be imported. Install \
+                 it from your package manager (often named exiftool or \
+                 libimage-exiftool-perl) and restart Roadtrip.",
+            ));
+        }
+
+        if !Self::player_available() {
+            self.add_check(&i18n(
+                "The GStreamer GTK plugin was not found, so videos cannot be \
+                 played. Install the GStreamer good plugins (often named \
+                 gstreamer1.0-gtk3 or gst-plugins-good) and restart Roadtrip.",
+            ));
+        }
+
+        self.page.set_valign(gtk::Align::Center);
+        self.page.set_halign(gtk::Align::Center);
+        self.page.set_border_width(24);
+        self.page.add(&self.title);
+        self.page.add(&self.body);
+        self.page.add(&self.buttons);
+        self.page.add(&self.checks);
+    }
+}
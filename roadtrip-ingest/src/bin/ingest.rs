@@ -1,13 +1,116 @@
 use futures::pin_mut;
 use futures::stream::StreamExt;
 
-use roadtrip_ingest::ingest::Exiftool;
+use roadtrip_core::media::Media;
+use roadtrip_core::Hash;
+
+use roadtrip_ingest::error::Error;
+use roadtrip_ingest::ingest::{
+    Blackvue, Exiftool, Fit, FreeGps, Photo, Sidecar, Tesla,
+};
 use roadtrip_ingest::Scanner;
 
+use std::collections::HashMap;
 use std::env::args_os;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
 
 use tokio::runtime::Runtime;
 
+// Counts of what the scan produced. One file can hold several media (like a
+// FIT file with more than one session), so these aren't counts of files.
+#[derive(Debug, Default)]
+struct Summary {
+    media: usize,
+    unsupported: usize,
+    errors: usize,
+    duplicates: usize,
+}
+
+impl Summary {
+    fn record(&mut self, result: &Result<Media, Error>) {
+        match result {
+            Ok(_) => self.media += 1,
+            Err(Error::Unsupported { .. }) => self.unsupported += 1,
+            Err(_) => self.errors += 1,
+        }
+    }
+}
+
+// Quotes and escapes `text` as a JSON string.
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                write!(out, "\\u{:04x}", c as u32).unwrap();
+            }
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+// One line per media, so the output can be consumed a line at a time.
+fn json(media: &Media, duplicate_of: Option<&Path>) -> String {
+    let mut points = media.geometry().iter();
+
+    let (bbox, times) = match points.next() {
+        None => ("null".to_owned(), "null".to_owned()),
+        Some(first) => {
+            let mut south = first.latitude();
+            let mut north = south;
+            let mut west = first.longitude();
+            let mut east = west;
+            let mut start = first.time();
+            let mut end = start;
+
+            for point in points {
+                south = south.min(point.latitude());
+                north = north.max(point.latitude());
+                west = west.min(point.longitude());
+                east = east.max(point.longitude());
+                start = start.min(point.time());
+                end = end.max(point.time());
+            }
+
+            (
+                format!("[{}, {}, {}, {}]", west, south, east, north),
+                format!(
+                    "[{}, {}]",
+                    json_string(&start.to_rfc3339()),
+                    json_string(&end.to_rfc3339()),
+                ),
+            )
+        }
+    };
+
+    let duplicate_of = match duplicate_of {
+        Some(p) => json_string(&p.to_string_lossy()),
+        None => "null".to_owned(),
+    };
+
+    format!(
+        "{{\"path\": {}, \"hash\": {}, \"points\": {}, \"bbox\": {}, \
+         \"time\": {}, \"duplicate_of\": {}}}",
+        json_string(&media.path().to_string_lossy()),
+        json_string(&media.hash().to_string()),
+        media.geometry().len(),
+        bbox,
+        times,
+        duplicate_of,
+    )
+}
+
 fn main() {
     let mut scanner = Scanner::default();
     let ingester = Exiftool::new(
@@ -18,30 +121,88 @@ fn main() {
         .into(),
     );
 
+    scanner.add_ingester(Sidecar::new());
+    scanner.add_ingester(Tesla::new());
+    scanner.add_ingester(FreeGps::new());
+    scanner.add_ingester(Blackvue::new());
+    scanner.add_ingester(Fit::new());
+    scanner.add_ingester(Photo::new());
     scanner.add_ingester(ingester);
 
+    let mut as_json = false;
+
     for arg in args_os().skip(1) {
-        scanner.insert_path(arg);
+        if arg == "--json" {
+            as_json = true;
+        } else {
+            scanner.insert_path(arg);
+        }
     }
 
     let mut rt = Runtime::new().unwrap();
-    rt.block_on(async {
+    let summary = rt.block_on(async {
+        let mut summary = Summary::default();
+
+        // The first path each hash was seen at, so copies of the same file
+        // (say, on two SD cards) can be pointed out.
+        let mut seen: HashMap<Hash, PathBuf> = HashMap::new();
+
         let scan = scanner.scan();
         pin_mut!(scan);
 
         while let Some(res) = scan.next().await {
+            summary.record(&res);
+
             match res {
+                // With `--json`, stdout is kept for media only.
+                Err(e) if as_json => {
+                    eprintln!("ERR: {} ({})", e.path().to_string_lossy(), e);
+                }
                 Err(e) => {
                     println!("ERR: {} ({})", e.path().to_string_lossy(), e);
                 }
                 Ok(m) => {
-                    println!(
-                        " OK: {} ({} points)",
-                        m.path().to_string_lossy(),
-                        m.geometry().len(),
-                    );
+                    let original = seen
+                        .entry(m.hash().clone())
+                        .or_insert_with(|| m.path().to_owned());
+
+                    let duplicate_of = if original != m.path() {
+                        summary.duplicates += 1;
+                        Some(original.as_path())
+                    } else {
+                        None
+                    };
+
+                    if as_json {
+                        println!("{}", json(&m, duplicate_of));
+                    } else if let Some(original) = duplicate_of {
+                        println!(
+                            "DUP: {} (same as {})",
+                            m.path().to_string_lossy(),
+                            original.to_string_lossy(),
+                        );
+                    } else {
+                        println!(
+                            " OK: {} ({} points)",
+                            m.path().to_string_lossy(),
+                            m.geometry().len(),
+                        );
+                    }
                 }
             }
         }
+
+        summary
     });
+
+    eprintln!(
+        "{} media found ({} duplicates), {} unsupported files, {} errors",
+        summary.media, summary.duplicates, summary.unsupported, summary.errors,
+    );
+
+    // Files nothing could ingest are expected in most directories, so only
+    // real failures are reflected in the exit code.
+    if summary.errors > 0 {
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,207 @@
+// Remembers the size and modification time of every file that's been
+// ingested, so scanning the same directories again only has to look at files
+// that are new or have changed since.
+//
+// It's stored as text, one file per line: `<hashes> <size> <mtime> <path>`,
+// where the hashes are those of every media from the file, written with their
+// kind (see `Hash`'s `Display`) and separated by commas, and the mtime is seconds and nanoseconds since the Unix epoch.
+
+mod error {
+    use snafu::Snafu;
+
+    use std::path::PathBuf;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility = "pub(super)")]
+    pub enum Error {
+        Load {
+            source: std::io::Error,
+            path: PathBuf,
+        },
+        Save {
+            source: std::io::Error,
+            path: PathBuf,
+        },
+    }
+}
+
+use roadtrip_core::Hash;
+
+pub use self::error::Error;
+
+use snafu::{IntoError, ResultExt};
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::fs;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    size: u64,
+    modified: Duration,
+    hashes: Vec<Hash>,
+}
+
+impl Entry {
+    fn new(metadata: &Metadata, hashes: Vec<Hash>) -> Option<Self> {
+        let modified = metadata.modified().ok()?;
+
+        Some(Self {
+            size: metadata.len(),
+            modified: modified.duration_since(UNIX_EPOCH).ok()?,
+            hashes,
+        })
+    }
+
+    fn parse(line: &str) -> Option<(PathBuf, Self)> {
+        let mut parts = line.splitn(4, ' ');
+
+        let hashes = parts
+            .next()?
+            .split(',')
+            .filter(|h| !h.is_empty())
+            .map(|h| h.parse::<Hash>().ok())
+            .collect::<Option<_>>()?;
+
+        let size = parts.next()?.parse().ok()?;
+
+        let mut mtime = parts.next()?.split('.');
+        let secs = mtime.next()?.parse().ok()?;
+        let nanos = mtime.next()?.parse().ok()?;
+
+        let path = parts.next().filter(|p| !p.is_empty())?;
+
+        let entry = Self {
+            size,
+            modified: Duration::new(secs, nanos),
+            hashes,
+        };
+
+        Some((PathBuf::from(path), entry))
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn modified(&self) -> SystemTime {
+        UNIX_EPOCH + self.modified
+    }
+
+    pub fn hashes(&self) -> &[Hash] {
+        &self.hashes
+    }
+
+    fn matches(&self, metadata: &Metadata) -> bool {
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|m| m.duration_since(UNIX_EPOCH).ok());
+
+        self.size == metadata.len() && Some(self.modified) == modified
+    }
+}
+
+// Cheap to clone, and every clone shares the same entries.
+#[derive(Debug, Clone)]
+pub struct Index {
+    path: PathBuf,
+    entries: Arc<Mutex<HashMap<PathBuf, Entry>>>,
+}
+
+impl Index {
+    pub async fn load(path: PathBuf) -> Result<Self, Error> {
+        let text = match fs::read_to_string(&path).await {
+            Ok(t) => t,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(error::Load { path }.into_error(e)),
+        };
+
+        // Lines that don't parse are forgotten, and get ingested again.
+        let entries = text.lines().filter_map(Entry::parse).collect();
+
+        Ok(Self {
+            path,
+            entries: Arc::new(Mutex::new(entries)),
+        })
+    }
+
+    pub async fn save(&self) -> Result<(), Error> {
+        let mut text = String::new();
+
+        for (path, entry) in self.entries.lock().unwrap().iter() {
+            // Paths are one per line, so these can't be stored.
+            let path = match path.to_str() {
+                Some(p) if !p.contains(&['\n', '\r'][..]) => p,
+                _ => continue,
+            };
+
+            let hashes: Vec<_> =
+                entry.hashes.iter().map(Hash::to_string).collect();
+
+            writeln!(
+                text,
+                "{} {} {}.{:09} {}",
+                hashes.join(","),
+                entry.size,
+                entry.modified.as_secs(),
+                entry.modified.subsec_nanos(),
+                path
+            )
+            .unwrap();
+        }
+
+        fs::write(&self.path, text)
+            .await
+            .with_context(|| error::Save {
+                path: self.path.clone(),
+            })
+    }
+
+    pub fn get(&self, path: &Path) -> Option<Entry> {
+        self.entries.lock().unwrap().get(path).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // True if `path` was ingested before, and still has the same size and
+    // modification time.
+    pub fn is_unchanged(&self, path: &Path, metadata: &Metadata) -> bool {
+        match self.entries.lock().unwrap().get(path) {
+            Some(entry) => entry.matches(metadata),
+            None => false,
+        }
+    }
+
+    pub fn insert(
+        &self,
+        path: PathBuf,
+        metadata: &Metadata,
+        hashes: Vec<Hash>,
+    ) {
+        let mut entries = self.entries.lock().unwrap();
+
+        match Entry::new(metadata, hashes) {
+            Some(entry) => entries.insert(path, entry),
+            None => entries.remove(&path),
+        };
+    }
+
+    // Forgets every file with a media with the given hash, so the next scan
+    // that comes across one ingests it again.
+    pub fn forget(&self, hash: &Hash) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, e| !e.hashes.contains(hash));
+    }
+}
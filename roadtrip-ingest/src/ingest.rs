@@ -1,52 +1,209 @@
+mod blackvue;
 mod exiftool;
+mod fit;
+mod free_gps;
+mod mp4;
+mod nmea;
+mod photo;
+mod probe;
+mod sidecar;
+mod sniff;
+mod srt;
+mod tesla;
 
 use futures::TryFutureExt;
 
-use roadtrip_core::geometry::Geometry;
-use roadtrip_core::media::Media;
+use roadtrip_core::geometry::{Geometry, Path as CorePath, Point};
+use roadtrip_core::hasher::{self, Algorithm, Hasher, Kind};
+use roadtrip_core::media::{keys, Media};
+use roadtrip_core::Hash;
 
+pub use self::blackvue::Blackvue;
 pub use self::exiftool::Exiftool;
+pub use self::fit::Fit;
+pub use self::free_gps::FreeGps;
+pub use self::photo::Photo;
+pub use self::sidecar::Sidecar;
+pub use self::tesla::Tesla;
 
-use sha3::{Digest, Sha3_256};
+pub(crate) use self::sniff::sniff;
 
 use std::fmt;
 use std::future::Future;
-use std::path::PathBuf;
+use std::io::{self, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
 
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
 
+// Large files can be identified by a few pieces instead of their entire
+// contents. Anything at most three pieces long is hashed in full anyway.
+const SAMPLE_LEN: u64 = 1024 * 1024;
+
+// Hashed before the samples. Sampled hashes are a kind of their own, so they
+// can't match the full hash of any file either way.
+const SAMPLE_HEADER: &[u8] = b"roadtrip sampled hash v1\0";
+
+// Hashed along with the file's hash and a part number, for every part of a
+// file but the first.
+const PART_HEADER: &[u8] = b"roadtrip part hash v1\0";
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Hashing {
+    pub algorithm: Algorithm,
+    pub sampled: bool,
+}
+
+impl Default for Hashing {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::Sha3_256,
+            sampled: false,
+        }
+    }
+}
+
+tokio::task_local! {
+    // Chosen by the scanner for each file it ingests. Anything else gets the
+    // default.
+    static HASHING: Hashing;
+}
+
+pub(crate) async fn with_hashing<F>(hashing: Hashing, f: F) -> F::Output
+where
+    F: Future,
+{
+    HASHING.scope(hashing, f).await
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| extensions.iter().any(|x| x.eq_ignore_ascii_case(e)))
+        .unwrap_or(false)
+}
+
+// A single fix becomes a point, anything else a path.
+fn points_to_geometry(mut points: Vec<Point>) -> Geometry {
+    if points.len() == 1 {
+        Geometry::from(points.remove(0))
+    } else {
+        Geometry::from(CorePath::from_iter(points))
+    }
+}
+
+// Points without a time of their own fall back to the time in the metadata.
+// Returns `None` if neither is there.
+fn gpx_points(gpx: &gpx::Gpx) -> Option<Vec<Point>> {
+    let meta_time = gpx.metadata.as_ref().and_then(|m| m.time);
+
+    gpx.tracks
+        .iter()
+        .flat_map(|x| x.segments.iter())
+        .flat_map(|x| x.points.iter())
+        .map(|x| {
+            let point = x.point();
+            let time = x.time.or(meta_time)?;
+            let point = Point::new(point.lat(), point.lng(), time)
+                .with_altitude(x.elevation)
+                .with_speed(x.speed);
+
+            Some(point)
+        })
+        .collect()
+}
+
+// The size, and the start, middle, and end of the file.
+async fn hash_samples(
+    file: &mut File,
+    len: u64,
+    hasher: &mut dyn Hasher,
+) -> io::Result<()> {
+    hasher.update(SAMPLE_HEADER);
+    hasher.update(&len.to_le_bytes());
+
+    let mut buf = vec![0u8; SAMPLE_LEN as usize];
+
+    for start in &[0, (len - SAMPLE_LEN) / 2, len - SAMPLE_LEN] {
+        file.seek(SeekFrom::Start(*start)).await?;
+        file.read_exact(&mut buf).await?;
+        hasher.update(&buf);
+    }
+
+    Ok(())
+}
+
+// The file's hash and its size.
+async fn hash_file(path: &Path, hashing: Hashing) -> io::Result<(Hash, u64)> {
+    let mut file = File::open(path).await?;
+    let metadata = file.metadata().await?;
+    let len = metadata.len();
+
+    let mut hasher = hashing.algorithm.hasher();
+
+    if hashing.sampled && len > 3 * SAMPLE_LEN {
+        hash_samples(&mut file, len, &mut *hasher).await?;
+        let sampled = Kind::Sampled(hashing.algorithm);
+        return Ok((Hash::new(sampled, *hasher.finish()), len));
+    }
+
+    let buf_len = hasher::buffer_len(&metadata);
+    hasher::update_from_async_read(&mut *hasher, &mut file, buf_len).await?;
+
+    Ok((hasher.finish(), len))
+}
+
 async fn create_media(
     path: PathBuf,
     geometry: Geometry,
+    camera: Option<String>,
 ) -> Result<Media, std::io::Error> {
-    let mut file = File::open(&path).await?;
-    let mut hasher = Sha3_256::new();
+    let mut parts = create_media_parts(path, vec![geometry], camera).await?;
+    Ok(parts.remove(0))
+}
 
-    // TODO: Use st_blksize to get the buffer size
-    let mut buf = [0u8; 10240];
+// One media for each geometry, all from the same file. The first gets the
+// file's hash, so it's still recognized if the file used to be ingested as a
+// single media. The rest get a hash of the file's hash and their position.
+async fn create_media_parts(
+    path: PathBuf,
+    geometries: Vec<Geometry>,
+    camera: Option<String>,
+) -> Result<Vec<Media>, std::io::Error> {
+    let hashing = HASHING.try_with(|h| *h).unwrap_or_default();
+    let (hash, size) = hash_file(&path, hashing).await?;
+    let info = probe::probe(&path).await;
 
-    loop {
-        let n_read = file.read(&mut buf).await?;
-        if n_read == 0 {
-            break;
-        }
+    let parts = geometries.into_iter().enumerate().map(|(part, geometry)| {
+        let hash = if part == 0 {
+            hash.clone()
+        } else {
+            let mut hasher = hashing.algorithm.hasher();
+            hasher.update(PART_HEADER);
+            hasher.update(&hash[..]);
+            hasher.update(&(part as u64).to_le_bytes());
 
-        let read = &buf[0..n_read];
-        hasher.update(read);
-    }
+            // Only as good as the hash it came from.
+            Hash::new(hash.kind(), *hasher.finish())
+        };
 
-    let hash = hasher.finalize();
-    let array: [u8; 32] = hash.into();
+        let mut media = Media::builder()
+            .path(path.clone())
+            .geometry(geometry)
+            .hash(hash)
+            .camera(camera.clone())
+            .duration(info.duration)
+            .resolution(info.resolution)
+            .container(info.container.clone())
+            .codec(info.codec.clone())
+            .build();
 
-    let media = Media::builder()
-        .path(path)
-        .geometry(geometry)
-        .hash(array.into())
-        .build();
+        media.set_metadata(keys::FILE_SIZE, size as i64);
+        media
+    });
 
-    Ok(media)
+    Ok(parts.collect())
 }
 
 #[derive(Debug)]
@@ -74,8 +231,20 @@ where
     {
         Box::pin(self.0.ingest(path).map_err(Into::into))
     }
+
+    fn ingest_all<'a>(&'a self, path: PathBuf) -> IngestAll<'a, Error> {
+        Box::pin(self.0.ingest_all(path).map_err(Into::into))
+    }
+
+    fn supports(&self, path: &Path) -> bool {
+        self.0.supports(path)
+    }
 }
 
+// What `Ingest::ingest_all` returns.
+pub type IngestAll<'a, E> =
+    Pin<Box<dyn Future<Output = Result<Vec<Media>, E>> + 'a + Send>>;
+
 pub trait Ingest: std::fmt::Debug + Send + Sync {
     type Error: Into<Error>;
 
@@ -83,6 +252,20 @@ pub trait Ingest: std::fmt::Debug + Send + Sync {
         &'a self,
         path: PathBuf,
     ) -> Pin<Box<dyn Future<Output = Result<Media, Self::Error>> + 'a + Send>>;
+
+    // Like `ingest`, but for files that can hold more than one recording
+    // (like several sessions of an activity), each becoming its own media.
+    // The scanner only ever calls this one.
+    fn ingest_all<'a>(&'a self, path: PathBuf) -> IngestAll<'a, Self::Error> {
+        Box::pin(self.ingest(path).map_ok(|m| vec![m]))
+    }
+
+    // A quick check, usually of the extension, for whether `path` is worth
+    // trying at all. The scanner only gives files this rejects to `ingest` if
+    // their contents look like a kind of file it accepts.
+    fn supports(&self, _path: &Path) -> bool {
+        true
+    }
 }
 
 #[derive(Debug)]
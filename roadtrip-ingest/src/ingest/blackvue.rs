@@ -0,0 +1,168 @@
+// Blackvue dashcams keep an NMEA log in a `gps ` atom, inside a `free` atom at
+// the top level of their MP4 files. Each line starts with the camera's own
+// clock, in milliseconds since the Unix epoch: `[1598445296000]$GPRMC,...`.
+
+mod error {
+    use snafu::Snafu;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility = "pub(super)")]
+    pub enum Error {
+        Open { source: std::io::Error },
+        Read { source: std::io::Error },
+        NotMp4,
+        NoGps,
+        NoPoints,
+    }
+}
+
+use chrono::{TimeZone, Utc};
+
+use roadtrip_core::geometry::Point;
+use roadtrip_core::media::Media;
+
+pub use self::error::Error;
+
+use snafu::{ensure, ResultExt};
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use super::{mp4, nmea, Ingest};
+
+use tokio::fs::File;
+
+use tracing::trace;
+
+// The log is usually a few hundred kilobytes. Bigger `free` atoms are just
+// padding.
+const MAX_FREE: u64 = 16 * 1024 * 1024;
+
+impl From<Error> for super::Error {
+    fn from(e: Error) -> Self {
+        // Let other ingesters try files without a log we understand.
+        let supported = match e {
+            Error::Open { .. }
+            | Error::NotMp4
+            | Error::NoGps
+            | Error::NoPoints => false,
+            Error::Read { .. } => true,
+        };
+
+        Self::new(e, supported)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Blackvue {
+    _p: (),
+}
+
+impl Blackvue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn read_log(file: &mut File, len: u64) -> Result<Vec<u8>, Error> {
+        let mut pos = 0;
+        let mut first = true;
+
+        while let Some(atom) =
+            mp4::header(file, pos, len).await.context(error::Read)?
+        {
+            if first {
+                ensure!(&atom.kind == b"ftyp", error::NotMp4);
+                first = false;
+            }
+
+            ensure!(atom.size >= atom.header_len, error::NotMp4);
+            pos = atom.end();
+
+            if &atom.kind != b"free" {
+                continue;
+            }
+
+            if atom.body_len() > MAX_FREE {
+                trace!(size = atom.size, "skipping free atom");
+                continue;
+            }
+
+            let free = atom.read_body(file).await.context(error::Read)?;
+
+            if let Some((_, gps)) =
+                mp4::Atoms(&free).find(|(kind, _)| kind == b"gps ")
+            {
+                return Ok(gps.to_vec());
+            }
+        }
+
+        if first {
+            error::NotMp4.fail()
+        } else {
+            error::NoGps.fail()
+        }
+    }
+
+    fn parse(log: &[u8]) -> Vec<Point> {
+        let text = String::from_utf8_lossy(log);
+        let mut parser = nmea::Parser::default();
+        let mut points = Vec::new();
+
+        // The log is padded out with NULs.
+        for line in text.lines().map(|l| l.trim_matches('\0')) {
+            // GGA sentences don't have a date, so until an RMC sentence comes
+            // along, use the camera's.
+            let millis = line
+                .strip_prefix('[')
+                .and_then(|l| l.split(']').next())
+                .and_then(|l| l.parse::<i64>().ok());
+
+            if let Some(millis) = millis {
+                if let Some(time) =
+                    Utc.timestamp_opt(millis.div_euclid(1000), 0).single()
+                {
+                    parser.assume_date(time.naive_utc().date());
+                }
+            }
+
+            points.extend(parser.line(line));
+        }
+
+        nmea::dedup(&mut points);
+        points
+    }
+
+    async fn async_ingest(&self, path: PathBuf) -> Result<Media, Error> {
+        let mut file = File::open(&path).await.context(error::Open)?;
+        let len = file.metadata().await.context(error::Open)?.len();
+
+        let log = Self::read_log(&mut file, len).await?;
+        drop(file);
+
+        let points = Self::parse(&log);
+        ensure!(!points.is_empty(), error::NoPoints);
+
+        let geometry = super::points_to_geometry(points);
+        let media = super::create_media(path, geometry, None)
+            .await
+            .context(error::Read)?;
+
+        Ok(media)
+    }
+}
+
+impl Ingest for Blackvue {
+    type Error = Error;
+
+    fn ingest<'a>(
+        &'a self,
+        path: PathBuf,
+    ) -> Pin<Box<dyn Future<Output = Result<Media, Error>> + 'a + Send>> {
+        Box::pin(self.async_ingest(path))
+    }
+
+    fn supports(&self, path: &Path) -> bool {
+        super::has_extension(path, mp4::EXTENSIONS)
+    }
+}
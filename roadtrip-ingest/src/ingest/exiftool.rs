@@ -7,10 +7,16 @@ mod error {
         Spawn {
             source: tokio::io::Error,
         },
+        Pipe {
+            source: tokio::io::Error,
+        },
         CmdFail {
             status: std::process::ExitStatus,
             err: String,
         },
+        NoOutput {
+            err: String,
+        },
         Gpx {
             source: gpx::errors::Error,
         },
@@ -21,20 +27,36 @@ mod error {
     }
 }
 
-use roadtrip_core::geometry::{Geometry, Path as CorePath, Point};
 use roadtrip_core::media::Media;
 
 pub use self::error::Error;
 
-use snafu::ResultExt;
+use snafu::{OptionExt, ResultExt};
 
 use std::future::Future;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::process::Stdio;
+use std::sync::Mutex;
 
 use super::Ingest;
 
-use tokio::process::Command;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command};
+use tokio::sync::Semaphore;
+
+use tracing::{debug, trace};
+
+const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+// Videos and photos that might have a track or a position in their metadata.
+// exiftool reads plenty of other formats, but spawning it for every text file
+// in a directory is a waste.
+const EXTENSIONS: &[&str] = &[
+    "mp4", "mov", "m4v", "3gp", "avi", "mts", "m2ts", "ts", "mkv", "insv",
+    "lrv", "jpg", "jpeg", "heic", "heif", "tif", "tiff", "dng", "cr2", "cr3",
+    "nef", "arw", "orf", "rw2", "raf", "png",
+];
 
 impl From<Error> for super::Error {
     fn from(e: Error) -> Self {
@@ -42,9 +64,107 @@ impl From<Error> for super::Error {
     }
 }
 
+// Reads lines until exiftool prints `marker`, which it does once it has
+// finished with a command.
+async fn read_until_ready<R>(
+    reader: &mut R,
+    marker: &[u8],
+) -> std::io::Result<Vec<u8>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut out = Vec::new();
+
+    loop {
+        let start = out.len();
+        let n_read = reader.read_until(b'\n', &mut out).await?;
+
+        if n_read == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let line = &out[start..];
+        let end = line
+            .iter()
+            .rposition(|b| *b != b'\n' && *b != b'\r')
+            .map(|i| i + 1)
+            .unwrap_or(0);
+
+        if &line[..end] == marker {
+            out.truncate(start);
+            return Ok(out);
+        }
+    }
+}
+
+// An exiftool started with `-stay_open`, which reads commands from stdin
+// instead of exiting after the first file.
+#[derive(Debug)]
+struct Process {
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    stderr: BufReader<ChildStderr>,
+    next: u64,
+}
+
+impl Process {
+    fn spawn(format: &Path) -> std::io::Result<Self> {
+        let mut child = Command::new("exiftool")
+            .arg("-stay_open")
+            .arg("True")
+            .arg("-@")
+            .arg("-")
+            .arg("-common_args")
+            .arg("-ee")
+            .arg("-p")
+            .arg(format)
+            .arg("-d")
+            .arg(DATE_FORMAT)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+
+        // All three were just set to piped, so they're always there.
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let stderr = BufReader::new(child.stderr.take().unwrap());
+
+        Ok(Self {
+            _child: child,
+            stdin,
+            stdout,
+            stderr,
+            next: 0,
+        })
+    }
+
+    async fn run(&mut self, path: &str) -> std::io::Result<(Vec<u8>, String)> {
+        self.next += 1;
+
+        // `-execute` prints the marker to stdout, and `-echo4` to stderr.
+        let marker = format!("{{ready{}}}", self.next);
+        let command =
+            format!("{}\n-echo4\n{}\n-execute{}\n", path, marker, self.next);
+
+        self.stdin.write_all(command.as_bytes()).await?;
+        self.stdin.flush().await?;
+
+        let stdout = read_until_ready(&mut self.stdout, marker.as_bytes());
+        let stderr = read_until_ready(&mut self.stderr, marker.as_bytes());
+        let (stdout, stderr) = futures::try_join!(stdout, stderr)?;
+
+        Ok((stdout, String::from_utf8_lossy(&stderr).into_owned()))
+    }
+}
+
 #[derive(Debug)]
 pub struct Exiftool {
     format: PathBuf,
+    permits: Semaphore,
+    idle: Mutex<Vec<Process>>,
 }
 
 impl Exiftool {
@@ -54,23 +174,44 @@ impl Exiftool {
     ));
 
     pub fn new(format: PathBuf) -> Self {
-        Self { format }
+        Self::with_pool_size(format, 1)
     }
 
-    async fn async_ingest(&self, path: PathBuf) -> Result<Media, Error> {
+    // Runs up to `size` exiftool processes, for ingesting that many files at
+    // once. They're started as needed, and live as long as this does.
+    pub fn with_pool_size(format: PathBuf, size: usize) -> Self {
+        Self {
+            format,
+            permits: Semaphore::new(size.max(1)),
+            idle: Mutex::new(Vec::new()),
+        }
+    }
+
+    // Paths are sent to a running exiftool one per line, so anything that
+    // can't be written that way needs a process of its own.
+    fn as_line(path: &Path) -> Option<&str> {
+        match path.to_str() {
+            Some(p) if !p.contains(&['\n', '\r'][..]) => Some(p),
+            _ => None,
+        }
+    }
+
+    async fn run_once(&self, path: &Path) -> Result<Vec<u8>, Error> {
         let output = Command::new("exiftool")
             .arg("-ee")
             .arg("-p")
             .arg(&self.format)
             .arg("-d")
-            .arg("%Y-%m-%dT%H:%M:%SZ")
-            .arg(&path)
+            .arg(DATE_FORMAT)
+            .arg(path)
+            .kill_on_drop(true)
             .output()
             .await
             .context(error::Spawn)?;
 
         if !output.status.success() {
-            let err = String::from_utf8_lossy(&output.stderr).to_owned();
+            let err = String::from_utf8_lossy(&output.stderr).into_owned();
+            debug!(status = %output.status, stderr = %err, "exiftool failed");
             return error::CmdFail {
                 status: output.status,
                 err,
@@ -78,35 +219,57 @@ impl Exiftool {
             .fail();
         }
 
-        let gpx = gpx::read(output.stdout.as_slice()).context(error::Gpx)?;
-        let meta_time = gpx.metadata.and_then(|m| m.time);
+        Ok(output.stdout)
+    }
+
+    async fn run_pooled(&self, path: &str) -> Result<Vec<u8>, Error> {
+        let _permit = self.permits.acquire().await;
+
+        let idle = self.idle.lock().unwrap().pop();
+        let mut process = match idle {
+            Some(p) => p,
+            None => {
+                trace!("starting exiftool");
+                Process::spawn(&self.format).context(error::Spawn)?
+            }
+        };
+
+        // If this future is dropped part way through, so is the process,
+        // since there'd be no telling what it was in the middle of.
+        let (stdout, err) = process.run(path).await.context(error::Pipe)?;
+
+        self.idle.lock().unwrap().push(process);
+
+        // Without an exit status, the only sign of failure is no output.
+        if stdout.is_empty() {
+            debug!(stderr = %err, "exiftool failed");
+            return error::NoOutput { err }.fail();
+        }
+
+        Ok(stdout)
+    }
 
-        let mut points: Vec<_> = gpx
+    async fn async_ingest(&self, path: PathBuf) -> Result<Media, Error> {
+        let stdout = match Self::as_line(&path) {
+            Some(p) => self.run_pooled(p).await?,
+            None => self.run_once(&path).await?,
+        };
+
+        let gpx = gpx::read(stdout.as_slice()).context(error::Gpx)?;
+
+        // The format file stores the camera model as the track name.
+        let camera = gpx
             .tracks
             .iter()
-            .flat_map(|x| x.segments.iter())
-            .flat_map(|x| x.points.iter())
-            .map(|x| {
-                let point = x.point();
-                let time = match x.time {
-                    Some(t) => t,
-                    None => match meta_time {
-                        Some(t) => t,
-                        None => return Err(error::NoTimestamp {}.build()),
-                    },
-                };
-
-                Ok(Point::new(point.lat(), point.lng(), time))
-            })
-            .collect::<Result<_, _>>()?;
-
-        let geometry = if points.len() == 1 {
-            Geometry::from(points.remove(0))
-        } else {
-            Geometry::from(CorePath::from_iter(points))
-        };
+            .filter_map(|t| t.name.as_deref())
+            .map(str::trim)
+            .find(|n| !n.is_empty())
+            .map(String::from);
 
-        let media = super::create_media(path, geometry)
+        let points = super::gpx_points(&gpx).context(error::NoTimestamp)?;
+        let geometry = super::points_to_geometry(points);
+
+        let media = super::create_media(path, geometry, camera)
             .await
             .context(error::Read)?;
 
@@ -123,4 +286,8 @@ impl Ingest for Exiftool {
     ) -> Pin<Box<dyn Future<Output = Result<Media, Error>> + 'a + Send>> {
         Box::pin(self.async_ingest(path))
     }
+
+    fn supports(&self, path: &Path) -> bool {
+        super::has_extension(path, EXTENSIONS)
+    }
 }
@@ -0,0 +1,425 @@
+// Reads the track from a Garmin FIT activity. Only `record` messages are
+// used, and only their timestamp, position, altitude and speed fields. `session` messages are
+// only used to tell where one session ends and the next begins.
+
+mod error {
+    use snafu::Snafu;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility = "pub(super)")]
+    pub enum Error {
+        Open { source: std::io::Error },
+        Read { source: std::io::Error },
+        NotFit,
+        TooLarge { size: u64 },
+        Malformed { offset: usize },
+        NoPoints,
+    }
+}
+
+use chrono::{LocalResult, TimeZone, Utc};
+
+use roadtrip_core::geometry::Point;
+use roadtrip_core::media::Media;
+
+pub use self::error::Error;
+
+use snafu::{ensure, OptionExt, ResultExt};
+
+use std::convert::TryInto;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use super::{Ingest, IngestAll};
+
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+// Activities are rarely more than a few megabytes.
+const MAX_SIZE: u64 = 64 * 1024 * 1024;
+
+const MAGIC: &[u8] = b".FIT";
+
+const EXTENSIONS: &[&str] = &["fit"];
+
+// FIT timestamps count seconds from 1989-12-31T00:00:00Z.
+const EPOCH: i64 = 631_065_600;
+
+const SESSION: u16 = 18;
+const RECORD: u16 = 20;
+
+const FIELD_LAT: u8 = 0;
+const FIELD_LNG: u8 = 1;
+const FIELD_ALTITUDE: u8 = 2;
+const FIELD_SPEED: u8 = 6;
+const FIELD_ENHANCED_SPEED: u8 = 73;
+const FIELD_ENHANCED_ALTITUDE: u8 = 78;
+const FIELD_TIMESTAMP: u8 = 253;
+
+const INVALID_SINT32: i32 = 0x7fff_ffff;
+const INVALID_UINT16: u16 = 0xffff;
+const INVALID_UINT32: u32 = 0xffff_ffff;
+
+impl From<Error> for super::Error {
+    fn from(e: Error) -> Self {
+        // A FIT file without positions (like a device's settings) isn't worth
+        // reporting, but a damaged activity is.
+        let supported = match e {
+            Error::Open { .. }
+            | Error::NotFit
+            | Error::TooLarge { .. }
+            | Error::NoPoints => false,
+            Error::Read { .. } | Error::Malformed { .. } => true,
+        };
+
+        Self::new(e, supported)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Definition {
+    global: u16,
+    big_endian: bool,
+    // Field number and size, in the order they appear in a message.
+    fields: Vec<(u8, usize)>,
+    len: usize,
+}
+
+impl Definition {
+    fn u16_at(&self, bytes: &[u8]) -> u16 {
+        let bytes = bytes.try_into().unwrap();
+        if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }
+    }
+
+    fn u32_at(&self, bytes: &[u8]) -> u32 {
+        let bytes = bytes.try_into().unwrap();
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+}
+
+// Walks the records of one FIT file, collecting positions.
+struct Records<'a> {
+    data: &'a [u8],
+    offset: usize,
+    definitions: Vec<Option<Definition>>,
+    last_time: u32,
+    session_ended: bool,
+}
+
+impl<'a> Records<'a> {
+    fn new(data: &'a [u8], offset: usize) -> Self {
+        Self {
+            data,
+            offset,
+            definitions: vec![None; 16],
+            last_time: 0,
+            session_ended: false,
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let offset = self.offset;
+        ensure!(self.data.len() >= len, error::Malformed { offset });
+
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        self.offset += len;
+
+        Ok(head)
+    }
+
+    fn definition(
+        &mut self,
+        local: usize,
+        developer: bool,
+    ) -> Result<(), Error> {
+        let fixed = self.take(5)?;
+        let big_endian = fixed[1] == 1;
+        let global = fixed[2..4].try_into().unwrap();
+        let global = if big_endian {
+            u16::from_be_bytes(global)
+        } else {
+            u16::from_le_bytes(global)
+        };
+
+        let count = usize::from(fixed[4]);
+        let fields: Vec<_> = self
+            .take(count * 3)?
+            .chunks_exact(3)
+            .map(|f| (f[0], usize::from(f[1])))
+            .collect();
+
+        let mut len = fields.iter().map(|(_, size)| size).sum();
+
+        // Developer fields are never needed, but their size still counts.
+        if developer {
+            let count = usize::from(self.take(1)?[0]);
+            len += self
+                .take(count * 3)?
+                .chunks_exact(3)
+                .map(|f| usize::from(f[1]))
+                .sum::<usize>();
+        }
+
+        self.definitions[local] = Some(Definition {
+            global,
+            big_endian,
+            fields,
+            len,
+        });
+
+        Ok(())
+    }
+
+    fn message(
+        &mut self,
+        local: usize,
+        compressed: Option<u32>,
+    ) -> Result<Option<Point>, Error> {
+        let offset = self.offset;
+        let definition = self.definitions[local]
+            .clone()
+            .context(error::Malformed { offset })?;
+
+        let body = self.take(definition.len)?;
+
+        let mut time = compressed;
+        let mut lat = None;
+        let mut lng = None;
+        let mut altitude = None;
+        let mut speed = None;
+        let mut at = 0;
+
+        for (number, size) in &definition.fields {
+            let value = &body[at..at + size];
+            at += size;
+
+            // Altitude and speed are the same scaled values either way, with
+            // the enhanced fields having more range.
+            let value = match *size {
+                2 => match definition.u16_at(value) {
+                    INVALID_UINT16 => continue,
+                    v => u32::from(v),
+                },
+                4 => definition.u32_at(value),
+                _ => continue,
+            };
+
+            match (*number, *size) {
+                (FIELD_TIMESTAMP, 4) => time = Some(value),
+                (FIELD_LAT, 4) => lat = Some(value as i32),
+                (FIELD_LNG, 4) => lng = Some(value as i32),
+                (FIELD_ALTITUDE, 2) | (FIELD_ENHANCED_ALTITUDE, 4)
+                    if value != INVALID_UINT32 =>
+                {
+                    altitude = Some(f64::from(value) / 5.0 - 500.0);
+                }
+                (FIELD_SPEED, 2) | (FIELD_ENHANCED_SPEED, 4)
+                    if value != INVALID_UINT32 =>
+                {
+                    speed = Some(f64::from(value) / 1000.0);
+                }
+                _ => (),
+            }
+        }
+
+        if let Some(time) = time {
+            self.last_time = time;
+        }
+
+        // A session's summary comes after all of its records.
+        if definition.global == SESSION {
+            self.session_ended = true;
+        }
+
+        if definition.global != RECORD {
+            return Ok(None);
+        }
+
+        let (time, lat, lng) = match (time, lat, lng) {
+            (Some(t), Some(lat), Some(lng))
+                if lat != INVALID_SINT32 && lng != INVALID_SINT32 =>
+            {
+                (t, lat, lng)
+            }
+            _ => return Ok(None),
+        };
+
+        let time = match Utc.timestamp_opt(EPOCH + i64::from(time), 0) {
+            LocalResult::Single(t) => t,
+            _ => return Ok(None),
+        };
+
+        let point =
+            Point::new(Fit::semicircles(lat), Fit::semicircles(lng), time)
+                .with_altitude(altitude)
+                .with_speed(speed);
+
+        Ok(Some(point))
+    }
+
+    // Adds the points of each session to the end of `sessions`, starting a
+    // new one whenever a session ends.
+    fn read(mut self, sessions: &mut Vec<Vec<Point>>) -> Result<(), Error> {
+        while !self.data.is_empty() {
+            let header = self.take(1)?[0];
+
+            let point = if header & 0x80 != 0 {
+                // Compressed timestamp headers only carry the low five bits
+                // of the time, relative to the last full timestamp.
+                let local = usize::from((header >> 5) & 0x03);
+                let offset = u32::from(header & 0x1f);
+                let mut time = (self.last_time & !0x1f) + offset;
+                if offset < self.last_time & 0x1f {
+                    time += 0x20;
+                }
+
+                self.message(local, Some(time))?
+            } else if header & 0x40 != 0 {
+                let local = usize::from(header & 0x0f);
+                self.definition(local, header & 0x20 != 0)?;
+                None
+            } else {
+                self.message(usize::from(header & 0x0f), None)?
+            };
+
+            if let Some(point) = point {
+                match sessions.last_mut() {
+                    Some(points) if !self.session_ended => points.push(point),
+                    _ => sessions.push(vec![point]),
+                }
+
+                self.session_ended = false;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Fit {
+    _p: (),
+}
+
+impl Fit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn semicircles(value: i32) -> f64 {
+        f64::from(value) * (180.0 / 2_147_483_648.0)
+    }
+
+    // Returns the header length and the size of the records that follow.
+    fn header(data: &[u8]) -> Option<(usize, usize)> {
+        let header_len = usize::from(*data.first()?);
+
+        if header_len < 12 || data.get(8..12)? != MAGIC {
+            return None;
+        }
+
+        let size = u32::from_le_bytes(data[4..8].try_into().unwrap());
+        Some((header_len, size as usize))
+    }
+
+    // Returns the points of each session, none of them empty.
+    fn parse(mut data: &[u8]) -> Result<Vec<Vec<Point>>, Error> {
+        let mut sessions = Vec::new();
+        let mut offset = 0;
+
+        // Several FIT files can be chained one after another, each followed
+        // by a two byte CRC. Each file's sessions are kept apart from the
+        // next file's.
+        while let Some((header_len, size)) = Self::header(data) {
+            let start = offset + header_len;
+            let records = data
+                .get(header_len..header_len + size)
+                .context(error::Malformed { offset: start })?;
+
+            let mut found = Vec::new();
+            Records::new(records, start).read(&mut found)?;
+            sessions.extend(found);
+
+            let end = (header_len + size + 2).min(data.len());
+            data = &data[end..];
+            offset += end;
+        }
+
+        Ok(sessions)
+    }
+
+    async fn read(path: &Path) -> Result<Vec<Vec<Point>>, Error> {
+        let mut file = File::open(&path).await.context(error::Open)?;
+        let len = file.metadata().await.context(error::Open)?.len();
+
+        let mut header = [0u8; 12];
+        file.read_exact(&mut header).await.context(error::Open)?;
+        ensure!(Self::header(&header).is_some(), error::NotFit);
+        ensure!(len <= MAX_SIZE, error::TooLarge { size: len });
+
+        let mut data = header.to_vec();
+        file.read_to_end(&mut data).await.context(error::Read)?;
+        drop(file);
+
+        let sessions = Self::parse(&data)?;
+        ensure!(!sessions.is_empty(), error::NoPoints);
+
+        Ok(sessions)
+    }
+
+    async fn async_ingest(&self, path: PathBuf) -> Result<Media, Error> {
+        let points = Self::read(&path).await?.concat();
+
+        let geometry = super::points_to_geometry(points);
+        let media = super::create_media(path, geometry, None)
+            .await
+            .context(error::Read)?;
+
+        Ok(media)
+    }
+
+    async fn async_ingest_all(
+        &self,
+        path: PathBuf,
+    ) -> Result<Vec<Media>, Error> {
+        let geometries = Self::read(&path)
+            .await?
+            .into_iter()
+            .map(super::points_to_geometry)
+            .collect();
+
+        super::create_media_parts(path, geometries, None)
+            .await
+            .context(error::Read)
+    }
+}
+
+impl Ingest for Fit {
+    type Error = Error;
+
+    fn ingest<'a>(
+        &'a self,
+        path: PathBuf,
+    ) -> Pin<Box<dyn Future<Output = Result<Media, Error>> + 'a + Send>> {
+        Box::pin(self.async_ingest(path))
+    }
+
+    // One media for each session.
+    fn ingest_all<'a>(&'a self, path: PathBuf) -> IngestAll<'a, Error> {
+        Box::pin(self.async_ingest_all(path))
+    }
+
+    fn supports(&self, path: &Path) -> bool {
+        super::has_extension(path, EXTENSIONS)
+    }
+}
@@ -0,0 +1,234 @@
+// Reads the GPS log that Novatek based dashcams (Viofo, Street Guardian, and
+// many unbranded ones) embed in their MP4 files. A `gps ` atom inside `moov`
+// lists the offset and size of one `freeGPS ` block per second of video.
+
+mod error {
+    use snafu::Snafu;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility = "pub(super)")]
+    pub enum Error {
+        Open { source: std::io::Error },
+        Read { source: std::io::Error },
+        NotMp4,
+        NoGps,
+        NoPoints,
+        TooLarge { size: u64 },
+    }
+}
+
+use chrono::{TimeZone, Utc};
+
+use roadtrip_core::geometry::Point;
+use roadtrip_core::media::Media;
+
+pub use self::error::Error;
+
+use snafu::{ensure, OptionExt, ResultExt};
+
+use std::convert::TryInto;
+use std::future::Future;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use super::{mp4, Ingest};
+
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+use tracing::trace;
+
+// Bigger than any `moov` seen in practice, but small enough to read into
+// memory without worrying.
+const MAX_MOOV: u64 = 64 * 1024 * 1024;
+
+// The blocks are usually 0x8000 bytes, mostly padding.
+const MAX_BLOCK: u64 = 64 * 1024;
+
+const BLOCK_MAGIC: &[u8] = b"freeGPS ";
+
+impl From<Error> for super::Error {
+    fn from(e: Error) -> Self {
+        // Let other ingesters try files without a GPS log we understand.
+        let supported = match e {
+            Error::Open { .. }
+            | Error::NotMp4
+            | Error::NoGps
+            | Error::NoPoints
+            | Error::TooLarge { .. } => false,
+            Error::Read { .. } => true,
+        };
+
+        Self::new(e, supported)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct FreeGps {
+    _p: (),
+}
+
+impl FreeGps {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Finds the `moov` atom at the top level of the file and reads it.
+    async fn read_moov(file: &mut File, len: u64) -> Result<Vec<u8>, Error> {
+        let mut pos = 0;
+        let mut first = true;
+
+        while let Some(atom) =
+            mp4::header(file, pos, len).await.context(error::Read)?
+        {
+            // Every MP4 starts with `ftyp`, which rules out most other files
+            // before reading any further.
+            if first {
+                ensure!(&atom.kind == b"ftyp", error::NotMp4);
+                first = false;
+            }
+
+            ensure!(atom.size >= atom.header_len, error::NotMp4);
+
+            if &atom.kind == b"moov" {
+                let size = atom.size;
+                ensure!(size <= MAX_MOOV, error::TooLarge { size });
+                return atom.read_body(file).await.context(error::Read);
+            }
+
+            pos = atom.end();
+        }
+
+        if first {
+            error::NotMp4.fail()
+        } else {
+            error::NoGps.fail()
+        }
+    }
+
+    // Each entry in the table is a big-endian offset and size.
+    fn table(gps: &[u8]) -> impl Iterator<Item = (u64, u64)> + '_ {
+        let count = gps
+            .get(4..8)
+            .map(|c| u32::from_be_bytes(c.try_into().unwrap()))
+            .unwrap_or(0);
+
+        gps.get(8..)
+            .unwrap_or_default()
+            .chunks_exact(8)
+            .take(count as usize)
+            .map(|e| {
+                let offset = u32::from_be_bytes(e[..4].try_into().unwrap());
+                let size = u32::from_be_bytes(e[4..].try_into().unwrap());
+                (offset.into(), size.into())
+            })
+    }
+
+    // Coordinates are stored like NMEA, as degrees and minutes run together
+    // (`ddmm.mmmm`).
+    fn degrees(value: f32, negative: bool) -> f64 {
+        let value = f64::from(value);
+        let degrees = (value / 100.0).trunc();
+        let decimal = degrees + (value - degrees * 100.0) / 60.0;
+
+        if negative {
+            -decimal
+        } else {
+            decimal
+        }
+    }
+
+    fn parse_block(block: &[u8]) -> Option<Point> {
+        if block.get(4..12)? != BLOCK_MAGIC || block.len() < 0x34 {
+            return None;
+        }
+
+        let u32_at = |at: usize| {
+            u32::from_le_bytes(block[at..at + 4].try_into().unwrap())
+        };
+        let f32_at = |at: usize| {
+            f32::from_le_bytes(block[at..at + 4].try_into().unwrap())
+        };
+
+        // `V` means the receiver didn't have a fix yet.
+        if block[0x28] != b'A' {
+            return None;
+        }
+
+        let mut year = u32_at(0x1c) as i32;
+        if year < 2000 {
+            year += 2000;
+        }
+
+        let time = Utc
+            .ymd_opt(year, u32_at(0x20), u32_at(0x24))
+            .and_hms_opt(u32_at(0x10), u32_at(0x14), u32_at(0x18))
+            .single()?;
+
+        let lat = Self::degrees(f32_at(0x2c), block[0x29] == b'S');
+        let lng = Self::degrees(f32_at(0x30), block[0x2a] == b'W');
+
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lng) {
+            return None;
+        }
+
+        Some(Point::new(lat, lng, time))
+    }
+
+    async fn async_ingest(&self, path: PathBuf) -> Result<Media, Error> {
+        let mut file = File::open(&path).await.context(error::Open)?;
+        let len = file.metadata().await.context(error::Open)?.len();
+
+        let moov = Self::read_moov(&mut file, len).await?;
+        let (_, gps) = mp4::Atoms(&moov)
+            .find(|(kind, _)| kind == b"gps ")
+            .context(error::NoGps)?;
+
+        let mut points = Vec::new();
+
+        for (offset, size) in Self::table(gps) {
+            if size > MAX_BLOCK || offset + size > len {
+                trace!(offset, size, "skipping gps block");
+                continue;
+            }
+
+            let mut block = vec![0u8; size as usize];
+            file.seek(SeekFrom::Start(offset))
+                .await
+                .context(error::Read)?;
+            file.read_exact(&mut block).await.context(error::Read)?;
+
+            if let Some(point) = Self::parse_block(&block) {
+                points.push(point);
+            }
+        }
+
+        ensure!(!points.is_empty(), error::NoPoints);
+
+        let geometry = super::points_to_geometry(points);
+
+        drop(file);
+
+        let media = super::create_media(path, geometry, None)
+            .await
+            .context(error::Read)?;
+
+        Ok(media)
+    }
+}
+
+impl Ingest for FreeGps {
+    type Error = Error;
+
+    fn ingest<'a>(
+        &'a self,
+        path: PathBuf,
+    ) -> Pin<Box<dyn Future<Output = Result<Media, Error>> + 'a + Send>> {
+        Box::pin(self.async_ingest(path))
+    }
+
+    fn supports(&self, path: &Path) -> bool {
+        super::has_extension(path, mp4::EXTENSIONS)
+    }
+}
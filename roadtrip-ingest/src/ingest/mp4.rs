@@ -0,0 +1,109 @@
+// Just enough of the MP4 (ISO base media) container to find atoms holding
+// vendor specific GPS logs.
+
+use std::convert::TryInto;
+use std::io::SeekFrom;
+
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+// Cameras that write MP4 files name them one of these.
+pub(super) const EXTENSIONS: &[&str] = &["mp4", "mov", "m4v"];
+
+// Iterates over the atoms packed into a slice, stopping at the first one that
+// doesn't fit.
+pub(super) struct Atoms<'a>(pub &'a [u8]);
+
+impl<'a> Iterator for Atoms<'a> {
+    type Item = ([u8; 4], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let header = self.0.get(..8)?;
+        let size = u32::from_be_bytes(header[..4].try_into().unwrap());
+        let kind = header[4..8].try_into().unwrap();
+
+        let (start, end) = match size {
+            0 => (8, self.0.len()),
+            1 => {
+                let large = self.0.get(8..16)?;
+                let size = u64::from_be_bytes(large.try_into().unwrap());
+                (16, size.try_into().ok()?)
+            }
+            s => (8, s as usize),
+        };
+
+        if end < start || end > self.0.len() {
+            self.0 = &[];
+            return None;
+        }
+
+        let body = &self.0[start..end];
+        self.0 = &self.0[end..];
+
+        Some((kind, body))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Header {
+    pub kind: [u8; 4],
+    pub start: u64,
+    pub header_len: u64,
+    pub size: u64,
+}
+
+impl Header {
+    pub fn end(&self) -> u64 {
+        self.start + self.size
+    }
+
+    pub fn body_len(&self) -> u64 {
+        self.size.saturating_sub(self.header_len)
+    }
+
+    pub async fn read_body(&self, file: &mut File) -> std::io::Result<Vec<u8>> {
+        let mut body = vec![0u8; self.body_len() as usize];
+        file.seek(SeekFrom::Start(self.start + self.header_len))
+            .await?;
+        file.read_exact(&mut body).await?;
+        Ok(body)
+    }
+}
+
+// Reads the header of the atom starting at `pos`, without reading its body.
+// Returns `None` once there's no room left for another atom.
+pub(super) async fn header(
+    file: &mut File,
+    pos: u64,
+    len: u64,
+) -> std::io::Result<Option<Header>> {
+    if pos + 8 > len {
+        return Ok(None);
+    }
+
+    let mut raw = [0u8; 8];
+    file.seek(SeekFrom::Start(pos)).await?;
+    file.read_exact(&mut raw).await?;
+
+    let kind = raw[4..8].try_into().unwrap();
+    let raw_size = u32::from_be_bytes(raw[..4].try_into().unwrap());
+    let mut header_len = 8;
+
+    let size = match raw_size {
+        0 => len - pos,
+        1 => {
+            let mut large = [0u8; 8];
+            file.read_exact(&mut large).await?;
+            header_len = 16;
+            u64::from_be_bytes(large)
+        }
+        s => s.into(),
+    };
+
+    Ok(Some(Header {
+        kind,
+        start: pos,
+        header_len,
+        size,
+    }))
+}
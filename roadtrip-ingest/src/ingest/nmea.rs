@@ -0,0 +1,177 @@
+// Just enough NMEA 0183 to pull positions out of a log. RMC sentences carry
+// both the date and the time of a fix, while GGA only has the time, so GGA
+// fixes use the date of the last RMC. RMC also has the speed and heading, and
+// GGA the altitude.
+
+use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+
+use roadtrip_core::geometry::Point;
+
+// Speeds are in knots, one of which is this many metres per second.
+const METRES_PER_KNOT: f64 = 1852.0 / 3600.0;
+
+// Coordinates are degrees and minutes run together (`ddmm.mmmm`).
+fn degrees(value: &str, negative: bool) -> Option<f64> {
+    let value: f64 = value.parse().ok()?;
+    let degrees = (value / 100.0).trunc();
+    let decimal = degrees + (value - degrees * 100.0) / 60.0;
+
+    if negative {
+        Some(-decimal)
+    } else {
+        Some(decimal)
+    }
+}
+
+// Blank fields are common for readings the receiver doesn't have.
+fn number(fields: &[&str], at: usize) -> Option<f64> {
+    fields.get(at)?.parse().ok()
+}
+
+fn two_digits(text: &str, at: usize) -> Option<u32> {
+    text.get(at..at + 2)?.parse().ok()
+}
+
+// `hhmmss.sss`
+fn time(text: &str) -> Option<NaiveTime> {
+    let seconds: f64 = text.get(4..)?.parse().ok()?;
+    let millis = (seconds * 1000.0).round() as u32;
+
+    NaiveTime::from_hms_milli_opt(
+        two_digits(text, 0)?,
+        two_digits(text, 2)?,
+        millis / 1000,
+        millis % 1000,
+    )
+}
+
+// `ddmmyy`
+fn date(text: &str) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(
+        2000 + two_digits(text, 4)? as i32,
+        two_digits(text, 2)?,
+        two_digits(text, 0)?,
+    )
+}
+
+fn position(fields: &[&str]) -> Option<(f64, f64)> {
+    let lat = degrees(fields.first()?, *fields.get(1)? == "S")?;
+    let lng = degrees(fields.get(2)?, *fields.get(3)? == "W")?;
+
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lng) {
+        return None;
+    }
+
+    Some((lat, lng))
+}
+
+// Sentences may end with `*hh`, the XOR of every byte between `$` and `*`.
+fn verify(sentence: &str) -> Option<&str> {
+    let (body, checksum) = match sentence.find('*') {
+        Some(idx) => (&sentence[..idx], &sentence[idx + 1..]),
+        None => return Some(sentence),
+    };
+
+    let expected = u8::from_str_radix(checksum.get(..2)?, 16).ok()?;
+    let actual = body.bytes().fold(0, |acc, b| acc ^ b);
+
+    if expected == actual {
+        Some(body)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+pub(super) struct Parser {
+    date: Option<NaiveDate>,
+}
+
+impl Parser {
+    // Used for GGA fixes until an RMC sentence supplies a date.
+    pub fn assume_date(&mut self, date: NaiveDate) {
+        self.date.get_or_insert(date);
+    }
+
+    fn rmc(&mut self, fields: &[&str]) -> Option<Point> {
+        let date = date(fields.get(9)?)?;
+        self.date = Some(date);
+
+        // `V` means the receiver didn't have a fix.
+        if *fields.get(2)? != "A" {
+            return None;
+        }
+
+        let time = date.and_time(time(fields.get(1)?)?);
+        let (lat, lng) = position(fields.get(3..)?)?;
+        let speed = number(fields, 7).map(|knots| knots * METRES_PER_KNOT);
+
+        let point = Point::new(lat, lng, Utc.from_utc_datetime(&time))
+            .with_speed(speed)
+            .with_heading(number(fields, 8));
+
+        Some(point)
+    }
+
+    fn gga(&mut self, fields: &[&str]) -> Option<Point> {
+        // A fix quality of zero means there's no fix.
+        match fields.get(6)?.parse::<u8>() {
+            Ok(0) | Err(_) => return None,
+            Ok(_) => (),
+        }
+
+        let time = self.date?.and_time(time(fields.get(1)?)?);
+        let (lat, lng) = position(fields.get(2..)?)?;
+
+        let point = Point::new(lat, lng, Utc.from_utc_datetime(&time))
+            .with_altitude(number(fields, 9));
+
+        Some(point)
+    }
+
+    // Anything before the `$` is ignored, since some recorders prefix each
+    // line with a timestamp of their own.
+    pub fn line(&mut self, line: &str) -> Option<Point> {
+        let start = line.find('$')?;
+        let body = verify(line[start + 1..].trim_end())?;
+
+        let fields: Vec<_> = body.split(',').collect();
+
+        // Any talker (GP, GN, GL, ...) will do.
+        let kind = fields.first()?;
+        if kind.len() != 5 {
+            return None;
+        }
+
+        match kind.get(2..)? {
+            "RMC" => self.rmc(&fields),
+            "GGA" => self.gga(&fields),
+            _ => None,
+        }
+    }
+}
+
+// Receivers often report the same fix in more than one sentence, so
+// consecutive points at the same time are merged into the first.
+pub(super) fn dedup(points: &mut Vec<Point>) {
+    points.dedup_by(|next, kept| {
+        if next.time() != kept.time() {
+            return false;
+        }
+
+        *kept = kept
+            .with_speed(kept.speed().or_else(|| next.speed()))
+            .with_altitude(kept.altitude().or_else(|| next.altitude()))
+            .with_heading(kept.heading().or_else(|| next.heading()));
+
+        true
+    });
+}
+
+pub(super) fn parse(text: &str) -> Vec<Point> {
+    let mut parser = Parser::default();
+    let mut points: Vec<_> =
+        text.lines().filter_map(|l| parser.line(l)).collect();
+    dedup(&mut points);
+    points
+}
@@ -0,0 +1,212 @@
+// Reads the location a phone or camera stored in a photo's EXIF data, without
+// spawning exiftool.
+
+mod error {
+    use snafu::Snafu;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility = "pub(super)")]
+    pub enum Error {
+        Open { source: std::io::Error },
+        Exif { source: exif::Error },
+        Join { source: tokio::task::JoinError },
+        NoPosition,
+        NoTimestamp,
+        Read { source: std::io::Error },
+    }
+}
+
+use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use exif::{Exif, In, Tag, Value};
+
+use roadtrip_core::datetime::DateTime;
+use roadtrip_core::geometry::{Geometry, Point};
+use roadtrip_core::media::{keys, Media, Metadata};
+
+pub use self::error::Error;
+
+use snafu::{OptionExt, ResultExt};
+
+use std::fs::File;
+use std::future::Future;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use super::Ingest;
+
+const EXTENSIONS: &[&str] = &["jpg", "jpeg", "heic", "heif", "tif", "tiff"];
+
+impl From<Error> for super::Error {
+    fn from(e: Error) -> Self {
+        // Only a photo that was read successfully, but has no location or
+        // time, is reported. Anything else might still suit another ingester.
+        let supported = match e {
+            Error::NoPosition | Error::NoTimestamp | Error::Read { .. } => true,
+            Error::Open { .. } | Error::Exif { .. } | Error::Join { .. } => {
+                false
+            }
+        };
+
+        Self::new(e, supported)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Photo {
+    _p: (),
+}
+
+impl Photo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn ascii(exif: &Exif, tag: Tag) -> Option<&[u8]> {
+        match exif.get_field(tag, In::PRIMARY)?.value {
+            Value::Ascii(ref v) => v.first().map(Vec::as_slice),
+            _ => None,
+        }
+    }
+
+    fn rationals(exif: &Exif, tag: Tag) -> Option<Vec<f64>> {
+        match exif.get_field(tag, In::PRIMARY)?.value {
+            Value::Rational(ref v) => {
+                Some(v.iter().map(|r| r.to_f64()).collect())
+            }
+            _ => None,
+        }
+    }
+
+    // Degrees, minutes, and seconds, with the hemisphere stored separately.
+    // Some cameras leave the hemisphere out, which exiftool reads as north
+    // or east, so that's what it is here too.
+    fn coordinate(exif: &Exif, tag: Tag, reference: Tag) -> Option<f64> {
+        let dms = Self::rationals(exif, tag)?;
+        let degrees = dms.first()?
+            + dms.get(1).unwrap_or(&0.0) / 60.0
+            + dms.get(2).unwrap_or(&0.0) / 3600.0;
+
+        match Self::ascii(exif, reference) {
+            Some(b"S") | Some(b"W") => Some(-degrees),
+            _ => Some(degrees),
+        }
+    }
+
+    // GPS time is always UTC, so prefer it over the camera's clock.
+    fn gps_time(exif: &Exif) -> Option<DateTime> {
+        let date =
+            std::str::from_utf8(Self::ascii(exif, Tag::GPSDateStamp)?).ok()?;
+        let date = NaiveDate::parse_from_str(date, "%Y:%m:%d").ok()?;
+
+        let time = match Self::rationals(exif, Tag::GPSTimeStamp)?[..] {
+            [h, m, s] => date.and_hms_opt(h as u32, m as u32, s as u32)?,
+            _ => return None,
+        };
+
+        Some(Utc.from_utc_datetime(&time))
+    }
+
+    // The camera's clock has no time zone, so like exiftool's output it's
+    // taken to be UTC.
+    fn original_time(exif: &Exif) -> Option<DateTime> {
+        let text = Self::ascii(exif, Tag::DateTimeOriginal)?;
+        let text = std::str::from_utf8(text).ok()?;
+        let time =
+            NaiveDateTime::parse_from_str(text, "%Y:%m:%d %H:%M:%S").ok()?;
+
+        Some(Utc.from_utc_datetime(&time))
+    }
+
+    fn text(exif: &Exif, tag: Tag) -> Option<String> {
+        let text = Self::ascii(exif, tag)?;
+        let text = String::from_utf8_lossy(text);
+        let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+
+        if text.is_empty() {
+            None
+        } else {
+            Some(text.to_owned())
+        }
+    }
+
+    fn camera(exif: &Exif) -> Option<String> {
+        Self::text(exif, Tag::Model)
+    }
+
+    fn metadata(exif: &Exif) -> Metadata {
+        let mut metadata = Metadata::new();
+
+        for (key, tag) in &[
+            (keys::DEVICE, Tag::Make),
+            (keys::SERIAL, Tag::BodySerialNumber),
+        ] {
+            if let Some(text) = Self::text(exif, *tag) {
+                metadata.insert((*key).to_owned(), text.into());
+            }
+        }
+
+        metadata
+    }
+
+    fn read(path: &Path) -> Result<(Point, Option<String>, Metadata), Error> {
+        let file = File::open(path).context(error::Open)?;
+        let mut reader = BufReader::new(file);
+
+        let exif = exif::Reader::new()
+            .read_from_container(&mut reader)
+            .context(error::Exif)?;
+
+        let lat =
+            Self::coordinate(&exif, Tag::GPSLatitude, Tag::GPSLatitudeRef);
+        let lng =
+            Self::coordinate(&exif, Tag::GPSLongitude, Tag::GPSLongitudeRef);
+
+        let (lat, lng) = match (lat, lng) {
+            (Some(lat), Some(lng)) => (lat, lng),
+            _ => return error::NoPosition.fail(),
+        };
+
+        let time = Self::gps_time(&exif)
+            .or_else(|| Self::original_time(&exif))
+            .context(error::NoTimestamp)?;
+
+        let point = Point::new(lat, lng, time);
+        Ok((point, Self::camera(&exif), Self::metadata(&exif)))
+    }
+
+    async fn async_ingest(&self, path: PathBuf) -> Result<Media, Error> {
+        let blocking = path.clone();
+        let (point, camera, metadata) =
+            tokio::task::spawn_blocking(move || Self::read(&blocking))
+                .await
+                .context(error::Join)??;
+
+        let mut media =
+            super::create_media(path, Geometry::from(point), camera)
+                .await
+                .context(error::Read)?;
+
+        for (key, value) in metadata {
+            media.set_metadata(key, value);
+        }
+
+        Ok(media)
+    }
+}
+
+impl Ingest for Photo {
+    type Error = Error;
+
+    fn ingest<'a>(
+        &'a self,
+        path: PathBuf,
+    ) -> Pin<Box<dyn Future<Output = Result<Media, Error>> + 'a + Send>> {
+        Box::pin(self.async_ingest(path))
+    }
+
+    fn supports(&self, path: &Path) -> bool {
+        super::has_extension(path, EXTENSIONS)
+    }
+}
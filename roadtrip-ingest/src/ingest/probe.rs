@@ -0,0 +1,112 @@
+// Asks ffprobe what the container says about a file: how long it is, and the
+// size and codec of its video. None of it is needed to put media on the map,
+// so any of it can be missing, including ffprobe itself.
+
+use std::path::Path;
+use std::time::Duration;
+
+use tokio::process::Command;
+
+use tracing::debug;
+
+const ENTRIES: &str =
+    "format=format_name,duration:stream=codec_type,codec_name,width,height";
+
+#[derive(Debug, Default)]
+pub(super) struct Info {
+    pub duration: Option<Duration>,
+    pub resolution: Option<(u32, u32)>,
+    pub container: Option<String>,
+    pub codec: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Stream<'a> {
+    codec_type: Option<&'a str>,
+    codec_name: Option<&'a str>,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+impl Info {
+    fn video(&mut self, stream: Stream) {
+        if stream.codec_type != Some("video") || self.codec.is_some() {
+            return;
+        }
+
+        self.codec = stream.codec_name.map(String::from);
+
+        if let (Some(w), Some(h)) = (stream.width, stream.height) {
+            self.resolution = Some((w, h));
+        }
+    }
+
+    // ffprobe's default output is `[STREAM]` and `[FORMAT]` sections, each
+    // made of `key=value` lines. Values it doesn't know are `N/A`.
+    fn parse(text: &str) -> Self {
+        let mut info = Self::default();
+        let mut stream = None;
+
+        for line in text.lines().map(str::trim) {
+            match line {
+                "[STREAM]" => stream = Some(Stream::default()),
+                "[/STREAM]" => info.video(stream.take().unwrap_or_default()),
+                _ => (),
+            }
+
+            let mut parts = line.splitn(2, '=');
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(k), Some(v)) if v != "N/A" && !v.is_empty() => (k, v),
+                _ => continue,
+            };
+
+            match (&mut stream, key) {
+                (Some(s), "codec_type") => s.codec_type = Some(value),
+                (Some(s), "codec_name") => s.codec_name = Some(value),
+                (Some(s), "width") => s.width = value.parse().ok(),
+                (Some(s), "height") => s.height = value.parse().ok(),
+                (None, "format_name") => info.container = Some(value.into()),
+                (None, "duration") => {
+                    info.duration = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|d| d.is_finite() && *d >= 0.0)
+                        .map(Duration::from_secs_f64);
+                }
+                _ => (),
+            }
+        }
+
+        info
+    }
+}
+
+pub(super) async fn probe(path: &Path) -> Info {
+    let output = Command::new("ffprobe")
+        .arg("-v")
+        .arg("error")
+        .arg("-show_entries")
+        .arg(ENTRIES)
+        .arg("-of")
+        .arg("default")
+        .arg("-i")
+        .arg(path)
+        .kill_on_drop(true)
+        .output()
+        .await;
+
+    match output {
+        Ok(o) if o.status.success() => {
+            Info::parse(&String::from_utf8_lossy(&o.stdout))
+        }
+        Ok(o) => {
+            let err = String::from_utf8_lossy(&o.stderr);
+            debug!(status = %o.status, stderr = %err, "ffprobe failed");
+            Info::default()
+        }
+        Err(e) => {
+            debug!(error = %e, "unable to run ffprobe");
+            Info::default()
+        }
+    }
+}
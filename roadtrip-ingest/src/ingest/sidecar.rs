@@ -0,0 +1,150 @@
+// Some recorders write their track into a separate file next to the video,
+// like `VIDEO0001.gpx` or `VIDEO0001.nmea` beside `VIDEO0001.MP4`. DJI drones
+// do the same with subtitles (`DJI_0001.SRT`).
+
+mod error {
+    use snafu::Snafu;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility = "pub(super)")]
+    pub enum Error {
+        NoSidecar,
+        Open { source: std::io::Error },
+        Gpx { source: gpx::errors::Error },
+        NoPoints,
+        NoTimestamp,
+        Read { source: std::io::Error },
+    }
+}
+
+use roadtrip_core::media::Media;
+
+pub use self::error::Error;
+
+use snafu::{ensure, OptionExt, ResultExt};
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use super::{nmea, srt, Ingest};
+
+use tracing::trace;
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Gpx,
+    Nmea,
+    Srt,
+}
+
+const SIDECARS: &[(&str, Format)] = &[
+    ("gpx", Format::Gpx),
+    ("GPX", Format::Gpx),
+    ("nmea", Format::Nmea),
+    ("NMEA", Format::Nmea),
+    ("srt", Format::Srt),
+    ("SRT", Format::Srt),
+];
+
+impl From<Error> for super::Error {
+    fn from(e: Error) -> Self {
+        // Once there's a sidecar, any problem with it is worth reporting.
+        let supported = match e {
+            Error::NoSidecar => false,
+            Error::Open { .. }
+            | Error::Gpx { .. }
+            | Error::NoPoints
+            | Error::NoTimestamp
+            | Error::Read { .. } => true,
+        };
+
+        Self::new(e, supported)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Sidecar {
+    _p: (),
+}
+
+impl Sidecar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Tracks don't have sidecars of their own.
+    fn is_track(path: &Path) -> bool {
+        let extension = match path.extension().and_then(|e| e.to_str()) {
+            Some(e) => e,
+            None => return false,
+        };
+
+        SIDECARS
+            .iter()
+            .any(|(e, _)| e.eq_ignore_ascii_case(extension))
+    }
+
+    async fn find(path: &Path) -> Option<(PathBuf, Format)> {
+        if path.extension().is_none() || Self::is_track(path) {
+            return None;
+        }
+
+        for (extension, format) in SIDECARS {
+            let candidate = path.with_extension(extension);
+
+            let is_file = tokio::fs::metadata(&candidate)
+                .await
+                .map(|m| m.is_file())
+                .unwrap_or(false);
+
+            if is_file {
+                return Some((candidate, *format));
+            }
+        }
+
+        None
+    }
+
+    async fn async_ingest(&self, path: PathBuf) -> Result<Media, Error> {
+        let (sidecar, format) =
+            Self::find(&path).await.context(error::NoSidecar)?;
+
+        trace!(sidecar = %sidecar.display(), "reading sidecar");
+
+        let data = tokio::fs::read(&sidecar).await.context(error::Open)?;
+
+        let points = match format {
+            Format::Gpx => {
+                let gpx = gpx::read(data.as_slice()).context(error::Gpx)?;
+                super::gpx_points(&gpx).context(error::NoTimestamp)?
+            }
+            Format::Nmea => nmea::parse(&String::from_utf8_lossy(&data)),
+            Format::Srt => srt::parse(&String::from_utf8_lossy(&data)),
+        };
+
+        ensure!(!points.is_empty(), error::NoPoints);
+
+        let geometry = super::points_to_geometry(points);
+        let media = super::create_media(path, geometry, None)
+            .await
+            .context(error::Read)?;
+
+        Ok(media)
+    }
+}
+
+impl Ingest for Sidecar {
+    type Error = Error;
+
+    fn ingest<'a>(
+        &'a self,
+        path: PathBuf,
+    ) -> Pin<Box<dyn Future<Output = Result<Media, Error>> + 'a + Send>> {
+        Box::pin(self.async_ingest(path))
+    }
+
+    fn supports(&self, path: &Path) -> bool {
+        !Self::is_track(path)
+    }
+}
@@ -0,0 +1,55 @@
+// Guesses what a file is from its first few bytes, for files with a missing
+// or misleading extension (like the `.bin` fragments some cameras leave
+// behind). The guess is an extension the ingesters would recognize.
+
+use std::path::Path;
+
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+// Enough for every signature below.
+const HEADER_LEN: usize = 16;
+
+// Brands in an ISO base media `ftyp` atom that mean a still image.
+const IMAGE_BRANDS: &[&[u8]] = &[b"heic", b"heix", b"mif1", b"msf1"];
+
+// Atoms that can start an MP4 or QuickTime file, or a fragment of one.
+const MP4_ATOMS: &[&[u8]] = &[b"ftyp", b"moov", b"moof", b"mdat", b"styp"];
+
+fn guess(header: &[u8]) -> Option<&'static str> {
+    // The four bytes starting at `start`, if there are that many.
+    let at = |start: usize| header.get(start..start + 4);
+
+    if header.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("jpg")
+    } else if header.starts_with(b"II*\0") || header.starts_with(b"MM\0*") {
+        Some("tif")
+    } else if at(8) == Some(&b".FIT"[..]) {
+        Some("fit")
+    } else if at(4) == Some(&b"ftyp"[..])
+        && IMAGE_BRANDS.iter().any(|b| at(8) == Some(*b))
+    {
+        Some("heic")
+    } else if MP4_ATOMS.iter().any(|a| at(4) == Some(*a)) {
+        Some("mp4")
+    } else if header.starts_with(b"RIFF") && at(8) == Some(&b"AVI "[..]) {
+        Some("avi")
+    } else if header.starts_with(&[0x1a, 0x45, 0xdf, 0xa3]) {
+        Some("mkv")
+    } else {
+        None
+    }
+}
+
+// `None` if the file can't be read, or isn't anything recognizable.
+pub(crate) async fn sniff(path: &Path) -> Option<&'static str> {
+    let file = File::open(path).await.ok()?;
+    let mut header = Vec::with_capacity(HEADER_LEN);
+
+    file.take(HEADER_LEN as u64)
+        .read_to_end(&mut header)
+        .await
+        .ok()?;
+
+    guess(&header)
+}
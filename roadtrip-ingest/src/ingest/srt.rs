@@ -0,0 +1,141 @@
+// DJI drones write their telemetry as subtitles, one per frame. The layout
+// has changed between models, so rather than parsing any one of them, each
+// subtitle is searched for a date and time, and a position written either as
+// `[latitude: 48.1] [longitude: -11.5]` or as `GPS(-11.5,48.1,16)`.
+//
+// Drones don't record a time zone, so like exiftool's output the time is taken
+// to be UTC.
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+use roadtrip_core::geometry::Point;
+
+fn date(text: &str) -> Option<NaiveDate> {
+    ["%Y-%m-%d", "%Y.%m.%d", "%Y/%m/%d"]
+        .iter()
+        .find_map(|f| NaiveDate::parse_from_str(text, f).ok())
+}
+
+// `hh:mm:ss`, optionally followed by milliseconds (and sometimes more
+// digits after those, which are ignored).
+fn time(text: &str) -> Option<NaiveTime> {
+    let time = NaiveTime::parse_from_str(text.get(..8)?, "%H:%M:%S").ok()?;
+
+    let fraction: String = match text.get(8..9) {
+        Some(".") | Some(",") => text[9..]
+            .chars()
+            .take_while(char::is_ascii_digit)
+            .take(3)
+            .collect(),
+        _ => String::new(),
+    };
+
+    let millis = format!("{:0<3}", fraction).parse::<i64>().unwrap_or(0);
+
+    Some(time + chrono::Duration::milliseconds(millis))
+}
+
+fn datetime(text: &str) -> Option<NaiveDateTime> {
+    let words: Vec<_> = text.split_whitespace().collect();
+
+    words
+        .windows(2)
+        .find_map(|w| Some(date(w[0])?.and_time(time(w[1])?)))
+}
+
+// `[latitude: 48.1] [longitude: -11.5]`, where some models spell it
+// `longtitude`.
+fn bracketed(text: &str) -> Option<(f64, f64)> {
+    let mut lat = None;
+    let mut lng = None;
+
+    for field in text.split('[').skip(1) {
+        let field = field.split(']').next()?;
+        let mut parts = field.splitn(2, ':');
+        let key = parts.next()?.trim();
+        let value = parts.next().and_then(|v| v.split_whitespace().next());
+        let value = match value.and_then(|v| v.parse::<f64>().ok()) {
+            Some(v) => v,
+            None => continue,
+        };
+
+        match key.to_ascii_lowercase().as_str() {
+            "latitude" => lat = Some(value),
+            "longitude" | "longtitude" => lng = Some(value),
+            _ => (),
+        }
+    }
+
+    Some((lat?, lng?))
+}
+
+// `GPS(longitude,latitude,altitude)`
+fn gps(text: &str) -> Option<(f64, f64)> {
+    let start = text.find("GPS")? + 3;
+    let inner = text[start..].trim_start().strip_prefix('(')?;
+    let inner = inner.split(')').next()?;
+
+    let mut values = inner.split(',').map(|v| v.trim().parse::<f64>());
+    let lng = values.next()?.ok()?;
+    let lat = values.next()?.ok()?;
+
+    Some((lat, lng))
+}
+
+fn subtitle(text: &str) -> Option<Point> {
+    let time = datetime(text)?;
+    let (lat, lng) = bracketed(text).or_else(|| gps(text))?;
+
+    // Zeros mean the drone didn't have a fix.
+    if lat == 0.0 && lng == 0.0 {
+        return None;
+    }
+
+    if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lng) {
+        return None;
+    }
+
+    Some(Point::new(lat, lng, Utc.from_utc_datetime(&time)))
+}
+
+// Removes the `<font>` tags some models wrap around everything.
+fn strip_tags(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                out.push(' ');
+            }
+            c if !in_tag => out.push(c),
+            _ => (),
+        }
+    }
+
+    out
+}
+
+pub(super) fn parse(text: &str) -> Vec<Point> {
+    let mut points = Vec::new();
+    let mut block = String::new();
+
+    // Subtitles are separated by blank lines.
+    for line in text.lines().chain(std::iter::once("")) {
+        let line = line.trim();
+
+        if !line.is_empty() {
+            block.push_str(line);
+            block.push('\n');
+            continue;
+        }
+
+        points.extend(subtitle(&strip_tags(&block)));
+        block.clear();
+    }
+
+    points.dedup_by_key(|p| p.time());
+    points
+}
@@ -0,0 +1,231 @@
+// TeslaCam records several cameras at once, a minute to a file, with no
+// position in any of them. Each file is named for the local time it starts at
+// and the camera: `2020-08-26_12-34-56-front.mp4`, then `-back`,
+// `-left_repeater`, and so on. Saved and sentry clips get a folder per event,
+// with an `event.json` holding roughly where the car was.
+//
+// Only the front camera becomes media, since the others show the same minute.
+// Positions come from a GPX track in the same folder (like one exported from
+// a trip logger) if there is one, or else the event's estimated location.
+
+mod error {
+    use snafu::Snafu;
+
+    #[derive(Debug, Snafu)]
+    #[snafu(visibility = "pub(super)")]
+    pub enum Error {
+        Name,
+        Angle,
+        NoPosition,
+        Gpx { source: gpx::errors::Error },
+        Read { source: std::io::Error },
+    }
+}
+
+use chrono::{FixedOffset, NaiveDateTime, TimeZone, Utc};
+
+use roadtrip_core::datetime::DateTime;
+use roadtrip_core::geometry::{Geometry, Point};
+use roadtrip_core::media::Media;
+
+pub use self::error::Error;
+
+use snafu::{OptionExt, ResultExt};
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use super::{Ingest, IngestAll};
+
+use tracing::trace;
+
+const FRONT: &str = "front";
+
+// Every camera on every model so far.
+const CAMERAS: &[&str] = &[
+    FRONT,
+    "back",
+    "left_repeater",
+    "right_repeater",
+    "left_pillar",
+    "right_pillar",
+];
+
+const NAME_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+
+// Files are cut at a minute, so that's as much of a track as one covers.
+const CLIP_LEN: i64 = 60;
+
+impl From<Error> for super::Error {
+    fn from(e: Error) -> Self {
+        // Without a position, another ingester might still find one.
+        let supported = match e {
+            Error::Name | Error::Angle | Error::NoPosition => false,
+            Error::Gpx { .. } | Error::Read { .. } => true,
+        };
+
+        Self::new(e, supported)
+    }
+}
+
+// Finds `"key": "value"` or `"key": value` in a flat JSON object.
+fn json_field<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    let quoted = format!("\"{}\"", key);
+    let start = text.find(&quoted)? + quoted.len();
+    let rest = text[start..].trim_start().strip_prefix(':')?.trim_start();
+
+    match rest.strip_prefix('"') {
+        Some(s) => s.split('"').next(),
+        None => rest.split(&[',', '}'][..]).next().map(str::trim),
+    }
+}
+
+#[derive(Debug)]
+pub struct Tesla {
+    zone: FixedOffset,
+}
+
+impl Default for Tesla {
+    fn default() -> Self {
+        Self::recorded_in(FixedOffset::east(0))
+    }
+}
+
+impl Tesla {
+    // For a car whose clock is set to UTC.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // For a car whose clock is set to the local time in `zone`, which is
+    // what the car does by default.
+    pub fn recorded_in(zone: FixedOffset) -> Self {
+        Self { zone }
+    }
+
+    // The time the file starts at, and which camera recorded it.
+    fn parse_name(&self, path: &Path) -> Option<(DateTime, &'static str)> {
+        let stem = path.file_stem()?.to_str()?;
+        let (time, camera) = (stem.get(..19)?, stem.get(19..)?);
+
+        let camera = *CAMERAS
+            .iter()
+            .find(|c| camera.strip_prefix('-') == Some(**c))?;
+
+        let time = NaiveDateTime::parse_from_str(time, NAME_FORMAT).ok()?;
+        let time = self.zone.from_local_datetime(&time).single()?;
+
+        Some((time.with_timezone(&Utc), camera))
+    }
+
+    // Points from every GPX file next to `path`, during the minute starting
+    // at `start`.
+    async fn track(path: &Path, start: DateTime) -> Result<Vec<Point>, Error> {
+        let dir = match path.parent() {
+            Some(d) => d,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut entries = match tokio::fs::read_dir(dir).await {
+            Ok(e) => e,
+            Err(_) => return Ok(Vec::new()),
+        };
+
+        let end = start + chrono::Duration::seconds(CLIP_LEN);
+        let mut points = Vec::new();
+
+        while let Some(entry) =
+            entries.next_entry().await.context(error::Read)?
+        {
+            let gpx_path = entry.path();
+            if !super::has_extension(&gpx_path, &["gpx"]) {
+                continue;
+            }
+
+            trace!(track = %gpx_path.display(), "reading track");
+
+            let data = tokio::fs::read(&gpx_path).await.context(error::Read)?;
+            let gpx = gpx::read(data.as_slice()).context(error::Gpx)?;
+
+            let found = super::gpx_points(&gpx).unwrap_or_default();
+            points.extend(
+                found
+                    .into_iter()
+                    .filter(|p| p.time() >= start && p.time() < end),
+            );
+        }
+
+        points.sort_by_key(Point::time);
+        Ok(points)
+    }
+
+    // Where the car was when the event in `path`'s folder was saved, if the
+    // folder has an `event.json`.
+    async fn event(path: &Path, start: DateTime) -> Option<Point> {
+        let event = path.parent()?.join("event.json");
+        let text = tokio::fs::read_to_string(&event).await.ok()?;
+
+        let lat = json_field(&text, "est_lat")?.parse().ok()?;
+        let lng = json_field(&text, "est_lon")?.parse().ok()?;
+
+        Some(Point::new(lat, lng, start))
+    }
+
+    async fn async_ingest(&self, path: PathBuf) -> Result<Media, Error> {
+        let (start, camera) = self.parse_name(&path).context(error::Name)?;
+
+        if camera != FRONT {
+            return error::Angle.fail();
+        }
+
+        let points = Self::track(&path, start).await?;
+
+        let geometry = if points.is_empty() {
+            Self::event(&path, start)
+                .await
+                .map(Geometry::from)
+                .context(error::NoPosition)?
+        } else {
+            super::points_to_geometry(points)
+        };
+
+        let media =
+            super::create_media(path, geometry, Some("Tesla".to_owned()))
+                .await
+                .context(error::Read)?;
+
+        Ok(media)
+    }
+
+    async fn async_ingest_all(
+        &self,
+        path: PathBuf,
+    ) -> Result<Vec<Media>, Error> {
+        match self.async_ingest(path).await {
+            Ok(media) => Ok(vec![media]),
+            // The other cameras are accounted for by the front one.
+            Err(Error::Angle) => Ok(Vec::new()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Ingest for Tesla {
+    type Error = Error;
+
+    fn ingest<'a>(
+        &'a self,
+        path: PathBuf,
+    ) -> Pin<Box<dyn Future<Output = Result<Media, Error>> + 'a + Send>> {
+        Box::pin(self.async_ingest(path))
+    }
+
+    fn ingest_all<'a>(&'a self, path: PathBuf) -> IngestAll<'a, Error> {
+        Box::pin(self.async_ingest_all(path))
+    }
+
+    fn supports(&self, path: &Path) -> bool {
+        super::has_extension(path, &["mp4"]) && self.parse_name(path).is_some()
+    }
+}
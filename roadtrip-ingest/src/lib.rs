@@ -1,4 +1,6 @@
+pub mod index;
 pub mod ingest;
+pub mod trip;
 
 pub mod error {
     use snafu::Snafu;
@@ -34,14 +36,21 @@ pub mod error {
     }
 }
 
-use crate::ingest::{Error as IngestError, Ingest, IngestErase};
+use crate::index::Index;
+use crate::ingest::{Error as IngestError, Hashing, Ingest, IngestErase};
+
+use chrono::FixedOffset;
 
 use futures::{Stream, StreamExt};
 
+use roadtrip_cancel::Token;
+
+use roadtrip_core::geometry::Interpolation;
+use roadtrip_core::hasher::Algorithm;
 use roadtrip_core::media::Media;
 
 use roadtrip_walkdir::error::Error as WalkError;
-use roadtrip_walkdir::{DirEntry, WalkDir};
+use roadtrip_walkdir::{DirEntry, Discovered, WalkDir};
 
 use self::error::Error;
 
@@ -49,6 +58,13 @@ use snafu::IntoError;
 
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
+
+use tokio::fs;
+
+use tracing::{debug, debug_span, info_span, trace};
+
+use tracing_futures::Instrument;
 
 type Ingesters = Vec<Box<dyn Ingest<Error = IngestError>>>;
 
@@ -56,6 +72,12 @@ type Ingesters = Vec<Box<dyn Ingest<Error = IngestError>>>;
 pub struct Scanner {
     walkdir: WalkDir,
     ingesters: Ingesters,
+    cancel: Token,
+    index: Option<Index>,
+    hashing: Hashing,
+    clock_offset: chrono::Duration,
+    recorded_in: Option<FixedOffset>,
+    densify: Option<(chrono::Duration, Interpolation)>,
 }
 
 impl Default for Scanner {
@@ -69,6 +91,12 @@ impl Scanner {
         Self {
             walkdir: WalkDir::default(),
             ingesters: Vec::new(),
+            cancel: Token::new(),
+            index: None,
+            hashing: Hashing::default(),
+            clock_offset: chrono::Duration::zero(),
+            recorded_in: None,
+            densify: None,
         }
     }
 
@@ -86,43 +114,210 @@ impl Scanner {
         self.walkdir.insert(path);
     }
 
+    // Stops walking directories and abandons any ingest in progress once
+    // `token` is cancelled.
+    pub fn cancel_on(&mut self, token: Token) {
+        self.walkdir.cancel_on(token.clone());
+        self.cancel = token;
+    }
+
+    // Skips files that haven't changed since they were last ingested, and
+    // records the ones that are ingested in `index`. Saving it is up to the
+    // caller.
+    pub fn use_index(&mut self, index: Index) {
+        self.index = Some(index);
+    }
+
+    // SHA3-256 unless told otherwise. BLAKE3 is much faster on large videos,
+    // but the same file gets a different hash with each.
+    pub fn hash_with(&mut self, algorithm: Algorithm) {
+        self.hashing.algorithm = algorithm;
+    }
+
+    // Identifies large files by their size and a few pieces of them, instead
+    // of reading them in full. Much faster for long videos, but files that
+    // only differ somewhere in between are taken to be the same. Sampled and
+    // full hashes of the same file are different.
+    pub fn sample_hashes(&mut self, sampled: bool) {
+        self.hashing.sampled = sampled;
+    }
+
+    // Added to every timestamp ingested, for cameras with clocks that are
+    // wrong by a known amount. Negative if the camera runs fast.
+    pub fn clock_offset(&mut self, offset: chrono::Duration) {
+        self.clock_offset = offset;
+    }
+
+    // For cameras that record the local time in `zone` as if it were UTC.
+    // Applied along with any clock offset.
+    pub fn recorded_in(&mut self, zone: FixedOffset) {
+        self.recorded_in = Some(zone);
+    }
+
+    // Fills in tracks from loggers that only record a fix every so often, so
+    // there's a point at least every `interval`.
+    pub fn densify(
+        &mut self,
+        interval: chrono::Duration,
+        interpolation: Interpolation,
+    ) {
+        self.densify = Some((interval, interpolation));
+    }
+
+    fn time_shift(&self) -> chrono::Duration {
+        let zone = self.recorded_in.map(|z| z.local_minus_utc()).unwrap_or(0);
+        self.clock_offset - chrono::Duration::seconds(i64::from(zone))
+    }
+
+    // Files found so far, whether or not they've been ingested yet.
+    pub fn discovered(&self) -> Discovered {
+        self.walkdir.discovered()
+    }
+
     async fn step_file(
         ingesters: Arc<Ingesters>,
         path: PathBuf,
-    ) -> Result<Media, Error> {
+    ) -> Result<Vec<Media>, Error> {
+        let start = Instant::now();
+
+        // Only read once an ingester turns down the file's extension.
+        let mut sniffed = None;
+
         for ingester in ingesters.iter() {
-            match ingester.ingest(path.clone()).await {
-                Ok(m) => return Ok(m),
+            if !ingester.supports(&path) {
+                if sniffed.is_none() {
+                    sniffed = Some(ingest::sniff(&path).await);
+                    trace!(extension = ?sniffed.flatten(), "sniffed");
+                }
+
+                let supported = match sniffed.flatten() {
+                    Some(e) => ingester.supports(&path.with_extension(e)),
+                    None => false,
+                };
+
+                if !supported {
+                    continue;
+                }
+            }
+
+            match ingester.ingest_all(path.clone()).await {
+                Ok(media) => {
+                    for m in &media {
+                        debug!(
+                            hash = %m.hash(),
+                            points = m.geometry().len(),
+                            elapsed_ms = start.elapsed().as_millis() as u64,
+                            "ingested"
+                        );
+                    }
+                    return Ok(media);
+                }
                 Err(e) if e.is_supported() => {
-                    return Err(error::Ingest { path }.into_error(e))
+                    debug!(
+                        error = %e,
+                        elapsed_ms = start.elapsed().as_millis() as u64,
+                        "ingest failed"
+                    );
+                    return Err(error::Ingest { path }.into_error(e));
                 }
-                Err(_) => (),
+                Err(e) => trace!(ingester = ?ingester, error = %e, "skipped"),
             }
         }
 
+        trace!("unsupported");
         error::Unsupported { path }.fail()
     }
 
+    async fn step_indexed(
+        ingesters: Arc<Ingesters>,
+        index: Option<Index>,
+        path: PathBuf,
+    ) -> Option<Result<Vec<Media>, Error>> {
+        let index = match index {
+            Some(i) => i,
+            None => return Some(Self::step_file(ingesters, path).await),
+        };
+
+        // Without metadata there's no telling if the file changed, so leave
+        // it to the ingesters.
+        let metadata = match fs::metadata(&path).await {
+            Ok(m) => m,
+            Err(_) => return Some(Self::step_file(ingesters, path).await),
+        };
+
+        if index.is_unchanged(&path, &metadata) {
+            trace!("unchanged");
+            return None;
+        }
+
+        let result = Self::step_file(ingesters, path.clone()).await;
+
+        if let Ok(ref media) = result {
+            let hashes = media.iter().map(|m| m.hash().clone()).collect();
+            index.insert(path, &metadata, hashes);
+        }
+
+        Some(result)
+    }
+
     async fn scan_one(
         ingesters: Arc<Ingesters>,
+        index: Option<Index>,
+        hashing: Hashing,
+        shift: chrono::Duration,
+        densify: Option<(chrono::Duration, Interpolation)>,
         result: Result<DirEntry, WalkError>,
-    ) -> Option<Result<Media, Error>> {
+    ) -> Vec<Result<Media, Error>> {
         match result {
-            Ok(e) if e.file_type().is_dir() => None,
-            Ok(e) => Some(Self::step_file(ingesters, e.into_path()).await),
-            Err(e) => Some(Err(Error::from(e))),
+            Ok(e) if e.file_type().is_dir() => vec![],
+            Ok(e) => {
+                let span = debug_span!("ingest", path = %e.path().display());
+                let path = e.into_path();
+                let step = Self::step_indexed(ingesters, index, path);
+
+                let result =
+                    ingest::with_hashing(hashing, step).instrument(span).await;
+
+                match result {
+                    None => vec![],
+                    Some(Err(e)) => vec![Err(e)],
+                    Some(Ok(media)) => media
+                        .into_iter()
+                        .map(|mut m| {
+                            m.shift_time(shift);
+                            if let Some((interval, interpolation)) = densify {
+                                m.densify(interval, interpolation);
+                            }
+                            Ok(m)
+                        })
+                        .collect(),
+                }
+            }
+            Err(e) => vec![Err(Error::from(e))],
         }
     }
 
     pub fn scan(self) -> impl Stream<Item = Result<Media, Error>> + Send {
+        let shift = self.time_shift();
         let walkdir = self.walkdir;
+        let cancel = self.cancel;
+        let index = self.index;
+        let hashing = self.hashing;
+        let densify = self.densify;
 
         // TODO: Figure out why this needs to be an Arc, and get rid of it.
         let ingesters = Arc::new(self.ingesters);
 
-        walkdir.walk().filter_map(move |result| {
-            let mine = ingesters.clone();
-            Self::scan_one(mine, result)
-        })
+        let scan = walkdir
+            .walk()
+            .then(move |result| {
+                let mine = ingesters.clone();
+                let index = index.clone();
+                Self::scan_one(mine, index, hashing, shift, densify, result)
+            })
+            .flat_map(futures::stream::iter);
+
+        // Dropping the in-flight ingest also kills any tool it spawned.
+        cancel.stream(scan).instrument(info_span!("scan"))
     }
 }
@@ -0,0 +1,141 @@
+// Dashcams split a drive into files a few minutes long. Grouping puts the
+// clips that pick up where another left off, in both time and place, back
+// together into one trip per drive.
+
+use roadtrip_core::datetime::DateTime;
+use roadtrip_core::geometry::{Geometry, Point};
+use roadtrip_core::media::Media;
+use roadtrip_core::Hash;
+
+// Mean radius of the earth, in metres.
+const EARTH_RADIUS: f64 = 6_371_008.8;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trip {
+    media: Vec<Hash>,
+    start: DateTime,
+    end: DateTime,
+}
+
+impl Trip {
+    // In the order they were recorded.
+    pub fn media(&self) -> &[Hash] {
+        &self.media
+    }
+
+    pub fn start(&self) -> DateTime {
+        self.start
+    }
+
+    pub fn end(&self) -> DateTime {
+        self.end
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Grouping {
+    max_gap: chrono::Duration,
+    max_distance: f64,
+}
+
+impl Default for Grouping {
+    fn default() -> Self {
+        Self {
+            max_gap: chrono::Duration::minutes(2),
+            max_distance: 500.0,
+        }
+    }
+}
+
+// The first and last point of one clip.
+#[derive(Debug)]
+struct Span<'a> {
+    media: &'a Media,
+    first: Point,
+    last: Point,
+}
+
+impl<'a> Span<'a> {
+    fn new(media: &'a Media) -> Option<Self> {
+        let geometry = match media.geometry() {
+            Geometry::Point(_) => return None,
+            g => g,
+        };
+
+        let first = geometry.iter().min_by_key(Point::time)?;
+        let last = geometry.iter().max_by_key(Point::time)?;
+
+        Some(Self { media, first, last })
+    }
+}
+
+fn distance(a: &Point, b: &Point) -> f64 {
+    let (lat_a, lat_b) = (a.latitude().to_radians(), b.latitude().to_radians());
+    let d_lat = lat_b - lat_a;
+    let d_lng = (b.longitude() - a.longitude()).to_radians();
+
+    let h = (d_lat / 2.0).sin().powi(2)
+        + lat_a.cos() * lat_b.cos() * (d_lng / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS * h.sqrt().asin()
+}
+
+impl Grouping {
+    // The longest pause between one clip ending and the next starting.
+    pub fn max_gap(mut self, gap: chrono::Duration) -> Self {
+        self.max_gap = gap;
+        self
+    }
+
+    // How far apart, in metres, one clip can end and the next start.
+    pub fn max_distance(mut self, metres: f64) -> Self {
+        self.max_distance = metres;
+        self
+    }
+
+    fn contiguous(&self, prev: &Span, next: &Span) -> bool {
+        let gap = next.first.time() - prev.last.time();
+
+        prev.media.camera() == next.media.camera()
+            && gap <= self.max_gap
+            && -gap <= self.max_gap
+            && distance(&prev.last, &next.first) <= self.max_distance
+    }
+
+    // Photos, and anything else without a path, aren't part of any trip.
+    // Every clip with a path ends up in exactly one, even if it's alone.
+    pub fn group<'a, I>(&self, media: I) -> Vec<Trip>
+    where
+        I: IntoIterator<Item = &'a Media>,
+    {
+        let mut spans: Vec<_> =
+            media.into_iter().filter_map(Span::new).collect();
+        spans.sort_by_key(|s| (s.first.time(), s.media.path().to_owned()));
+
+        let mut trips: Vec<Trip> = Vec::new();
+        let mut prev: Option<Span> = None;
+
+        for span in spans {
+            let joined = prev
+                .as_ref()
+                .map(|p| self.contiguous(p, &span))
+                .unwrap_or(false);
+
+            match trips.last_mut() {
+                Some(trip) if joined => {
+                    trip.media.push(span.media.hash().clone());
+                    trip.end = trip.end.max(span.last.time());
+                }
+                _ => trips.push(Trip {
+                    media: vec![span.media.hash().clone()],
+                    start: span.first.time(),
+                    end: span.last.time(),
+                }),
+            }
+
+            prev = Some(span);
+        }
+
+        trips
+    }
+}
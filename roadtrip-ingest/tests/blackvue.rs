@@ -0,0 +1,104 @@
+mod util;
+
+use chrono::{TimeZone, Utc};
+
+use roadtrip_ingest::ingest::{Blackvue, Error, Ingest};
+
+use self::util::scratch;
+
+fn atom(kind: &[u8], body: &[u8]) -> Vec<u8> {
+    let mut out = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(body);
+    out
+}
+
+// Prefixes a sentence with the camera's clock, and appends its checksum.
+fn line(millis: u64, body: &str) -> String {
+    let checksum = body.bytes().fold(0, |acc, b| acc ^ b);
+    format!("[{}]${}*{:02X}\n", millis, body, checksum)
+}
+
+fn mp4(log: &str) -> Vec<u8> {
+    let mut log = log.as_bytes().to_vec();
+    log.resize(log.len() + 16, 0);
+
+    [
+        atom(b"ftyp", b"mp42\0\0\0\0"),
+        atom(
+            b"free",
+            &[atom(b"gps ", &log), atom(b"3gf ", &[0; 8])].concat(),
+        ),
+        atom(b"mdat", &[0; 32]),
+    ]
+    .concat()
+}
+
+#[tokio::test]
+async fn ingest_points() {
+    let dir = scratch();
+    let path = dir.path().join("points.mp4");
+    let log = [
+        // Dated by the camera's clock, since there hasn't been an RMC yet.
+        line(
+            1_598_445_295_000,
+            "GPGGA,123455.00,4807.000,N,01131.000,W,1,08,0.9,545.4,M,,M,,",
+        ),
+        line(
+            1_598_445_296_000,
+            "GPRMC,123456.00,A,4807.038,N,01131.000,W,22.4,84.4,260820,,,A",
+        ),
+        line(
+            1_598_445_296_000,
+            "GPGGA,123456.00,4807.038,N,01131.000,W,1,08,0.9,545.4,M,,M,,",
+        ),
+        line(1_598_445_297_000, "GPRMC,123457.00,V,,,,,,,260820,,,N"),
+        line(
+            1_598_445_297_000,
+            "GPGGA,123457.00,4807.050,N,01131.000,W,0,00,,,M,,M,,",
+        ),
+        line(
+            1_598_445_298_000,
+            "GPRMC,123458.00,A,4807.100,N,01131.500,W,22.4,84.4,260820,,,A",
+        ),
+    ]
+    .concat();
+    std::fs::write(&path, mp4(&log)).unwrap();
+
+    let media = Blackvue::new().ingest(path.clone()).await.unwrap();
+    let points: Vec<_> = media.geometry().iter().collect();
+
+    assert_eq!(media.path(), path);
+    assert_eq!(points.len(), 3);
+
+    assert_eq!(points[0].time(), Utc.ymd(2020, 8, 26).and_hms(12, 34, 55));
+    assert!((points[1].latitude() - 48.1173).abs() < 1e-4);
+    assert!((points[1].longitude() + 11.5167).abs() < 1e-4);
+    assert_eq!(points[1].time(), Utc.ymd(2020, 8, 26).and_hms(12, 34, 56));
+    assert_eq!(points[2].time(), Utc.ymd(2020, 8, 26).and_hms(12, 34, 58));
+}
+
+#[tokio::test]
+async fn no_fix_unsupported() {
+    let dir = scratch();
+    let path = dir.path().join("no_fix.mp4");
+    let log = line(1_598_445_297_000, "GPRMC,123457.00,V,,,,,,,260820,,,N");
+    std::fs::write(&path, mp4(&log)).unwrap();
+
+    let result = Blackvue::new().ingest(path).await;
+    let err: Error = result.unwrap_err().into();
+
+    assert!(!err.is_supported());
+}
+
+#[tokio::test]
+async fn other_files_unsupported() {
+    let dir = scratch();
+    let path = dir.path().join("other.txt");
+    std::fs::write(&path, b"definitely not a video").unwrap();
+
+    let result = Blackvue::new().ingest(path).await;
+    let err: Error = result.unwrap_err().into();
+
+    assert!(!err.is_supported());
+}
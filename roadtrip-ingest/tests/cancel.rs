@@ -0,0 +1,82 @@
+mod util;
+
+use futures::StreamExt;
+
+use roadtrip_cancel::Token;
+
+use roadtrip_core::media::Media;
+
+use roadtrip_ingest::ingest::{Error, Ingest};
+use roadtrip_ingest::Scanner;
+
+use self::util::scratch;
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// Never finishes an ingest, and counts how many it started. Cancels its
+// token, if it has one, as soon as an ingest starts.
+#[derive(Debug, Default, Clone)]
+struct Stuck(Arc<AtomicUsize>, Option<Token>);
+
+impl Ingest for Stuck {
+    type Error = Error;
+
+    fn ingest<'a>(
+        &'a self,
+        _path: PathBuf,
+    ) -> Pin<Box<dyn Future<Output = Result<Media, Error>> + 'a + Send>> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+
+        if let Some(token) = &self.1 {
+            token.cancel();
+        }
+
+        Box::pin(futures::future::pending())
+    }
+}
+
+#[tokio::test]
+async fn cancelled_before_scan() {
+    let tmp = scratch();
+    let dir = tmp.path();
+    std::fs::write(dir.join("a.mp4"), b"first").unwrap();
+
+    let stuck = Stuck::default();
+    let token = Token::new();
+    token.cancel();
+
+    let mut scanner = Scanner::default();
+    scanner.add_ingester(stuck.clone());
+    scanner.insert_path(dir);
+    scanner.cancel_on(token);
+
+    let results: Vec<_> = scanner.scan().collect().await;
+
+    assert!(results.is_empty());
+    assert_eq!(stuck.0.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn cancelled_during_ingest() {
+    let tmp = scratch();
+    let dir = tmp.path();
+    std::fs::write(dir.join("a.mp4"), b"first").unwrap();
+    std::fs::write(dir.join("b.mp4"), b"second").unwrap();
+
+    let token = Token::new();
+    let stuck = Stuck(Arc::default(), Some(token.clone()));
+
+    let mut scanner = Scanner::default();
+    scanner.add_ingester(stuck.clone());
+    scanner.insert_path(dir);
+    scanner.cancel_on(token);
+
+    let results: Vec<_> = scanner.scan().collect().await;
+
+    assert!(results.is_empty());
+    assert_eq!(stuck.0.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,69 @@
+mod util;
+
+use chrono::{FixedOffset, TimeZone, Utc};
+
+use futures::StreamExt;
+
+use roadtrip_ingest::ingest::Sidecar;
+use roadtrip_ingest::Scanner;
+
+use self::util::scratch;
+
+use std::path::Path;
+
+const NMEA: &str = "\
+$GPRMC,123456.00,A,4807.038,N,01131.000,W,022.4,084.4,260820,,,A*4B
+$GPRMC,123458.50,A,4807.100,N,01131.500,W,022.4,084.4,260820,,,A*4F
+";
+
+fn scanner(dir: &Path) -> Scanner {
+    let path = dir.join("clip.mp4");
+    std::fs::write(&path, b"video").unwrap();
+    std::fs::write(path.with_extension("nmea"), NMEA).unwrap();
+
+    let mut scanner = Scanner::default();
+    scanner.add_ingester(Sidecar::new());
+    scanner.insert_path(dir);
+    scanner
+}
+
+async fn first_time(scanner: Scanner) -> chrono::DateTime<Utc> {
+    let media: Vec<_> = scanner
+        .scan()
+        .filter_map(|r| async { r.ok() })
+        .collect()
+        .await;
+
+    assert_eq!(media.len(), 1);
+
+    let first = media[0].geometry().iter().next().unwrap();
+    first.time()
+}
+
+#[tokio::test]
+async fn unchanged_by_default() {
+    let tmp = scratch();
+    let time = first_time(scanner(tmp.path())).await;
+    assert_eq!(time, Utc.ymd(2020, 8, 26).and_hms(12, 34, 56));
+}
+
+#[tokio::test]
+async fn clock_offset() {
+    let tmp = scratch();
+    let mut scanner = scanner(tmp.path());
+    scanner.clock_offset(chrono::Duration::seconds(-90));
+
+    let time = first_time(scanner).await;
+    assert_eq!(time, Utc.ymd(2020, 8, 26).and_hms(12, 33, 26));
+}
+
+#[tokio::test]
+async fn recorded_in_local_time() {
+    let tmp = scratch();
+    let mut scanner = scanner(tmp.path());
+    scanner.recorded_in(FixedOffset::east(2 * 60 * 60));
+    scanner.clock_offset(chrono::Duration::seconds(4));
+
+    let time = first_time(scanner).await;
+    assert_eq!(time, Utc.ymd(2020, 8, 26).and_hms(10, 35, 0));
+}
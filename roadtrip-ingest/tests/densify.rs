@@ -0,0 +1,81 @@
+mod util;
+
+use chrono::{Duration, TimeZone, Utc};
+
+use futures::StreamExt;
+
+use roadtrip_core::geometry::{Interpolation, Point};
+
+use roadtrip_ingest::ingest::Sidecar;
+use roadtrip_ingest::Scanner;
+
+use self::util::scratch;
+
+// Fixes every half minute, driving north, then one twenty minutes later.
+const NMEA: &str = "\
+$GPRMC,123456.00,A,4800.000,N,01130.000,W,022.4,000.0,260820,,,A*4E
+$GPRMC,123526.00,A,4803.000,N,01130.000,W,022.4,000.0,260820,,,A*4B
+$GPRMC,123556.00,A,4806.000,N,01130.000,W,022.4,000.0,260820,,,A*49
+$GPRMC,125556.00,A,4809.000,N,01130.000,W,022.4,000.0,260820,,,A*40
+";
+
+async fn points(densify: Option<Interpolation>) -> Vec<Point> {
+    let tmp = scratch();
+    let path = tmp.path().join("clip.mp4");
+    std::fs::write(&path, b"video").unwrap();
+    std::fs::write(path.with_extension("nmea"), NMEA).unwrap();
+
+    let mut scanner = Scanner::default();
+    scanner.add_ingester(Sidecar::new());
+    scanner.insert_path(tmp.path());
+
+    if let Some(interpolation) = densify {
+        scanner.densify(Duration::seconds(10), interpolation);
+    }
+
+    let media: Vec<_> = scanner
+        .scan()
+        .filter_map(|r| async { r.ok() })
+        .collect()
+        .await;
+
+    assert_eq!(media.len(), 1);
+    media[0].geometry().iter().collect()
+}
+
+#[tokio::test]
+async fn off_by_default() {
+    let points = points(None).await;
+    assert_eq!(points.len(), 4);
+}
+
+#[tokio::test]
+async fn linear() {
+    let points = points(Some(Interpolation::Linear)).await;
+    let start = Utc.ymd(2020, 8, 26).and_hms(12, 34, 56);
+
+    // Two new points in each half minute, and none in the long gap.
+    assert_eq!(points.len(), 8);
+    assert_eq!(points[1].time(), start + Duration::seconds(10));
+    assert_eq!(points[6].time(), start + Duration::seconds(60));
+    assert_eq!(points[7].time(), start + Duration::minutes(21));
+
+    assert!((points[1].latitude() - 48.01667).abs() < 1e-4);
+    assert!((points[1].longitude() + 11.5).abs() < 1e-9);
+    assert!((points[5].latitude() - 48.08333).abs() < 1e-4);
+}
+
+#[tokio::test]
+async fn spline() {
+    let points = points(Some(Interpolation::Spline)).await;
+    let lats: Vec<_> = points.iter().map(Point::latitude).collect();
+
+    assert_eq!(points.len(), 8);
+
+    // The fixes themselves don't move.
+    assert!((lats[0] - 48.0).abs() < 1e-9);
+    assert!((lats[3] - 48.05).abs() < 1e-9);
+    assert!((lats[6] - 48.1).abs() < 1e-9);
+
+    assert!(lats.windows(2).all(|w| w[0] < w[1]));
+}
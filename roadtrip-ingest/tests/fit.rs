@@ -0,0 +1,222 @@
+mod util;
+
+use chrono::{TimeZone, Utc};
+
+use roadtrip_ingest::ingest::{Error, Fit, Ingest};
+
+use self::util::scratch;
+
+// 2020-08-26T12:34:56Z, in seconds since the FIT epoch.
+const TIME: u32 = 1_598_445_296 - 631_065_600;
+
+fn semicircles(degrees: f64) -> i32 {
+    (degrees * 2_147_483_648.0 / 180.0) as i32
+}
+
+fn fit(records: &[u8]) -> Vec<u8> {
+    let mut out = vec![12, 0x10];
+    out.extend_from_slice(&2132u16.to_le_bytes());
+    out.extend_from_slice(&(records.len() as u32).to_le_bytes());
+    out.extend_from_slice(b".FIT");
+    out.extend_from_slice(records);
+    out.extend_from_slice(&[0, 0]);
+    out
+}
+
+// Defines local message 0 as a `record` with a timestamp and position.
+fn definition() -> Vec<u8> {
+    let mut out = vec![0x40, 0, 0];
+    out.extend_from_slice(&20u16.to_le_bytes());
+    out.push(3);
+    out.extend_from_slice(&[253, 4, 0x86, 0, 4, 0x85, 1, 4, 0x85]);
+    out
+}
+
+fn record(header: u8, time: u32, lat: i32, lng: i32) -> Vec<u8> {
+    let mut out = vec![header];
+    out.extend_from_slice(&time.to_le_bytes());
+    out.extend_from_slice(&lat.to_le_bytes());
+    out.extend_from_slice(&lng.to_le_bytes());
+    out
+}
+
+#[tokio::test]
+async fn ingest_points() {
+    let dir = scratch();
+    let path = dir.path().join("activity.fit");
+    let records = [
+        definition(),
+        record(0, TIME, semicircles(48.1173), semicircles(-11.5167)),
+        record(0, TIME + 1, 0x7fff_ffff, 0x7fff_ffff),
+        record(0, TIME + 2, semicircles(48.1183), semicircles(-11.525)),
+    ]
+    .concat();
+    std::fs::write(&path, fit(&records)).unwrap();
+
+    let media = Fit::new().ingest(path.clone()).await.unwrap();
+    let points: Vec<_> = media.geometry().iter().collect();
+
+    assert_eq!(media.path(), path);
+    assert_eq!(points.len(), 2);
+
+    assert!((points[0].latitude() - 48.1173).abs() < 1e-4);
+    assert!((points[0].longitude() + 11.5167).abs() < 1e-4);
+    assert_eq!(points[0].time(), Utc.ymd(2020, 8, 26).and_hms(12, 34, 56));
+    assert_eq!(points[1].time(), Utc.ymd(2020, 8, 26).and_hms(12, 34, 58));
+}
+
+#[tokio::test]
+async fn altitude_and_speed() {
+    let dir = scratch();
+    let path = dir.path().join("motion.fit");
+
+    // A `record` with a timestamp, position, altitude, and enhanced speed.
+    let mut definition = vec![0x40, 0, 0];
+    definition.extend_from_slice(&20u16.to_le_bytes());
+    definition.push(5);
+    definition.extend_from_slice(&[253, 4, 0x86, 0, 4, 0x85, 1, 4, 0x85]);
+    definition.extend_from_slice(&[2, 2, 0x84, 73, 4, 0x86]);
+
+    let motion = |altitude: u16, speed: u32| {
+        [&altitude.to_le_bytes()[..], &speed.to_le_bytes()[..]].concat()
+    };
+
+    let records = [
+        definition,
+        record(0, TIME, semicircles(48.1173), semicircles(-11.5167)),
+        // 545.4 metres, and 12.5 metres per second.
+        motion(5227, 12_500),
+        record(0, TIME + 1, semicircles(48.1183), semicircles(-11.525)),
+        motion(0xffff, 0xffff_ffff),
+    ]
+    .concat();
+    std::fs::write(&path, fit(&records)).unwrap();
+
+    let media = Fit::new().ingest(path).await.unwrap();
+    let points: Vec<_> = media.geometry().iter().collect();
+
+    assert_eq!(points.len(), 2);
+
+    assert!((points[0].altitude().unwrap() - 545.4).abs() < 1e-9);
+    assert!((points[0].speed().unwrap() - 12.5).abs() < 1e-9);
+    assert_eq!(points[0].heading(), None);
+
+    assert_eq!(points[1].altitude(), None);
+    assert_eq!(points[1].speed(), None);
+}
+
+#[tokio::test]
+async fn compressed_timestamps() {
+    let dir = scratch();
+    let path = dir.path().join("compressed.fit");
+
+    // Local message 1 is a `record` without a timestamp, for use with
+    // compressed timestamp headers.
+    let mut compressed = vec![0x41, 0, 0];
+    compressed.extend_from_slice(&20u16.to_le_bytes());
+    compressed.push(2);
+    compressed.extend_from_slice(&[0, 4, 0x85, 1, 4, 0x85]);
+
+    let lat = semicircles(48.1173).to_le_bytes();
+    let lng = semicircles(-11.5167).to_le_bytes();
+
+    // The low five bits of TIME are 0x10, so 0x02 wraps to the next block.
+    let offset = (TIME + 0x12) & 0x1f;
+
+    let records = [
+        definition(),
+        record(0, TIME, semicircles(48.1173), semicircles(-11.5167)),
+        compressed,
+        vec![0x80 | 0x20 | offset as u8],
+        lat.to_vec(),
+        lng.to_vec(),
+    ]
+    .concat();
+    std::fs::write(&path, fit(&records)).unwrap();
+
+    let media = Fit::new().ingest(path).await.unwrap();
+    let points: Vec<_> = media.geometry().iter().collect();
+
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[1].time(), Utc.ymd(2020, 8, 26).and_hms(12, 35, 14));
+}
+
+#[tokio::test]
+async fn truncated_is_reported() {
+    let dir = scratch();
+    let path = dir.path().join("truncated.fit");
+    let mut records = [
+        definition(),
+        record(0, TIME, semicircles(48.1173), semicircles(-11.5167)),
+    ]
+    .concat();
+    let len = records.len() as u32;
+    records.truncate(records.len() - 4);
+
+    let mut data = fit(&records);
+    data[4..8].copy_from_slice(&len.to_le_bytes());
+    std::fs::write(&path, data).unwrap();
+
+    let result = Fit::new().ingest(path).await;
+    let err: Error = result.unwrap_err().into();
+
+    assert!(err.is_supported());
+}
+
+#[tokio::test]
+async fn other_files_unsupported() {
+    let dir = scratch();
+    let path = dir.path().join("other.txt");
+    std::fs::write(&path, b"definitely not a fit file").unwrap();
+
+    let result = Fit::new().ingest(path).await;
+    let err: Error = result.unwrap_err().into();
+
+    assert!(!err.is_supported());
+}
+
+#[tokio::test]
+async fn sessions() {
+    let dir = scratch();
+    let path = dir.path().join("sessions.fit");
+
+    // Local message 1 is a `session`, with only a timestamp.
+    let mut session = vec![0x41, 0, 0];
+    session.extend_from_slice(&18u16.to_le_bytes());
+    session.push(1);
+    session.extend_from_slice(&[253, 4, 0x86]);
+
+    let records = [
+        definition(),
+        session,
+        record(0, TIME, semicircles(48.1173), semicircles(-11.5167)),
+        record(0, TIME + 1, semicircles(48.1183), semicircles(-11.525)),
+        [&[1][..], &(TIME + 1).to_le_bytes()].concat(),
+        record(0, TIME + 60, semicircles(48.2), semicircles(-11.6)),
+        [&[1][..], &(TIME + 60).to_le_bytes()].concat(),
+    ]
+    .concat();
+
+    // A second, chained file is a session of its own.
+    let chained = [
+        definition(),
+        record(0, TIME + 120, semicircles(48.3), semicircles(-11.7)),
+    ]
+    .concat();
+
+    std::fs::write(&path, [fit(&records), fit(&chained)].concat()).unwrap();
+
+    let media = Fit::new().ingest_all(path.clone()).await.unwrap();
+    let lengths: Vec<_> = media.iter().map(|m| m.geometry().len()).collect();
+
+    assert_eq!(lengths, [2, 1, 1]);
+    assert!(media.iter().all(|m| m.path() == path));
+    assert_ne!(media[0].hash(), media[1].hash());
+    assert_ne!(media[1].hash(), media[2].hash());
+
+    // All in one, when asked for a single media.
+    let single = Fit::new().ingest(path).await.unwrap();
+
+    assert_eq!(single.geometry().len(), 4);
+    assert_eq!(single.hash(), media[0].hash());
+}
@@ -0,0 +1,103 @@
+mod util;
+
+use chrono::{TimeZone, Utc};
+
+use roadtrip_ingest::ingest::{Error, FreeGps, Ingest};
+
+use self::util::scratch;
+
+fn atom(kind: &[u8], body: &[u8]) -> Vec<u8> {
+    let mut out = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(body);
+    out
+}
+
+fn block(time: [u32; 6], status: u8, lat: f32, lng: f32) -> Vec<u8> {
+    let mut out = vec![0u8; 0x40];
+    out[..4].copy_from_slice(&0x40u32.to_be_bytes());
+    out[4..12].copy_from_slice(b"freeGPS ");
+
+    for (idx, value) in time.iter().enumerate() {
+        let at = 0x10 + idx * 4;
+        out[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    out[0x28] = status;
+    out[0x29] = b'N';
+    out[0x2a] = b'W';
+    out[0x2c..0x30].copy_from_slice(&lat.to_le_bytes());
+    out[0x30..0x34].copy_from_slice(&lng.to_le_bytes());
+    out
+}
+
+fn mp4(blocks: &[Vec<u8>]) -> Vec<u8> {
+    let ftyp = atom(b"ftyp", b"isom\0\0\0\0");
+    let mdat = atom(b"mdat", &blocks.concat());
+
+    let mut table = 0x101u32.to_be_bytes().to_vec();
+    table.extend_from_slice(&(blocks.len() as u32).to_be_bytes());
+
+    let mut offset = ftyp.len() + 8;
+    for block in blocks {
+        table.extend_from_slice(&(offset as u32).to_be_bytes());
+        table.extend_from_slice(&(block.len() as u32).to_be_bytes());
+        offset += block.len();
+    }
+
+    let moov = atom(b"moov", &atom(b"gps ", &table));
+
+    [ftyp, mdat, moov].concat()
+}
+
+#[tokio::test]
+async fn ingest_points() {
+    let dir = scratch();
+    let path = dir.path().join("points.mp4");
+    let data = mp4(&[
+        block([12, 34, 56, 20, 8, 26], b'A', 4807.038, 1131.0),
+        block([12, 34, 57, 20, 8, 26], b'V', 0.0, 0.0),
+        block([12, 34, 58, 20, 8, 26], b'A', 4807.1, 1131.5),
+    ]);
+    std::fs::write(&path, data).unwrap();
+
+    let media = FreeGps::new().ingest(path.clone()).await.unwrap();
+    let points: Vec<_> = media.geometry().iter().collect();
+
+    assert_eq!(media.path(), path);
+    assert_eq!(points.len(), 2);
+
+    assert!((points[0].latitude() - 48.1173).abs() < 1e-4);
+    assert!((points[0].longitude() + 11.5167).abs() < 1e-4);
+    assert_eq!(points[0].time(), Utc.ymd(2020, 8, 26).and_hms(12, 34, 56));
+    assert_eq!(points[1].time(), Utc.ymd(2020, 8, 26).and_hms(12, 34, 58));
+}
+
+#[tokio::test]
+async fn other_files_unsupported() {
+    let dir = scratch();
+    let path = dir.path().join("other.txt");
+    std::fs::write(&path, b"definitely not a video").unwrap();
+
+    let result = FreeGps::new().ingest(path).await;
+    let err: Error = result.unwrap_err().into();
+
+    assert!(!err.is_supported());
+}
+
+#[tokio::test]
+async fn mp4_without_gps_unsupported() {
+    let dir = scratch();
+    let path = dir.path().join("plain.mp4");
+    let data = [
+        atom(b"ftyp", b"isom\0\0\0\0"),
+        atom(b"moov", &atom(b"mvhd", &[0; 16])),
+    ]
+    .concat();
+    std::fs::write(&path, data).unwrap();
+
+    let result = FreeGps::new().ingest(path).await;
+    let err: Error = result.unwrap_err().into();
+
+    assert!(!err.is_supported());
+}
@@ -0,0 +1,122 @@
+mod util;
+
+use futures::StreamExt;
+
+use roadtrip_core::hasher::{Algorithm, Kind};
+use roadtrip_core::media::Media;
+use roadtrip_core::Hash;
+
+use roadtrip_ingest::ingest::{Ingest, Sidecar};
+use roadtrip_ingest::Scanner;
+
+use self::util::scratch;
+
+use std::path::{Path, PathBuf};
+
+const NMEA: &str = "\
+$GPRMC,123456.00,A,4807.038,N,01131.000,W,022.4,084.4,260820,,,A*4B
+";
+
+const SHA3_ABC: &str =
+    "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532";
+
+const BLAKE3_ABC: &str =
+    "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85";
+
+fn clip(dir: &Path) -> PathBuf {
+    let path = dir.join("clip.mp4");
+    std::fs::write(&path, b"abc").unwrap();
+    std::fs::write(path.with_extension("nmea"), NMEA).unwrap();
+    path
+}
+
+#[tokio::test]
+async fn sha3_by_default() {
+    let tmp = scratch();
+    let path = clip(tmp.path());
+
+    let media = Sidecar::new().ingest(path).await.unwrap();
+
+    assert_eq!(media.hash().to_hex(), SHA3_ABC);
+}
+
+#[tokio::test]
+async fn blake3_from_scanner() {
+    let tmp = scratch();
+    let dir = tmp.path();
+    let path = clip(dir);
+
+    let mut scanner = Scanner::default();
+    scanner.add_ingester(Sidecar::new());
+    scanner.insert_path(dir);
+    scanner.hash_with(Algorithm::Blake3);
+
+    let media: Vec<_> = scanner
+        .scan()
+        .filter_map(|r| async { r.ok() })
+        .collect()
+        .await;
+
+    assert_eq!(media.len(), 1);
+    assert_eq!(media[0].path(), path);
+    assert_eq!(media[0].hash().to_hex(), BLAKE3_ABC);
+    assert_eq!(media[0].hash().kind(), Kind::Full(Algorithm::Blake3));
+}
+
+async fn scan_sampled(dir: &Path) -> Vec<Media> {
+    let mut scanner = Scanner::default();
+    scanner.add_ingester(Sidecar::new());
+    scanner.insert_path(dir);
+    scanner.sample_hashes(true);
+
+    scanner
+        .scan()
+        .filter_map(|r| async { r.ok() })
+        .collect()
+        .await
+}
+
+#[tokio::test]
+async fn sampled_small_files_hashed_in_full() {
+    let tmp = scratch();
+    let dir = tmp.path();
+    clip(dir);
+
+    let media = scan_sampled(dir).await;
+
+    assert_eq!(media.len(), 1);
+    assert_eq!(media[0].hash().to_hex(), SHA3_ABC);
+    assert_eq!(media[0].hash().kind(), Kind::Full(Algorithm::Sha3_256));
+}
+
+#[tokio::test]
+async fn sampled_large_files() {
+    let tmp = scratch();
+    let dir = tmp.path();
+    let path = clip(dir);
+
+    let mut contents: Vec<u8> = (0..4 << 20).map(|i| i as u8).collect();
+    std::fs::write(&path, &contents).unwrap();
+
+    let full = Sidecar::new().ingest(path.clone()).await.unwrap();
+    let sampled = scan_sampled(dir).await;
+
+    assert_eq!(sampled.len(), 1);
+    assert_ne!(sampled[0].hash(), full.hash());
+
+    let kind = Kind::Sampled(Algorithm::Sha3_256);
+    assert_eq!(sampled[0].hash().kind(), kind);
+
+    let text = sampled[0].hash().to_string();
+    assert_eq!(text.parse::<Hash>().unwrap(), *sampled[0].hash());
+
+    // Between the first and middle samples, so it goes unnoticed.
+    contents[1_200_000] ^= 0xff;
+    std::fs::write(&path, &contents).unwrap();
+    assert_eq!(scan_sampled(dir).await[0].hash(), sampled[0].hash());
+
+    // In the last sample.
+    contents[(4 << 20) - 1] ^= 0xff;
+    std::fs::write(&path, &contents).unwrap();
+    assert_ne!(scan_sampled(dir).await[0].hash(), sampled[0].hash());
+}
@@ -0,0 +1,151 @@
+mod util;
+
+use chrono::{TimeZone, Utc};
+
+use futures::StreamExt;
+
+use roadtrip_core::geometry::{Geometry, Point};
+use roadtrip_core::media::Media;
+use roadtrip_core::Hash;
+
+use roadtrip_ingest::index::Index;
+use roadtrip_ingest::ingest::{Error, Ingest};
+use roadtrip_ingest::Scanner;
+
+use self::util::scratch;
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Ingests anything, and counts how many times it was asked to.
+#[derive(Debug, Default, Clone)]
+struct Counter(Arc<AtomicUsize>);
+
+impl Counter {
+    fn count(&self) -> usize {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl Ingest for Counter {
+    type Error = Error;
+
+    fn ingest<'a>(
+        &'a self,
+        path: PathBuf,
+    ) -> Pin<Box<dyn Future<Output = Result<Media, Error>> + 'a + Send>> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+
+        Box::pin(async move {
+            let mut contents = std::fs::read(&path).unwrap();
+            contents.resize(32, 0);
+            let time = Utc.ymd(2020, 8, 26).and_hms(12, 34, 56);
+
+            Ok(Media::builder()
+                .path(path)
+                .geometry(Geometry::from(Point::new(48.1, -11.5, time)))
+                .hash(Hash::from_slice(&contents))
+                .build())
+        })
+    }
+}
+
+async fn scan(dir: &Path, index: &Index, counter: &Counter) -> Vec<Media> {
+    let mut scanner = Scanner::default();
+    scanner.add_ingester(counter.clone());
+    scanner.insert_path(dir);
+    scanner.use_index(index.clone());
+
+    scanner.scan().map(Result::unwrap).collect::<Vec<_>>().await
+}
+
+#[tokio::test]
+async fn skips_unchanged() {
+    let tmp = scratch();
+    let dir = tmp.path().join("clips");
+    std::fs::create_dir(&dir).unwrap();
+    std::fs::write(dir.join("a.mp4"), b"first").unwrap();
+    std::fs::write(dir.join("b.mp4"), b"second").unwrap();
+
+    let index = Index::load(tmp.path().join("index")).await.unwrap();
+    let counter = Counter::default();
+
+    assert_eq!(scan(&dir, &index, &counter).await.len(), 2);
+    assert_eq!(counter.count(), 2);
+
+    assert!(scan(&dir, &index, &counter).await.is_empty());
+    assert_eq!(counter.count(), 2);
+}
+
+#[tokio::test]
+async fn rescans_modified() {
+    let tmp = scratch();
+    let dir = tmp.path().join("clips");
+    std::fs::create_dir(&dir).unwrap();
+    std::fs::write(dir.join("a.mp4"), b"first").unwrap();
+
+    let index = Index::load(tmp.path().join("index")).await.unwrap();
+    let counter = Counter::default();
+
+    scan(&dir, &index, &counter).await;
+
+    // Make sure the modification time changes, along with the size.
+    std::thread::sleep(Duration::from_millis(20));
+    std::fs::write(dir.join("a.mp4"), b"changed").unwrap();
+    std::fs::write(dir.join("c.mp4"), b"third").unwrap();
+
+    let mut found: Vec<_> = scan(&dir, &index, &counter)
+        .await
+        .into_iter()
+        .map(|m| m.path().file_name().unwrap().to_owned())
+        .collect();
+    found.sort();
+
+    assert_eq!(found, ["a.mp4", "c.mp4"]);
+    assert_eq!(counter.count(), 3);
+}
+
+#[tokio::test]
+async fn save_and_load() {
+    let tmp = scratch();
+    let dir = tmp.path().join("clips");
+    std::fs::create_dir(&dir).unwrap();
+    std::fs::write(dir.join("with space.mp4"), b"first").unwrap();
+
+    let path = tmp.path().join("index");
+    let index = Index::load(path.clone()).await.unwrap();
+    let counter = Counter::default();
+
+    scan(&dir, &index, &counter).await;
+    index.save().await.unwrap();
+
+    let loaded = Index::load(path).await.unwrap();
+    let file = dir.join("with space.mp4");
+
+    assert_eq!(loaded.len(), 1);
+    assert_eq!(loaded.get(&file), index.get(&file));
+    assert!(scan(&dir, &loaded, &counter).await.is_empty());
+    assert_eq!(counter.count(), 1);
+}
+
+#[tokio::test]
+async fn forget() {
+    let tmp = scratch();
+    let dir = tmp.path().join("clips");
+    std::fs::create_dir(&dir).unwrap();
+    std::fs::write(dir.join("a.mp4"), b"first").unwrap();
+
+    let index = Index::load(tmp.path().join("index")).await.unwrap();
+    let counter = Counter::default();
+
+    let media = scan(&dir, &index, &counter).await;
+    index.forget(media[0].hash());
+
+    assert!(index.is_empty());
+    assert_eq!(scan(&dir, &index, &counter).await.len(), 1);
+    assert_eq!(counter.count(), 2);
+}
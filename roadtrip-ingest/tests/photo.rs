@@ -0,0 +1,161 @@
+mod util;
+
+use chrono::{TimeZone, Utc};
+
+use roadtrip_core::media::{keys, MetadataValue};
+
+use roadtrip_ingest::ingest::{Error, Ingest, Photo};
+
+use self::util::scratch;
+
+const ASCII: u16 = 2;
+const LONG: u16 = 4;
+const RATIONAL: u16 = 5;
+
+// A little-endian TIFF IFD starting at `start`, with values that don't fit in
+// an entry stored right after it.
+fn ifd(start: usize, entries: &[(u16, u16, u32, Vec<u8>)]) -> Vec<u8> {
+    let mut out = (entries.len() as u16).to_le_bytes().to_vec();
+    let mut data = Vec::new();
+    let data_start = start + 2 + entries.len() * 12 + 4;
+
+    for (tag, kind, count, value) in entries {
+        out.extend_from_slice(&tag.to_le_bytes());
+        out.extend_from_slice(&kind.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+
+        if value.len() <= 4 {
+            let mut inline = value.clone();
+            inline.resize(4, 0);
+            out.extend_from_slice(&inline);
+        } else {
+            let offset = (data_start + data.len()) as u32;
+            out.extend_from_slice(&offset.to_le_bytes());
+            data.extend_from_slice(value);
+        }
+    }
+
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&data);
+    out
+}
+
+fn rationals(values: &[u32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|v| [v.to_le_bytes(), 1u32.to_le_bytes()].concat())
+        .collect()
+}
+
+fn ascii(text: &str) -> (u32, Vec<u8>) {
+    let mut bytes = text.as_bytes().to_vec();
+    bytes.push(0);
+    (bytes.len() as u32, bytes)
+}
+
+fn jpeg(gps: &[(u16, u16, u32, Vec<u8>)]) -> Vec<u8> {
+    let (make_len, make) = ascii("Acme");
+    let (model_len, model) = ascii("Phone");
+
+    // IFD0 holds the make, model and a pointer to the GPS IFD, which follows
+    // it.
+    let ifd0_len = 2 + 3 * 12 + 4 + make.len() + model.len();
+    let gps_start = 8 + ifd0_len as u32;
+
+    let mut tiff = b"II*\0".to_vec();
+    tiff.extend_from_slice(&8u32.to_le_bytes());
+    tiff.extend(ifd(
+        8,
+        &[
+            (0x010f, ASCII, make_len, make),
+            (0x0110, ASCII, model_len, model),
+            (0x8825, LONG, 1, gps_start.to_le_bytes().to_vec()),
+        ],
+    ));
+    tiff.extend(ifd(gps_start as usize, gps));
+
+    let mut out = vec![0xff, 0xd8, 0xff, 0xe1];
+    out.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+    out.extend_from_slice(b"Exif\0\0");
+    out.extend(tiff);
+    out.extend_from_slice(&[0xff, 0xd9]);
+    out
+}
+
+#[tokio::test]
+async fn ingest_point() {
+    let dir = scratch();
+    let path = dir.path().join("point.jpg");
+    let (date_len, date) = ascii("2020:08:26");
+    let data = jpeg(&[
+        (0x0001, ASCII, 2, b"N\0".to_vec()),
+        (0x0002, RATIONAL, 3, rationals(&[48, 7, 9])),
+        (0x0003, ASCII, 2, b"W\0".to_vec()),
+        (0x0004, RATIONAL, 3, rationals(&[11, 31, 0])),
+        (0x0007, RATIONAL, 3, rationals(&[12, 34, 56])),
+        (0x001d, ASCII, date_len, date),
+    ]);
+    let len = data.len() as i64;
+    std::fs::write(&path, data).unwrap();
+
+    let media = Photo::new().ingest(path.clone()).await.unwrap();
+    let points: Vec<_> = media.geometry().iter().collect();
+
+    assert_eq!(media.path(), path);
+    assert_eq!(media.camera(), Some("Phone"));
+    assert_eq!(points.len(), 1);
+
+    let metadata = media.metadata();
+    let device = MetadataValue::Text("Acme".to_owned());
+    assert_eq!(metadata.get(keys::DEVICE), Some(&device));
+    assert_eq!(metadata.get(keys::FILE_SIZE), Some(&len.into()));
+    assert_eq!(metadata.get(keys::SERIAL), None);
+
+    assert!((points[0].latitude() - 48.1192).abs() < 1e-4);
+    assert!((points[0].longitude() + 11.5167).abs() < 1e-4);
+    assert_eq!(points[0].time(), Utc.ymd(2020, 8, 26).and_hms(12, 34, 56));
+}
+
+#[tokio::test]
+async fn ingest_without_hemisphere() {
+    let dir = scratch();
+    let path = dir.path().join("unreferenced.jpg");
+    let (date_len, date) = ascii("2020:08:26");
+    let data = jpeg(&[
+        (0x0002, RATIONAL, 3, rationals(&[48, 7, 9])),
+        (0x0004, RATIONAL, 3, rationals(&[11, 31, 0])),
+        (0x0007, RATIONAL, 3, rationals(&[12, 34, 56])),
+        (0x001d, ASCII, date_len, date),
+    ]);
+    std::fs::write(&path, data).unwrap();
+
+    let media = Photo::new().ingest(path).await.unwrap();
+    let point = media.geometry().iter().next().unwrap();
+
+    assert!((point.latitude() - 48.1192).abs() < 1e-4);
+    assert!((point.longitude() - 11.5167).abs() < 1e-4);
+}
+
+#[tokio::test]
+async fn photo_without_position() {
+    let dir = scratch();
+    let path = dir.path().join("nowhere.jpg");
+    std::fs::write(&path, jpeg(&[])).unwrap();
+
+    let result = Photo::new().ingest(path).await;
+    let err: Error = result.unwrap_err().into();
+
+    assert!(err.is_supported());
+}
+
+#[tokio::test]
+async fn other_files_unsupported() {
+    let dir = scratch();
+    let path = dir.path().join("other.txt");
+    std::fs::write(&path, b"definitely not a photo").unwrap();
+
+    let result = Photo::new().ingest(path).await;
+    let err: Error = result.unwrap_err().into();
+
+    assert!(!err.is_supported());
+}
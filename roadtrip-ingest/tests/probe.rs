@@ -0,0 +1,63 @@
+mod util;
+
+use roadtrip_ingest::ingest::{Ingest, Sidecar};
+
+use self::util::scratch;
+
+use std::os::unix::fs::PermissionsExt;
+use std::time::Duration;
+
+const NMEA: &str = "\
+$GPRMC,123456.00,A,4807.038,N,01131.000,W,022.4,084.4,260820,,,A*4B
+";
+
+// What ffprobe prints for a dashcam clip with a video and an audio stream.
+const FFPROBE: &str = "\
+#!/bin/sh
+cat <<EOF
+[STREAM]
+codec_name=aac
+codec_type=audio
+width=N/A
+height=N/A
+[/STREAM]
+[STREAM]
+codec_name=h264
+codec_type=video
+width=1920
+height=1080
+[/STREAM]
+[FORMAT]
+format_name=mov,mp4,m4a,3gp,3g2,mj2
+duration=60.500000
+[/FORMAT]
+EOF
+";
+
+// Only one test here, since it changes `PATH` for the whole process.
+#[tokio::test]
+async fn video_metadata() {
+    let tmp = scratch();
+    let bin = tmp.path().join("bin");
+    std::fs::create_dir(&bin).unwrap();
+
+    let ffprobe = bin.join("ffprobe");
+    std::fs::write(&ffprobe, FFPROBE).unwrap();
+    std::fs::set_permissions(&ffprobe, PermissionsExt::from_mode(0o755))
+        .unwrap();
+
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let paths = std::iter::once(bin).chain(std::env::split_paths(&path));
+    std::env::set_var("PATH", std::env::join_paths(paths).unwrap());
+
+    let path = tmp.path().join("clip.MP4");
+    std::fs::write(&path, b"video").unwrap();
+    std::fs::write(path.with_extension("nmea"), NMEA).unwrap();
+
+    let media = Sidecar::new().ingest(path).await.unwrap();
+
+    assert_eq!(media.duration(), Some(Duration::from_millis(60_500)));
+    assert_eq!(media.resolution(), Some((1920, 1080)));
+    assert_eq!(media.container(), Some("mov,mp4,m4a,3gp,3g2,mj2"));
+    assert_eq!(media.codec(), Some("h264"));
+}
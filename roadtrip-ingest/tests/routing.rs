@@ -0,0 +1,131 @@
+mod util;
+
+use chrono::{TimeZone, Utc};
+
+use futures::StreamExt;
+
+use roadtrip_core::geometry::{Geometry, Point};
+use roadtrip_core::media::Media;
+use roadtrip_core::Hash;
+
+use roadtrip_ingest::error::Error as ScanError;
+use roadtrip_ingest::ingest::{
+    Blackvue, Error, Exiftool, Fit, FreeGps, Ingest, Photo, Sidecar,
+};
+use roadtrip_ingest::Scanner;
+
+use self::util::scratch;
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+// Ingests `.mp4` files, and counts how many times it was asked to.
+#[derive(Debug, Default, Clone)]
+struct Videos(Arc<AtomicUsize>);
+
+impl Ingest for Videos {
+    type Error = Error;
+
+    fn ingest<'a>(
+        &'a self,
+        path: PathBuf,
+    ) -> Pin<Box<dyn Future<Output = Result<Media, Error>> + 'a + Send>> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+
+        Box::pin(async move {
+            let time = Utc.ymd(2020, 8, 26).and_hms(12, 34, 56);
+
+            Ok(Media::builder()
+                .path(path)
+                .geometry(Geometry::from(Point::new(48.1, -11.5, time)))
+                .hash(Hash::from([0; 32]))
+                .build())
+        })
+    }
+
+    fn supports(&self, path: &Path) -> bool {
+        path.extension().map(|e| e == "mp4").unwrap_or(false)
+    }
+}
+
+#[tokio::test]
+async fn skips_unsupported_ingesters() {
+    let tmp = scratch();
+    let dir = tmp.path();
+    std::fs::write(dir.join("clip.mp4"), b"video").unwrap();
+    std::fs::write(dir.join("notes.txt"), b"text").unwrap();
+
+    let videos = Videos::default();
+
+    let mut scanner = Scanner::default();
+    scanner.add_ingester(videos.clone());
+    scanner.insert_path(dir);
+
+    let results: Vec<_> = scanner.scan().collect().await;
+
+    assert_eq!(videos.0.load(Ordering::SeqCst), 1);
+    assert_eq!(results.len(), 2);
+
+    for result in results {
+        match result {
+            Ok(media) => assert_eq!(media.path(), dir.join("clip.mp4")),
+            Err(ScanError::Unsupported { path }) => {
+                assert_eq!(path, dir.join("notes.txt"))
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+}
+
+#[test]
+fn extensions() {
+    let exiftool = Exiftool::new(PathBuf::from("gpx.fmt"));
+
+    assert!(exiftool.supports(Path::new("clip.MP4")));
+    assert!(exiftool.supports(Path::new("photo.jpg")));
+    assert!(!exiftool.supports(Path::new("notes.txt")));
+    assert!(!exiftool.supports(Path::new("README")));
+
+    assert!(Photo::new().supports(Path::new("photo.JPEG")));
+    assert!(!Photo::new().supports(Path::new("clip.mp4")));
+
+    assert!(Fit::new().supports(Path::new("activity.FIT")));
+    assert!(!Fit::new().supports(Path::new("clip.mp4")));
+
+    assert!(FreeGps::new().supports(Path::new("clip.MOV")));
+    assert!(Blackvue::new().supports(Path::new("clip.mp4")));
+    assert!(!Blackvue::new().supports(Path::new("photo.jpg")));
+
+    assert!(Sidecar::new().supports(Path::new("clip.mp4")));
+    assert!(!Sidecar::new().supports(Path::new("clip.gpx")));
+}
+
+#[tokio::test]
+async fn sniffs_misnamed_files() {
+    let tmp = scratch();
+    let dir = tmp.path();
+    std::fs::write(dir.join("fragment.bin"), b"\0\0\0\x18ftypmp42\0\0\0\0")
+        .unwrap();
+    std::fs::write(dir.join("clip"), b"\0\0\0\x08moov").unwrap();
+    std::fs::write(dir.join("noise.bin"), b"\0\0\0\0\0\0\0\0").unwrap();
+
+    let videos = Videos::default();
+
+    let mut scanner = Scanner::default();
+    scanner.add_ingester(videos.clone());
+    scanner.insert_path(dir);
+
+    let mut ingested: Vec<_> = scanner
+        .scan()
+        .filter_map(|r| async move { r.ok() })
+        .map(|m| m.path().file_name().unwrap().to_owned())
+        .collect()
+        .await;
+    ingested.sort();
+
+    assert_eq!(ingested, ["clip", "fragment.bin"]);
+    assert_eq!(videos.0.load(Ordering::SeqCst), 2);
+}
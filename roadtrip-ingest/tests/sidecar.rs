@@ -0,0 +1,206 @@
+mod util;
+
+use chrono::{TimeZone, Utc};
+
+use roadtrip_ingest::ingest::{Error, Ingest, Sidecar};
+
+use self::util::scratch;
+
+const NMEA: &str = "\
+$GPGGA,123456.00,4807.038,N,01131.000,W,1,08,0.9,545.4,M,46.9,M,,*71
+$GPRMC,123456.00,A,4807.038,N,01131.000,W,022.4,084.4,260820,,,A*4B
+$GNRMC,123456.00,A,4807.038,N,01131.000,W,022.4,084.4,260820,,,A*55
+$GPRMC,123457.00,V,,,,,,,260820,,,N*75
+$GPRMC,123458.50,A,4807.100,N,01131.500,W,022.4,084.4,260820,,,A*4F
+$GPRMC,123459.00,A,4807.200,N,01131.600,W,022.4,084.4,260820,,,A*00
+";
+
+// The same fix from RMC (speed and heading) and GGA (altitude), then one
+// without a speed.
+const NMEA_MOTION: &str = "\
+$GPRMC,123456.00,A,4807.038,N,01131.000,W,022.4,084.4,260820,,,A*4B
+$GPGGA,123456.00,4807.038,N,01131.000,W,1,08,0.9,545.4,M,46.9,M,,*71
+$GPRMC,123457.00,A,4807.100,N,01131.500,W,,,260820,,,A*49
+";
+
+const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <trk>
+    <trkseg>
+      <trkpt lat="48.1173" lon="-11.5167">
+        <ele>545.4</ele>
+        <time>2020-08-26T12:34:56Z</time>
+      </trkpt>
+      <trkpt lat="48.1183" lon="-11.5250">
+        <time>2020-08-26T12:34:58Z</time>
+      </trkpt>
+    </trkseg>
+  </trk>
+</gpx>
+"#;
+
+const SRT: &str = "\
+1
+00:00:00,000 --> 00:00:00,033
+<font size=\"28\">FrameCnt: 1, DiffTime: 33ms
+2020-08-26 12:34:56.000
+[iso: 100] [shutter: 1/640.0] [fnum: 280] [ev: 0] [latitude: 48.117300] \
+[longitude: -11.516700] [rel_alt: 1.300 abs_alt: 300.0] </font>
+
+2
+00:00:00,033 --> 00:00:00,066
+<font size=\"28\">FrameCnt: 2, DiffTime: 33ms
+2020-08-26 12:34:56.033
+[iso: 100] [latitude: 0.000000] [longitude: 0.000000] </font>
+
+3
+00:00:00,066 --> 00:00:00,100
+<font size=\"28\">FrameCnt: 3, DiffTime: 33ms
+2020-08-26 12:34:56.066
+[iso: 100] [latitude : 48.118300] [longtitude : -11.525000] </font>
+";
+
+const OLD_SRT: &str = "\
+1
+00:00:00,000 --> 00:00:01,000
+HOME(-11.5167,48.1173) 2020.08.26 12:34:56
+GPS(-11.5167,48.1173,16) BAROMETER:1.9
+ISO:100 Shutter:60 EV: Fnum:F2.2
+
+2
+00:00:01,000 --> 00:00:02,000
+HOME(-11.5167,48.1173) 2020.08.26 12:34:57
+GPS(-11.5250,48.1183,16) BAROMETER:2.0
+ISO:100 Shutter:60 EV: Fnum:F2.2
+";
+
+#[tokio::test]
+async fn nmea_sidecar() {
+    let dir = scratch();
+    let path = dir.path().join("nmea.MP4");
+    std::fs::write(&path, b"video").unwrap();
+    std::fs::write(path.with_extension("nmea"), NMEA).unwrap();
+
+    let media = Sidecar::new().ingest(path.clone()).await.unwrap();
+    let points: Vec<_> = media.geometry().iter().collect();
+
+    assert_eq!(media.path(), path);
+
+    // The GGA, repeated, no fix, and bad checksum sentences are skipped.
+    assert_eq!(points.len(), 2);
+
+    assert!((points[0].latitude() - 48.1173).abs() < 1e-4);
+    assert!((points[0].longitude() + 11.5167).abs() < 1e-4);
+    assert_eq!(points[0].time(), Utc.ymd(2020, 8, 26).and_hms(12, 34, 56));
+    assert_eq!(
+        points[1].time(),
+        Utc.ymd(2020, 8, 26).and_hms_milli(12, 34, 58, 500)
+    );
+}
+
+#[tokio::test]
+async fn nmea_motion() {
+    let dir = scratch();
+    let path = dir.path().join("motion.MP4");
+    std::fs::write(&path, b"video").unwrap();
+    std::fs::write(path.with_extension("nmea"), NMEA_MOTION).unwrap();
+
+    let media = Sidecar::new().ingest(path).await.unwrap();
+    let points: Vec<_> = media.geometry().iter().collect();
+
+    assert_eq!(points.len(), 2);
+
+    // 22.4 knots.
+    assert!((points[0].speed().unwrap() - 11.5236).abs() < 1e-4);
+    assert!((points[0].heading().unwrap() - 84.4).abs() < 1e-9);
+    assert!((points[0].altitude().unwrap() - 545.4).abs() < 1e-9);
+
+    assert_eq!(points[1].speed(), None);
+    assert_eq!(points[1].heading(), None);
+    assert_eq!(points[1].altitude(), None);
+}
+
+#[tokio::test]
+async fn gpx_sidecar() {
+    let dir = scratch();
+    let path = dir.path().join("gpx.MP4");
+    std::fs::write(&path, b"video").unwrap();
+    std::fs::write(path.with_extension("gpx"), GPX).unwrap();
+
+    let media = Sidecar::new().ingest(path.clone()).await.unwrap();
+    let points: Vec<_> = media.geometry().iter().collect();
+
+    assert_eq!(media.path(), path);
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[1].time(), Utc.ymd(2020, 8, 26).and_hms(12, 34, 58));
+
+    assert!((points[0].altitude().unwrap() - 545.4).abs() < 1e-9);
+    assert_eq!(points[1].altitude(), None);
+}
+
+#[tokio::test]
+async fn no_sidecar_unsupported() {
+    let dir = scratch();
+    let path = dir.path().join("alone.MP4");
+    std::fs::write(&path, b"video").unwrap();
+
+    let result = Sidecar::new().ingest(path).await;
+    let err: Error = result.unwrap_err().into();
+
+    assert!(!err.is_supported());
+}
+
+#[tokio::test]
+async fn sidecar_itself_unsupported() {
+    let dir = scratch();
+    let path = dir.path().join("track.MP4");
+    std::fs::write(&path, b"video").unwrap();
+    std::fs::write(path.with_extension("gpx"), GPX).unwrap();
+
+    let result = Sidecar::new().ingest(path.with_extension("gpx")).await;
+    let err: Error = result.unwrap_err().into();
+
+    assert!(!err.is_supported());
+}
+
+#[tokio::test]
+async fn srt_sidecar() {
+    let dir = scratch();
+    let path = dir.path().join("DJI_0001.MP4");
+    std::fs::write(&path, b"video").unwrap();
+    std::fs::write(path.with_extension("SRT"), SRT).unwrap();
+
+    let media = Sidecar::new().ingest(path.clone()).await.unwrap();
+    let points: Vec<_> = media.geometry().iter().collect();
+
+    assert_eq!(media.path(), path);
+
+    // The subtitle without a fix is skipped.
+    assert_eq!(points.len(), 2);
+
+    assert!((points[0].latitude() - 48.1173).abs() < 1e-4);
+    assert!((points[0].longitude() + 11.5167).abs() < 1e-4);
+    assert_eq!(points[0].time(), Utc.ymd(2020, 8, 26).and_hms(12, 34, 56));
+    assert_eq!(
+        points[1].time(),
+        Utc.ymd(2020, 8, 26).and_hms_milli(12, 34, 56, 66)
+    );
+    assert!((points[1].longitude() + 11.525).abs() < 1e-4);
+}
+
+#[tokio::test]
+async fn old_srt_sidecar() {
+    let dir = scratch();
+    let path = dir.path().join("DJI_0002.MP4");
+    std::fs::write(&path, b"video").unwrap();
+    std::fs::write(path.with_extension("SRT"), OLD_SRT).unwrap();
+
+    let media = Sidecar::new().ingest(path).await.unwrap();
+    let points: Vec<_> = media.geometry().iter().collect();
+
+    assert_eq!(points.len(), 2);
+
+    assert!((points[1].latitude() - 48.1183).abs() < 1e-4);
+    assert!((points[1].longitude() + 11.525).abs() < 1e-4);
+    assert_eq!(points[1].time(), Utc.ymd(2020, 8, 26).and_hms(12, 34, 57));
+}
@@ -0,0 +1,133 @@
+mod util;
+
+use chrono::{FixedOffset, TimeZone, Utc};
+
+use futures::StreamExt;
+
+use roadtrip_ingest::ingest::{Error, Ingest, Tesla};
+use roadtrip_ingest::Scanner;
+
+use self::util::scratch;
+
+use std::path::Path;
+
+// Four cameras for one minute, like in a `SavedClips` event folder.
+fn event(dir: &Path) {
+    for camera in &["front", "back", "left_repeater", "right_repeater"] {
+        let name = format!("2020-08-26_12-34-56-{}.mp4", camera);
+        std::fs::write(dir.join(name), camera).unwrap();
+    }
+}
+
+const EVENT: &str = r#"{"timestamp":"2020-08-26T12:35:40","city":"Nowhere",
+"est_lat":"48.1173","est_lon":"-11.5167","reason":"sentry_aware_object_detection",
+"camera":"0"}"#;
+
+const GPX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <trk>
+    <trkseg>
+      <trkpt lat="48.1" lon="-11.5">
+        <time>2020-08-26T12:33:56Z</time>
+      </trkpt>
+      <trkpt lat="48.1173" lon="-11.5167">
+        <time>2020-08-26T12:34:56Z</time>
+      </trkpt>
+      <trkpt lat="48.1183" lon="-11.5250">
+        <time>2020-08-26T12:35:26Z</time>
+      </trkpt>
+      <trkpt lat="48.2" lon="-11.6">
+        <time>2020-08-26T12:35:56Z</time>
+      </trkpt>
+    </trkseg>
+  </trk>
+</gpx>
+"#;
+
+#[tokio::test]
+async fn event_location() {
+    let tmp = scratch();
+    let dir = tmp.path();
+    event(dir);
+    std::fs::write(dir.join("event.json"), EVENT).unwrap();
+
+    let mut scanner = Scanner::default();
+    scanner.add_ingester(Tesla::new());
+    scanner.insert_path(dir);
+
+    let (media, errors): (Vec<_>, Vec<_>) = scanner
+        .scan()
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .partition(Result::is_ok);
+
+    // The other cameras are folded into the front one, and `event.json`
+    // isn't a clip.
+    assert_eq!(errors.len(), 1);
+    assert_eq!(media.len(), 1);
+
+    let media = media.into_iter().next().unwrap().unwrap();
+    let points: Vec<_> = media.geometry().iter().collect();
+
+    assert_eq!(media.path(), dir.join("2020-08-26_12-34-56-front.mp4"));
+    assert_eq!(media.camera(), Some("Tesla"));
+    assert_eq!(points.len(), 1);
+    assert!((points[0].latitude() - 48.1173).abs() < 1e-4);
+    assert_eq!(points[0].time(), Utc.ymd(2020, 8, 26).and_hms(12, 34, 56));
+}
+
+#[tokio::test]
+async fn gpx_track() {
+    let tmp = scratch();
+    let dir = tmp.path();
+    event(dir);
+    std::fs::write(dir.join("trip.gpx"), GPX).unwrap();
+
+    let path = dir.join("2020-08-26_12-34-56-front.mp4");
+    let media = Tesla::new().ingest(path).await.unwrap();
+    let points: Vec<_> = media.geometry().iter().collect();
+
+    // Only the points during the clip.
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[1].time(), Utc.ymd(2020, 8, 26).and_hms(12, 35, 26));
+}
+
+#[tokio::test]
+async fn local_time() {
+    let tmp = scratch();
+    let dir = tmp.path();
+    event(dir);
+    std::fs::write(dir.join("event.json"), EVENT).unwrap();
+
+    let path = dir.join("2020-08-26_12-34-56-front.mp4");
+    let zone = FixedOffset::east(2 * 60 * 60);
+    let media = Tesla::recorded_in(zone).ingest(path).await.unwrap();
+    let time = media.geometry().iter().next().unwrap().time();
+
+    assert_eq!(time, Utc.ymd(2020, 8, 26).and_hms(10, 34, 56));
+}
+
+#[tokio::test]
+async fn without_position() {
+    let tmp = scratch();
+    let dir = tmp.path();
+    event(dir);
+
+    let path = dir.join("2020-08-26_12-34-56-front.mp4");
+    let result = Tesla::new().ingest(path).await;
+    let err: Error = result.unwrap_err().into();
+
+    assert!(!err.is_supported());
+}
+
+#[test]
+fn names() {
+    let tesla = Tesla::new();
+
+    assert!(tesla.supports("2020-08-26_12-34-56-front.mp4".as_ref()));
+    assert!(tesla.supports("2020-08-26_12-34-56-left_repeater.mp4".as_ref()));
+    assert!(!tesla.supports("2020-08-26_12-34-56-front.mov".as_ref()));
+    assert!(!tesla.supports("2020-08-26_12-34-56-roof.mp4".as_ref()));
+    assert!(!tesla.supports("clip.mp4".as_ref()));
+}
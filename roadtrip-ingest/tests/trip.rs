@@ -0,0 +1,87 @@
+use chrono::{Duration, TimeZone, Utc};
+
+use roadtrip_core::geometry::{Geometry, Path, Point};
+use roadtrip_core::media::Media;
+use roadtrip_core::Hash;
+
+use roadtrip_ingest::trip::Grouping;
+
+// A clip `minutes` long, starting `start` minutes past noon, driving north
+// from `lat` at about a kilometre a minute.
+fn clip(id: u8, start: i64, minutes: i64, lat: f64) -> Media {
+    let noon = Utc.ymd(2020, 8, 26).and_hms(12, 0, 0);
+
+    let points = (0..=minutes).map(|m| {
+        let time = noon + Duration::minutes(start + m);
+        Point::new(lat + m as f64 * 0.009, -11.5, time)
+    });
+
+    Media::builder()
+        .path(format!("clip-{}.mp4", id).into())
+        .geometry(Geometry::from(Path::from_iter(points)))
+        .hash(Hash::from([id; 32]))
+        .build()
+}
+
+#[test]
+fn joins_contiguous_clips() {
+    // Listed out of order, like files from a directory might be.
+    let media = vec![
+        clip(2, 3, 3, 48.027),
+        clip(1, 0, 3, 48.0),
+        clip(3, 6, 3, 48.054),
+    ];
+
+    let trips = Grouping::default().group(&media);
+
+    assert_eq!(trips.len(), 1);
+    assert_eq!(
+        trips[0].media(),
+        [
+            Hash::from([1; 32]),
+            Hash::from([2; 32]),
+            Hash::from([3; 32])
+        ]
+    );
+    assert_eq!(trips[0].start(), Utc.ymd(2020, 8, 26).and_hms(12, 0, 0));
+    assert_eq!(trips[0].end(), Utc.ymd(2020, 8, 26).and_hms(12, 9, 0));
+}
+
+#[test]
+fn splits_on_gaps() {
+    let media = vec![
+        clip(1, 0, 3, 48.0),
+        // Parked for an hour.
+        clip(2, 63, 3, 48.027),
+        // Starts somewhere else entirely.
+        clip(3, 66, 3, 49.0),
+    ];
+
+    let trips = Grouping::default().group(&media);
+    let lengths: Vec<_> = trips.iter().map(|t| t.media().len()).collect();
+
+    assert_eq!(lengths, [1, 1, 1]);
+
+    let trips = Grouping::default()
+        .max_gap(Duration::hours(2))
+        .max_distance(200_000.0)
+        .group(&media);
+
+    assert_eq!(trips.len(), 1);
+}
+
+#[test]
+fn skips_photos() {
+    let time = Utc.ymd(2020, 8, 26).and_hms(12, 1, 0);
+    let photo = Media::builder()
+        .path("photo.jpg".into())
+        .geometry(Geometry::from(Point::new(48.0, -11.5, time)))
+        .hash(Hash::from([9; 32]))
+        .build();
+
+    let media = vec![clip(1, 0, 3, 48.0), photo];
+    let trips = Grouping::default().group(&media);
+
+    assert_eq!(trips.len(), 1);
+    assert_eq!(trips[0].media(), [Hash::from([1; 32])]);
+}
@@ -0,0 +1,10 @@
+use tempfile::TempDir;
+
+// A directory for just the one test, removed with everything in it once
+// it's dropped.
+pub fn scratch() -> TempDir {
+    tempfile::Builder::new()
+        .prefix("roadtrip-ingest-")
+        .tempdir()
+        .unwrap()
+}
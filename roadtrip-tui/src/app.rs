@@ -0,0 +1,361 @@
+use crate::browser::Browser;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use tui::widgets::ListState;
+
+use roadtrip::core::geometry::Filter;
+use roadtrip::core::media::Media;
+use roadtrip::core::Hash;
+use roadtrip::viewer::error::SendError;
+use roadtrip::viewer::labels::Labels;
+use roadtrip::viewer::{Event, Handle};
+
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Browser,
+    Media,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    Tagging(String),
+    ConfirmDelete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    Path,
+    Duration,
+}
+
+#[derive(Debug)]
+pub struct Entry {
+    pub media: Media,
+    pub labels: Labels,
+}
+
+#[derive(Debug)]
+pub struct App {
+    handle: Handle,
+    pub browser: Browser,
+    pub media: Vec<Entry>,
+    pub media_state: ListState,
+    pub focus: Focus,
+    pub mode: Mode,
+    pub sort: Sort,
+    pub status: String,
+    scan_paused: bool,
+    quit: bool,
+}
+
+impl App {
+    pub fn new(handle: Handle, dir: PathBuf) -> std::io::Result<Self> {
+        Ok(Self {
+            handle,
+            browser: Browser::new(dir)?,
+            media: Vec::new(),
+            media_state: ListState::default(),
+            focus: Focus::Browser,
+            mode: Mode::Normal,
+            sort: Sort::Path,
+            status: String::from("Tab switches panes, ? shows keys"),
+            scan_paused: false,
+            quit: false,
+        })
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    pub async fn start(&mut self) -> Result<(), SendError> {
+        self.handle.filter(Filter::default()).await?;
+        self.handle.list_media().await?;
+        Ok(())
+    }
+
+    pub async fn exit(&self) {
+        self.handle.exit().await;
+    }
+
+    pub fn selected(&self) -> Option<&Entry> {
+        let idx = self.media_state.selected()?;
+        self.media.get(idx)
+    }
+
+    fn selected_hash(&self) -> Option<Hash> {
+        self.selected().map(|e| e.media.hash().clone())
+    }
+
+    fn position(&self, hash: &Hash) -> Option<usize> {
+        self.media.iter().position(|e| e.media.hash() == hash)
+    }
+
+    // Media without a known length goes last.
+    fn sort_media(&mut self) {
+        let selected = self.selected_hash();
+
+        match self.sort {
+            Sort::Path => self
+                .media
+                .sort_by(|a, b| a.media.path().cmp(b.media.path())),
+            Sort::Duration => self.media.sort_by(|a, b| {
+                let a = &a.media;
+                let b = &b.media;
+
+                a.duration()
+                    .is_none()
+                    .cmp(&b.duration().is_none())
+                    .then_with(|| a.duration().cmp(&b.duration()))
+                    .then_with(|| a.path().cmp(b.path()))
+            }),
+        }
+
+        if let Some(hash) = selected {
+            self.media_state.select(self.position(&hash));
+        }
+    }
+
+    fn select_media(&mut self, offset: isize) {
+        let len = self.media.len() as isize;
+        if len == 0 {
+            self.media_state.select(None);
+            return;
+        }
+
+        let current = self.media_state.selected().unwrap_or(0) as isize;
+        let next = (current + offset).rem_euclid(len);
+        self.media_state.select(Some(next as usize));
+    }
+
+    pub fn event(&mut self, event: Event) {
+        match event {
+            Event::FilterChanged => {
+                self.media.clear();
+                self.media_state.select(None);
+            }
+            Event::FilterMatched(media) => {
+                let entry = Entry {
+                    media,
+                    labels: Labels::default(),
+                };
+
+                match self.position(entry.media.hash()) {
+                    Some(idx) => self.media[idx] = entry,
+                    None => self.media.push(entry),
+                }
+
+                self.sort_media();
+
+                if self.media_state.selected().is_none() {
+                    self.media_state.select(Some(0));
+                }
+            }
+            Event::LabelsChanged(hash, labels) => {
+                if let Some(idx) = self.position(&hash) {
+                    self.media[idx].labels = labels;
+                }
+            }
+            Event::MediaRemoved(hash) => {
+                if let Some(idx) = self.position(&hash) {
+                    self.media.remove(idx);
+                    self.select_media(0);
+                }
+            }
+            Event::MediaScanStarted => {
+                self.status = String::from("Scanning…");
+            }
+            Event::MediaScanProgress {
+                discovered,
+                ingested,
+                errored,
+            } => {
+                self.status = format!(
+                    "Scanning… {}/{} ({} failed)",
+                    ingested + errored,
+                    discovered,
+                    errored
+                );
+            }
+            Event::MediaScanCompleted => {
+                self.status = String::from("Scan complete");
+            }
+            Event::MediaScanCancelled(_) => {
+                self.status = String::from("Scan cancelled");
+            }
+            Event::MediaScanPaused => {
+                self.scan_paused = true;
+                self.status = String::from("Scanning paused");
+            }
+            Event::MediaScanResumed => {
+                self.scan_paused = false;
+                self.status = String::from("Scanning resumed");
+            }
+            Event::Notification(n) => {
+                self.status =
+                    format!("{}: {}", n.path().display(), n.message());
+            }
+            Event::Error(e) => {
+                self.status = format!("Error: {}", e);
+            }
+            _ => (),
+        }
+    }
+
+    pub async fn key(&mut self, key: KeyEvent) -> Result<(), SendError> {
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => self.key_normal(key).await,
+            Mode::Tagging(tag) => self.key_tagging(key, tag).await,
+            Mode::ConfirmDelete => self.key_confirm_delete(key).await,
+        }
+    }
+
+    async fn key_normal(&mut self, key: KeyEvent) -> Result<(), SendError> {
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('p') => {
+                if self.scan_paused {
+                    self.handle.resume_scan().await?;
+                } else {
+                    self.handle.pause_scan().await?;
+                }
+            }
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Focus::Browser => Focus::Media,
+                    Focus::Media => Focus::Browser,
+                };
+            }
+            KeyCode::Char('?') => {
+                self.status = String::from(
+                    "q quit · Tab pane · ↑↓ move · Enter open · ← up · \
+                     i import · p pause scan · f favorite · t tag · \
+                     o sort · r remove · d delete",
+                );
+            }
+            _ => match self.focus {
+                Focus::Browser => self.key_browser(key).await?,
+                Focus::Media => self.key_media(key).await?,
+            },
+        }
+
+        Ok(())
+    }
+
+    async fn key_browser(&mut self, key: KeyEvent) -> Result<(), SendError> {
+        let result = match key.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.browser.next();
+                Ok(())
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.browser.previous();
+                Ok(())
+            }
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                self.browser.open()
+            }
+            KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => {
+                self.browser.parent()
+            }
+            KeyCode::Char('i') => {
+                let dir = self
+                    .browser
+                    .selected()
+                    .unwrap_or_else(|| self.browser.dir())
+                    .to_owned();
+
+                self.status = format!("Importing {}", dir.display());
+                self.handle.scan_media(dir).await?;
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+
+        if let Err(e) = result {
+            self.status = format!("Error: {}", e);
+        }
+
+        Ok(())
+    }
+
+    async fn key_media(&mut self, key: KeyEvent) -> Result<(), SendError> {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => self.select_media(1),
+            KeyCode::Up | KeyCode::Char('k') => self.select_media(-1),
+            KeyCode::Char('f') => {
+                let favorite = match self.selected() {
+                    Some(e) => !e.labels.favorite(),
+                    None => return Ok(()),
+                };
+
+                let hashes = self.selected_hash();
+                self.handle.favorite(hashes, favorite).await?;
+            }
+            KeyCode::Char('t') if self.selected().is_some() => {
+                self.mode = Mode::Tagging(String::new());
+            }
+            KeyCode::Char('o') => {
+                self.sort = match self.sort {
+                    Sort::Path => Sort::Duration,
+                    Sort::Duration => Sort::Path,
+                };
+                self.sort_media();
+            }
+            KeyCode::Char('r') => {
+                let hashes = self.selected_hash();
+                self.handle.remove_media(hashes).await?;
+            }
+            KeyCode::Char('d') if self.selected().is_some() => {
+                self.mode = Mode::ConfirmDelete;
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    async fn key_tagging(
+        &mut self,
+        key: KeyEvent,
+        mut tag: String,
+    ) -> Result<(), SendError> {
+        match key.code {
+            KeyCode::Esc => (),
+            KeyCode::Enter => {
+                let tag = tag.trim();
+                if !tag.is_empty() {
+                    let hashes = self.selected_hash();
+                    self.handle.tag(hashes, tag).await?;
+                }
+            }
+            KeyCode::Backspace => {
+                tag.pop();
+                self.mode = Mode::Tagging(tag);
+            }
+            KeyCode::Char(c) => {
+                tag.push(c);
+                self.mode = Mode::Tagging(tag);
+            }
+            _ => self.mode = Mode::Tagging(tag),
+        }
+
+        Ok(())
+    }
+
+    async fn key_confirm_delete(
+        &mut self,
+        key: KeyEvent,
+    ) -> Result<(), SendError> {
+        if let KeyCode::Char('y') | KeyCode::Char('Y') = key.code {
+            let hashes = self.selected_hash();
+            self.handle.delete_media(hashes).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use tui::widgets::ListState;
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub struct Browser {
+    dir: PathBuf,
+    entries: Vec<PathBuf>,
+    state: ListState,
+}
+
+impl Browser {
+    pub fn new(dir: PathBuf) -> io::Result<Self> {
+        let mut browser = Self {
+            dir,
+            entries: Vec::new(),
+            state: ListState::default(),
+        };
+
+        browser.refresh()?;
+        Ok(browser)
+    }
+
+    // Only folders are listed, since imports always scan a whole folder.
+    fn refresh(&mut self) -> io::Result<()> {
+        let mut entries = Vec::new();
+
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            if !hidden {
+                entries.push(entry.path());
+            }
+        }
+
+        entries.sort();
+
+        self.entries = entries;
+        self.state.select(if self.entries.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+
+        Ok(())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn entries(&self) -> &[PathBuf] {
+        &self.entries
+    }
+
+    pub fn state_mut(&mut self) -> &mut ListState {
+        &mut self.state
+    }
+
+    pub fn selected(&self) -> Option<&Path> {
+        let idx = self.state.selected()?;
+        self.entries.get(idx).map(PathBuf::as_path)
+    }
+
+    pub fn next(&mut self) {
+        let len = self.entries.len();
+        if len == 0 {
+            return;
+        }
+
+        let idx = self.state.selected().map(|i| (i + 1) % len).unwrap_or(0);
+        self.state.select(Some(idx));
+    }
+
+    pub fn previous(&mut self) {
+        let len = self.entries.len();
+        if len == 0 {
+            return;
+        }
+
+        let idx = match self.state.selected() {
+            Some(0) | None => len - 1,
+            Some(i) => i - 1,
+        };
+        self.state.select(Some(idx));
+    }
+
+    pub fn open(&mut self) -> io::Result<()> {
+        let selected = match self.selected() {
+            Some(s) => s.to_owned(),
+            None => return Ok(()),
+        };
+
+        let previous = std::mem::replace(&mut self.dir, selected);
+        if let Err(e) = self.refresh() {
+            self.dir = previous;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    pub fn parent(&mut self) -> io::Result<()> {
+        let parent = match self.dir.parent() {
+            Some(p) => p.to_owned(),
+            None => return Ok(()),
+        };
+
+        let child = std::mem::replace(&mut self.dir, parent);
+        self.refresh()?;
+
+        // Keep the folder we just left selected.
+        if let Some(idx) = self.entries.iter().position(|e| *e == child) {
+            self.state.select(Some(idx));
+        }
+
+        Ok(())
+    }
+}
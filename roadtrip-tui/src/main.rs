@@ -0,0 +1,107 @@
+mod app;
+mod browser;
+mod ui;
+
+use crate::app::App;
+
+use crossterm::event::{Event as TermEvent, EventStream};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen,
+    LeaveAlternateScreen,
+};
+
+use futures::StreamExt;
+
+use tui::backend::CrosstermBackend;
+use tui::Terminal;
+
+use roadtrip::viewer::Viewer;
+
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::{self, Stdout};
+use std::path::PathBuf;
+
+use tracing_subscriber::EnvFilter;
+
+const LOG_VAR: &str = "ROADTRIP_TUI_LOG";
+
+type Term = Terminal<CrosstermBackend<Stdout>>;
+
+// Logging to the terminal would draw over the interface, so logs are only
+// written when `ROADTRIP_TUI_LOG` names a file to append them to.
+fn logging() -> io::Result<()> {
+    let path = match std::env::var_os(LOG_VAR) {
+        Some(p) => p,
+        None => return Ok(()),
+    };
+
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_ansi(false)
+        .with_writer(move || file.try_clone().expect("unable to open log"))
+        .init();
+
+    Ok(())
+}
+
+fn setup() -> Result<Term, Box<dyn Error>> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    Ok(Terminal::new(CrosstermBackend::new(io::stdout()))?)
+}
+
+fn restore(terminal: &mut Term) -> Result<(), Box<dyn Error>> {
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    Ok(terminal.show_cursor()?)
+}
+
+async fn run(terminal: &mut Term, dir: PathBuf) -> Result<(), Box<dyn Error>> {
+    let viewer = Viewer::spawn().await?;
+    let mut app = App::new(viewer.handle(), dir)?;
+    let mut viewer_events = viewer.events();
+    let mut term_events = EventStream::new();
+
+    app.start().await?;
+
+    while !app.should_quit() {
+        terminal.draw(|f| ui::draw(f, &mut app))?;
+
+        tokio::select! {
+            event = viewer_events.next() => match event {
+                Some(event) => app.event(event),
+                None => break,
+            },
+            event = term_events.next() => match event {
+                Some(Ok(TermEvent::Key(key))) => app.key(key).await?,
+                Some(Ok(_)) => (),
+                Some(Err(e)) => return Err(e.into()),
+                None => break,
+            },
+        }
+    }
+
+    app.exit().await;
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    let dir = match std::env::args_os().nth(1) {
+        Some(d) => PathBuf::from(d),
+        None => std::env::current_dir()?,
+    };
+
+    logging()?;
+
+    let mut terminal = setup()?;
+    let result = run(&mut terminal, dir).await;
+    restore(&mut terminal)?;
+
+    result
+}
@@ -0,0 +1,264 @@
+use crate::app::{App, Entry, Focus, Mode, Sort};
+
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
+use tui::symbols::Marker;
+use tui::text::{Span, Spans};
+use tui::widgets::canvas::{Canvas, Line as Segment};
+use tui::widgets::{Block, Borders, List, ListItem, Paragraph, Wrap};
+use tui::Frame;
+
+use std::borrow::Cow;
+
+fn block(title: &str, focused: bool) -> Block<'_> {
+    let style = if focused {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default()
+    };
+
+    Block::default()
+        .borders(Borders::ALL)
+        .border_style(style)
+        .title(title)
+}
+
+fn highlight() -> Style {
+    Style::default().add_modifier(Modifier::REVERSED)
+}
+
+pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(1)])
+        .split(f.size());
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
+        .split(rows[0]);
+
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(columns[1]);
+
+    let details = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(right[1]);
+
+    draw_browser(f, app, columns[0]);
+    draw_media(f, app, right[0]);
+    draw_metadata(f, app.selected(), details[0]);
+    draw_track(f, app.selected(), details[1]);
+    draw_status(f, app, rows[1]);
+}
+
+fn draw_browser<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let title = app.browser.dir().display().to_string();
+
+    let items: Vec<_> = app
+        .browser
+        .entries()
+        .iter()
+        .map(|path| {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            ListItem::new(format!("{}/", name))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block(&title, app.focus == Focus::Browser))
+        .highlight_style(highlight());
+
+    f.render_stateful_widget(list, area, app.browser.state_mut());
+}
+
+fn draw_media<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let items: Vec<_> = app
+        .media
+        .iter()
+        .map(|entry| {
+            let name = entry
+                .media
+                .path()
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            let star = if entry.labels.favorite() {
+                "★ "
+            } else {
+                "  "
+            };
+            ListItem::new(format!("{}{}", star, name))
+        })
+        .collect();
+
+    let title = match app.sort {
+        Sort::Path => format!("Media ({})", app.media.len()),
+        Sort::Duration => format!("Media ({}, by length)", app.media.len()),
+    };
+    let list = List::new(items)
+        .block(block(&title, app.focus == Focus::Media))
+        .highlight_style(highlight());
+
+    f.render_stateful_widget(list, area, &mut app.media_state);
+}
+
+fn field<'a, N>(name: N, value: String) -> Spans<'a>
+where
+    N: Into<Cow<'a, str>>,
+{
+    Spans::from(vec![
+        Span::styled(name, Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(value),
+    ])
+}
+
+fn minutes(secs: i64) -> String {
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+fn draw_metadata<B: Backend>(
+    f: &mut Frame<B>,
+    entry: Option<&Entry>,
+    area: Rect,
+) {
+    let mut lines = Vec::new();
+
+    if let Some(entry) = entry {
+        let media = &entry.media;
+        let geometry = media.geometry();
+
+        lines.push(field("Path: ", media.path().display().to_string()));
+
+        if let Some(camera) = media.camera() {
+            lines.push(field("Camera: ", camera.to_owned()));
+        }
+
+        if let Some((width, height)) = media.resolution() {
+            lines.push(field("Resolution: ", format!("{}×{}", width, height)));
+        }
+
+        let format: Vec<_> =
+            media.codec().into_iter().chain(media.container()).collect();
+        if !format.is_empty() {
+            lines.push(field("Format: ", format.join(", ")));
+        }
+
+        for (key, value) in media.metadata() {
+            lines.push(field(format!("{}: ", key), value.to_string()));
+        }
+
+        lines.push(field("Points: ", geometry.len().to_string()));
+
+        let mut points = geometry.iter();
+        let first = points.next();
+        let last = points.last().or(first);
+
+        if let (Some(first), Some(last)) = (first, last) {
+            let start = first.time().with_timezone(&chrono::Local);
+            lines.push(field("Start: ", start.to_string()));
+
+            // The clip's own length, if known, rather than the track's.
+            let length = match media.duration() {
+                Some(d) => d.as_secs() as i64,
+                None => (last.time() - first.time()).num_seconds(),
+            };
+            lines.push(field("Length: ", minutes(length)));
+        }
+
+        let favorite = if entry.labels.favorite() { "yes" } else { "no" };
+        lines.push(field("Favorite: ", favorite.to_owned()));
+
+        let tags: Vec<_> = entry.labels.tags().collect();
+        if !tags.is_empty() {
+            lines.push(field("Tags: ", tags.join(", ")));
+        }
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(block("Details", false))
+        .wrap(Wrap { trim: false });
+
+    f.render_widget(paragraph, area);
+}
+
+// Bounds around the track with a little padding, so single points and
+// perfectly straight tracks still have some area to draw into.
+fn bounds(entry: &Entry) -> Option<([f64; 2], [f64; 2])> {
+    let mut points = entry.media.geometry().iter();
+    let first = points.next()?;
+
+    let mut x = [first.longitude(); 2];
+    let mut y = [first.latitude(); 2];
+
+    for point in points {
+        x[0] = x[0].min(point.longitude());
+        x[1] = x[1].max(point.longitude());
+        y[0] = y[0].min(point.latitude());
+        y[1] = y[1].max(point.latitude());
+    }
+
+    let pad = ((x[1] - x[0]).max(y[1] - y[0]) * 0.05).max(0.0005);
+
+    Some(([x[0] - pad, x[1] + pad], [y[0] - pad, y[1] + pad]))
+}
+
+fn draw_track<B: Backend>(f: &mut Frame<B>, entry: Option<&Entry>, area: Rect) {
+    let (entry, (x, y)) = match entry.and_then(|e| Some((e, bounds(e)?))) {
+        Some(e) => e,
+        None => {
+            f.render_widget(block("Track", false), area);
+            return;
+        }
+    };
+
+    let points: Vec<_> = entry.media.geometry().iter().collect();
+
+    let canvas = Canvas::default()
+        .block(block("Track", false))
+        .marker(Marker::Braille)
+        .x_bounds(x)
+        .y_bounds(y)
+        .paint(move |ctx| {
+            for pair in points.windows(2) {
+                ctx.draw(&Segment {
+                    x1: pair[0].longitude(),
+                    y1: pair[0].latitude(),
+                    x2: pair[1].longitude(),
+                    y2: pair[1].latitude(),
+                    color: Color::Cyan,
+                });
+            }
+
+            if let Some(first) = points.first() {
+                ctx.print(
+                    first.longitude(),
+                    first.latitude(),
+                    "S",
+                    Color::White,
+                );
+            }
+        });
+
+    f.render_widget(canvas, area);
+}
+
+fn draw_status<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let text = match &app.mode {
+        Mode::Normal => app.status.clone(),
+        Mode::Tagging(tag) => format!("Tag: {}_", tag),
+        Mode::ConfirmDelete => {
+            String::from("Delete the selected file from disk? (y/N)")
+        }
+    };
+
+    f.render_widget(Paragraph::new(text), area);
+}
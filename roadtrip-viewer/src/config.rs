@@ -0,0 +1,94 @@
+use crate::error::{self, Error};
+
+use roadtrip_core::geometry;
+
+use serde::{Deserialize, Serialize};
+
+use snafu::{IntoError, ResultExt};
+
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+
+// Settings shared by every frontend, stored as TOML in the config directory.
+// Missing keys fall back to their defaults, so older files keep loading.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    pub watched: Vec<PathBuf>,
+
+    // Skip files that haven't changed since they were last scanned.
+    pub incremental_scans: bool,
+
+    // Seconds added to every timestamp found while scanning, for cameras with
+    // clocks that are off. Negative if the camera runs fast.
+    pub clock_offset: i64,
+
+    // For cameras set to local time but recording it as UTC: the difference
+    // between local time and UTC, in minutes.
+    pub camera_utc_offset: i32,
+
+    // For loggers that only record a fix every so often: seconds between
+    // points filled in while scanning, or zero to leave tracks as they are.
+    pub densify_interval: u32,
+
+    // Fill in tracks along a curve through the fixes, instead of straight
+    // lines between them.
+    pub smooth_tracks: bool,
+
+    // Areas left out of every track shown or exported. Kept last, since TOML
+    // needs tables to come after plain values.
+    pub privacy_zones: Vec<PrivacyZone>,
+}
+
+// Either a circle, with `radius` in metres, or a polygon with `[lat, lng]`
+// vertices.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PrivacyZone {
+    Circle {
+        latitude: f64,
+        longitude: f64,
+        radius: f64,
+    },
+    Polygon {
+        vertices: Vec<(f64, f64)>,
+    },
+}
+
+impl From<&PrivacyZone> for geometry::PrivacyZone {
+    fn from(zone: &PrivacyZone) -> Self {
+        match zone {
+            PrivacyZone::Circle {
+                latitude,
+                longitude,
+                radius,
+            } => Self::circle(*latitude, *longitude, *radius),
+            PrivacyZone::Polygon { vertices } => {
+                Self::polygon(vertices.clone())
+            }
+        }
+    }
+}
+
+impl Config {
+    pub async fn load(path: &Path) -> Result<Self, Error> {
+        let text = match fs::read_to_string(path).await {
+            Ok(t) => t,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Self::default())
+            }
+            Err(e) => return Err(error::Fs { path }.into_error(e)),
+        };
+
+        toml::from_str(&text).context(error::ConfigParse { path })
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<(), Error> {
+        let text = toml::to_string(self).context(error::ConfigFormat)?;
+
+        fs::write(path, text)
+            .await
+            .with_context(|| error::Fs { path })
+    }
+}
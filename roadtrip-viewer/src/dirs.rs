@@ -4,32 +4,83 @@ use directories::ProjectDirs;
 
 use snafu::ResultExt;
 
-use std::path::Path;
+use std::env;
+use std::path::{Path, PathBuf};
 
 use tokio::fs::create_dir_all;
 
+pub const DATA_DIR_VAR: &str = "ROADTRIP_DATA_DIR";
+pub const CACHE_DIR_VAR: &str = "ROADTRIP_CACHE_DIR";
+pub const CONFIG_DIR_VAR: &str = "ROADTRIP_CONFIG_DIR";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paths {
+    pub data_local_dir: PathBuf,
+    pub cache_dir: PathBuf,
+    pub config_dir: PathBuf,
+}
+
 #[derive(Debug)]
-pub struct Dirs(ProjectDirs);
+pub struct Dirs(Paths);
 
 impl Dirs {
+    // Uses the platform's usual locations, unless overridden by the
+    // `ROADTRIP_DATA_DIR`, `ROADTRIP_CACHE_DIR`, or `ROADTRIP_CONFIG_DIR`
+    // environment variables.
     pub fn new() -> Option<Self> {
-        let inner =
-            ProjectDirs::from("rocks.tabby", "Tabby Rocks", "roadtrip")?;
+        let project =
+            ProjectDirs::from("rocks.tabby", "Tabby Rocks", "roadtrip");
+
+        let data_local_dir = match env::var_os(DATA_DIR_VAR) {
+            Some(d) => PathBuf::from(d),
+            None => project.as_ref()?.data_local_dir().to_owned(),
+        };
+
+        let cache_dir = match env::var_os(CACHE_DIR_VAR) {
+            Some(d) => PathBuf::from(d),
+            None => project.as_ref()?.cache_dir().to_owned(),
+        };
+
+        let config_dir = match env::var_os(CONFIG_DIR_VAR) {
+            Some(d) => PathBuf::from(d),
+            None => project.as_ref()?.config_dir().to_owned(),
+        };
+
+        Some(Self::custom(Paths {
+            data_local_dir,
+            cache_dir,
+            config_dir,
+        }))
+    }
 
-        Some(Dirs(inner))
+    pub fn custom(paths: Paths) -> Self {
+        Dirs(paths)
+    }
+
+    // Where everything goes, without creating any of it.
+    pub fn paths(&self) -> &Paths {
+        &self.0
     }
 
     pub async fn data_local_dir(&self) -> Result<&Path, Error> {
-        let path = self.0.data_local_dir();
-        create_dir_all(&path)
+        let path = &self.0.data_local_dir;
+        create_dir_all(path)
+            .await
+            .with_context(|| error::Fs { path })?;
+        Ok(path)
+    }
+
+    pub async fn config_dir(&self) -> Result<&Path, Error> {
+        let path = &self.0.config_dir;
+        create_dir_all(path)
             .await
             .with_context(|| error::Fs { path })?;
         Ok(path)
     }
 
     pub async fn cache_dir(&self) -> Result<&Path, Error> {
-        let path = self.0.cache_dir();
-        create_dir_all(&path)
+        let path = &self.0.cache_dir;
+        create_dir_all(path)
             .await
             .with_context(|| error::Fs { path })?;
         Ok(path)
@@ -1,3 +1,5 @@
+use roadtrip_core::Hash;
+
 use snafu::Snafu;
 
 use std::fmt;
@@ -31,6 +33,7 @@ impl From<TokioSendError<Command>> for SendError {
     }
 }
 
+#[cfg(feature = "thumbnails")]
 #[derive(Debug, Snafu)]
 #[snafu(visibility = "pub(crate)")]
 pub enum GstError {
@@ -53,10 +56,25 @@ pub enum GstError {
     },
 }
 
+// Stands in for gstreamer errors when thumbnails are disabled. It has no
+// variants, so it can never actually be returned.
+#[cfg(not(feature = "thumbnails"))]
+#[derive(Debug)]
+pub enum GstError {}
+
+#[cfg(not(feature = "thumbnails"))]
+impl fmt::Display for GstError {
+    fn fmt(&self, _: &mut fmt::Formatter) -> fmt::Result {
+        match *self {}
+    }
+}
+
+#[cfg(not(feature = "thumbnails"))]
+impl std::error::Error for GstError {}
+
 #[derive(Debug, Snafu)]
 #[snafu(visibility = "pub(crate)")]
 pub enum Error {
-    Utf8,
     Directories,
     Fs {
         source: std::io::Error,
@@ -73,6 +91,13 @@ pub enum Error {
     CacheInsert {
         source: roadtrip_cache::error::InsertError,
     },
+    CacheRead {
+        source: std::io::Error,
+    },
+    #[snafu(context(false))]
+    ScanIndex {
+        source: roadtrip_ingest::index::Error,
+    },
     Join {
         what: &'static str,
         source: tokio::task::JoinError,
@@ -82,4 +107,22 @@ pub enum Error {
         source: GstError,
     },
     AlreadyRunning,
+    ConfigParse {
+        source: toml::de::Error,
+        path: PathBuf,
+    },
+    ConfigFormat {
+        source: toml::ser::Error,
+    },
+    UnknownMedia {
+        hash: Hash,
+    },
+    // Exporting it would give away somewhere in a privacy zone.
+    InPrivacyZone {
+        path: PathBuf,
+    },
+    #[snafu(context(false))]
+    Cancelled {
+        source: roadtrip_cancel::Cancelled,
+    },
 }
@@ -1,13 +1,17 @@
-use futures::channel::oneshot::{channel, Sender};
-use futures::stream::{Stream, StreamExt as _};
+use futures::future::{abortable, AbortHandle};
+use futures::stream::Stream;
 
-use std::collections::HashMap;
+use roadtrip_cancel::Token;
+
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
 use std::pin::Pin;
-use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
+use tokio::task::JoinHandle;
 
 struct Helper<T> {
     id: usize,
@@ -23,13 +27,11 @@ impl<T> Drop for Helper<T> {
         match tokio::runtime::Handle::try_current() {
             Ok(handle) => {
                 handle.spawn(async move {
-                    let mut locked = inner.lock().await;
-                    locked.senders.remove(&id);
+                    inner.lock().await.finish(id);
                 });
             }
             Err(_) => {
-                let mut guard = self.inner.try_lock().unwrap();
-                guard.senders.remove(&self.id);
+                self.inner.try_lock().unwrap().finish(self.id);
             }
         }
     }
@@ -58,13 +60,36 @@ where
 #[derive(Debug)]
 pub struct Inner {
     count: usize,
-    senders: HashMap<usize, Sender<()>>,
+    tasks: HashMap<usize, AbortHandle>,
+    running: HashSet<usize>,
+    running_count: watch::Sender<usize>,
+}
+
+impl Inner {
+    fn start(&mut self) -> usize {
+        let id = self.count;
+        self.count += 1;
+
+        self.running.insert(id);
+        self.running_count.broadcast(self.running.len()).ok();
+
+        id
+    }
+
+    fn finish(&mut self, id: usize) {
+        self.tasks.remove(&id);
+
+        if self.running.remove(&id) {
+            self.running_count.broadcast(self.running.len()).ok();
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Exit {
-    exited: Arc<AtomicBool>,
+    token: Token,
     inner: Arc<Mutex<Inner>>,
+    running: watch::Receiver<usize>,
 }
 
 impl Default for Exit {
@@ -75,63 +100,115 @@ impl Default for Exit {
 
 impl Exit {
     pub fn new() -> Self {
+        let (sender, running) = watch::channel(0);
+
         Self {
-            exited: Default::default(),
+            token: Token::new(),
             inner: Arc::new(Mutex::new(Inner {
                 count: 0,
-                senders: HashMap::new(),
+                tasks: HashMap::new(),
+                running: HashSet::new(),
+                running_count: sender,
             })),
+            running,
         }
     }
 
     pub async fn exit(&self) {
-        if self.exited.load(Ordering::SeqCst) {
-            return;
+        self.token.cancel();
+    }
+
+    // Cancelled on exit. Hand it to anything doing long-running work, so it
+    // can stop part way through instead of only when its stream is dropped.
+    pub fn token(&self) -> Token {
+        self.token.clone()
+    }
+
+    // Signals every stream like `exit`, then gives tasks registered with
+    // `spawn` until `timeout` to finish before aborting them. A task can be
+    // stuck in a long `.await` between items, where it never sees the signal.
+    //
+    // Returns how many tasks were left that can't be aborted, like registered
+    // handles and streams nobody has dropped. Nothing waits for those.
+    pub async fn exit_within(&self, timeout: Duration) -> usize {
+        self.exit().await;
+
+        if tokio::time::timeout(timeout, self.wait_all()).await.is_ok() {
+            return 0;
         }
 
         let mut inner = self.inner.lock().await;
+        let aborted = inner.tasks.len();
 
-        if self.exited.fetch_or(true, Ordering::SeqCst) {
-            return;
+        for (_, task) in inner.tasks.drain() {
+            task.abort();
         }
 
-        for (_, sender) in inner.senders.drain() {
-            sender.send(()).ok();
+        inner.running.len() - aborted
+    }
+
+    // Resolves once every wrapped stream, spawned task, and registered handle
+    // has finished.
+    pub async fn wait_all(&self) {
+        let mut running = self.running.clone();
+
+        while *running.borrow() != 0 {
+            if running.recv().await.is_none() {
+                return;
+            }
         }
     }
 
-    pub async fn from<S>(
-        &self,
-        stream: S,
-    ) -> impl Stream<Item = S::Item> + Unpin + Send
+    // Tracks a task spawned elsewhere, such as blocking work, so `wait_all`
+    // waits for it too. Unlike tasks from `spawn`, it can't be aborted.
+    pub async fn register(&self, handle: JoinHandle<()>) {
+        let id = self.inner.lock().await.start();
+        let inner = self.inner.clone();
+
+        tokio::spawn(async move {
+            handle.await.ok();
+            inner.lock().await.finish(id);
+        });
+    }
+
+    // Spawns `future` as a task that `exit_within` can abort. The task
+    // resolves to `None` if it was aborted.
+    pub async fn spawn<F>(&self, future: F) -> JoinHandle<Option<F::Output>>
     where
-        S: Stream + Unpin + Send,
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
     {
-        let id;
-        let receiver;
+        let (future, handle) = abortable(future);
 
-        {
+        let id = {
             let mut inner = self.inner.lock().await;
 
-            if self.exited.load(Ordering::SeqCst) {
-                drop(inner);
-                todo!("exit already triggered");
-            }
-
-            let (sender, r) = channel();
-            receiver = r;
+            let id = inner.start();
+            inner.tasks.insert(id, handle);
+            id
+        };
 
-            id = inner.count;
-            inner.count += 1;
+        let inner = self.inner.clone();
 
-            inner.senders.insert(id, sender);
-        };
+        tokio::spawn(async move {
+            let output = future.await.ok();
+            inner.lock().await.finish(id);
+            output
+        })
+    }
 
-        let out = stream.take_until(receiver);
+    pub async fn from<S>(
+        &self,
+        stream: S,
+    ) -> impl Stream<Item = S::Item> + Unpin + Send
+    where
+        S: Stream + Unpin + Send,
+    {
+        let id = self.inner.lock().await.start();
 
         Helper {
             id,
-            stream: out,
+            stream: self.token.stream(stream),
             inner: self.inner.clone(),
         }
     }
@@ -0,0 +1,63 @@
+use crate::error::{self, Error};
+
+use snafu::{IntoError, ResultExt};
+
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+
+#[derive(Debug)]
+pub struct History {
+    path: PathBuf,
+    roots: Vec<PathBuf>,
+}
+
+impl History {
+    const LIMIT: usize = 10;
+
+    pub async fn load(path: PathBuf) -> Result<Self, Error> {
+        let text = match fs::read_to_string(&path).await {
+            Ok(t) => t,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(error::Fs { path }.into_error(e)),
+        };
+
+        let roots = text
+            .lines()
+            .filter(|l| !l.is_empty())
+            .map(PathBuf::from)
+            .take(Self::LIMIT)
+            .collect();
+
+        Ok(Self { path, roots })
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    pub async fn push<P>(&mut self, root: P) -> Result<(), Error>
+    where
+        P: AsRef<Path>,
+    {
+        let root = root.as_ref();
+
+        self.roots.retain(|r| r != root);
+        self.roots.insert(0, root.to_owned());
+        self.roots.truncate(Self::LIMIT);
+
+        let mut text = String::new();
+        for root in &self.roots {
+            text.push_str(&root.to_string_lossy());
+            text.push('\n');
+        }
+
+        fs::write(&self.path, text)
+            .await
+            .with_context(|| error::Fs {
+                path: self.path.clone(),
+            })?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Labels {
+    favorite: bool,
+    tags: BTreeSet<String>,
+}
+
+impl Labels {
+    pub fn is_empty(&self) -> bool {
+        !self.favorite && self.tags.is_empty()
+    }
+
+    pub fn favorite(&self) -> bool {
+        self.favorite
+    }
+
+    pub(crate) fn set_favorite(&mut self, favorite: bool) {
+        self.favorite = favorite;
+    }
+
+    pub fn tags(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().map(String::as_str)
+    }
+
+    pub(crate) fn add_tag<S>(&mut self, tag: S) -> bool
+    where
+        S: Into<String>,
+    {
+        self.tags.insert(tag.into())
+    }
+}
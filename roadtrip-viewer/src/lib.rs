@@ -1,61 +1,143 @@
+pub mod config;
 pub mod dirs;
 pub mod error;
 mod exit;
+mod history;
+pub mod labels;
+mod library;
+pub mod metrics;
+pub mod notification;
+#[cfg(feature = "thumbnails")]
 mod thumbs;
 
+use crate::config::Config;
 use crate::dirs::Dirs;
 use crate::error::{Error, SendError};
 use crate::exit::Exit;
-use crate::thumbs::Thumbs;
+use crate::history::History;
+use crate::labels::Labels;
+use crate::library::Library;
+use crate::metrics::Metrics;
+use crate::notification::{Kind as NotificationKind, Notification};
+#[cfg(feature = "thumbnails")]
+use crate::thumbs::{Thumbs, DEFAULT_SIZE as DEFAULT_THUMBNAIL_SIZE};
+
+use chrono::FixedOffset;
 
 use futures::{pin_mut, Stream, StreamExt};
 
-use roadtrip_core::geometry::Filter;
-use roadtrip_core::media::{Media, Thumbnails};
+use roadtrip_cancel::Token;
+
+use roadtrip_core::geometry::{Filter, Interpolation, PrivacyZone};
+use roadtrip_core::media::{Frame, Media, Thumbnails};
+use roadtrip_core::Hash;
 
-use roadtrip_ingest::ingest::Exiftool;
+use roadtrip_ingest::index::Index;
+use roadtrip_ingest::ingest::{
+    Blackvue, Exiftool, Fit, FreeGps, Photo, Sidecar, Tesla,
+};
+use roadtrip_ingest::trip::{Grouping, Trip};
 use roadtrip_ingest::Scanner;
 
-use snafu::{IntoError, OptionExt, ResultExt};
+use snafu::{IntoError, NoneError, OptionExt, ResultExt};
 
+use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use tokio::fs::{self, OpenOptions};
 use tokio::io::AsyncWriteExt;
 use tokio::sync::mpsc::{channel, Receiver, Sender};
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::{watch, Mutex, RwLock};
 use tokio::task::JoinHandle;
 
+#[cfg(feature = "thumbnails")]
+use tracing::debug_span;
+use tracing::{debug, error, info, info_span, warn};
+
+use tracing_futures::Instrument;
+
+const EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+const MAX_CLOCK_OFFSET: i64 = 10 * 365 * 24 * 60 * 60;
+
+#[cfg(not(feature = "thumbnails"))]
+const DEFAULT_THUMBNAIL_SIZE: u32 = 200;
+
 #[derive(Debug)]
 struct State {
     dirs: Dirs,
+    #[cfg(feature = "thumbnails")]
     thumbs: Thumbs,
     filter: RwLock<Option<Filter>>,
+    library: RwLock<Library>,
+    config: RwLock<Config>,
+    config_path: PathBuf,
+    thumbnail_size: RwLock<u32>,
+    history: Mutex<History>,
+    scan_index: Index,
     scans: Mutex<usize>,
+    scan_tokens: Mutex<HashMap<ScanId, Token>>,
+    pause: Mutex<watch::Sender<bool>>,
+    paused: watch::Receiver<bool>,
     events: Sender<Event>,
     exit: Exit,
+    metrics: Arc<Metrics>,
 }
 
 impl State {
-    pub async fn new(events: Sender<Event>) -> Result<Self, Error> {
-        let dirs = Dirs::new().context(error::Directories)?;
-        let thumbs_dir = dirs.cache_dir().await?.join("thumbnails");
-
-        fs::create_dir_all(&thumbs_dir)
-            .await
-            .with_context(|| error::Fs {
-                path: thumbs_dir.clone(),
+    pub async fn new(events: Sender<Event>, dirs: Dirs) -> Result<Self, Error> {
+        let metrics = Arc::new(Metrics::default());
+        let exit = Exit::new();
+        let (pause, paused) = watch::channel(false);
+
+        #[cfg(feature = "thumbnails")]
+        let thumbs = {
+            let thumbs_dir = dirs.cache_dir().await?.join("thumbnails");
+
+            fs::create_dir_all(&thumbs_dir).await.with_context(|| {
+                error::Fs {
+                    path: thumbs_dir.clone(),
+                }
             })?;
 
+            Thumbs::new(thumbs_dir, metrics.clone(), exit.token()).await?
+        };
+
+        let history_path = dirs.data_local_dir().await?.join("import-history");
+        let history = History::load(history_path).await?;
+
+        let library_path = dirs.data_local_dir().await?.join("library.json");
+        let library = Library::load(library_path).await?;
+
+        let scan_index_path = dirs.data_local_dir().await?.join("scan-index");
+        let scan_index = Index::load(scan_index_path).await?;
+
+        let config_path = dirs.config_dir().await?.join("config.toml");
+        let config = Config::load(&config_path).await?;
+
         let new = Self {
-            thumbs: Thumbs::new(thumbs_dir).await?,
+            history: Mutex::new(history),
+            scan_index,
+            #[cfg(feature = "thumbnails")]
+            thumbs,
             filter: RwLock::new(None),
+            library: RwLock::new(library),
+            config: RwLock::new(config),
+            config_path,
+            thumbnail_size: RwLock::new(DEFAULT_THUMBNAIL_SIZE),
             scans: Mutex::new(0),
-            exit: Exit::new(),
+            scan_tokens: Mutex::new(HashMap::new()),
+            pause: Mutex::new(pause),
+            paused,
+            exit,
             dirs,
             events,
+            metrics,
         };
 
         Ok(new)
@@ -89,31 +171,594 @@ impl State {
 #[derive(Debug)]
 pub enum Event {
     MediaScanStarted,
+    MediaScanProgress {
+        discovered: usize,
+        ingested: usize,
+        errored: usize,
+    },
     MediaScanCompleted,
-    MediaScanError(roadtrip_ingest::error::Error),
+    MediaScanCancelled(ScanId),
+    MediaScanPaused,
+    MediaScanResumed,
+
+    Notification(Notification),
 
     FilterMatched(Media),
     FilterChanged,
 
+    MediaRemoved(Hash),
+    MediaExported(usize),
+    TracksExported(usize),
+    LabelsChanged(Hash, Labels),
+    Duplicates(Hash, Vec<PathBuf>),
+
+    // Sent after listing media, grouping the matched clips into drives.
+    Trips(Vec<Trip>),
+
+    Cameras(Vec<String>),
+    LibrarySize(usize),
+    Config(Config),
+
+    ImportHistory(Vec<PathBuf>),
+
     Thumbnails(Thumbnails),
+    Frame(Frame),
 
     Error(Error),
 }
 
+#[derive(Debug, Clone)]
+pub enum Resolution {
+    Ignore,
+    Keep(PathBuf),
+    DeleteExtras(PathBuf),
+}
+
+// Identifies one call to `Handle::scan_media`, so it can be cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScanId(u64);
+
 #[derive(Debug)]
 enum Command {
-    ScanMedia(PathBuf),
+    ScanMedia(ScanId, PathBuf),
+    CancelScan(ScanId),
+    PauseScan,
+    ResumeScan,
     Filter(Option<Filter>),
+    RemoveMedia(Vec<Hash>),
+    DeleteMedia(Vec<Hash>),
+    ExportMedia(Vec<Hash>, PathBuf),
+    ExportTracks(Vec<Hash>, PathBuf),
+    Favorite(Vec<Hash>, bool),
+    Tag(Vec<Hash>, String),
+    ResolveDuplicates(Hash, Resolution),
+    FrameAt(Media, Duration),
+    ThumbnailSize(u32),
+    ImportHistory,
+    ListMedia,
+    Cameras,
+    Config,
+    SaveConfig(Config),
 }
 
 impl Command {
     async fn run(self, state: &Arc<State>) -> Result<(), Error> {
         match self {
-            Command::ScanMedia(path) => {
-                Self::scan_media(path, state.clone()).await
+            Command::ScanMedia(id, path) => {
+                Self::scan_media(id, path, state.clone()).await
+            }
+            Command::CancelScan(id) => {
+                Self::cancel_scan(id, state).await;
+                Ok(())
+            }
+            Command::PauseScan => {
+                Self::pause_scan(true, state).await;
+                Ok(())
+            }
+            Command::ResumeScan => {
+                Self::pause_scan(false, state).await;
+                Ok(())
             }
             Command::Filter(filter) => Self::filter(filter, state).await,
+            Command::RemoveMedia(hashes) => {
+                Self::remove_media(hashes, state).await.map(|_| ())
+            }
+            Command::DeleteMedia(hashes) => {
+                Self::delete_media(hashes, state).await
+            }
+            Command::ExportMedia(hashes, dest) => {
+                Self::export_media(hashes, dest, state.clone()).await
+            }
+            Command::ExportTracks(hashes, dest) => {
+                Self::export_tracks(hashes, dest, state.clone()).await
+            }
+            Command::Favorite(hashes, favorite) => {
+                Self::label(hashes, state, |l| l.set_favorite(favorite)).await
+            }
+            Command::ResolveDuplicates(hash, resolution) => {
+                Self::resolve_duplicates(hash, resolution, state).await
+            }
+            Command::Tag(hashes, tag) => {
+                Self::label(hashes, state, |l| {
+                    l.add_tag(tag.as_str());
+                })
+                .await
+            }
+            Command::FrameAt(media, offset) => {
+                Self::frame_at(media, offset, state.clone()).await;
+                Ok(())
+            }
+            Command::ThumbnailSize(size) => {
+                Self::thumbnail_size(size, state).await
+            }
+            Command::ImportHistory => Self::import_history(state).await,
+            Command::ListMedia => Self::list_media(state).await,
+            Command::Cameras => Self::cameras(state).await,
+            Command::Config => Self::config(state).await,
+            Command::SaveConfig(config) => {
+                Self::save_config(config, state).await
+            }
+        }
+    }
+
+    async fn report(state: &State, error: Error) {
+        error!(error = %error, "command failed");
+        state
+            .events
+            .clone()
+            .send(Event::Error(error))
+            .await
+            .expect("unable to send error event");
+    }
+
+    async fn remove_media(
+        hashes: Vec<Hash>,
+        state: &Arc<State>,
+    ) -> Result<Vec<Media>, Error> {
+        let mut removed = Vec::with_capacity(hashes.len());
+        let mut unknown = Vec::new();
+
+        let size = {
+            let mut library = state.library.write().await;
+
+            for hash in hashes {
+                match library.remove(&hash) {
+                    Some(media) => removed.push(media),
+                    None => unknown.push(hash),
+                }
+            }
+
+            library.save().await?;
+            library.len()
+        };
+
+        // Otherwise the next scan would skip them, and they'd never come back.
+        if !removed.is_empty() {
+            for media in &removed {
+                state.scan_index.forget(media.hash());
+            }
+
+            state.scan_index.save().await?;
+        }
+
+        for hash in unknown {
+            let err = error::UnknownMedia { hash }.into_error(NoneError);
+            Self::report(state, err).await;
+        }
+
+        let mut events = state.events.clone();
+        for media in &removed {
+            events
+                .send(Event::MediaRemoved(media.hash().clone()))
+                .await
+                .ok();
         }
+
+        events.send(Event::LibrarySize(size)).await.ok();
+
+        Ok(removed)
+    }
+
+    async fn delete_media(
+        hashes: Vec<Hash>,
+        state: &Arc<State>,
+    ) -> Result<(), Error> {
+        let paths: Vec<_> = {
+            let library = state.library.read().await;
+            hashes
+                .into_iter()
+                .map(|h| {
+                    let path = library.get(&h).map(|m| m.path().to_owned());
+                    (h, path)
+                })
+                .collect()
+        };
+
+        // Only what's actually gone from disk comes out of the library, so a
+        // file that couldn't be deleted doesn't disappear from view.
+        let mut deleted = Vec::with_capacity(paths.len());
+
+        for (hash, path) in paths {
+            let path = match path {
+                Some(p) => p,
+                None => {
+                    // Left for `remove_media` to report.
+                    deleted.push(hash);
+                    continue;
+                }
+            };
+
+            match fs::remove_file(&path).await {
+                Ok(()) => deleted.push(hash),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    deleted.push(hash)
+                }
+                Err(e) => {
+                    let err = error::Fs { path }.into_error(e);
+                    Self::report(state, err).await;
+                }
+            }
+        }
+
+        Self::remove_media(deleted, state).await?;
+
+        Ok(())
+    }
+
+    async fn library_media(hashes: &[Hash], state: &State) -> Vec<Media> {
+        let library = state.library.read().await;
+        hashes
+            .iter()
+            .filter_map(|h| library.get(h))
+            .cloned()
+            .collect()
+    }
+
+    async fn notify_export_failed(state: &State, path: &Path, error: &Error) {
+        debug!(path = %path.display(), error = %error, "not exported");
+
+        let notification =
+            Notification::error(NotificationKind::Export, path, error);
+        state
+            .events
+            .clone()
+            .send(Event::Notification(notification))
+            .await
+            .ok();
+    }
+
+    // Copies of the original files, which keep whatever location they
+    // recorded. Anything that's been inside a privacy zone stays behind.
+    async fn export_media(
+        hashes: Vec<Hash>,
+        dest: PathBuf,
+        state: Arc<State>,
+    ) -> Result<(), Error> {
+        let media = Self::library_media(&hashes, &state).await;
+        let zones = Self::privacy_zones(&state).await;
+
+        let span = info_span!(
+            "export",
+            dest = %dest.display(),
+            count = media.len()
+        );
+
+        let exit = state.exit.clone();
+        let task = async move {
+            let mut exported = 0;
+
+            for media in media {
+                let src = media.path();
+                let file_name = match src.file_name() {
+                    Some(f) => f,
+                    None => continue,
+                };
+
+                if media.geometry().enters(&zones) {
+                    let path = src.to_owned();
+                    let err =
+                        error::InPrivacyZone { path }.into_error(NoneError);
+                    Self::notify_export_failed(&state, src, &err).await;
+                    continue;
+                }
+
+                let target = dest.join(file_name);
+
+                let result =
+                    Self::copy_new(src, &target).await.with_context(|| {
+                        error::Fs {
+                            path: target.clone(),
+                        }
+                    });
+
+                match result {
+                    Ok(_) => exported += 1,
+                    Err(e) => Self::notify_export_failed(&state, src, &e).await,
+                }
+            }
+
+            info!(exported, "export finished");
+            let event = Event::MediaExported(exported);
+            state.events.clone().send(event).await.ok();
+        };
+
+        exit.spawn(task.instrument(span)).await;
+
+        Ok(())
+    }
+
+    // Each track as GPX, named after its file, with privacy zones cut out.
+    async fn export_tracks(
+        hashes: Vec<Hash>,
+        dest: PathBuf,
+        state: Arc<State>,
+    ) -> Result<(), Error> {
+        let media = Self::library_media(&hashes, &state).await;
+        let zones = Self::privacy_zones(&state).await;
+
+        let span = info_span!(
+            "export_tracks",
+            dest = %dest.display(),
+            count = media.len()
+        );
+
+        let exit = state.exit.clone();
+        let task = async move {
+            let mut exported = 0;
+
+            for media in media {
+                let src = media.path();
+                let mut file_name = match src.file_stem() {
+                    Some(f) => f.to_owned(),
+                    None => continue,
+                };
+                file_name.push(".gpx");
+
+                let geometry = match media.redacted(&zones) {
+                    Some(m) => m.geometry().clone(),
+                    None => {
+                        let path = src.to_owned();
+                        let err =
+                            error::InPrivacyZone { path }.into_error(NoneError);
+                        Self::notify_export_failed(&state, src, &err).await;
+                        continue;
+                    }
+                };
+
+                let mut gpx = Vec::new();
+                geometry.write_gpx(&mut gpx).expect("writing to a vec");
+
+                let target = dest.join(file_name);
+
+                let result =
+                    Self::write_new(&target, &gpx).await.with_context(|| {
+                        error::Fs {
+                            path: target.clone(),
+                        }
+                    });
+
+                match result {
+                    Ok(()) => exported += 1,
+                    Err(e) => Self::notify_export_failed(&state, src, &e).await,
+                }
+            }
+
+            info!(exported, "track export finished");
+            let event = Event::TracksExported(exported);
+            state.events.clone().send(event).await.ok();
+        };
+
+        exit.spawn(task.instrument(span)).await;
+
+        Ok(())
+    }
+
+    // Like `copy_new`, but from memory.
+    async fn write_new(target: &Path, contents: &[u8]) -> std::io::Result<()> {
+        let mut dst = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(target)
+            .await?;
+
+        let result = Self::write_into(contents, &mut dst).await;
+
+        if result.is_err() {
+            drop(dst);
+            fs::remove_file(target).await.ok();
+        }
+
+        result
+    }
+
+    // Fails if `target` already exists. Creating it is the check, so a file
+    // that shows up after looking can't be overwritten.
+    async fn copy_new(src: &Path, target: &Path) -> std::io::Result<u64> {
+        let mut dst = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(target)
+            .await?;
+
+        let result = Self::copy_into(src, &mut dst).await;
+
+        // Whatever made it across is only part of the file.
+        if result.is_err() {
+            drop(dst);
+            fs::remove_file(target).await.ok();
+        }
+
+        result
+    }
+
+    async fn copy_into(src: &Path, dst: &mut fs::File) -> std::io::Result<u64> {
+        let mut src = fs::File::open(src).await?;
+        let copied = tokio::io::copy(&mut src, dst).await?;
+        dst.sync_all().await?;
+        Ok(copied)
+    }
+
+    async fn write_into(
+        contents: &[u8],
+        dst: &mut fs::File,
+    ) -> std::io::Result<()> {
+        dst.write_all(contents).await?;
+        dst.sync_all().await
+    }
+
+    async fn label<F>(
+        hashes: Vec<Hash>,
+        state: &Arc<State>,
+        mut f: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(&mut Labels),
+    {
+        let mut changed = Vec::with_capacity(hashes.len());
+
+        {
+            let mut library = state.library.write().await;
+
+            for hash in hashes {
+                let labels = match library.labels_mut(&hash) {
+                    Some(l) => l,
+                    None => continue,
+                };
+
+                f(labels);
+                changed.push((hash, labels.clone()));
+            }
+
+            library.save().await?;
+        }
+
+        let mut events = state.events.clone();
+        for (hash, labels) in changed {
+            events.send(Event::LabelsChanged(hash, labels)).await.ok();
+        }
+
+        Ok(())
+    }
+
+    async fn privacy_zones(state: &Arc<State>) -> Vec<PrivacyZone> {
+        let config = state.config.read().await;
+        config.privacy_zones.iter().map(Into::into).collect()
+    }
+
+    // The part of `media` that can be shown, if it matches `filter`. Zones are
+    // cut out first, so nothing inside one can make a track match.
+    fn visible(
+        media: Media,
+        filter: &Filter,
+        zones: &[PrivacyZone],
+    ) -> Option<Media> {
+        let media = if zones.is_empty() {
+            media
+        } else {
+            media.redacted(zones)?
+        };
+
+        if media.matches(filter) {
+            Some(media)
+        } else {
+            None
+        }
+    }
+
+    async fn send_matched(media: Media, filter: &Filter, state: &Arc<State>) {
+        let zones = Self::privacy_zones(state).await;
+        if let Some(media) = Self::visible(media, filter, &zones) {
+            Self::send_visible(media, state).await;
+        }
+    }
+
+    async fn send_visible(media: Media, state: &Arc<State>) {
+        let mut events = state.events.clone();
+        let hash = media.hash().clone();
+
+        let (labels, duplicates) = {
+            let library = state.library.read().await;
+            (library.labels(&hash), library.duplicates(&hash))
+        };
+
+        Self::thumbnail(media.clone(), state.clone()).await;
+        events.send(Event::FilterMatched(media)).await.ok();
+
+        if !labels.is_empty() {
+            let event = Event::LabelsChanged(hash.clone(), labels);
+            events.send(event).await.ok();
+        }
+
+        if let Some(paths) = duplicates {
+            events.send(Event::Duplicates(hash, paths)).await.ok();
+        }
+    }
+
+    async fn resolve_duplicates(
+        hash: Hash,
+        resolution: Resolution,
+        state: &Arc<State>,
+    ) -> Result<(), Error> {
+        let (keep, delete) = match resolution {
+            Resolution::Ignore => {
+                let mut library = state.library.write().await;
+                if library.ignore_duplicates(&hash) {
+                    library.save().await?;
+                }
+
+                drop(library);
+
+                let event = Event::Duplicates(hash, Vec::new());
+                state.events.clone().send(event).await.ok();
+                return Ok(());
+            }
+            Resolution::Keep(keep) => (keep, false),
+            Resolution::DeleteExtras(keep) => (keep, true),
+        };
+
+        let (dropped, media) = {
+            let mut library = state.library.write().await;
+            let old_path = library.get(&hash).map(|m| m.path().to_owned());
+
+            let dropped = library
+                .keep_copy(&hash, &keep)
+                .context(error::UnknownMedia { hash: hash.clone() })?;
+
+            library.save().await?;
+
+            // The primary path changed, so the frontend needs the new media.
+            let media = match old_path {
+                Some(old) if old != keep => library.get(&hash).cloned(),
+                _ => None,
+            };
+
+            (dropped, media)
+        };
+
+        let event = Event::Duplicates(hash, vec![keep]);
+        state.events.clone().send(event).await.ok();
+
+        if let Some(media) = media {
+            if let Some(filter) = &*state.filter.read().await {
+                Self::send_matched(media, filter, state).await;
+            }
+        }
+
+        if !delete {
+            return Ok(());
+        }
+
+        for path in dropped {
+            let result = fs::remove_file(&path)
+                .await
+                .with_context(|| error::Fs { path: path.clone() });
+
+            if let Err(e) = result {
+                Self::report(state, e).await;
+            }
+        }
+
+        Ok(())
     }
 
     async fn filter(
@@ -132,6 +777,112 @@ impl Command {
         Ok(())
     }
 
+    async fn thumbnail_size(
+        size: u32,
+        state: &Arc<State>,
+    ) -> Result<(), Error> {
+        let size = size.max(1);
+
+        {
+            let mut current = state.thumbnail_size.write().await;
+            if *current == size {
+                return Ok(());
+            }
+            *current = size;
+        }
+
+        let filter = state.filter.read().await;
+        let filter = match &*filter {
+            Some(f) => f,
+            None => return Ok(()),
+        };
+
+        let zones = Self::privacy_zones(state).await;
+        let library = state.library.read().await;
+        for media in library.values() {
+            if let Some(media) = Self::visible(media.clone(), filter, &zones) {
+                Self::thumbnail(media, state.clone()).await;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn list_media(state: &Arc<State>) -> Result<(), Error> {
+        let filter = state.filter.read().await;
+        let filter = match &*filter {
+            Some(f) => f,
+            None => return Ok(()),
+        };
+
+        let zones = Self::privacy_zones(state).await;
+
+        let (size, matched) = {
+            let library = state.library.read().await;
+            let matched: Vec<_> = library
+                .values()
+                .filter_map(|m| Self::visible(m.clone(), filter, &zones))
+                .collect();
+
+            (library.len(), matched)
+        };
+
+        let trips = Grouping::default().group(&matched);
+
+        for media in matched {
+            Self::send_visible(media, state).await;
+        }
+
+        state.events.clone().send(Event::Trips(trips)).await.ok();
+
+        state
+            .events
+            .clone()
+            .send(Event::LibrarySize(size))
+            .await
+            .ok();
+
+        Ok(())
+    }
+
+    async fn cameras(state: &Arc<State>) -> Result<(), Error> {
+        let cameras = state.library.read().await.cameras();
+        state
+            .events
+            .clone()
+            .send(Event::Cameras(cameras))
+            .await
+            .ok();
+        Ok(())
+    }
+
+    async fn config(state: &Arc<State>) -> Result<(), Error> {
+        let config = state.config.read().await.clone();
+        state.events.clone().send(Event::Config(config)).await.ok();
+        Ok(())
+    }
+
+    async fn save_config(
+        config: Config,
+        state: &Arc<State>,
+    ) -> Result<(), Error> {
+        let mut current = state.config.write().await;
+        config.save(&state.config_path).await?;
+        *current = config;
+        Ok(())
+    }
+
+    async fn import_history(state: &Arc<State>) -> Result<(), Error> {
+        let roots = state.history.lock().await.roots().to_vec();
+        state
+            .events
+            .clone()
+            .send(Event::ImportHistory(roots))
+            .await
+            .ok();
+        Ok(())
+    }
+
     async fn write_exiftool_format(state: &State) -> Result<PathBuf, Error> {
         let path = state.dirs.data_local_dir().await?.join("gpx.fmt");
 
@@ -144,7 +895,7 @@ impl Command {
         let mut file = match opened {
             Ok(f) => f,
             Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
-                return Ok(path)
+                return Self::refresh_exiftool_format(path).await;
             }
             Err(e) => return Err(error::Fs { path }.into_error(e)),
         };
@@ -156,38 +907,267 @@ impl Command {
         Ok(path)
     }
 
-    fn thumbnail(media: Media, state: Arc<State>) {
-        tokio::task::spawn_blocking(move || {
+    // Format files written by older versions may be missing fields.
+    async fn refresh_exiftool_format(path: PathBuf) -> Result<PathBuf, Error> {
+        let current = fs::read(&path)
+            .await
+            .with_context(|| error::Fs { path: path.clone() })?;
+
+        if current != Exiftool::FORMAT {
+            fs::write(&path, Exiftool::FORMAT)
+                .await
+                .with_context(|| error::Fs { path: path.clone() })?;
+        }
+
+        Ok(path)
+    }
+
+    #[cfg(feature = "thumbnails")]
+    async fn thumbnail(media: Media, state: Arc<State>) {
+        let span = debug_span!(
+            "thumbnail",
+            path = %media.path().display(),
+            hash = %media.hash()
+        );
+
+        state.metrics.thumbnail_queued();
+
+        let exit = state.exit.clone();
+        let handle = tokio::task::spawn_blocking(move || {
             let rt = tokio::runtime::Handle::current();
-            rt.block_on(async move {
+            let task = async move {
                 let mut events = state.events.clone();
 
-                match state.thumbs.thumbnails(&media).await {
+                let size = *state.thumbnail_size.read().await;
+                let start = Instant::now();
+
+                match state.thumbs.thumbnails(&media, size).await {
                     Ok(t) => {
+                        debug!(
+                            size,
+                            elapsed_ms = start.elapsed().as_millis() as u64,
+                            "thumbnails ready"
+                        );
                         events.send(Event::Thumbnails(t)).await.ok();
                     }
+                    Err(Error::Cancelled { .. }) => {
+                        debug!("thumbnails cancelled");
+                    }
                     Err(err) => {
+                        error!(error = %err, "thumbnails failed");
+                        let notification = Notification::error(
+                            NotificationKind::Thumbnail,
+                            media.path(),
+                            &err,
+                        );
+
                         events
-                            .send(Event::Error(err))
+                            .send(Event::Notification(notification))
                             .await
                             .expect("unable to send error event");
                     }
                 }
-            });
+
+                state.metrics.thumbnail_done();
+            };
+
+            rt.block_on(task.instrument(span));
         });
+
+        exit.register(handle).await;
+    }
+
+    #[cfg(feature = "thumbnails")]
+    async fn frame_at(media: Media, offset: Duration, state: Arc<State>) {
+        let span = debug_span!(
+            "frame_at",
+            path = %media.path().display(),
+            offset_ms = offset.as_millis() as u64
+        );
+
+        let exit = state.exit.clone();
+        let handle = tokio::task::spawn_blocking(move || {
+            let rt = tokio::runtime::Handle::current();
+            let task = async move {
+                let mut events = state.events.clone();
+                let start = Instant::now();
+
+                match state.thumbs.frame_at(&media, offset).await {
+                    Ok(f) => {
+                        debug!(
+                            elapsed_ms = start.elapsed().as_millis() as u64,
+                            "frame ready"
+                        );
+                        events.send(Event::Frame(f)).await.ok();
+                    }
+                    Err(Error::Cancelled { .. }) => debug!("frame cancelled"),
+                    Err(err) => {
+                        error!(error = %err, "frame failed");
+                        let notification = Notification::error(
+                            NotificationKind::Thumbnail,
+                            media.path(),
+                            &err,
+                        );
+
+                        events
+                            .send(Event::Notification(notification))
+                            .await
+                            .expect("unable to send error event");
+                    }
+                }
+            };
+
+            rt.block_on(task.instrument(span));
+        });
+
+        exit.register(handle).await;
+    }
+
+    // Without the `thumbnails` feature there's nothing to render images
+    // with, so requests for them are dropped.
+    #[cfg(not(feature = "thumbnails"))]
+    async fn thumbnail(_media: Media, _state: Arc<State>) {}
+
+    #[cfg(not(feature = "thumbnails"))]
+    async fn frame_at(_media: Media, _offset: Duration, _state: Arc<State>) {}
+
+    async fn cancel_scan(id: ScanId, state: &State) {
+        match state.scan_tokens.lock().await.get(&id) {
+            Some(token) => token.cancel(),
+            None => debug!(id = id.0, "no scan to cancel"),
+        }
     }
 
-    async fn scan_media(path: PathBuf, state: Arc<State>) -> Result<(), Error> {
+    // Applies to every scan, including ones started while paused.
+    async fn pause_scan(paused: bool, state: &State) {
+        if *state.paused.borrow() == paused {
+            return;
+        }
+
+        if state.pause.lock().await.broadcast(paused).is_err() {
+            return;
+        }
+
+        let event = if paused {
+            info!("scanning paused");
+            Event::MediaScanPaused
+        } else {
+            info!("scanning resumed");
+            Event::MediaScanResumed
+        };
+
+        state.events.clone().send(event).await.ok();
+    }
+
+    async fn scan_media(
+        id: ScanId,
+        path: PathBuf,
+        state: Arc<State>,
+    ) -> Result<(), Error> {
+        // Only for offering it again later, so not worth giving up the scan.
+        let pushed = state.history.lock().await.push(&path).await;
+        if let Err(e) = pushed {
+            Self::report(&state, e).await;
+        }
+
         let mut scanner = Scanner::default();
 
         let format_path = Self::write_exiftool_format(&state).await?;
         let ingester = Exiftool::new(format_path);
 
+        let span = info_span!("scan_media", id = id.0, path = %path.display());
+
+        let token = state.exit.token().child();
+        state.scan_tokens.lock().await.insert(id, token.clone());
+
+        // Native ingesters go first, since they don't need to spawn exiftool.
+        scanner.add_ingester(Sidecar::new());
+        scanner.add_ingester(Tesla::new());
+        scanner.add_ingester(FreeGps::new());
+        scanner.add_ingester(Blackvue::new());
+        scanner.add_ingester(Fit::new());
+        scanner.add_ingester(Photo::new());
         scanner.add_ingester(ingester);
         scanner.insert_path(path);
+        scanner.cancel_on(token.clone());
+
+        {
+            let config = state.config.read().await;
+
+            if config.incremental_scans {
+                scanner.use_index(state.scan_index.clone());
+            }
+
+            // Anything more than a few years off is a typo.
+            match config.clock_offset {
+                0 => (),
+                secs if secs.abs() <= MAX_CLOCK_OFFSET => {
+                    scanner.clock_offset(chrono::Duration::seconds(secs));
+                }
+                secs => warn!(secs, "clock-offset out of range"),
+            }
+
+            if config.camera_utc_offset != 0 {
+                let secs = config.camera_utc_offset.saturating_mul(60);
+                match FixedOffset::east_opt(secs) {
+                    Some(zone) => scanner.recorded_in(zone),
+                    None => warn!(
+                        minutes = config.camera_utc_offset,
+                        "camera-utc-offset out of range"
+                    ),
+                }
+            }
+
+            if config.densify_interval != 0 {
+                let interval = i64::from(config.densify_interval);
+                let interpolation = if config.smooth_tracks {
+                    Interpolation::Spline
+                } else {
+                    Interpolation::Linear
+                };
+
+                scanner.densify(
+                    chrono::Duration::seconds(interval),
+                    interpolation,
+                );
+            }
+        }
+
+        let ingested = Arc::new(AtomicUsize::new(0));
+        let errored = Arc::new(AtomicUsize::new(0));
+
+        // Stops the progress reports once the scan is over.
+        let done = token.child();
+
+        let progress = {
+            let discovered = scanner.discovered();
+            let ingested = ingested.clone();
+            let errored = errored.clone();
+            let done = done.clone();
+            let mut events = state.events.clone();
+
+            async move {
+                let first = tokio::time::Instant::now() + PROGRESS_INTERVAL;
+                let ticks = tokio::time::interval_at(first, PROGRESS_INTERVAL);
+                let ticks = done.stream(ticks);
+                pin_mut!(ticks);
+
+                while ticks.next().await.is_some() {
+                    let event = Event::MediaScanProgress {
+                        discovered: discovered.get(),
+                        ingested: ingested.load(Ordering::Relaxed),
+                        errored: errored.load(Ordering::Relaxed),
+                    };
+
+                    events.send(event).await.ok();
+                }
+            }
+        };
 
-        tokio::spawn(async move {
+        let exit = state.exit.clone();
+        let task = async move {
             let mut events = state.events.clone();
+            let start = Instant::now();
 
             state.start_scan().await;
 
@@ -196,26 +1176,115 @@ impl Command {
 
             let mut exit = state.exit.from(stream).await;
 
-            while let Some(media_res) = exit.next().await {
+            // Ends early if the scan is cancelled, so a paused scan can
+            // still be stopped.
+            let paused = token.stream(state.paused.clone());
+            pin_mut!(paused);
+
+            loop {
+                // Nothing more is pulled from the scanner while paused, so
+                // it picks up where it left off.
+                while *state.paused.borrow() {
+                    if paused.next().await.is_none() {
+                        break;
+                    }
+                }
+
+                // Time from asking for the next file until it's ingested.
+                let requested = Instant::now();
+
+                let media_res = match exit.next().await {
+                    Some(r) => r,
+                    None => break,
+                };
+
                 let media = match media_res {
-                    Ok(m) => m,
+                    Ok(m) => {
+                        state.metrics.file_scanned(requested.elapsed());
+                        ingested.fetch_add(1, Ordering::Relaxed);
+                        m
+                    }
                     Err(e) => {
-                        events.send(Event::MediaScanError(e)).await.ok();
+                        state.metrics.scan_error();
+                        errored.fetch_add(1, Ordering::Relaxed);
+
+                        let notification = Notification::error(
+                            NotificationKind::Scan,
+                            e.path(),
+                            &e,
+                        );
+
+                        events
+                            .send(Event::Notification(notification))
+                            .await
+                            .ok();
                         continue;
                     }
                 };
 
+                let duplicate = {
+                    let mut library = state.library.write().await;
+
+                    match library.get(media.hash()) {
+                        Some(known) if known.path() != media.path() => {
+                            Some(library.add_copy(&media))
+                        }
+                        _ => {
+                            library.insert(media.clone());
+                            None
+                        }
+                    }
+                };
+
+                // Copies of known media only update the duplicate group; the
+                // original entry stays in place.
+                if let Some(paths) = duplicate {
+                    if let Some(paths) = paths {
+                        let hash = media.hash().clone();
+                        let event = Event::Duplicates(hash, paths);
+                        events.send(event).await.ok();
+                    }
+
+                    continue;
+                }
+
                 let opt_filter = state.filter.read().await;
                 if let Some(filter) = &*opt_filter {
-                    if media.geometry().matches(filter) {
-                        Self::thumbnail(media.clone(), state.clone());
-                        events.send(Event::FilterMatched(media)).await.ok();
-                    }
+                    Self::send_matched(media, filter, &state).await;
                 }
             }
 
+            done.cancel();
+            state.scan_tokens.lock().await.remove(&id);
+
+            // Media found before the scan was cancelled is still kept. The
+            // index is only saved along with the library, so later scans
+            // don't skip anything the library is missing.
+            if let Err(e) = state.library.read().await.save().await {
+                error!(error = %e, "unable to save library");
+                events.send(Event::Error(e)).await.ok();
+            } else if let Err(e) = state.scan_index.save().await {
+                error!(error = %e, "unable to save scan index");
+                events.send(Event::Error(e.into())).await.ok();
+            }
+
+            info!(
+                elapsed_ms = start.elapsed().as_millis() as u64,
+                ingested = ingested.load(Ordering::Relaxed),
+                errored = errored.load(Ordering::Relaxed),
+                "scan finished"
+            );
+
+            if token.is_cancelled() && !state.exit.token().is_cancelled() {
+                info!("scan cancelled");
+                events.send(Event::MediaScanCancelled(id)).await.ok();
+            }
+
             state.stop_scan().await;
-        });
+        };
+
+        exit.spawn(progress).await;
+        exit.spawn(task.instrument(span)).await;
 
         Ok(())
     }
@@ -231,10 +1300,12 @@ pub struct Viewer {
 impl Viewer {
     async fn run(recv: Receiver<Command>, state: Arc<State>) {
         let mut events = state.events.clone();
+
         let mut cmds = state.exit.from(recv).await;
 
         while let Some(cmd) = cmds.next().await {
             if let Err(e) = cmd.run(&state).await {
+                error!(error = %e, "command failed");
                 events
                     .send(Event::Error(e))
                     .await
@@ -242,14 +1313,29 @@ impl Viewer {
             }
         }
 
-        state.exit.exit().await;
+        // The command stream is tracked too, so it has to go before waiting.
+        drop(cmds);
+
+        debug!("shutting down");
+
+        let outstanding = state.exit.exit_within(EXIT_TIMEOUT).await;
+        if outstanding > 0 {
+            warn!(outstanding, "shut down with tasks still running");
+        }
     }
 
     pub async fn spawn() -> Result<Self, Error> {
+        let dirs = Dirs::new().context(error::Directories)?;
+        Self::spawn_with(dirs).await
+    }
+
+    pub async fn spawn_with(dirs: Dirs) -> Result<Self, Error> {
         let (event_sender, event_receiver) = channel(5);
         let (cmd_sender, cmd_receiver) = channel(5);
-        let state = Arc::new(State::new(event_sender).await?);
+        let state = Arc::new(State::new(event_sender, dirs).await?);
         let exit = state.exit.clone();
+        #[cfg(feature = "metrics")]
+        let metrics = state.metrics.clone();
 
         let join = tokio::spawn(Self::run(cmd_receiver, state));
 
@@ -257,6 +1343,9 @@ impl Viewer {
             handle: Handle {
                 sender: cmd_sender,
                 exit,
+                next_scan: Arc::new(AtomicU64::new(0)),
+                #[cfg(feature = "metrics")]
+                metrics,
             },
             events: event_receiver,
             join,
@@ -291,6 +1380,9 @@ impl DerefMut for Viewer {
 pub struct Handle {
     sender: Sender<Command>,
     exit: Exit,
+    next_scan: Arc<AtomicU64>,
+    #[cfg(feature = "metrics")]
+    metrics: Arc<Metrics>,
 }
 
 impl Handle {
@@ -298,11 +1390,38 @@ impl Handle {
         self.exit.exit().await;
     }
 
-    pub async fn scan_media<P>(&mut self, path: P) -> Result<(), SendError>
+    // Read directly instead of going through the command queue, so a busy
+    // viewer can still be inspected.
+    #[cfg(feature = "metrics")]
+    pub fn metrics_snapshot(&self) -> metrics::Snapshot {
+        self.metrics.snapshot()
+    }
+
+    pub async fn scan_media<P>(&mut self, path: P) -> Result<ScanId, SendError>
     where
         P: Into<PathBuf>,
     {
-        self.sender.send(Command::ScanMedia(path.into())).await?;
+        let id = ScanId(self.next_scan.fetch_add(1, Ordering::Relaxed));
+        self.sender
+            .send(Command::ScanMedia(id, path.into()))
+            .await?;
+        Ok(id)
+    }
+
+    pub async fn pause_scan(&mut self) -> Result<(), SendError> {
+        self.sender.send(Command::PauseScan).await?;
+        Ok(())
+    }
+
+    pub async fn resume_scan(&mut self) -> Result<(), SendError> {
+        self.sender.send(Command::ResumeScan).await?;
+        Ok(())
+    }
+
+    // Stops one scan started by `scan_media`, keeping whatever it found so
+    // far. Does nothing if the scan has already finished.
+    pub async fn cancel_scan(&mut self, id: ScanId) -> Result<(), SendError> {
+        self.sender.send(Command::CancelScan(id)).await?;
         Ok(())
     }
 
@@ -314,6 +1433,137 @@ impl Handle {
         Ok(())
     }
 
+    pub async fn remove_media<I>(&mut self, hashes: I) -> Result<(), SendError>
+    where
+        I: IntoIterator<Item = Hash>,
+    {
+        let hashes = hashes.into_iter().collect();
+        self.sender.send(Command::RemoveMedia(hashes)).await?;
+        Ok(())
+    }
+
+    pub async fn delete_media<I>(&mut self, hashes: I) -> Result<(), SendError>
+    where
+        I: IntoIterator<Item = Hash>,
+    {
+        let hashes = hashes.into_iter().collect();
+        self.sender.send(Command::DeleteMedia(hashes)).await?;
+        Ok(())
+    }
+
+    pub async fn export_media<I, P>(
+        &mut self,
+        hashes: I,
+        dest: P,
+    ) -> Result<(), SendError>
+    where
+        I: IntoIterator<Item = Hash>,
+        P: Into<PathBuf>,
+    {
+        let hashes = hashes.into_iter().collect();
+        let cmd = Command::ExportMedia(hashes, dest.into());
+        self.sender.send(cmd).await?;
+        Ok(())
+    }
+
+    // Writes a GPX file into `dest` for each track, leaving out privacy
+    // zones.
+    pub async fn export_tracks<I, P>(
+        &mut self,
+        hashes: I,
+        dest: P,
+    ) -> Result<(), SendError>
+    where
+        I: IntoIterator<Item = Hash>,
+        P: Into<PathBuf>,
+    {
+        let hashes = hashes.into_iter().collect();
+        let cmd = Command::ExportTracks(hashes, dest.into());
+        self.sender.send(cmd).await?;
+        Ok(())
+    }
+
+    pub async fn favorite<I>(
+        &mut self,
+        hashes: I,
+        favorite: bool,
+    ) -> Result<(), SendError>
+    where
+        I: IntoIterator<Item = Hash>,
+    {
+        let hashes = hashes.into_iter().collect();
+        self.sender
+            .send(Command::Favorite(hashes, favorite))
+            .await?;
+        Ok(())
+    }
+
+    pub async fn tag<I, S>(
+        &mut self,
+        hashes: I,
+        tag: S,
+    ) -> Result<(), SendError>
+    where
+        I: IntoIterator<Item = Hash>,
+        S: Into<String>,
+    {
+        let hashes = hashes.into_iter().collect();
+        self.sender.send(Command::Tag(hashes, tag.into())).await?;
+        Ok(())
+    }
+
+    pub async fn import_history(&mut self) -> Result<(), SendError> {
+        self.sender.send(Command::ImportHistory).await?;
+        Ok(())
+    }
+
+    pub async fn list_media(&mut self) -> Result<(), SendError> {
+        self.sender.send(Command::ListMedia).await?;
+        Ok(())
+    }
+
+    pub async fn cameras(&mut self) -> Result<(), SendError> {
+        self.sender.send(Command::Cameras).await?;
+        Ok(())
+    }
+
+    pub async fn config(&mut self) -> Result<(), SendError> {
+        self.sender.send(Command::Config).await?;
+        Ok(())
+    }
+
+    pub async fn save_config(
+        &mut self,
+        config: Config,
+    ) -> Result<(), SendError> {
+        self.sender.send(Command::SaveConfig(config)).await?;
+        Ok(())
+    }
+
+    pub async fn thumbnail_size(&mut self, size: u32) -> Result<(), SendError> {
+        self.sender.send(Command::ThumbnailSize(size)).await?;
+        Ok(())
+    }
+
+    pub async fn frame_at(
+        &mut self,
+        media: Media,
+        offset: Duration,
+    ) -> Result<(), SendError> {
+        self.sender.send(Command::FrameAt(media, offset)).await?;
+        Ok(())
+    }
+
+    pub async fn resolve_duplicates(
+        &mut self,
+        hash: Hash,
+        resolution: Resolution,
+    ) -> Result<(), SendError> {
+        let cmd = Command::ResolveDuplicates(hash, resolution);
+        self.sender.send(cmd).await?;
+        Ok(())
+    }
+
     pub fn into_sync(self) -> SyncHandle {
         SyncHandle {
             handle: self,
@@ -333,6 +1583,11 @@ impl SyncHandle {
         self.runtime.block_on(self.handle.exit())
     }
 
+    #[cfg(feature = "metrics")]
+    pub fn metrics_snapshot(&self) -> metrics::Snapshot {
+        self.handle.metrics_snapshot()
+    }
+
     pub fn filter<F>(&mut self, filter: F) -> Result<(), SendError>
     where
         F: Into<Option<Filter>>,
@@ -340,10 +1595,123 @@ impl SyncHandle {
         self.runtime.block_on(self.handle.filter(filter))
     }
 
-    pub fn scan_media<P>(&mut self, path: P) -> Result<(), SendError>
+    pub fn scan_media<P>(&mut self, path: P) -> Result<ScanId, SendError>
     where
         P: Into<PathBuf>,
     {
         self.runtime.block_on(self.handle.scan_media(path))
     }
+
+    pub fn cancel_scan(&mut self, id: ScanId) -> Result<(), SendError> {
+        self.runtime.block_on(self.handle.cancel_scan(id))
+    }
+
+    pub fn pause_scan(&mut self) -> Result<(), SendError> {
+        self.runtime.block_on(self.handle.pause_scan())
+    }
+
+    pub fn resume_scan(&mut self) -> Result<(), SendError> {
+        self.runtime.block_on(self.handle.resume_scan())
+    }
+
+    pub fn remove_media<I>(&mut self, hashes: I) -> Result<(), SendError>
+    where
+        I: IntoIterator<Item = Hash>,
+    {
+        self.runtime.block_on(self.handle.remove_media(hashes))
+    }
+
+    pub fn delete_media<I>(&mut self, hashes: I) -> Result<(), SendError>
+    where
+        I: IntoIterator<Item = Hash>,
+    {
+        self.runtime.block_on(self.handle.delete_media(hashes))
+    }
+
+    pub fn export_media<I, P>(
+        &mut self,
+        hashes: I,
+        dest: P,
+    ) -> Result<(), SendError>
+    where
+        I: IntoIterator<Item = Hash>,
+        P: Into<PathBuf>,
+    {
+        self.runtime
+            .block_on(self.handle.export_media(hashes, dest))
+    }
+
+    pub fn export_tracks<I, P>(
+        &mut self,
+        hashes: I,
+        dest: P,
+    ) -> Result<(), SendError>
+    where
+        I: IntoIterator<Item = Hash>,
+        P: Into<PathBuf>,
+    {
+        self.runtime
+            .block_on(self.handle.export_tracks(hashes, dest))
+    }
+
+    pub fn favorite<I>(
+        &mut self,
+        hashes: I,
+        favorite: bool,
+    ) -> Result<(), SendError>
+    where
+        I: IntoIterator<Item = Hash>,
+    {
+        self.runtime
+            .block_on(self.handle.favorite(hashes, favorite))
+    }
+
+    pub fn tag<I, S>(&mut self, hashes: I, tag: S) -> Result<(), SendError>
+    where
+        I: IntoIterator<Item = Hash>,
+        S: Into<String>,
+    {
+        self.runtime.block_on(self.handle.tag(hashes, tag))
+    }
+
+    pub fn resolve_duplicates(
+        &mut self,
+        hash: Hash,
+        resolution: Resolution,
+    ) -> Result<(), SendError> {
+        self.runtime
+            .block_on(self.handle.resolve_duplicates(hash, resolution))
+    }
+
+    pub fn import_history(&mut self) -> Result<(), SendError> {
+        self.runtime.block_on(self.handle.import_history())
+    }
+
+    pub fn list_media(&mut self) -> Result<(), SendError> {
+        self.runtime.block_on(self.handle.list_media())
+    }
+
+    pub fn cameras(&mut self) -> Result<(), SendError> {
+        self.runtime.block_on(self.handle.cameras())
+    }
+
+    pub fn config(&mut self) -> Result<(), SendError> {
+        self.runtime.block_on(self.handle.config())
+    }
+
+    pub fn save_config(&mut self, config: Config) -> Result<(), SendError> {
+        self.runtime.block_on(self.handle.save_config(config))
+    }
+
+    pub fn thumbnail_size(&mut self, size: u32) -> Result<(), SendError> {
+        self.runtime.block_on(self.handle.thumbnail_size(size))
+    }
+
+    pub fn frame_at(
+        &mut self,
+        media: Media,
+        offset: Duration,
+    ) -> Result<(), SendError> {
+        self.runtime.block_on(self.handle.frame_at(media, offset))
+    }
 }
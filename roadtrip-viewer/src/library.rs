@@ -0,0 +1,272 @@
+use crate::error::{self, Error};
+use crate::labels::Labels;
+
+use roadtrip_core::media::Media;
+use roadtrip_core::Hash;
+
+use serde::{Deserialize, Serialize};
+
+use snafu::{IntoError, ResultExt};
+
+use std::borrow::Cow;
+use std::collections::hash_map::{HashMap, Values};
+use std::collections::{BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+use tokio::fs::{self, File};
+use tokio::io::AsyncWriteExt;
+
+use tracing::warn;
+
+// One media and everything the library knows about it. Each is written as
+// JSON on a line of its own, so paths, tags and metadata keys can hold any
+// text (even newlines) and still come back the same.
+#[derive(Debug, Serialize, Deserialize)]
+struct Record<'a> {
+    media: Cow<'a, Media>,
+    #[serde(default)]
+    labels: Cow<'a, Labels>,
+    #[serde(default)]
+    copies: Cow<'a, [PathBuf]>,
+    #[serde(default)]
+    ignored: bool,
+}
+
+#[derive(Debug)]
+pub struct Library {
+    path: PathBuf,
+    media: HashMap<Hash, Media>,
+    labels: HashMap<Hash, Labels>,
+    copies: HashMap<Hash, Vec<PathBuf>>,
+    ignored: HashSet<Hash>,
+}
+
+impl Library {
+    pub async fn load(path: PathBuf) -> Result<Self, Error> {
+        let text = match fs::read_to_string(&path).await {
+            Ok(t) => t,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(error::Fs { path }.into_error(e)),
+        };
+
+        let mut library = Self {
+            path,
+            media: HashMap::new(),
+            labels: HashMap::new(),
+            copies: HashMap::new(),
+            ignored: HashSet::new(),
+        };
+
+        for (number, line) in text.lines().enumerate() {
+            // Losing one record is better than losing the whole library.
+            let record: Record = match serde_json::from_str(line) {
+                Ok(r) => r,
+                Err(e) => {
+                    warn!(
+                        path = %library.path.display(),
+                        line = number + 1,
+                        error = %e,
+                        "skipping malformed library record"
+                    );
+                    continue;
+                }
+            };
+
+            let media = record.media.into_owned();
+            let hash = media.hash().clone();
+
+            if !record.labels.is_empty() {
+                library
+                    .labels
+                    .insert(hash.clone(), record.labels.into_owned());
+            }
+
+            if !record.copies.is_empty() {
+                library
+                    .copies
+                    .insert(hash.clone(), record.copies.into_owned());
+            }
+
+            if record.ignored {
+                library.ignored.insert(hash.clone());
+            }
+
+            library.media.insert(hash, media);
+        }
+
+        Ok(library)
+    }
+
+    fn format(&self) -> String {
+        let mut text = String::new();
+        let no_labels = Labels::default();
+
+        for (hash, media) in &self.media {
+            let record = Record {
+                media: Cow::Borrowed(media),
+                labels: Cow::Borrowed(
+                    self.labels.get(hash).unwrap_or(&no_labels),
+                ),
+                copies: Cow::Borrowed(
+                    self.copies.get(hash).map(Vec::as_slice).unwrap_or(&[]),
+                ),
+                ignored: self.ignored.contains(hash),
+            };
+
+            // Only paths that aren't UTF-8 can't be written.
+            match serde_json::to_string(&record) {
+                Ok(line) => {
+                    text.push_str(&line);
+                    text.push('\n');
+                }
+                Err(e) => warn!(
+                    path = %media.path().display(),
+                    error = %e,
+                    "unable to store media in library"
+                ),
+            }
+        }
+
+        text
+    }
+
+    // Written off to the side and renamed over the old library once it's
+    // complete, so a crash part way through never leaves half of one.
+    pub async fn save(&self) -> Result<(), Error> {
+        let text = self.format();
+
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+        let temp = PathBuf::from(temp);
+
+        let fs_error = || error::Fs { path: temp.clone() };
+
+        let mut file = File::create(&temp).await.with_context(fs_error)?;
+        file.write_all(text.as_bytes())
+            .await
+            .with_context(fs_error)?;
+        file.sync_all().await.with_context(fs_error)?;
+        drop(file);
+
+        fs::rename(&temp, &self.path)
+            .await
+            .with_context(|| error::Fs {
+                path: self.path.clone(),
+            })
+    }
+
+    pub fn insert(&mut self, media: Media) -> Option<Media> {
+        self.media.insert(media.hash().clone(), media)
+    }
+
+    pub fn remove(&mut self, hash: &Hash) -> Option<Media> {
+        self.labels.remove(hash);
+        self.copies.remove(hash);
+        self.ignored.remove(hash);
+        self.media.remove(hash)
+    }
+
+    pub fn get(&self, hash: &Hash) -> Option<&Media> {
+        self.media.get(hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.media.len()
+    }
+
+    pub fn values(&self) -> Values<'_, Hash, Media> {
+        self.media.values()
+    }
+
+    pub fn cameras(&self) -> Vec<String> {
+        let cameras: BTreeSet<_> =
+            self.media.values().filter_map(Media::camera).collect();
+
+        cameras.into_iter().map(String::from).collect()
+    }
+
+    pub fn labels(&self, hash: &Hash) -> Labels {
+        self.labels.get(hash).cloned().unwrap_or_default()
+    }
+
+    pub fn labels_mut(&mut self, hash: &Hash) -> Option<&mut Labels> {
+        if !self.media.contains_key(hash) {
+            return None;
+        }
+
+        Some(self.labels.entry(hash.clone()).or_default())
+    }
+
+    // Records `media` as another copy of content already in the library.
+    // Returns every known path if this creates a duplicate that hasn't been
+    // ignored.
+    pub fn add_copy(&mut self, media: &Media) -> Option<Vec<PathBuf>> {
+        let hash = media.hash();
+        let primary = self.media.get(hash)?;
+
+        if primary.path() == media.path() {
+            return None;
+        }
+
+        let copies = self.copies.entry(hash.clone()).or_default();
+        if copies.iter().any(|c| c == media.path()) {
+            return None;
+        }
+
+        copies.push(media.path().to_owned());
+        self.duplicates(hash)
+    }
+
+    pub fn paths(&self, hash: &Hash) -> Vec<PathBuf> {
+        let primary = self.media.get(hash).map(|m| m.path().to_owned());
+        let copies = self.copies.get(hash).into_iter().flatten().cloned();
+
+        primary.into_iter().chain(copies).collect()
+    }
+
+    pub fn duplicates(&self, hash: &Hash) -> Option<Vec<PathBuf>> {
+        if self.ignored.contains(hash) {
+            return None;
+        }
+
+        match self.copies.get(hash) {
+            Some(c) if !c.is_empty() => Some(self.paths(hash)),
+            _ => None,
+        }
+    }
+
+    pub fn ignore_duplicates(&mut self, hash: &Hash) -> bool {
+        self.media.contains_key(hash) && self.ignored.insert(hash.clone())
+    }
+
+    // Forgets every path for `hash` except `keep`, returning the dropped paths.
+    pub fn keep_copy(
+        &mut self,
+        hash: &Hash,
+        keep: &Path,
+    ) -> Option<Vec<PathBuf>> {
+        let paths = self.paths(hash);
+        if !paths.iter().any(|p| p == keep) {
+            return None;
+        }
+
+        let media = self.media.get_mut(hash)?;
+        if media.path() != keep {
+            *media = Media::builder()
+                .path(keep.to_owned())
+                .geometry(media.geometry().clone())
+                .hash(hash.clone())
+                .camera(media.camera().map(String::from))
+                .duration(media.duration())
+                .resolution(media.resolution())
+                .container(media.container().map(String::from))
+                .codec(media.codec().map(String::from))
+                .metadata(media.metadata().clone())
+                .build();
+        }
+
+        self.copies.remove(hash);
+
+        Some(paths.into_iter().filter(|p| p != keep).collect())
+    }
+}
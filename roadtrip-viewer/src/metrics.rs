@@ -0,0 +1,256 @@
+// Counters are always updated through `Metrics`, but without the `metrics`
+// feature it's an empty type and recording compiles down to nothing.
+
+#[cfg(feature = "metrics")]
+use std::fmt::Write;
+#[cfg(feature = "metrics")]
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+#[cfg(feature = "prometheus")]
+use crate::Handle;
+
+#[cfg(feature = "prometheus")]
+use std::net::SocketAddr;
+
+#[cfg(feature = "prometheus")]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "prometheus")]
+use tokio::net::TcpListener;
+
+#[cfg(feature = "metrics")]
+const LATENCY_BOUNDS_MS: [u64; 11] =
+    [5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+
+#[cfg(feature = "metrics")]
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: [AtomicU64; 11],
+    count: AtomicU64,
+    sum_ms: AtomicU64,
+}
+
+#[cfg(feature = "metrics")]
+impl Histogram {
+    fn observe(&self, value: Duration) {
+        let ms = value.as_millis() as u64;
+
+        if let Some(idx) = LATENCY_BOUNDS_MS.iter().position(|b| ms <= *b) {
+            self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        }
+
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_ms.fetch_add(ms, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> HistogramSnapshot {
+        let mut total = 0;
+        let buckets = LATENCY_BOUNDS_MS
+            .iter()
+            .zip(self.buckets.iter())
+            .map(|(bound, count)| {
+                total += count.load(Ordering::Relaxed);
+                (Duration::from_millis(*bound), total)
+            })
+            .collect();
+
+        HistogramSnapshot {
+            buckets,
+            count: self.count.load(Ordering::Relaxed),
+            sum: Duration::from_millis(self.sum_ms.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    files_scanned: AtomicU64,
+    scan_errors: AtomicU64,
+    ingest_latency: Histogram,
+    thumbnail_queue: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+#[cfg(feature = "metrics")]
+impl Metrics {
+    pub fn file_scanned(&self, latency: Duration) {
+        self.files_scanned.fetch_add(1, Ordering::Relaxed);
+        self.ingest_latency.observe(latency);
+    }
+
+    pub fn scan_error(&self) {
+        self.scan_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg_attr(not(feature = "thumbnails"), allow(dead_code))]
+    pub fn thumbnail_queued(&self) {
+        self.thumbnail_queue.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg_attr(not(feature = "thumbnails"), allow(dead_code))]
+    pub fn thumbnail_done(&self) {
+        self.thumbnail_queue.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    #[cfg_attr(not(feature = "thumbnails"), allow(dead_code))]
+    pub fn cache_hit(&self) {
+        self.cache_hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg_attr(not(feature = "thumbnails"), allow(dead_code))]
+    pub fn cache_miss(&self) {
+        self.cache_misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            files_scanned: self.files_scanned.load(Ordering::Relaxed),
+            scan_errors: self.scan_errors.load(Ordering::Relaxed),
+            ingest_latency: self.ingest_latency.snapshot(),
+            thumbnail_queue_depth: self.thumbnail_queue.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+#[derive(Debug, Default)]
+pub(crate) struct Metrics;
+
+#[cfg(not(feature = "metrics"))]
+impl Metrics {
+    pub fn file_scanned(&self, _latency: Duration) {}
+    pub fn scan_error(&self) {}
+    #[cfg_attr(not(feature = "thumbnails"), allow(dead_code))]
+    pub fn thumbnail_queued(&self) {}
+    #[cfg_attr(not(feature = "thumbnails"), allow(dead_code))]
+    pub fn thumbnail_done(&self) {}
+    #[cfg_attr(not(feature = "thumbnails"), allow(dead_code))]
+    pub fn cache_hit(&self) {}
+    #[cfg_attr(not(feature = "thumbnails"), allow(dead_code))]
+    pub fn cache_miss(&self) {}
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistogramSnapshot {
+    // Upper bound of each bucket, and the number of observations at or below
+    // it (cumulative, like Prometheus).
+    pub buckets: Vec<(Duration, u64)>,
+    pub count: u64,
+    pub sum: Duration,
+}
+
+#[cfg(feature = "metrics")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub files_scanned: u64,
+    pub scan_errors: u64,
+    pub ingest_latency: HistogramSnapshot,
+    pub thumbnail_queue_depth: u64,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+}
+
+#[cfg(feature = "metrics")]
+impl Snapshot {
+    pub fn cache_hit_rate(&self) -> Option<f64> {
+        let total = self.cache_hits + self.cache_misses;
+        if total == 0 {
+            None
+        } else {
+            Some(self.cache_hits as f64 / total as f64)
+        }
+    }
+
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+
+        let counters = [
+            (
+                "files_scanned_total",
+                "Files ingested by scans.",
+                self.files_scanned,
+            ),
+            (
+                "scan_errors_total",
+                "Files that failed to scan.",
+                self.scan_errors,
+            ),
+            (
+                "thumbnail_cache_hits_total",
+                "Thumbnail cache hits.",
+                self.cache_hits,
+            ),
+            (
+                "thumbnail_cache_misses_total",
+                "Thumbnail cache misses.",
+                self.cache_misses,
+            ),
+        ];
+
+        for (name, help, value) in counters.iter() {
+            writeln!(out, "# HELP roadtrip_{} {}", name, help).unwrap();
+            writeln!(out, "# TYPE roadtrip_{} counter", name).unwrap();
+            writeln!(out, "roadtrip_{} {}", name, value).unwrap();
+        }
+
+        let name = "roadtrip_thumbnail_queue_depth";
+        writeln!(out, "# HELP {} Thumbnails waiting to be made.", name)
+            .unwrap();
+        writeln!(out, "# TYPE {} gauge", name).unwrap();
+        writeln!(out, "{} {}", name, self.thumbnail_queue_depth).unwrap();
+
+        let name = "roadtrip_ingest_latency_seconds";
+        let latency = &self.ingest_latency;
+        writeln!(out, "# HELP {} Time taken to ingest a file.", name).unwrap();
+        writeln!(out, "# TYPE {} histogram", name).unwrap();
+
+        for (bound, count) in &latency.buckets {
+            let le = bound.as_secs_f64();
+            writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, le, count)
+                .unwrap();
+        }
+
+        writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, latency.count)
+            .unwrap();
+        writeln!(out, "{}_sum {}", name, latency.sum.as_secs_f64()).unwrap();
+        writeln!(out, "{}_count {}", name, latency.count).unwrap();
+
+        out
+    }
+}
+
+// Answers every connection with the current metrics in Prometheus' text
+// format, regardless of the request. Meant for scraping a long-running
+// viewer, not as a general purpose HTTP server.
+#[cfg(feature = "prometheus")]
+pub async fn serve_prometheus(
+    addr: SocketAddr,
+    handle: Handle,
+) -> std::io::Result<()> {
+    let mut listener = TcpListener::bind(addr).await?;
+
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let body = handle.metrics_snapshot().to_prometheus();
+
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            stream.read(&mut buf).await.ok();
+
+            let response = format!(
+                "HTTP/1.0 200 OK\r\n\
+                 Content-Type: text/plain; version=0.0.4\r\n\
+                 Content-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+
+            stream.write_all(response.as_bytes()).await.ok();
+        });
+    }
+}
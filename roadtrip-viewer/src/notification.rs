@@ -0,0 +1,57 @@
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Kind {
+    Scan,
+    Thumbnail,
+    Export,
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    kind: Kind,
+    time: SystemTime,
+    path: PathBuf,
+    message: String,
+}
+
+impl Notification {
+    pub(crate) fn error<P, E>(kind: Kind, path: P, error: &E) -> Self
+    where
+        P: Into<PathBuf>,
+        E: std::error::Error,
+    {
+        let mut message = error.to_string();
+        let mut source = error.source();
+
+        while let Some(s) = source {
+            message.push_str(": ");
+            message.push_str(&s.to_string());
+            source = s.source();
+        }
+
+        Self {
+            kind,
+            time: SystemTime::now(),
+            path: path.into(),
+            message,
+        }
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    pub fn time(&self) -> SystemTime {
+        self.time
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
@@ -1,4 +1,5 @@
 use crate::error::{self, Error, GstError};
+use crate::metrics::Metrics;
 
 use futures::{Stream, StreamExt};
 
@@ -14,33 +15,61 @@ use gstreamer::{
 use roadtrip_cache::error::{Error as CacheError, InsertError};
 use roadtrip_cache::{Cache, Entry, OccupiedEntry, VacantEntry};
 
-use roadtrip_core::media::{Media, Thumbnails};
+use roadtrip_cancel::Token;
 
-use snafu::{IntoError, OptionExt};
+use roadtrip_core::media::{Frame, Media, Thumbnail, Thumbnails};
+
+use snafu::{IntoError, OptionExt, ResultExt};
 
-use std::fs::File as StdFile;
 use std::path::PathBuf;
-use std::sync::Once;
+use std::sync::{Arc, Once};
+use std::time::Duration;
+
+use tokio::io::AsyncReadExt;
 
-use tokio::io::AsyncWriteExt;
+use tracing::debug;
 
 const CACHE_SIZE: u64 = 10 * 1024 * 1024;
 
+// Enough for the thumbnails on screen, so redrawing the icon view doesn't go
+// back to disk for them.
+const HOT_SIZE: u64 = 2 * 1024 * 1024;
+
+// Long enough for a previous instance to finish shutting down.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(3);
+const LOCK_POLL: Duration = Duration::from_millis(100);
+
+pub const DEFAULT_SIZE: u32 = 200;
+pub const FRAME_SIZE: u32 = 160;
+
 #[derive(Debug)]
 pub struct Thumbs {
     cache: Cache,
+    metrics: Arc<Metrics>,
+    cancel: Token,
 }
 
 impl Thumbs {
     const INIT: Once = Once::new();
 
-    pub async fn new(root: PathBuf) -> Result<Self, Error> {
+    pub async fn new(
+        root: PathBuf,
+        metrics: Arc<Metrics>,
+        cancel: Token,
+    ) -> Result<Self, Error> {
         Self::INIT.call_once(|| {
             // TODO: Probably shouldn't call this on behalf of the application.
             gstreamer::init().unwrap();
         });
 
-        let cache = match Cache::new(root, CACHE_SIZE).await {
+        let result = Cache::builder(root, CACHE_SIZE)
+            .hot_tier(HOT_SIZE)
+            .lock_timeout(LOCK_TIMEOUT, LOCK_POLL)
+            .cancel_on(cancel.clone())
+            .open()
+            .await;
+
+        let cache = match result {
             Ok(c) => c,
             Err(CacheError::AlreadyLocked) => {
                 return Err(Error::AlreadyRunning)
@@ -48,18 +77,87 @@ impl Thumbs {
             Err(e) => return Err(error::Cache {}.into_error(e)),
         };
 
-        Ok(Self { cache })
+        Ok(Self {
+            cache,
+            metrics,
+            cancel,
+        })
     }
 
-    pub async fn thumbnails(&self, media: &Media) -> Result<Thumbnails, Error> {
-        let key = media.hash().to_hex();
+    pub async fn thumbnails(
+        &self,
+        media: &Media,
+        size: u32,
+    ) -> Result<Thumbnails, Error> {
+        let key = format!("{}.{}", media.hash(), size);
 
         match self.cache.entry(&key).await? {
-            Entry::Vacant(v) => self.vacant(media, v).await,
-            Entry::Occupied(o) => self.occupied(media, o).await,
+            Entry::Vacant(v) => {
+                debug!(key = %key, "thumbnail cache miss");
+                self.metrics.cache_miss();
+                self.vacant(media, size, v).await
+            }
+            Entry::Occupied(o) => {
+                debug!(key = %key, "thumbnail cache hit");
+                self.metrics.cache_hit();
+                self.occupied(media, o).await
+            }
         }
     }
 
+    pub async fn frame_at(
+        &self,
+        media: &Media,
+        offset: Duration,
+    ) -> Result<Frame, Error> {
+        let uri = Self::uri(media)?;
+
+        let pipeline = Self::pipeline(&uri)?;
+        let mut stream =
+            self.cancel.stream(Self::filter_stream(pipeline.clone()));
+
+        let result = async {
+            pipeline
+                .set_state(gst::State::Paused)
+                .map_err(GstError::from)?;
+
+            Self::until_state(&mut stream, gst::State::Paused).await?;
+
+            let point = ClockTime::from_nseconds(offset.as_nanos() as u64);
+            Self::seek(&pipeline, &mut stream, point).await?;
+
+            Ok::<_, Error>(Self::capture(&pipeline, FRAME_SIZE)?)
+        }
+        .await;
+
+        let bytes = Self::finish(&pipeline, &self.cancel, result)?;
+
+        Ok(Frame::new(media.hash().clone(), offset, bytes))
+    }
+
+    // Stops the pipeline whether or not the work succeeded. The bus stream
+    // ends on cancellation, which looks like any other gstreamer failure, so
+    // the token is checked to report it properly.
+    fn finish<T>(
+        pipeline: &gst::Element,
+        cancel: &Token,
+        result: Result<T, Error>,
+    ) -> Result<T, Error> {
+        pipeline
+            .set_state(gst::State::Null)
+            .map_err(GstError::from)?;
+
+        cancel.check()?;
+
+        result
+    }
+
+    // Escaped, so paths with spaces or `#` in them work, and without needing
+    // them to be UTF-8.
+    fn uri(media: &Media) -> Result<String, GstError> {
+        Ok(glib::filename_to_uri(media.path(), None)?.to_string())
+    }
+
     fn pipeline(uri: &str) -> Result<gst::Element, GstError> {
         let afakesink = ElementFactory::make("fakesink", None)?;
         let vfakesink = ElementFactory::make("fakesink", None)?;
@@ -101,10 +199,14 @@ impl Thumbs {
         Ok(pipeline)
     }
 
-    fn capture(pipeline: &gst::Element) -> Result<Vec<u8>, GstError> {
+    fn capture(
+        pipeline: &gst::Element,
+        size: u32,
+    ) -> Result<Vec<u8>, GstError> {
+        let size = size as i32;
         let caps = gst::Caps::new_simple(
             "image/jpeg",
-            &[("width", &200), ("height", &200)],
+            &[("width", &size), ("height", &size)],
         );
         let sample = pipeline
             .emit("convert-sample", &[&caps])?
@@ -124,17 +226,10 @@ impl Thumbs {
         idx: usize,
         data: Vec<u8>,
         entry: &VacantEntry<'_>,
-    ) -> Result<StdFile, InsertError> {
+    ) -> Result<Thumbnail, InsertError> {
         let name = format!("{:0>2}.jpg", idx);
-        let file = entry
-            .insert_with(&name, move |mut f| async move {
-                f.write_all(&data).await?;
-                Ok(())
-            })
-            .await?
-            .into_std()
-            .await;
-        Ok(file)
+        entry.insert_bytes(&name, &data).await?;
+        Ok(Thumbnail::new(idx, name, data))
     }
 
     fn when(pipeline: &gst::Element) -> Vec<ClockTime> {
@@ -181,16 +276,13 @@ impl Thumbs {
     async fn vacant<'a>(
         &'a self,
         media: &'a Media,
+        size: u32,
         entry: VacantEntry<'a>,
     ) -> Result<Thumbnails, Error> {
-        let path = media.path().to_str().context(error::Utf8)?;
-
-        let uri = format!("file://{}", path);
-        let files = Self::thumbnail(&uri, &entry).await?;
+        let uri = Self::uri(media)?;
+        let files = Self::thumbnail(&uri, size, &entry, &self.cancel).await?;
 
-        let thumbnails =
-            Thumbnails::new(media.hash().clone(), files.into_iter());
-        Ok(thumbnails)
+        Ok(Thumbnails::new(media.hash().clone(), files))
     }
 
     async fn occupied<'a>(
@@ -198,15 +290,20 @@ impl Thumbs {
         media: &'a Media,
         entry: OccupiedEntry<'a>,
     ) -> Result<Thumbnails, Error> {
-        // TODO: Sort the files
-        let files = entry.into_files();
-        let mut std_files = Vec::new();
-        for file in files {
-            std_files.push(file.into_file().into_std().await);
+        let mut files = Vec::new();
+
+        for mut file in entry.into_files() {
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)
+                .await
+                .context(error::CacheRead)?;
+
+            // Anything not named like `save` names thumbnails isn't one.
+            let name = file.name().to_owned();
+            files.extend(Thumbnail::from_name(name, data));
         }
-        let thumbnails =
-            Thumbnails::new(media.hash().clone(), std_files.into_iter());
-        Ok(thumbnails)
+
+        Ok(Thumbnails::new(media.hash().clone(), files))
     }
 
     async fn until_state<S>(
@@ -260,6 +357,25 @@ impl Thumbs {
         Err(GstError::Missing)
     }
 
+    async fn seek<S>(
+        pipeline: &gst::Element,
+        stream: &mut S,
+        point: ClockTime,
+    ) -> Result<(), GstError>
+    where
+        S: Stream<Item = gst::Message> + Unpin + Send,
+    {
+        pipeline.seek_simple(
+            gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT,
+            point,
+        )?;
+
+        Self::until_async_done(stream).await?;
+        Self::until_state(stream, gst::State::Paused).await?;
+
+        Ok(())
+    }
+
     fn filter_stream(
         pipeline: gst::Element,
     ) -> impl Stream<Item = gst::Message> + Unpin + Send {
@@ -279,49 +395,56 @@ impl Thumbs {
 
     async fn thumbnail(
         uri: &str,
+        size: u32,
         entry: &VacantEntry<'_>,
-    ) -> Result<Vec<StdFile>, Error> {
-        // TODO: Handle exit events
-
+        cancel: &Token,
+    ) -> Result<Vec<Thumbnail>, Error> {
         let pipeline = Self::pipeline(uri)?;
-        let mut stream = Self::filter_stream(pipeline.clone());
+        let mut stream = cancel.stream(Self::filter_stream(pipeline.clone()));
 
+        let result =
+            Self::capture_all(&pipeline, &mut stream, size, entry, cancel)
+                .await;
+
+        Self::finish(&pipeline, cancel, result)
+    }
+
+    async fn capture_all<S>(
+        pipeline: &gst::Element,
+        stream: &mut S,
+        size: u32,
+        entry: &VacantEntry<'_>,
+        cancel: &Token,
+    ) -> Result<Vec<Thumbnail>, Error>
+    where
+        S: Stream<Item = gst::Message> + Unpin + Send,
+    {
         pipeline
             .set_state(gst::State::Paused)
             .map_err(GstError::from)?;
 
-        Self::until_state(&mut stream, gst::State::Paused).await?;
+        Self::until_state(stream, gst::State::Paused).await?;
 
-        let points = Self::when(&pipeline);
+        let points = Self::when(pipeline);
         let mut files = Vec::with_capacity(std::cmp::max(points.len(), 1));
 
         if points.is_empty() {
-            let bytes = Self::capture(&pipeline)?;
+            let bytes = Self::capture(pipeline, size)?;
             let file = Self::save(0, bytes, entry).await?;
             files.push(file);
         } else {
             for (idx, point) in points.into_iter().enumerate() {
-                pipeline
-                    .seek_simple(
-                        gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT,
-                        point,
-                    )
-                    .map_err(GstError::from)?;
+                cancel.check()?;
 
-                Self::until_async_done(&mut stream).await?;
-                Self::until_state(&mut stream, gst::State::Paused).await?;
+                Self::seek(pipeline, stream, point).await?;
 
-                let bytes = Self::capture(&pipeline)?;
+                let bytes = Self::capture(pipeline, size)?;
                 let file = Self::save(idx, bytes, entry).await?;
 
                 files.push(file);
             }
         }
 
-        pipeline
-            .set_state(gst::State::Null)
-            .map_err(GstError::from)?;
-
         Ok(files)
     }
 }
@@ -0,0 +1,46 @@
+use roadtrip_viewer::dirs::{Dirs, Paths};
+use roadtrip_viewer::{Event, Viewer};
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tokio::stream::StreamExt;
+use tokio::time::timeout;
+
+const TM: Duration = Duration::from_secs(10);
+
+const MEDIA_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/media");
+
+fn scratch(name: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("roadtrip-cancel-scan-{}", std::process::id()))
+        .join(name)
+}
+
+#[tokio::test]
+async fn cancel_scan() {
+    let dirs = Dirs::custom(Paths {
+        data_local_dir: scratch("data"),
+        cache_dir: scratch("cache"),
+        config_dir: scratch("config"),
+    });
+
+    let viewer = Viewer::spawn_with(dirs).await.unwrap();
+    let mut handle = viewer.handle();
+    let mut events = viewer.events();
+
+    let id = handle.scan_media(MEDIA_DIR).await.unwrap();
+    handle.cancel_scan(id).await.unwrap();
+
+    let mut cancelled = None;
+
+    loop {
+        match timeout(TM, events.next()).await.unwrap() {
+            Some(Event::MediaScanCancelled(c)) => cancelled = Some(c),
+            Some(Event::MediaScanCompleted) | None => break,
+            Some(_) => (),
+        }
+    }
+
+    assert_eq!(cancelled, Some(id));
+}
@@ -0,0 +1,54 @@
+use roadtrip_viewer::config::{Config, PrivacyZone};
+
+use std::path::PathBuf;
+
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+        .join(format!("roadtrip-config-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join(name)
+}
+
+#[tokio::test]
+async fn missing_file_is_default() {
+    let config = Config::load(&scratch("missing.toml")).await.unwrap();
+    assert_eq!(config, Config::default());
+}
+
+#[tokio::test]
+async fn round_trip() {
+    let path = scratch("round-trip.toml");
+
+    let config = Config {
+        watched: vec![PathBuf::from("/media/dashcam")],
+        incremental_scans: true,
+        clock_offset: -90,
+        camera_utc_offset: 120,
+        densify_interval: 5,
+        smooth_tracks: true,
+        privacy_zones: vec![
+            PrivacyZone::Circle {
+                latitude: 45.42,
+                longitude: -75.69,
+                radius: 250.0,
+            },
+            PrivacyZone::Polygon {
+                vertices: vec![(45.0, -75.0), (45.1, -75.0), (45.1, -75.1)],
+            },
+        ],
+    };
+
+    config.save(&path).await.unwrap();
+
+    let loaded = Config::load(&path).await.unwrap();
+    assert_eq!(loaded, config);
+}
+
+#[tokio::test]
+async fn unknown_keys_are_ignored() {
+    let path = scratch("unknown.toml");
+    std::fs::write(&path, "watched = []\nfuture-setting = true\n").unwrap();
+
+    let loaded = Config::load(&path).await.unwrap();
+    assert_eq!(loaded, Config::default());
+}
@@ -0,0 +1,52 @@
+use roadtrip_viewer::dirs::{
+    Dirs, Paths, CACHE_DIR_VAR, CONFIG_DIR_VAR, DATA_DIR_VAR,
+};
+
+use std::path::PathBuf;
+
+fn scratch(name: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("roadtrip-dirs-{}", std::process::id()))
+        .join(name)
+}
+
+#[tokio::test]
+async fn custom_dirs_are_created() {
+    let paths = Paths {
+        data_local_dir: scratch("custom").join("data"),
+        cache_dir: scratch("custom").join("cache"),
+        config_dir: scratch("custom").join("config"),
+    };
+
+    let dirs = Dirs::custom(paths.clone());
+    assert_eq!(dirs.paths(), &paths);
+
+    let data = dirs.data_local_dir().await.unwrap();
+    assert_eq!(data, paths.data_local_dir);
+    assert!(data.is_dir());
+
+    let cache = dirs.cache_dir().await.unwrap();
+    assert_eq!(cache, paths.cache_dir);
+    assert!(cache.is_dir());
+
+    let config = dirs.config_dir().await.unwrap();
+    assert_eq!(config, paths.config_dir);
+    assert!(config.is_dir());
+}
+
+#[tokio::test]
+async fn environment_overrides_dirs() {
+    let data_dir = scratch("env").join("data");
+    let cache_dir = scratch("env").join("cache");
+    let config_dir = scratch("env").join("config");
+
+    std::env::set_var(DATA_DIR_VAR, &data_dir);
+    std::env::set_var(CACHE_DIR_VAR, &cache_dir);
+    std::env::set_var(CONFIG_DIR_VAR, &config_dir);
+
+    let dirs = Dirs::new().unwrap();
+
+    assert_eq!(dirs.data_local_dir().await.unwrap(), data_dir);
+    assert_eq!(dirs.cache_dir().await.unwrap(), cache_dir);
+    assert_eq!(dirs.config_dir().await.unwrap(), config_dir);
+}
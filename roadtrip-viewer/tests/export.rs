@@ -0,0 +1,123 @@
+use roadtrip_core::geometry::Filter;
+use roadtrip_core::Hash;
+
+use roadtrip_viewer::config::{Config, PrivacyZone};
+use roadtrip_viewer::dirs::{Dirs, Paths};
+use roadtrip_viewer::notification::Kind;
+use roadtrip_viewer::{Event, Handle, Viewer};
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tokio::stream::{Stream, StreamExt};
+use tokio::time::timeout;
+
+const TM: Duration = Duration::from_secs(10);
+
+const MEDIA_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/media");
+
+fn scratch(name: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("roadtrip-export-{}", std::process::id()))
+        .join(name)
+}
+
+// A viewer that's scanned the test media, and the hash of what it found.
+async fn scanned(
+    name: &str,
+) -> (Handle, impl Stream<Item = Event> + Unpin, Hash) {
+    let dirs = Dirs::custom(Paths {
+        data_local_dir: scratch(name).join("data"),
+        cache_dir: scratch(name).join("cache"),
+        config_dir: scratch(name).join("config"),
+    });
+
+    let viewer = Viewer::spawn_with(dirs).await.unwrap();
+    let mut handle = viewer.handle();
+    let mut events = viewer.events();
+
+    handle.filter(Filter::default()).await.unwrap();
+    handle.scan_media(MEDIA_DIR).await.unwrap();
+
+    let mut hash = None;
+
+    loop {
+        match timeout(TM, events.next()).await.unwrap() {
+            Some(Event::FilterMatched(m)) => hash = Some(m.hash().clone()),
+            Some(Event::MediaScanCompleted) | None => break,
+            Some(_) => (),
+        }
+    }
+
+    (handle, events, hash.unwrap())
+}
+
+// Exports into a fresh `dest` both ways, returning how many of each were
+// written and how many export notifications came with them.
+async fn export<S>(
+    handle: &mut Handle,
+    events: &mut S,
+    hash: Hash,
+    dest: &PathBuf,
+) -> (usize, usize, usize)
+where
+    S: Stream<Item = Event> + Unpin,
+{
+    std::fs::create_dir_all(dest).unwrap();
+
+    handle.export_media(vec![hash.clone()], dest).await.unwrap();
+    handle.export_tracks(vec![hash], dest).await.unwrap();
+
+    let mut media = None;
+    let mut tracks = None;
+    let mut failed = 0;
+
+    while media.is_none() || tracks.is_none() {
+        match timeout(TM, events.next()).await.unwrap().unwrap() {
+            Event::MediaExported(n) => media = Some(n),
+            Event::TracksExported(n) => tracks = Some(n),
+            Event::Notification(n) if n.kind() == Kind::Export => failed += 1,
+            _ => (),
+        }
+    }
+
+    (media.unwrap(), tracks.unwrap(), failed)
+}
+
+fn zone(latitude: f64, longitude: f64) -> Config {
+    Config {
+        privacy_zones: vec![PrivacyZone::Circle {
+            latitude,
+            longitude,
+            radius: 1_000.0,
+        }],
+        ..Config::default()
+    }
+}
+
+#[tokio::test]
+async fn export_outside_zones() {
+    let (mut handle, mut events, hash) = scanned("outside").await;
+    handle.save_config(zone(0.0, 0.0)).await.unwrap();
+
+    let dest = scratch("outside").join("export");
+    let counts = export(&mut handle, &mut events, hash, &dest).await;
+
+    assert_eq!(counts, (1, 1, 0));
+    assert!(dest.join("with-gps.jpg").is_file());
+
+    let gpx = std::fs::read_to_string(dest.join("with-gps.gpx")).unwrap();
+    assert!(gpx.contains("41.88"));
+}
+
+#[tokio::test]
+async fn export_inside_zone() {
+    let (mut handle, mut events, hash) = scanned("inside").await;
+    handle.save_config(zone(41.8894, 87.6244)).await.unwrap();
+
+    let dest = scratch("inside").join("export");
+    let counts = export(&mut handle, &mut events, hash, &dest).await;
+
+    assert_eq!(counts, (0, 0, 2));
+    assert_eq!(std::fs::read_dir(&dest).unwrap().count(), 0);
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "thumbnails")]
+
 use roadtrip_core::geometry::Filter;
 
 use roadtrip_viewer::{Event, Viewer};
@@ -0,0 +1,92 @@
+#![cfg(feature = "metrics")]
+
+use roadtrip_viewer::dirs::{Dirs, Paths};
+use roadtrip_viewer::metrics::{HistogramSnapshot, Snapshot};
+use roadtrip_viewer::{Event, Viewer};
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tokio::stream::StreamExt;
+
+const MEDIA_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/media");
+
+fn scratch(name: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("roadtrip-metrics-{}", std::process::id()))
+        .join(name)
+}
+
+fn snapshot() -> Snapshot {
+    Snapshot {
+        files_scanned: 3,
+        scan_errors: 1,
+        ingest_latency: HistogramSnapshot {
+            buckets: vec![
+                (Duration::from_millis(100), 2),
+                (Duration::from_millis(1000), 3),
+            ],
+            count: 3,
+            sum: Duration::from_millis(1250),
+        },
+        thumbnail_queue_depth: 2,
+        cache_hits: 3,
+        cache_misses: 1,
+    }
+}
+
+#[test]
+fn cache_hit_rate() {
+    assert_eq!(snapshot().cache_hit_rate(), Some(0.75));
+
+    let empty = Snapshot {
+        cache_hits: 0,
+        cache_misses: 0,
+        ..snapshot()
+    };
+    assert_eq!(empty.cache_hit_rate(), None);
+}
+
+#[test]
+fn prometheus_text() {
+    let text = snapshot().to_prometheus();
+    let lines: Vec<_> = text.lines().collect();
+
+    assert!(lines.contains(&"roadtrip_files_scanned_total 3"));
+    assert!(lines.contains(&"roadtrip_scan_errors_total 1"));
+    assert!(lines.contains(&"roadtrip_thumbnail_queue_depth 2"));
+    assert!(lines.contains(&"roadtrip_thumbnail_cache_hits_total 3"));
+    assert!(lines.contains(&"roadtrip_thumbnail_cache_misses_total 1"));
+    assert!(
+        lines.contains(&"roadtrip_ingest_latency_seconds_bucket{le=\"0.1\"} 2")
+    );
+    assert!(lines
+        .contains(&"roadtrip_ingest_latency_seconds_bucket{le=\"+Inf\"} 3"));
+    assert!(lines.contains(&"roadtrip_ingest_latency_seconds_sum 1.25"));
+    assert!(lines.contains(&"roadtrip_ingest_latency_seconds_count 3"));
+}
+
+#[tokio::test]
+async fn scan_is_counted() {
+    let dirs = Dirs::custom(Paths {
+        data_local_dir: scratch("data"),
+        cache_dir: scratch("cache"),
+        config_dir: scratch("config"),
+    });
+
+    let viewer = Viewer::spawn_with(dirs).await.unwrap();
+    let mut handle = viewer.handle();
+    let mut events = viewer.events();
+
+    handle.scan_media(MEDIA_DIR).await.unwrap();
+
+    while let Some(event) = events.next().await {
+        if let Event::MediaScanCompleted = event {
+            break;
+        }
+    }
+
+    let snapshot = handle.metrics_snapshot();
+    assert_eq!(snapshot.files_scanned, 1);
+    assert_eq!(snapshot.ingest_latency.count, 1);
+}
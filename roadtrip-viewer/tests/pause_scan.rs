@@ -0,0 +1,75 @@
+use roadtrip_core::geometry::Filter;
+
+use roadtrip_viewer::dirs::{Dirs, Paths};
+use roadtrip_viewer::{Event, Viewer};
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tokio::stream::StreamExt;
+use tokio::time::timeout;
+
+const TM: Duration = Duration::from_secs(10);
+
+const MEDIA_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/media");
+
+fn scratch(name: &str) -> PathBuf {
+    std::env::temp_dir()
+        .join(format!("roadtrip-pause-scan-{}", std::process::id()))
+        .join(name)
+}
+
+#[tokio::test]
+async fn pause_and_resume() {
+    let dirs = Dirs::custom(Paths {
+        data_local_dir: scratch("data"),
+        cache_dir: scratch("cache"),
+        config_dir: scratch("config"),
+    });
+
+    let viewer = Viewer::spawn_with(dirs).await.unwrap();
+    let mut handle = viewer.handle();
+    let mut events = viewer.events();
+
+    handle.filter(Filter::default()).await.unwrap();
+    let event = timeout(TM, events.next()).await.unwrap();
+    assert!(matches!(event, Some(Event::FilterChanged)));
+
+    handle.pause_scan().await.unwrap();
+    let event = timeout(TM, events.next()).await.unwrap();
+    assert!(matches!(event, Some(Event::MediaScanPaused)));
+
+    handle.scan_media(MEDIA_DIR).await.unwrap();
+    let event = timeout(TM, events.next()).await.unwrap();
+    assert!(matches!(event, Some(Event::MediaScanStarted)));
+
+    // Nothing gets ingested while paused.
+    let ingested = timeout(Duration::from_millis(1500), async {
+        while let Some(event) = events.next().await {
+            match event {
+                Event::FilterMatched(_) | Event::MediaScanCompleted => return,
+                _ => (),
+            }
+        }
+    })
+    .await;
+
+    assert!(ingested.is_err());
+
+    handle.resume_scan().await.unwrap();
+
+    let mut resumed = false;
+    let mut matched = false;
+
+    loop {
+        match timeout(TM, events.next()).await.unwrap() {
+            Some(Event::MediaScanResumed) => resumed = true,
+            Some(Event::FilterMatched(_)) => matched = true,
+            Some(Event::MediaScanCompleted) | None => break,
+            Some(_) => (),
+        }
+    }
+
+    assert!(resumed);
+    assert!(matched);
+}
@@ -6,15 +6,21 @@ use crate::error::Error;
 
 use futures::Stream;
 
+use roadtrip_cancel::Token;
+
 pub use snafu;
 use snafu::ResultExt;
 
 use std::collections::{BTreeMap, BTreeSet};
 use std::fs::Metadata;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use tokio::fs;
 
+use tracing::{trace, warn};
+
 #[derive(Debug, Clone, Copy)]
 pub struct FileType {
     is_dir: bool,
@@ -46,6 +52,21 @@ impl DirEntry {
     }
 }
 
+// Counts files as soon as they're found in a directory, which is usually
+// well before the walk gets around to returning them.
+#[derive(Debug, Clone, Default)]
+pub struct Discovered(Arc<AtomicUsize>);
+
+impl Discovered {
+    pub fn get(&self) -> usize {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn add(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
 #[derive(Debug)]
 enum Kind {
     File,
@@ -69,6 +90,8 @@ impl From<&Metadata> for Kind {
 pub struct WalkDir {
     visited: BTreeSet<PathBuf>,
     unvisited: BTreeMap<PathBuf, Kind>,
+    cancel: Token,
+    discovered: Discovered,
 }
 
 impl Default for WalkDir {
@@ -76,6 +99,8 @@ impl Default for WalkDir {
         Self {
             visited: BTreeSet::new(),
             unvisited: BTreeMap::new(),
+            cancel: Token::new(),
+            discovered: Discovered::default(),
         }
     }
 }
@@ -97,6 +122,16 @@ impl WalkDir {
         self.unvisited.insert(path.into(), Kind::Unknown);
     }
 
+    // Stops the walk, even part way through a large directory, once `token`
+    // is cancelled.
+    pub fn cancel_on(&mut self, token: Token) {
+        self.cancel = token;
+    }
+
+    pub fn discovered(&self) -> Discovered {
+        self.discovered.clone()
+    }
+
     async fn step_file(&mut self, path: PathBuf) -> Result<DirEntry, Error> {
         Ok(DirEntry {
             file_type: FileType { is_dir: false },
@@ -105,12 +140,19 @@ impl WalkDir {
     }
 
     async fn step_dir(&mut self, path: PathBuf) -> Result<DirEntry, Error> {
+        trace!(path = %path.display(), "reading directory");
+
         let mut readdir = fs::read_dir(&path)
             .await
             .with_context(|| error::ReadDir { path: path.clone() })?;
 
         let mut err_count = 0;
         loop {
+            if self.cancel.is_cancelled() {
+                trace!(path = %path.display(), "cancelled reading directory");
+                break;
+            }
+
             match readdir.next_entry().await {
                 Ok(None) => break,
                 Ok(Some(entry)) => {
@@ -118,13 +160,27 @@ impl WalkDir {
                     if let Ok(metadata) = entry.metadata().await {
                         let kind = Kind::from(&metadata);
                         if metadata.is_file() || metadata.is_dir() {
-                            self.unvisited.insert(entry.path().into(), kind);
+                            let path = entry.path();
+                            let known = self.unvisited.contains_key(&path);
+
+                            if metadata.is_file() && !known {
+                                self.discovered.add();
+                            }
+
+                            self.unvisited.insert(path, kind);
                         }
                     }
                 }
-                Err(_) => {
+                Err(e) => {
+                    warn!(
+                        path = %path.display(),
+                        error = %e,
+                        "unable to read directory entry"
+                    );
+
                     err_count += 1;
                     if err_count >= 10 {
+                        warn!(path = %path.display(), "giving up on directory");
                         break;
                     }
                 }
@@ -139,6 +195,10 @@ impl WalkDir {
 
     async fn step(&mut self) -> Option<Result<DirEntry, Error>> {
         loop {
+            if self.cancel.is_cancelled() {
+                return None;
+            }
+
             let next_path;
             let next_kind;
 
@@ -180,6 +240,7 @@ impl WalkDir {
             if metadata.is_dir() {
                 return Some(self.step_dir(next_path).await);
             } else if metadata.is_file() {
+                self.discovered.add();
                 return Some(self.step_file(next_path).await);
             }
         }
@@ -1,5 +1,7 @@
 use futures::pin_mut;
 
+use roadtrip_cancel::Token;
+
 use roadtrip_walkdir::WalkDir;
 
 use std::collections::HashMap;
@@ -54,3 +56,51 @@ async fn tree() -> Result<(), String> {
         Err("missing path(s)".into())
     }
 }
+
+#[tokio::test]
+async fn cancelled() {
+    let manifest_dir = env::var_os("CARGO_MANIFEST_DIR").unwrap();
+    let mut root = PathBuf::from(manifest_dir);
+    root.push("tests");
+    root.push("testdata");
+
+    let token = Token::new();
+
+    let mut walkdir = WalkDir::new(root);
+    walkdir.cancel_on(token.clone());
+
+    let walkdir = walkdir.walk();
+    pin_mut!(walkdir);
+
+    assert!(walkdir.next().await.is_some());
+
+    token.cancel();
+
+    assert!(walkdir.next().await.is_none());
+}
+
+#[tokio::test]
+async fn discovered() {
+    let manifest_dir = env::var_os("CARGO_MANIFEST_DIR").unwrap();
+    let mut root = PathBuf::from(manifest_dir);
+    root.push("tests");
+    root.push("testdata");
+
+    let walkdir = WalkDir::new(root);
+    let discovered = walkdir.discovered();
+
+    let walkdir = walkdir.walk();
+    pin_mut!(walkdir);
+
+    let mut files = 0;
+
+    while let Some(entry) = walkdir.next().await {
+        if !entry.unwrap().file_type().is_dir() {
+            files += 1;
+            assert!(discovered.get() >= files);
+        }
+    }
+
+    assert_eq!(files, 5);
+    assert_eq!(discovered.get(), 5);
+}
@@ -1,5 +1,8 @@
 pub use roadtrip_cache as cache;
+pub use roadtrip_cancel as cancel;
 pub use roadtrip_core as core;
+#[cfg(feature = "ingest")]
 pub use roadtrip_ingest as ingest;
+#[cfg(feature = "viewer")]
 pub use roadtrip_viewer as viewer;
 pub use roadtrip_walkdir as walkdir;